@@ -0,0 +1,145 @@
+/// ## ChainPreset
+///
+/// Static facts about an EVM network and the confirmation depth commonly
+/// required before treating a transaction on it as final.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainPreset {
+    /// Human readable network name
+    pub name: &'static str,
+    pub chain_id: u64,
+    /// Decimals of the native currency
+    pub native_decimals: u8,
+    /// Whether the network prices gas with EIP-1559 base and priority fees
+    pub eip1559: bool,
+    /// Average time between blocks, in milliseconds
+    pub block_time_ms: u64,
+    /// Confirmations recommended before a transaction is considered final
+    pub recommended_confirmations: u64,
+}
+
+impl ChainPreset {
+    /// Poller delay matching the block time, so every block is checked once.
+    pub fn poller_delay_seconds(&self) -> u64 {
+        self.block_time_ms.div_ceil(1000).max(1)
+    }
+}
+
+pub const ETHEREUM: ChainPreset = ChainPreset {
+    name: "Ethereum",
+    chain_id: 1,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 12_000,
+    recommended_confirmations: 12,
+};
+
+pub const OPTIMISM: ChainPreset = ChainPreset {
+    name: "OP Mainnet",
+    chain_id: 10,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 2_000,
+    recommended_confirmations: 10,
+};
+
+pub const BSC: ChainPreset = ChainPreset {
+    name: "BNB Smart Chain",
+    chain_id: 56,
+    native_decimals: 18,
+    eip1559: false,
+    block_time_ms: 3_000,
+    recommended_confirmations: 15,
+};
+
+pub const GNOSIS: ChainPreset = ChainPreset {
+    name: "Gnosis",
+    chain_id: 100,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 5_000,
+    recommended_confirmations: 12,
+};
+
+pub const POLYGON: ChainPreset = ChainPreset {
+    name: "Polygon PoS",
+    chain_id: 137,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 2_000,
+    recommended_confirmations: 64,
+};
+
+pub const BASE: ChainPreset = ChainPreset {
+    name: "Base",
+    chain_id: 8453,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 2_000,
+    recommended_confirmations: 10,
+};
+
+pub const ARBITRUM_ONE: ChainPreset = ChainPreset {
+    name: "Arbitrum One",
+    chain_id: 42161,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 250,
+    recommended_confirmations: 20,
+};
+
+pub const AVALANCHE: ChainPreset = ChainPreset {
+    name: "Avalanche C-Chain",
+    chain_id: 43114,
+    native_decimals: 18,
+    eip1559: true,
+    block_time_ms: 2_000,
+    recommended_confirmations: 1,
+};
+
+/// All built-in presets.
+pub const PRESETS: &[ChainPreset] = &[
+    ETHEREUM,
+    OPTIMISM,
+    BSC,
+    GNOSIS,
+    POLYGON,
+    BASE,
+    ARBITRUM_ONE,
+    AVALANCHE,
+];
+
+/// Looks up the built-in preset of the network with `chain_id`.
+pub fn by_chain_id(chain_id: u64) -> Option<ChainPreset> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.chain_id == chain_id)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_have_unique_chain_ids() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert!(
+                PRESETS[i + 1..].iter().all(|p| p.chain_id != preset.chain_id),
+                "duplicate chain id {}",
+                preset.chain_id
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_by_chain_id() {
+        assert_eq!(by_chain_id(56), Some(BSC));
+        assert_eq!(by_chain_id(31337), None);
+    }
+
+    #[test]
+    fn poller_delay_rounds_up_to_whole_seconds() {
+        assert_eq!(ETHEREUM.poller_delay_seconds(), 12);
+        assert_eq!(ARBITRUM_ONE.poller_delay_seconds(), 1);
+    }
+}
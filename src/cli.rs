@@ -0,0 +1,625 @@
+//! The `acceptevm` command line tool, built with the `cli` feature.
+//!
+//! ```text
+//! acceptevm [--config <path>] run
+//! acceptevm [--config <path>] invoice create <amount> [--expires <seconds>]
+//!     [--message <text>] [--token <address>] [--external-id <id>]
+//! acceptevm [--config <path>] invoice list [--status <status>]
+//! acceptevm [--config <path>] invoice cancel <id>
+//! acceptevm [--config <path>] sweep <id>
+//! ```
+//!
+//! The configuration is a TOML file, `acceptevm.toml` by default:
+//!
+//! ```toml
+//! rpc_urls = ["https://bsc-dataseed1.binance.org/"]
+//! treasury = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+//! # CSV file holding the open invoices and their wallet keys
+//! database = "invoices.csv"
+//! # Optional, the library defaults apply otherwise
+//! expected_chain_id = 56
+//! min_confirmations = 10
+//! poller_delay_seconds = 10
+//! receipt_timeout_seconds = 60
+//! ```
+//!
+//! `run` polls the invoices of the database and prints paid invoices and
+//! gateway events as JSON lines. The other commands print JSON lines as well
+//! and can be used while `run` is running: the database is locked while it is
+//! read or written, and `run` picks up their changes within
+//! [`SYNC_INTERVAL`]. Invoices leave the database once they are delivered or
+//! expire.
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ahash::{AHashMap, AHashSet};
+use alloy::primitives::{Address, ChainId};
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use toml_edit::{DocumentMut, Item};
+
+use crate::gateway::backup::{read_csv, write_csv};
+use crate::gateway::error::GatewayError;
+use crate::gateway::{InvoiceRequest, PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::{Invoice, InvoiceOptions, InvoiceStatus};
+
+/// Configuration file read when `--config` is not given.
+pub const DEFAULT_CONFIG: &str = "acceptevm.toml";
+/// How often `run` writes its invoices to the database and adopts the
+/// changes of other commands.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+/// How long `run` waits for broadcast sweeps to confirm when interrupted.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub const USAGE: &str = "\
+Usage: acceptevm [--config <path>] <command>
+
+Commands:
+  run                                  Poll invoices and print payment events
+  invoice create <amount> [--expires <seconds>] [--message <text>]
+                 [--token <address>] [--external-id <id>]
+  invoice list [--status <status>]
+  invoice cancel <id>
+  sweep <id>                           Sweep a paid invoice to the treasury";
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+    #[error("Invalid config: {0}")]
+    Config(String),
+    #[error("I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Gateway(#[from] GatewayError),
+}
+
+pub type Result<T> = std::result::Result<T, CliError>;
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+fn config_error(message: impl Into<String>) -> CliError {
+    CliError::Config(message.into())
+}
+
+/// Settings of the configuration file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CliConfig {
+    pub rpc_urls: Vec<String>,
+    pub treasury: Address,
+    /// CSV file in the format of [`PaymentGateway::export_csv`]
+    pub database: PathBuf,
+    pub expected_chain_id: Option<ChainId>,
+    pub min_confirmations: Option<u64>,
+    pub poller_delay_seconds: Option<u64>,
+    pub receipt_timeout_seconds: Option<u64>,
+}
+
+impl CliConfig {
+    /// Parses a configuration file. Unknown keys are rejected to catch typos.
+    pub fn parse(toml: &str) -> Result<Self> {
+        let document = toml.parse::<DocumentMut>().map_err(|e| config_error(e.to_string()))?;
+        let mut rpc_urls = None;
+        let mut treasury = None;
+        let mut database = None;
+        let mut config = Self {
+            rpc_urls: Vec::new(),
+            treasury: Address::ZERO,
+            database: PathBuf::new(),
+            expected_chain_id: None,
+            min_confirmations: None,
+            poller_delay_seconds: None,
+            receipt_timeout_seconds: None,
+        };
+        for (key, item) in document.iter() {
+            match key {
+                "rpc_urls" => rpc_urls = Some(string_array(key, item)?),
+                "treasury" => {
+                    let address = string(key, item)?.parse().map_err(|e| {
+                        config_error(format!("treasury is not an address: {e}"))
+                    })?;
+                    treasury = Some(address);
+                }
+                "database" => database = Some(PathBuf::from(string(key, item)?)),
+                "expected_chain_id" => config.expected_chain_id = Some(integer(key, item)?),
+                "min_confirmations" => config.min_confirmations = Some(integer(key, item)?),
+                "poller_delay_seconds" => config.poller_delay_seconds = Some(integer(key, item)?),
+                "receipt_timeout_seconds" => {
+                    config.receipt_timeout_seconds = Some(integer(key, item)?)
+                }
+                _ => return Err(config_error(format!("unknown key {key}"))),
+            }
+        }
+        config.rpc_urls = rpc_urls.ok_or_else(|| config_error("rpc_urls is required"))?;
+        config.treasury = treasury.ok_or_else(|| config_error("treasury is required"))?;
+        config.database = database.ok_or_else(|| config_error("database is required"))?;
+        Ok(config)
+    }
+
+    /// Reads the configuration file at `path`. A relative `database` is
+    /// resolved against the directory of the file.
+    pub async fn load(path: &Path) -> Result<Self> {
+        let toml = tokio::fs::read_to_string(path).await.map_err(|e| {
+            config_error(format!("cannot read {}: {e}", path.display()))
+        })?;
+        let mut config = Self::parse(&toml)?;
+        if let Some(directory) = path.parent() {
+            config.database = directory.join(&config.database);
+        }
+        Ok(config)
+    }
+
+    /// Gateway configuration delivering paid invoices to `reflector`.
+    pub fn gateway_configuration(
+        &self,
+        reflector: mpsc::UnboundedSender<(String, Invoice)>,
+    ) -> PaymentGatewayConfiguration {
+        let mut configuration =
+            PaymentGatewayConfiguration::new(self.rpc_urls.clone(), self.treasury, reflector);
+        configuration.expected_chain_id = self.expected_chain_id;
+        if let Some(confirmations) = self.min_confirmations {
+            configuration.min_confirmations = confirmations;
+        }
+        if let Some(delay) = self.poller_delay_seconds {
+            configuration.poller_delay_seconds = delay;
+        }
+        if let Some(timeout) = self.receipt_timeout_seconds {
+            configuration.receipt_timeout_seconds = timeout;
+        }
+        configuration
+    }
+}
+
+fn string<'a>(key: &str, item: &'a Item) -> Result<&'a str> {
+    item.as_str()
+        .ok_or_else(|| config_error(format!("{key} must be a string")))
+}
+
+fn integer<T: TryFrom<i64>>(key: &str, item: &Item) -> Result<T> {
+    item.as_integer()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| config_error(format!("{key} must be a non-negative integer")))
+}
+
+fn string_array(key: &str, item: &Item) -> Result<Vec<String>> {
+    let error = || config_error(format!("{key} must be an array of strings"));
+    item.as_array()
+        .ok_or_else(error)?
+        .iter()
+        .map(|value| value.as_str().map(str::to_string).ok_or_else(error))
+        .collect()
+}
+
+/// A command of the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Run,
+    CreateInvoice {
+        /// Decimal amount in whole units, or the smallest unit when it is an
+        /// integer, see [`InvoiceRequest::new`]
+        amount: String,
+        expires_in_seconds: u64,
+        message: Option<String>,
+        token: Option<Address>,
+        external_id: Option<String>,
+    },
+    ListInvoices {
+        status: Option<InvoiceStatus>,
+    },
+    CancelInvoice {
+        id: String,
+    },
+    Sweep {
+        id: String,
+    },
+}
+
+impl Command {
+    /// Parses the arguments after the program name into the configuration
+    /// path and the command.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<(PathBuf, Self)> {
+        let mut config = PathBuf::from(DEFAULT_CONFIG);
+        let mut positional = Vec::new();
+        let mut flags = AHashMap::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+            let value = args
+                .next()
+                .ok_or_else(|| usage(format!("--{flag} needs a value")))?;
+            if flag == "config" {
+                config = PathBuf::from(value);
+            } else {
+                flags.insert(flag.to_string(), value);
+            }
+        }
+
+        let positional: Vec<&str> = positional.iter().map(String::as_str).collect();
+        let command = match positional.as_slice() {
+            ["run"] => Self::Run,
+            ["invoice", "create", amount] => Self::CreateInvoice {
+                amount: amount.to_string(),
+                expires_in_seconds: match flags.remove("expires") {
+                    Some(seconds) => seconds
+                        .parse()
+                        .map_err(|_| usage("--expires must be a number of seconds"))?,
+                    None => 3600,
+                },
+                message: flags.remove("message"),
+                token: flags
+                    .remove("token")
+                    .map(|token| token.parse())
+                    .transpose()
+                    .map_err(|_| usage("--token must be an address"))?,
+                external_id: flags.remove("external-id"),
+            },
+            ["invoice", "list"] => Self::ListInvoices {
+                status: flags
+                    .remove("status")
+                    .map(|status| serde_json::from_value(Value::String(status)))
+                    .transpose()
+                    .map_err(|_| usage("--status must be an invoice status, e.g. Pending"))?,
+            },
+            ["invoice", "cancel", id] => Self::CancelInvoice { id: id.to_string() },
+            ["sweep", id] => Self::Sweep { id: id.to_string() },
+            [] => return Err(usage("no command given")),
+            _ => return Err(usage(format!("unknown command {}", positional.join(" ")))),
+        };
+        if let Some(flag) = flags.keys().next() {
+            return Err(usage(format!("unknown option --{flag}")));
+        }
+        Ok((config, command))
+    }
+}
+
+/// Runs the command line `args`, without the program name.
+pub async fn main(args: impl IntoIterator<Item = String>) -> Result<()> {
+    let (config, command) = Command::parse(args)?;
+    let config = CliConfig::load(&config).await?;
+    execute(&config, command, &mut std::io::stdout()).await
+}
+
+/// Executes `command`, printing its JSON lines to `out`.
+pub async fn execute(config: &CliConfig, command: Command, out: &mut impl Write) -> Result<()> {
+    let (reflector, paid) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(config.gateway_configuration(reflector))?;
+    let database = Database::new(&config.database);
+    match command {
+        Command::Run => return run(&gateway, &database, paid, out).await,
+        Command::CreateInvoice {
+            amount,
+            expires_in_seconds,
+            message,
+            token,
+            external_id,
+        } => {
+            let _lock = database.load(&gateway).await?;
+            let request = InvoiceRequest::new(amount, expires_in_seconds)
+                .message(message.unwrap_or_default().into_bytes())
+                .options(InvoiceOptions {
+                    token,
+                    external_id,
+                    ..Default::default()
+                });
+            let (id, invoice) = gateway.create_invoice(request).await?;
+            database.write(&gateway).await?;
+            print(out, &invoice_line(&id, &invoice)?)?;
+        }
+        Command::ListInvoices { status } => {
+            let _lock = database.load(&gateway).await?;
+            let mut invoices = match status {
+                Some(status) => gateway.get_invoices_by_status(status).await?,
+                None => gateway.get_all_invoices().await?,
+            };
+            invoices.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (id, invoice) in invoices {
+                print(out, &invoice_line(&id, &invoice)?)?;
+            }
+        }
+        Command::CancelInvoice { id } => {
+            let _lock = database.load(&gateway).await?;
+            gateway.cancel_invoice(&id).await?;
+            database.write(&gateway).await?;
+            print(out, &json!({ "invoice_id": id, "cancelled": true }))?;
+        }
+        Command::Sweep { id } => {
+            let _lock = database.load(&gateway).await?;
+            let tx_hash = gateway.sweep_invoice(&id).await?;
+            database.write(&gateway).await?;
+            print(out, &json!({ "invoice_id": id, "tx_hash": tx_hash }))?;
+        }
+    }
+    Ok(())
+}
+
+/// Polls until interrupted with Ctrl-C or until the poller fails.
+async fn run(
+    gateway: &PaymentGateway,
+    database: &Database,
+    mut paid: mpsc::UnboundedReceiver<(String, Invoice)>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut written = AHashMap::new();
+    database.sync(gateway, &mut written).await?;
+    let mut events = gateway.subscribe_events();
+    let mut poller = gateway.poll_payments().await;
+    let mut sync = tokio::time::interval(SYNC_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            Some((id, invoice)) = paid.recv() => print_paid(out, &id, &invoice)?,
+            event = events.recv() => match event {
+                Ok(event) => print(out, &serde_json::to_value(event).map_err(metadata)?)?,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Skipped {skipped} gateway events");
+                }
+                Err(broadcast::error::RecvError::Closed) => break Ok(()),
+            },
+            _ = sync.tick() => database.sync(gateway, &mut written).await?,
+            result = poller.join() => break result.map_err(CliError::from),
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        }
+    };
+    if let Err(e) = poller.shutdown(SHUTDOWN_TIMEOUT).await {
+        tracing::warn!("{e}");
+    }
+    while let Ok((id, invoice)) = paid.try_recv() {
+        print_paid(out, &id, &invoice)?;
+    }
+    database.sync(gateway, &mut written).await?;
+    result
+}
+
+fn metadata(e: serde_json::Error) -> GatewayError {
+    GatewayError::Metadata(e.to_string())
+}
+
+/// An invoice as printed by the commands, without its wallet key.
+fn invoice_line(id: &str, invoice: &Invoice) -> Result<Value> {
+    let invoice = invoice.redacted().map_err(metadata)?;
+    Ok(json!({ "invoice_id": id, "invoice": invoice }))
+}
+
+fn print_paid(out: &mut impl Write, id: &str, invoice: &Invoice) -> Result<()> {
+    let mut line = invoice_line(id, invoice)?;
+    line["type"] = json!("InvoicePaid");
+    print(out, &line)
+}
+
+fn print(out: &mut impl Write, line: &Value) -> Result<()> {
+    writeln!(out, "{line}")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// The invoice CSV, guarded by an exclusive lock on a `.lock` file next to it.
+struct Database {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl Database {
+    fn new(path: &Path) -> Self {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        Self {
+            path: path.to_path_buf(),
+            lock_path: lock_path.into(),
+        }
+    }
+
+    /// Waits for the lock, which is held until the returned file is dropped.
+    async fn lock(&self) -> Result<File> {
+        let path = self.lock_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path)?;
+            file.lock()?;
+            Ok(file)
+        })
+        .await
+        .map_err(std::io::Error::other)?
+    }
+
+    /// Locks the database and adds its invoices to `gateway`.
+    async fn load(&self, gateway: &PaymentGateway) -> Result<File> {
+        let lock = self.lock().await?;
+        let mut invoices = gateway.invoices.write().await;
+        for (key, invoice) in self.read().await? {
+            gateway.index_invoice(&key, &invoice);
+            invoices.insert(key, invoice);
+        }
+        Ok(lock)
+    }
+
+    /// The stored invoices; none when the database does not exist yet.
+    async fn read(&self) -> Result<Vec<(String, Invoice)>> {
+        if !tokio::fs::try_exists(&self.path).await? {
+            return Ok(Vec::new());
+        }
+        Ok(read_csv(&self.path).await?)
+    }
+
+    async fn write(&self, gateway: &PaymentGateway) -> Result<()> {
+        write_csv(&self.path, gateway.get_all_invoices().await?).await?;
+        Ok(())
+    }
+
+    /// Merges the database into `gateway` and writes the result back.
+    ///
+    /// `written` holds the invoices of the previous sync. Stored invoices
+    /// that differ from it were created or changed by another command and
+    /// replace those of the gateway; invoices missing from the database were
+    /// cancelled and are removed.
+    async fn sync(
+        &self,
+        gateway: &PaymentGateway,
+        written: &mut AHashMap<String, Value>,
+    ) -> Result<()> {
+        let _lock = self.lock().await?;
+        let stored = self.read().await?;
+        {
+            let mut invoices = gateway.invoices.write().await;
+            let stored_ids: AHashSet<&String> = stored.iter().map(|(id, _)| id).collect();
+            for id in written.keys().filter(|id| !stored_ids.contains(id)) {
+                invoices.remove(id);
+            }
+            for (id, invoice) in &stored {
+                let value = serde_json::to_value(invoice).map_err(metadata)?;
+                if written.get(id) != Some(&value) {
+                    invoices.insert(id.clone(), invoice.clone());
+                }
+            }
+        }
+        let invoices = gateway.get_all_invoices().await?;
+        *written = invoices
+            .iter()
+            .map(|(id, invoice)| Ok((id.clone(), serde_json::to_value(invoice)?)))
+            .collect::<serde_json::Result<_>>()
+            .map_err(metadata)?;
+        write_csv(&self.path, invoices).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+
+    const CONFIG: &str = r#"
+rpc_urls = ["http://127.0.0.1:1"]
+treasury = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+database = "invoices.csv"
+min_confirmations = 3
+"#;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn test_config(name: &str) -> CliConfig {
+        let database = std::env::temp_dir().join(format!("acceptevm-cli-{name}.csv"));
+        let _ = std::fs::remove_file(&database);
+        CliConfig {
+            database,
+            ..CliConfig::parse(CONFIG).unwrap()
+        }
+    }
+
+    async fn lines(config: &CliConfig, command: &str) -> Vec<Value> {
+        let (_, command) = Command::parse(args(command)).unwrap();
+        let mut out = Vec::new();
+        execute(config, command, &mut out).await.unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn config_is_parsed_and_validated() {
+        let config = CliConfig::parse(CONFIG).unwrap();
+        assert_eq!(config.rpc_urls, vec!["http://127.0.0.1:1".to_string()]);
+        assert_eq!(config.database, PathBuf::from("invoices.csv"));
+        assert_eq!(config.min_confirmations, Some(3));
+        assert_eq!(config.poller_delay_seconds, None);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert_eq!(config.gateway_configuration(tx).min_confirmations, 3);
+
+        let typo = format!("{CONFIG}poller_delay = 1\n");
+        assert!(matches!(CliConfig::parse(&typo), Err(CliError::Config(_))));
+        let negative = CONFIG.replace("= 3", "= -3");
+        assert!(matches!(CliConfig::parse(&negative), Err(CliError::Config(_))));
+        let missing = CONFIG.replace("database = \"invoices.csv\"", "");
+        assert!(matches!(CliConfig::parse(&missing), Err(CliError::Config(_))));
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        let (config, command) =
+            Command::parse(args("--config /etc/acceptevm.toml invoice create 0.5 --expires 60"))
+                .unwrap();
+        assert_eq!(config, PathBuf::from("/etc/acceptevm.toml"));
+        assert_eq!(
+            command,
+            Command::CreateInvoice {
+                amount: "0.5".to_string(),
+                expires_in_seconds: 60,
+                message: None,
+                token: None,
+                external_id: None,
+            }
+        );
+        let (config, command) = Command::parse(args("invoice list --status Paid")).unwrap();
+        assert_eq!(config, PathBuf::from(DEFAULT_CONFIG));
+        assert_eq!(
+            command,
+            Command::ListInvoices {
+                status: Some(InvoiceStatus::Paid)
+            }
+        );
+        let (_, command) = Command::parse(args("sweep abc")).unwrap();
+        assert_eq!(command, Command::Sweep { id: "abc".to_string() });
+
+        for invalid in ["", "invoice", "sweep", "run --verbose 1", "invoice list --status Done"] {
+            assert!(matches!(Command::parse(args(invalid)), Err(CliError::Usage(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn invoices_persist_between_commands() {
+        let config = test_config("commands");
+        let created = lines(&config, "invoice create 1000 --external-id order-1").await;
+        let id = created[0]["invoice_id"].as_str().unwrap().to_string();
+        assert_eq!(created[0]["invoice"]["external_id"], "order-1");
+        assert!(created[0]["invoice"].get("wallet").is_none());
+
+        let listed = lines(&config, "invoice list --status Pending").await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["invoice_id"], id.as_str());
+        let stored = read_csv(&config.database).await.unwrap();
+        assert!(!stored[0].1.wallet.inner.is_empty());
+
+        lines(&config, &format!("invoice cancel {id}")).await;
+        assert!(lines(&config, "invoice list").await.is_empty());
+        std::fs::remove_file(&config.database).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sync_adopts_changes_of_other_commands() {
+        let config = test_config("sync");
+        let database = Database::new(&config.database);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let running = PaymentGateway::new(config.gateway_configuration(tx)).unwrap();
+        let (kept, _) = running.new_invoice(U256::from(1u64), vec![], 3600).await.unwrap();
+        let mut written = AHashMap::new();
+        database.sync(&running, &mut written).await.unwrap();
+
+        let created = lines(&config, "invoice create 2000").await;
+        let created = created[0]["invoice_id"].as_str().unwrap().to_string();
+        lines(&config, &format!("invoice cancel {kept}")).await;
+        database.sync(&running, &mut written).await.unwrap();
+        let invoices = running.get_all_invoices().await.unwrap();
+        assert_eq!(invoices.len(), 1);
+        assert_eq!(invoices[0].0, created);
+
+        // Changes of the running gateway win over unchanged stored invoices
+        running.invoices.write().await.get_mut(&created).unwrap().status = InvoiceStatus::Paid;
+        database.sync(&running, &mut written).await.unwrap();
+        let stored = read_csv(&config.database).await.unwrap();
+        assert_eq!(stored[0].1.status, InvoiceStatus::Paid);
+        std::fs::remove_file(&config.database).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+use alloy::primitives::{Address, U256};
+
+use crate::invoice::Invoice;
+
+/// Ids of the open invoices by what is observed on chain, so every
+/// observation maps back to its invoice in constant time.
+///
+/// Invoices with their own address are grouped by asset, `None` for the
+/// native currency, and indexed by address. Invoices paid to the shared
+/// deposit address are indexed by their unique amount. Callers look the ids
+/// up in the invoice map, which stays the source of truth.
+#[derive(Default)]
+pub(crate) struct InvoiceAddressIndex {
+    inner: Mutex<AddressIndex>,
+}
+
+#[derive(Default)]
+struct AddressIndex {
+    by_asset: AHashMap<Option<Address>, AHashMap<Address, String>>,
+    /// Several invoices can hold an amount when one was reused after expiry
+    shared_by_amount: AHashMap<U256, Vec<String>>,
+}
+
+impl InvoiceAddressIndex {
+    pub(crate) fn insert(&self, key: &str, invoice: &Invoice) {
+        let mut index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if invoice.shared_deposit {
+            index
+                .shared_by_amount
+                .entry(invoice.amount)
+                .or_default()
+                .push(key.to_string());
+        } else {
+            index
+                .by_asset
+                .entry(invoice.token)
+                .or_default()
+                .insert(invoice.to, key.to_string());
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &str, invoice: &Invoice) {
+        let mut index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if invoice.shared_deposit {
+            if let Some(keys) = index.shared_by_amount.get_mut(&invoice.amount) {
+                keys.retain(|indexed| indexed != key);
+                if keys.is_empty() {
+                    index.shared_by_amount.remove(&invoice.amount);
+                }
+            }
+        } else if let Some(addresses) = index.by_asset.get_mut(&invoice.token) {
+            if addresses.get(&invoice.to).is_some_and(|indexed| indexed == key) {
+                addresses.remove(&invoice.to);
+            }
+            if addresses.is_empty() {
+                index.by_asset.remove(&invoice.token);
+            }
+        }
+    }
+
+    /// The invoice paid in `token` to its own address `to`.
+    pub(crate) fn by_address(&self, token: Option<Address>, to: Address) -> Option<String> {
+        let index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        index.by_asset.get(&token)?.get(&to).cloned()
+    }
+
+    /// The invoices paid to the shared deposit address with `amount`.
+    pub(crate) fn shared_by_amount(&self, amount: U256) -> Vec<String> {
+        let index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        index.shared_by_amount.get(&amount).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invoices_are_found_by_asset_and_address() {
+        let token = Some(Address::repeat_byte(0x70));
+        let native = Invoice {
+            to: Address::repeat_byte(1),
+            ..Default::default()
+        };
+        let tokens = Invoice {
+            to: Address::repeat_byte(2),
+            token,
+            ..Default::default()
+        };
+        let index = InvoiceAddressIndex::default();
+        index.insert("a", &native);
+        index.insert("b", &tokens);
+
+        assert_eq!(index.by_address(None, Address::repeat_byte(1)).as_deref(), Some("a"));
+        assert_eq!(index.by_address(token, Address::repeat_byte(2)).as_deref(), Some("b"));
+        assert_eq!(index.by_address(None, Address::repeat_byte(2)), None);
+
+        index.remove("a", &native);
+        assert_eq!(index.by_address(None, Address::repeat_byte(1)), None);
+    }
+
+    #[test]
+    fn shared_invoices_are_found_by_amount() {
+        let shared = |amount: u64| Invoice {
+            amount: U256::from(amount),
+            shared_deposit: true,
+            ..Default::default()
+        };
+        let index = InvoiceAddressIndex::default();
+        index.insert("a", &shared(5));
+        index.insert("b", &shared(5));
+        index.insert("c", &shared(6));
+
+        assert_eq!(index.shared_by_amount(U256::from(5u64)), ["a", "b"]);
+        assert_eq!(index.shared_by_amount(U256::from(6u64)), ["c"]);
+        assert!(index.shared_by_amount(U256::from(7u64)).is_empty());
+        // Shared invoices have no address of their own
+        assert_eq!(index.by_address(None, Address::ZERO), None);
+
+        index.remove("a", &shared(5));
+        assert_eq!(index.shared_by_amount(U256::from(5u64)), ["b"]);
+    }
+}
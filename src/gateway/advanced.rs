@@ -0,0 +1,81 @@
+use alloy::providers::{DynProvider, Provider};
+use alloy::signers::local::PrivateKeySigner;
+
+use crate::web3::transfers::native_transfers::invoice_signer;
+
+use super::{error::GatewayError, result::Result, PaymentGateway};
+
+/// Low-level escape hatch for bespoke on-chain operations, enabled by the
+/// `advanced` feature. Transactions sent through these accessors bypass the
+/// poller and can race with automatic sweeps of the same invoice.
+impl PaymentGateway {
+    /// **Advanced.** Returns a provider connected to the next configured RPC
+    /// URL, using the same round-robin as the poller.
+    pub fn provider(&self) -> Result<DynProvider> {
+        let url = self
+            .next_rpc_url()
+            .parse()
+            .map_err(|e: url::ParseError| GatewayError::InvalidRpcUrl(e.to_string()))?;
+        Ok(self.connect(url).erased())
+    }
+
+    /// **Advanced.** Restores the signer controlling an invoice address,
+    /// deriving it from the HD wallet when the invoice is HD-derived.
+    ///
+    /// Forwarder invoices have no private key and return an error, as do
+    /// invoices whose key is held by a configured `sweep_signer`.
+    pub async fn signer_for(&self, invoice_id: &str) -> Result<PrivateKeySigner> {
+        let invoice = self.get_invoice(invoice_id).await?;
+        if invoice.forwarder_salt.is_some() {
+            return Err(GatewayError::InvalidWallet(
+                "forwarder invoices have no private key".to_string(),
+            ));
+        }
+        if invoice.derivation_index.is_none() && self.config.sweep_signer.is_some() {
+            return Err(GatewayError::InvalidWallet(
+                "the invoice key is held by the sweep signer".to_string(),
+            ));
+        }
+        invoice_signer(self, &invoice).map_err(|e| GatewayError::InvalidWallet(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gateway::{error::GatewayError, PaymentGateway, PaymentGatewayConfiguration, U256};
+
+    fn make_gateway(rpc_url: &str) -> PaymentGateway {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        PaymentGateway::new(PaymentGatewayConfiguration::new(
+            vec![rpc_url.to_string()],
+            alloy::primitives::Address::ZERO,
+            tx,
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn signer_for_controls_invoice_address() {
+        let gateway = make_gateway("http://x.com");
+        let (id, invoice) = gateway.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        assert_eq!(gateway.signer_for(&id).await.unwrap().address(), invoice.to);
+    }
+
+    #[tokio::test]
+    async fn signer_for_unknown_invoice_is_not_found() {
+        let gateway = make_gateway("http://x.com");
+        assert!(matches!(
+            gateway.signer_for("missing").await,
+            Err(GatewayError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn provider_rejects_invalid_url() {
+        let gateway = make_gateway("not a url");
+        assert!(matches!(
+            gateway.provider(),
+            Err(GatewayError::InvalidRpcUrl(_))
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use alloy::primitives::U256;
+
+use super::error::GatewayError;
+
+/// An amount in the smallest unit of an asset together with the asset's
+/// decimals, convertible to and from human-readable decimal strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Amount {
+    /// Amount in the smallest unit, e.g. wei
+    pub value: U256,
+    pub decimals: u8,
+}
+
+impl Amount {
+    pub fn new(value: U256, decimals: u8) -> Self {
+        Self { value, decimals }
+    }
+
+    /// Parses a decimal string such as `"12.50"` into an amount of an asset
+    /// with `decimals` decimals.
+    ///
+    /// Fails on anything but digits with an optional fractional part, and on
+    /// more fractional digits than the asset has, instead of rounding.
+    pub fn from_decimal_str(amount: &str, decimals: u8) -> Result<Self, GatewayError> {
+        let invalid = |reason: &str| GatewayError::InvalidAmount(format!("{amount:?}: {reason}"));
+        let (whole, fraction) = amount.trim().split_once('.').unwrap_or((amount.trim(), ""));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+            return Err(invalid("not a decimal number"));
+        }
+        if amount.trim().ends_with('.') {
+            return Err(invalid("missing fractional digits"));
+        }
+        if fraction.len() > decimals as usize {
+            return Err(invalid(&format!("more than {decimals} decimal places")));
+        }
+        let digits = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+        let value = U256::from_str_radix(&digits, 10).map_err(|_| invalid("too large"))?;
+        Ok(Self { value, decimals })
+    }
+
+    /// Formats the amount as a decimal string without trailing zeros, e.g.
+    /// `"12.5"`.
+    pub fn to_decimal_string(&self) -> String {
+        let decimals = self.decimals as usize;
+        let digits = format!("{:0>width$}", self.value.to_string(), width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{whole}.{fraction}")
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_decimal_string())
+    }
+}
+
+/// Amount requested by a new invoice: either in the smallest unit of the
+/// asset or as a decimal string resolved against the asset's decimals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvoiceAmount {
+    Units(U256),
+    Decimal(String),
+}
+
+impl From<U256> for InvoiceAmount {
+    fn from(units: U256) -> Self {
+        InvoiceAmount::Units(units)
+    }
+}
+
+impl From<Amount> for InvoiceAmount {
+    fn from(amount: Amount) -> Self {
+        InvoiceAmount::Units(amount.value)
+    }
+}
+
+impl From<&str> for InvoiceAmount {
+    fn from(decimal: &str) -> Self {
+        InvoiceAmount::Decimal(decimal.to_string())
+    }
+}
+
+impl From<String> for InvoiceAmount {
+    fn from(decimal: String) -> Self {
+        InvoiceAmount::Decimal(decimal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_strings_are_scaled_by_decimals() {
+        let amount = Amount::from_decimal_str("12.50", 6).unwrap();
+        assert_eq!(amount.value, U256::from(12_500_000u64));
+        assert_eq!(
+            Amount::from_decimal_str("3", 18).unwrap().value,
+            U256::from(3_000_000_000_000_000_000u128)
+        );
+        assert_eq!(Amount::from_decimal_str("0.000001", 6).unwrap().value, U256::from(1u64));
+    }
+
+    #[test]
+    fn malformed_or_too_precise_strings_are_rejected() {
+        for input in ["", ".5", "1.", "1.2.3", "-1", "1e6", "12,50", "0.0000001"] {
+            assert!(
+                matches!(
+                    Amount::from_decimal_str(input, 6),
+                    Err(GatewayError::InvalidAmount(_))
+                ),
+                "{input:?} must be rejected"
+            );
+        }
+        assert!(Amount::from_decimal_str(&"9".repeat(80), 0).is_err());
+    }
+
+    #[test]
+    fn decimal_string_round_trips() {
+        assert_eq!(Amount::new(U256::from(12_500_000u64), 6).to_decimal_string(), "12.5");
+        assert_eq!(Amount::new(U256::from(1u64), 6).to_decimal_string(), "0.000001");
+        assert_eq!(Amount::new(U256::from(42u64), 0).to_decimal_string(), "42");
+        assert_eq!(Amount::new(U256::ZERO, 18).to_string(), "0");
+        let parsed = Amount::from_decimal_str("1234.0567", 8).unwrap();
+        assert_eq!(parsed.to_decimal_string(), "1234.0567");
+    }
+}
@@ -0,0 +1,138 @@
+use ahash::AHashMap;
+use tokio::sync::RwLock;
+
+use crate::invoice::Invoice;
+
+/// ## ExpiredRetention
+///
+/// How long invoices that expired unpaid are kept in the expired archive,
+/// together with their wallet keys, so funds that arrive late can still be
+/// recovered. Invoices are dropped `max_age_seconds` after they expired, and
+/// the oldest ones first once more than `max_invoices` are archived.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpiredRetention {
+    pub max_age_seconds: u64,
+    pub max_invoices: usize,
+}
+
+impl Default for ExpiredRetention {
+    /// 30 days, at most 10 000 invoices.
+    fn default() -> Self {
+        Self {
+            max_age_seconds: 30 * 24 * 60 * 60,
+            max_invoices: 10_000,
+        }
+    }
+}
+
+/// Invoices that expired unpaid, by id.
+#[derive(Default)]
+pub(crate) struct ExpiredArchive {
+    invoices: RwLock<AHashMap<String, Invoice>>,
+}
+
+impl ExpiredArchive {
+    /// Archives an expired invoice and prunes what `retention` no longer keeps.
+    pub(crate) async fn insert(
+        &self,
+        key: &str,
+        invoice: Invoice,
+        retention: ExpiredRetention,
+        now: u64,
+    ) {
+        let mut invoices = self.invoices.write().await;
+        invoices.insert(key.to_string(), invoice);
+        invoices.retain(|_, invoice| {
+            invoice.expires.saturating_add(retention.max_age_seconds) >= now
+        });
+        while invoices.len() > retention.max_invoices {
+            let oldest = invoices
+                .iter()
+                .min_by_key(|(_, invoice)| invoice.expires)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => invoices.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    pub(crate) async fn get(&self, key: &str) -> Option<Invoice> {
+        self.invoices.read().await.get(key).cloned()
+    }
+
+    /// Removes the invoices that expired before `cutoff`, returning them.
+    pub(crate) async fn purge(&self, cutoff: u64) -> Vec<(String, Invoice)> {
+        let mut invoices = self.invoices.write().await;
+        let purged: Vec<String> = invoices
+            .iter()
+            .filter(|(_, invoice)| invoice.expires < cutoff)
+            .map(|(key, _)| key.clone())
+            .collect();
+        purged
+            .into_iter()
+            .filter_map(|key| invoices.remove(&key).map(|invoice| (key, invoice)))
+            .collect()
+    }
+
+    pub(crate) async fn all(&self) -> Vec<(String, Invoice)> {
+        self.invoices
+            .read()
+            .await
+            .iter()
+            .map(|(key, invoice)| (key.clone(), invoice.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expired_at(expires: u64) -> Invoice {
+        Invoice {
+            expires,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn invoices_past_max_age_are_pruned() {
+        let archive = ExpiredArchive::default();
+        let retention = ExpiredRetention {
+            max_age_seconds: 100,
+            max_invoices: 10,
+        };
+        archive.insert("old", expired_at(1_000), retention, 1_050).await;
+        archive.insert("new", expired_at(1_060), retention, 1_150).await;
+        assert!(archive.get("old").await.is_none());
+        assert!(archive.get("new").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn oldest_invoices_are_dropped_above_max_invoices() {
+        let archive = ExpiredArchive::default();
+        let retention = ExpiredRetention {
+            max_age_seconds: u64::MAX,
+            max_invoices: 2,
+        };
+        for (key, expires) in [("b", 20), ("a", 10), ("c", 30)] {
+            archive.insert(key, expired_at(expires), retention, 40).await;
+        }
+        let mut keys: Vec<_> = archive.all().await.into_iter().map(|(key, _)| key).collect();
+        keys.sort();
+        assert_eq!(keys, ["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn invoices_expired_before_cutoff_are_purged() {
+        let archive = ExpiredArchive::default();
+        let retention = ExpiredRetention::default();
+        for (key, expires) in [("a", 10), ("b", 20)] {
+            archive.insert(key, expired_at(expires), retention, 20).await;
+        }
+        let purged: Vec<_> = archive.purge(20).await.into_iter().map(|(key, _)| key).collect();
+        assert_eq!(purged, ["a"]);
+        assert!(archive.get("b").await.is_some());
+    }
+}
@@ -0,0 +1,279 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::invoice::{InvoiceError, InvoiceStatus};
+
+use super::{error::GatewayError, result::Result};
+
+/// Default size at which the audit file is rotated: 16 MiB.
+const DEFAULT_MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// One entry of the audit trail.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct AuditRecord {
+    pub timestamp_ms: u64,
+    pub invoice_id: String,
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+}
+
+/// What happened to an invoice. Serializes tagged by `type`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum AuditEntry {
+    /// The invoice was created with deposit address `to`.
+    Created {
+        to: Address,
+        amount: U256,
+        token: Option<Address>,
+    },
+    StatusChanged {
+        from: InvoiceStatus,
+        to: InvoiceStatus,
+    },
+    /// A treasury transfer was broadcast; `tx_hash` is the final transfer.
+    SweepBroadcast { tx_hash: String, nonce: u64 },
+    /// Funds of the expired invoice were sent back to `payer`.
+    Refunded {
+        payer: Address,
+        amount: U256,
+        tx_hash: String,
+    },
+    /// Checking or sweeping the invoice failed.
+    Error { error: InvoiceError },
+    /// The paid invoice was handed to the reflector.
+    Delivered { status: InvoiceStatus },
+    /// The invoice expired unpaid and was moved to the expired archive.
+    Expired,
+    Cancelled,
+    /// The invoice in `status` was dropped, wallet key included, once the
+    /// `retention` policy no longer kept it.
+    Purged { status: InvoiceStatus },
+}
+
+/// ## AuditLog
+///
+/// Append-only audit trail of invoice state transitions, sweeps and errors,
+/// written as one JSON [`AuditRecord`] per line to a single file.
+///
+/// Once the file reaches `max_file_bytes` it is renamed to the next numbered
+/// segment, `<path>.1`, `<path>.2` and so on, and a new file is started. An
+/// existing segment is never overwritten. All segments are kept unless
+/// `max_segments` is set, in which case the oldest ones beyond it are
+/// deleted on rotation. Failed writes are logged and never interrupt payment
+/// processing.
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    pub path: PathBuf,
+    pub max_file_bytes: u64,
+    pub max_segments: Option<usize>,
+    /// Serializes appends of all clones of this log
+    lock: Arc<Mutex<()>>,
+}
+
+impl AuditLog {
+    /// Audit log at `path`, rotated at 16 MiB and keeping every segment.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_segments: None,
+            lock: Arc::default(),
+        }
+    }
+
+    /// Path of the rotated segment `number`.
+    pub fn segment_path(&self, number: u64) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{number}"));
+        path.into()
+    }
+
+    /// Paths of the rotated segments, oldest first.
+    pub async fn segments(&self) -> Result<Vec<PathBuf>> {
+        let segments = self.numbered_segments().await?;
+        Ok(segments.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Appends a record for `invoice_id` made at `timestamp_ms`, rotating the
+    /// file first when it is full.
+    pub(crate) async fn append(
+        &self,
+        invoice_id: &str,
+        entry: AuditEntry,
+        timestamp_ms: u64,
+    ) -> Result<()> {
+        let record = AuditRecord {
+            timestamp_ms,
+            invoice_id: invoice_id.to_string(),
+            entry,
+        };
+        let mut line = serde_json::to_vec(&record).map_err(audit_error)?;
+        line.push(b'\n');
+
+        let _guard = self.lock.lock().await;
+        let full = tokio::fs::metadata(&self.path)
+            .await
+            .is_ok_and(|metadata| metadata.len() >= self.max_file_bytes);
+        if full {
+            self.rotate().await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(audit_error)?;
+        file.write_all(&line).await.map_err(audit_error)
+    }
+
+    /// Moves the full file to a segment numbered above all existing ones and
+    /// deletes the oldest segments beyond `max_segments`.
+    async fn rotate(&self) -> Result<()> {
+        let segments = self.numbered_segments().await?;
+        let next = segments.last().map_or(1, |(number, _)| number + 1);
+        let rotated = self.segment_path(next);
+        if tokio::fs::try_exists(&rotated).await.map_err(audit_error)? {
+            return Err(audit_error(format!("segment {} already exists", rotated.display())));
+        }
+        tokio::fs::rename(&self.path, &rotated).await.map_err(audit_error)?;
+
+        let Some(max_segments) = self.max_segments else {
+            return Ok(());
+        };
+        let excess = (segments.len() + 1).saturating_sub(max_segments);
+        for (_, path) in segments.iter().take(excess) {
+            tokio::fs::remove_file(path).await.map_err(audit_error)?;
+        }
+        Ok(())
+    }
+
+    /// The rotated segments with their numbers, oldest first.
+    async fn numbered_segments(&self) -> Result<Vec<(u64, PathBuf)>> {
+        let Some(name) = self.path.file_name() else {
+            return Ok(Vec::new());
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(audit_error(e)),
+        };
+        let mut segments = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(audit_error)? {
+            let file_name = entry.file_name();
+            let number = file_name
+                .to_str()
+                .and_then(|file_name| file_name.strip_prefix(&prefix))
+                .and_then(|number| number.parse::<u64>().ok());
+            if let Some(number) = number {
+                segments.push((number, self.segment_path(number)));
+            }
+        }
+        segments.sort_unstable();
+        Ok(segments)
+    }
+
+    /// Reads all records, oldest first, including the rotated segments.
+    pub async fn read(&self) -> Result<Vec<AuditRecord>> {
+        let mut records = Vec::new();
+        for segment in self.segments().await? {
+            records.extend(read_records(&segment).await?);
+        }
+        records.extend(read_records(&self.path).await?);
+        Ok(records)
+    }
+}
+
+async fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(audit_error(e)),
+    };
+    contents
+        .lines()
+        .map(|line| serde_json::from_str(line).map_err(audit_error))
+        .collect()
+}
+
+fn audit_error(e: impl std::fmt::Display) -> GatewayError {
+    GatewayError::Audit(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> AuditLog {
+        let id = alloy::primitives::B256::random();
+        let path = std::env::temp_dir().join(format!("acceptevm-audit-{name}-{id}"));
+        AuditLog::new(path)
+    }
+
+    #[tokio::test]
+    async fn records_are_appended_as_json_lines() {
+        let log = temp_log("append");
+        log.append("a", AuditEntry::Cancelled, 0).await.unwrap();
+        let status = AuditEntry::StatusChanged {
+            from: InvoiceStatus::Pending,
+            to: InvoiceStatus::Paid,
+        };
+        log.append("b", status.clone(), 0).await.unwrap();
+
+        let contents = std::fs::read_to_string(&log.path).unwrap();
+        assert!(contents.lines().next().unwrap().contains(r#""type":"Cancelled""#));
+        let records = log.read().await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].invoice_id, "b");
+        assert_eq!(records[1].entry, status);
+        std::fs::remove_file(&log.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn full_file_is_rotated_into_new_segments() {
+        let mut log = temp_log("rotate");
+        log.max_file_bytes = 1;
+        log.append("a", AuditEntry::Expired, 0).await.unwrap();
+        log.append("b", AuditEntry::Expired, 0).await.unwrap();
+        log.append("c", AuditEntry::Expired, 0).await.unwrap();
+
+        // Every rotation keeps the earlier segments
+        assert_eq!(log.segments().await.unwrap(), [log.segment_path(1), log.segment_path(2)]);
+        let ids: Vec<_> = log.read().await.unwrap().into_iter().map(|r| r.invoice_id).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        std::fs::remove_file(&log.path).unwrap();
+        for segment in log.segments().await.unwrap() {
+            std::fs::remove_file(segment).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn oldest_segments_beyond_the_limit_are_deleted() {
+        let mut log = temp_log("segments");
+        log.max_file_bytes = 1;
+        log.max_segments = Some(2);
+        for id in ["a", "b", "c", "d"] {
+            log.append(id, AuditEntry::Expired, 0).await.unwrap();
+        }
+
+        // Numbers keep increasing, so no segment is ever replaced
+        assert_eq!(log.segments().await.unwrap(), [log.segment_path(2), log.segment_path(3)]);
+        let ids: Vec<_> = log.read().await.unwrap().into_iter().map(|r| r.invoice_id).collect();
+        assert_eq!(ids, ["b", "c", "d"]);
+        std::fs::remove_file(&log.path).unwrap();
+        for segment in log.segments().await.unwrap() {
+            std::fs::remove_file(segment).unwrap();
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::invoice::Invoice;
+
+use super::{error::GatewayError, result::Result, PaymentGateway};
+
+/// Column holding the invoice id, ahead of the invoice fields.
+const ID_COLUMN: &str = "invoice_id";
+
+/// CSV backup and restore of the invoice database.
+///
+/// Every invoice field gets a column holding its value as JSON, including
+/// nested ones like the settlement. Strings are quoted, so a string such as
+/// `"12345"` is never read back as a number.
+impl PaymentGateway {
+    /// Writes all invoices to a CSV file at `path`, one row per invoice.
+    ///
+    /// The file contains the private keys of the invoice wallets; on Unix it
+    /// is created readable by the owner only.
+    pub async fn export_csv(&self, path: impl AsRef<Path>) -> Result<usize> {
+        write_csv(path.as_ref(), self.get_all_invoices().await?).await
+    }
+
+    /// Writes the expired archive to a CSV file at `path`, in the format of
+    /// [`PaymentGateway::export_csv`], to keep the wallet keys of expired
+    /// invoices beyond their `expired_retention`.
+    pub async fn export_expired_csv(&self, path: impl AsRef<Path>) -> Result<usize> {
+        write_csv(path.as_ref(), self.get_expired_invoices().await?).await
+    }
+
+    /// Reads invoices from a CSV file written by [`PaymentGateway::export_csv`]
+    /// and adds them to the gateway, replacing invoices with the same id.
+    /// Nothing is imported if any row is invalid.
+    pub async fn import_csv(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let imported = read_csv(path.as_ref()).await?;
+        let count = imported.len();
+        for (key, invoice) in &imported {
+            self.save_invoice(key, invoice).await?;
+        }
+        let mut invoices = self.invoices.write().await;
+        for (key, invoice) in imported {
+            if let Some(replaced) = invoices.remove(&key) {
+                self.unindex_invoice(&key, &replaced);
+            }
+            self.index_invoice(&key, &invoice);
+            invoices.insert(key, invoice);
+        }
+        Ok(count)
+    }
+}
+
+/// Reads the invoices of a CSV file in the format of [`PaymentGateway::export_csv`].
+pub(crate) async fn read_csv(path: &Path) -> Result<Vec<(String, Invoice)>> {
+    from_csv(&tokio::fs::read(path).await.map_err(csv_error)?)
+}
+
+/// Writes `invoices` sorted by id. On Unix the file is readable by the owner only.
+pub(crate) async fn write_csv(path: &Path, mut invoices: Vec<(String, Invoice)>) -> Result<usize> {
+    invoices.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let csv = to_csv(&invoices)?;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await.map_err(csv_error)?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, &csv)
+        .await
+        .map_err(csv_error)?;
+    Ok(invoices.len())
+}
+
+fn csv_error(e: impl std::fmt::Display) -> GatewayError {
+    GatewayError::Csv(e.to_string())
+}
+
+fn to_csv(invoices: &[(String, Invoice)]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let rows = invoices
+        .iter()
+        .map(|(id, invoice)| match serde_json::to_value(invoice).map_err(csv_error)? {
+            Value::Object(fields) => Ok((id, fields)),
+            _ => Err(csv_error("invoice is not a JSON object")),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let columns: Vec<String> = match rows.first() {
+        Some((_, fields)) => fields.keys().cloned().collect(),
+        None => serde_json::to_value(Invoice::default())
+            .ok()
+            .and_then(|invoice| invoice.as_object().map(|fields| fields.keys().cloned().collect()))
+            .unwrap_or_default(),
+    };
+    writer
+        .write_record(std::iter::once(ID_COLUMN).chain(columns.iter().map(String::as_str)))
+        .map_err(csv_error)?;
+    for (id, fields) in rows {
+        let cells = columns.iter().map(|column| fields[column].to_string());
+        writer
+            .write_record(std::iter::once(id.clone()).chain(cells))
+            .map_err(csv_error)?;
+    }
+    writer.into_inner().map_err(csv_error)
+}
+
+fn from_csv(csv: &[u8]) -> Result<Vec<(String, Invoice)>> {
+    let mut reader = csv::Reader::from_reader(csv);
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    if headers.get(0) != Some(ID_COLUMN) {
+        return Err(csv_error(format!("first column must be {ID_COLUMN}")));
+    }
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(csv_error)?;
+            let mut fields = Map::new();
+            for (column, cell) in headers.iter().zip(record.iter()).skip(1) {
+                // Files of earlier versions hold strings unquoted
+                let value = serde_json::from_str(cell)
+                    .unwrap_or_else(|_| Value::String(cell.to_string()));
+                fields.insert(column.to_string(), value);
+            }
+            let invoice = serde_json::from_value(Value::Object(fields)).map_err(csv_error)?;
+            Ok((record[0].to_string(), invoice))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invoice::{DepositRecord, InvoiceStatus, Settlement, StatusTransition, ZeroizedVec};
+    use alloy::primitives::{Address, B256, U256};
+
+    #[test]
+    fn csv_round_trips_every_field() {
+        let invoice = Invoice {
+            to: Address::repeat_byte(0xAB),
+            wallet: ZeroizedVec {
+                inner: vec![7u8; 32],
+            },
+            amount: U256::from(42u64),
+            message: b"order, \"quoted\"\nnext line".to_vec(),
+            hash: Some("0x1234".to_string()),
+            status: InvoiceStatus::Paid,
+            transitions: vec![StatusTransition {
+                from: InvoiceStatus::Pending,
+                to: InvoiceStatus::Paid,
+                at_ms: 1_700_000_000_000,
+            }],
+            settlement: Some(Settlement {
+                received_amount: U256::from(42u64),
+                ..Default::default()
+            }),
+            deposits: vec![DepositRecord {
+                tx_hash: B256::repeat_byte(1),
+                from: Address::repeat_byte(2),
+                amount: U256::from(42u64),
+                block_number: 9,
+            }],
+            ..Default::default()
+        };
+        let csv = to_csv(&[("id".to_string(), invoice.clone())]).unwrap();
+        let restored = from_csv(&csv).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].0, "id");
+        assert_eq!(
+            serde_json::to_value(&restored[0].1).unwrap(),
+            serde_json::to_value(&invoice).unwrap()
+        );
+    }
+
+    #[test]
+    fn strings_looking_like_json_stay_strings() {
+        let invoices: Vec<_> = ["12345", "true", "null", "[1]"]
+            .into_iter()
+            .map(|text| {
+                let invoice = Invoice {
+                    hash: Some(text.to_string()),
+                    external_id: Some(text.to_string()),
+                    ..Default::default()
+                };
+                (text.to_string(), invoice)
+            })
+            .collect();
+        let restored = from_csv(&to_csv(&invoices).unwrap()).unwrap();
+        for ((_, restored), (text, _)) in restored.iter().zip(&invoices) {
+            assert_eq!(restored.hash.as_deref(), Some(text.as_str()));
+            assert_eq!(restored.external_id.as_deref(), Some(text.as_str()));
+        }
+    }
+
+    #[test]
+    fn unquoted_strings_of_earlier_exports_are_read() {
+        let csv = to_csv(&[("id".to_string(), Invoice::default())]).unwrap();
+        let quoted = String::from_utf8(csv).unwrap();
+        let csv = quoted.replace("\"\"\"Pending\"\"\"", "Pending");
+        assert_ne!(csv, quoted);
+        let restored = from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(restored[0].1.status, InvoiceStatus::Pending);
+    }
+
+    #[test]
+    fn import_rejects_foreign_csv() {
+        assert!(matches!(
+            from_csv(b"name,amount\nfoo,1\n"),
+            Err(GatewayError::Csv(_))
+        ));
+    }
+}
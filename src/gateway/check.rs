@@ -0,0 +1,23 @@
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::invoice::InvoiceStatus;
+
+/// Result of an on-demand check with
+/// [`PaymentGateway::check_invoice_now`](super::PaymentGateway::check_invoice_now).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InvoiceCheck {
+    /// Status of the invoice when it was checked
+    pub status: InvoiceStatus,
+    /// Current balance of the invoice address in the invoice's asset
+    pub received: U256,
+    /// Amount requested; for multi-token invoices, the one of the asset paid
+    pub amount: U256,
+    /// Whether the payment was detected or `received` covers `amount`
+    pub paid: bool,
+    /// Blocks on top of the payment, or of the treasury transfer once it is
+    /// broadcast, while confirmations are awaited
+    pub confirmations: Option<u64>,
+    /// Confirmations required for the invoice
+    pub required_confirmations: u64,
+}
@@ -0,0 +1,198 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+
+/// ## CircuitBreaker
+///
+/// Backs the poller off RPC endpoints that keep failing instead of polling
+/// them at full rate. Every endpoint has its own circuit. After
+/// `failure_threshold` consecutive failed requests to an endpoint its circuit
+/// opens: requests skip it for `initial_backoff_seconds` and a
+/// `ProviderDegraded` event is raised. The poller then probes the endpoint
+/// with a single request; a failed probe doubles the backoff, up to
+/// `max_backoff_seconds`, and a successful one closes the circuit with a
+/// `ProviderRecovered` event. Polling only pauses while every endpoint is
+/// open.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub failure_threshold: u32,
+    pub initial_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for CircuitBreaker {
+    /// Opens after 5 failures in a row, backing off from 10 seconds up to 10 minutes.
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            initial_backoff_seconds: 10,
+            max_backoff_seconds: 600,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Seconds to pause after the circuit opened and `probes` probes failed.
+    pub fn backoff_seconds(&self, probes: u32) -> u64 {
+        self.initial_backoff_seconds
+            .saturating_mul(1u64 << probes.min(63))
+            .min(self.max_backoff_seconds)
+    }
+}
+
+/// Circuit state of every RPC endpoint, keyed by URL.
+#[derive(Default)]
+pub(crate) struct EndpointBreakers {
+    inner: Mutex<AHashMap<String, Breaker>>,
+}
+
+#[derive(Default)]
+struct Breaker {
+    /// Consecutive failed requests while closed
+    failures: u32,
+    /// Set while open: failed probes and when the next probe is due
+    open: Option<(u32, u64)>,
+}
+
+impl EndpointBreakers {
+    /// Records a failed request to `url` at `now`. Returns the seconds the
+    /// endpoint is skipped for when the failure opened its circuit.
+    pub(crate) fn failed(&self, url: &str, policy: &CircuitBreaker, now: u64) -> Option<u64> {
+        let mut breakers = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(url.to_string()).or_default();
+        if breaker.open.is_some() {
+            return None;
+        }
+        breaker.failures += 1;
+        if breaker.failures < policy.failure_threshold {
+            return None;
+        }
+        let backoff = policy.backoff_seconds(0);
+        breaker.open = Some((0, now.saturating_add(backoff)));
+        Some(backoff)
+    }
+
+    /// Records a successful request to `url`.
+    pub(crate) fn succeeded(&self, url: &str) {
+        let mut breakers = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(breaker) = breakers.get_mut(url) {
+            if breaker.open.is_none() {
+                breaker.failures = 0;
+            }
+        }
+    }
+
+    /// Records the outcome of a probe of `url` at `now`. Returns the seconds
+    /// until the next probe after a failed one.
+    pub(crate) fn probed(
+        &self,
+        url: &str,
+        policy: &CircuitBreaker,
+        now: u64,
+        healthy: bool,
+    ) -> Option<u64> {
+        let mut breakers = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if healthy {
+            breakers.remove(url);
+            return None;
+        }
+        let breaker = breakers.entry(url.to_string()).or_default();
+        let probes = breaker.open.map_or(0, |(probes, _)| probes) + 1;
+        let backoff = policy.backoff_seconds(probes);
+        breaker.open = Some((probes, now.saturating_add(backoff)));
+        Some(backoff)
+    }
+
+    /// Whether the circuit of `url` is open.
+    pub(crate) fn is_open(&self, url: &str) -> bool {
+        let breakers = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        breakers.get(url).is_some_and(|breaker| breaker.open.is_some())
+    }
+
+    /// The open endpoints due for a probe at `now`.
+    pub(crate) fn probes_due(&self, now: u64) -> Vec<String> {
+        let breakers = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        breakers
+            .iter()
+            .filter(|(_, breaker)| breaker.open.is_some_and(|(_, probe_at)| now >= probe_at))
+            .map(|(url, _)| url.clone())
+            .collect()
+    }
+
+    /// Seconds until the next probe when every one of `urls` is open, `None`
+    /// while any of them is closed.
+    pub(crate) fn all_open(&self, urls: &[String], now: u64) -> Option<u64> {
+        let breakers = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        urls.iter()
+            .map(|url| {
+                let (_, probe_at) = breakers.get(url)?.open?;
+                Some(probe_at.saturating_sub(now))
+            })
+            .try_fold(u64::MAX, |soonest, seconds| Some(soonest.min(seconds?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_per_failed_probe_up_to_the_cap() {
+        let breaker = CircuitBreaker::default();
+        assert_eq!(breaker.backoff_seconds(0), 10);
+        assert_eq!(breaker.backoff_seconds(1), 20);
+        assert_eq!(breaker.backoff_seconds(3), 80);
+        assert_eq!(breaker.backoff_seconds(10), 600);
+        assert_eq!(breaker.backoff_seconds(200), 600);
+    }
+
+    const POLICY: CircuitBreaker = CircuitBreaker {
+        failure_threshold: 2,
+        initial_backoff_seconds: 10,
+        max_backoff_seconds: 600,
+    };
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let breakers = EndpointBreakers::default();
+        assert_eq!(breakers.failed("a", &POLICY, 100), None);
+        breakers.succeeded("a");
+        assert_eq!(breakers.failed("a", &POLICY, 100), None);
+        assert!(!breakers.is_open("a"));
+        assert_eq!(breakers.failed("a", &POLICY, 100), Some(10));
+        assert!(breakers.is_open("a"));
+        assert!(breakers.probes_due(104).is_empty());
+        // Requests still in flight do not reopen it
+        assert_eq!(breakers.failed("a", &POLICY, 105), None);
+        assert_eq!(breakers.probes_due(110), ["a"]);
+    }
+
+    #[test]
+    fn failed_probes_back_off_and_a_healthy_one_closes() {
+        let breakers = EndpointBreakers::default();
+        breakers.failed("a", &POLICY, 0);
+        breakers.failed("a", &POLICY, 0);
+        assert_eq!(breakers.probed("a", &POLICY, 10, false), Some(20));
+        assert!(breakers.probes_due(29).is_empty());
+        assert_eq!(breakers.probed("a", &POLICY, 30, false), Some(40));
+        assert_eq!(breakers.probed("a", &POLICY, 70, true), None);
+        assert!(!breakers.is_open("a"));
+        assert_eq!(breakers.failed("a", &POLICY, 70), None);
+    }
+
+    #[test]
+    fn endpoints_open_independently() {
+        let breakers = EndpointBreakers::default();
+        let urls = ["a".to_string(), "b".to_string()];
+        breakers.failed("a", &POLICY, 0);
+        breakers.failed("a", &POLICY, 0);
+        assert!(breakers.is_open("a"));
+        assert!(!breakers.is_open("b"));
+        assert_eq!(breakers.all_open(&urls, 4), None);
+
+        breakers.failed("b", &POLICY, 5);
+        breakers.failed("b", &POLICY, 5);
+        // Paused until the first endpoint is due for its probe
+        assert_eq!(breakers.all_open(&urls, 4), Some(6));
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time for invoice creation and expiry, the poller's
+/// schedules (check intervals, sweep policies, retry backoff, notification
+/// throttles and stuck sweep detection), and the timestamps of status
+/// transitions, errors, audit and journal records.
+///
+/// Configure a [`MockClock`] as `clock` to test time-based behavior without
+/// waiting, and the same clock on a [`ChainlinkOracle`](super::pricing::ChainlinkOracle)
+/// judging the age of its prices.
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn unix_time_millis(&self) -> u64;
+
+    /// Seconds since the Unix epoch.
+    fn unix_time_seconds(&self) -> u64 {
+        self.unix_time_millis() / 1000
+    }
+}
+
+/// The default clock, reading the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn unix_time_millis(&self) -> u64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as u64,
+            Err(_) => 0,
+        }
+    }
+}
+
+/// Clock that only moves when told to. Clones share the same time, so tests
+/// keep one to advance the clock of the gateway they configured it on.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    millis: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    /// Clock stopped at `unix_seconds`.
+    pub fn new(unix_seconds: u64) -> Self {
+        Self {
+            millis: Arc::new(AtomicU64::new(unix_seconds.saturating_mul(1000))),
+        }
+    }
+
+    /// Moves the clock to `unix_seconds`.
+    pub fn set(&self, unix_seconds: u64) {
+        self.millis.store(unix_seconds.saturating_mul(1000), Ordering::SeqCst);
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.millis.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn unix_time_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_clock_is_reasonable() {
+        let t = SystemClock.unix_time_seconds();
+        // Must be after 2024-01-01 (unix 1704067200) and before year 2100
+        assert!(t > 1_704_067_200, "time must be after 2024-01-01");
+        assert!(t < 4_102_444_800, "time must be before 2100-01-01");
+    }
+
+    #[test]
+    fn mock_clock_moves_only_when_told() {
+        let clock = MockClock::new(1_000);
+        let shared = clock.clone();
+        assert_eq!(clock.unix_time_seconds(), 1_000);
+        shared.advance(Duration::from_millis(1_500));
+        assert_eq!(clock.unix_time_millis(), 1_001_500);
+        assert_eq!(clock.unix_time_seconds(), 1_001);
+        shared.set(50);
+        assert_eq!(clock.unix_time_seconds(), 50);
+    }
+}
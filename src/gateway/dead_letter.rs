@@ -0,0 +1,126 @@
+use tokio::sync::Mutex;
+
+use crate::invoice::Invoice;
+
+use super::error::GatewayError;
+use super::store::InvoiceStore;
+
+/// Paid invoices the reflector failed to deliver, oldest first, see
+/// [`PaymentGateway::drain_dead_letters`](super::PaymentGateway::drain_dead_letters).
+#[derive(Default)]
+pub(crate) struct DeadLetterQueue {
+    /// `None` until the `dead_letter_store` was read
+    letters: Mutex<Option<Vec<(String, Invoice)>>>,
+}
+
+impl DeadLetterQueue {
+    /// Queues an undelivered invoice and saves it to `store`.
+    pub(crate) async fn push(
+        &self,
+        store: Option<&dyn InvoiceStore>,
+        key: &str,
+        invoice: Invoice,
+    ) -> Result<(), GatewayError> {
+        let mut letters = self.letters.lock().await;
+        // Saved first, so the invoice is persisted even if the queue cannot
+        // be read, and kept in memory even if saving failed
+        let saved = match store {
+            Some(store) => store.save(key.to_string(), invoice.clone()).await,
+            None => Ok(()),
+        };
+        let queued = loaded(&mut letters, store).await?;
+        queued.retain(|(queued, _)| queued != key);
+        queued.push((key.to_string(), invoice));
+        saved
+    }
+
+    /// Takes every queued invoice and removes them from `store`. When the
+    /// store fails they stay queued.
+    pub(crate) async fn drain(
+        &self,
+        store: Option<&dyn InvoiceStore>,
+    ) -> Result<Vec<(String, Invoice)>, GatewayError> {
+        let mut letters = self.letters.lock().await;
+        let queued = loaded(&mut letters, store).await?;
+        if let Some(store) = store {
+            for (key, _) in queued.iter() {
+                store.remove(key.clone()).await?;
+            }
+        }
+        Ok(std::mem::take(queued))
+    }
+
+    /// Every queued invoice, leaving them queued.
+    pub(crate) async fn snapshot(
+        &self,
+        store: Option<&dyn InvoiceStore>,
+    ) -> Result<Vec<(String, Invoice)>, GatewayError> {
+        let mut letters = self.letters.lock().await;
+        Ok(loaded(&mut letters, store).await?.clone())
+    }
+
+    /// Removes a delivered invoice from the queue and `store`.
+    pub(crate) async fn remove(
+        &self,
+        store: Option<&dyn InvoiceStore>,
+        key: &str,
+    ) -> Result<(), GatewayError> {
+        let mut letters = self.letters.lock().await;
+        if let Some(store) = store {
+            store.remove(key.to_string()).await?;
+        }
+        loaded(&mut letters, store).await?.retain(|(queued, _)| queued != key);
+        Ok(())
+    }
+}
+
+/// The queue, read from `store` on first use.
+async fn loaded<'a>(
+    letters: &'a mut Option<Vec<(String, Invoice)>>,
+    store: Option<&dyn InvoiceStore>,
+) -> Result<&'a mut Vec<(String, Invoice)>, GatewayError> {
+    if letters.is_none() {
+        let mut saved = match store {
+            Some(store) => store.load().await?,
+            None => Vec::new(),
+        };
+        saved.sort_by_key(|(_, invoice)| invoice.paid_at_timestamp);
+        *letters = Some(saved);
+    }
+    Ok(letters.get_or_insert_with(Vec::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::store::CsvInvoiceStore;
+
+    fn paid(paid_at_timestamp: u64) -> Invoice {
+        Invoice {
+            paid_at_timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn queue_survives_restarts_until_drained() {
+        let path = std::env::temp_dir().join(format!("acceptevm-dlq-{}.csv", std::process::id()));
+        let store = CsvInvoiceStore::new(&path);
+        let queue = DeadLetterQueue::default();
+        queue.push(Some(&store), "b", paid(2)).await.unwrap();
+        queue.push(Some(&store), "a", paid(1)).await.unwrap();
+        queue.push(Some(&store), "b", paid(2)).await.unwrap();
+        assert_eq!(queue.snapshot(Some(&store)).await.unwrap().len(), 2);
+
+        // A new process reads the queue back, oldest payment first
+        let restarted = DeadLetterQueue::default();
+        let drained = restarted.drain(Some(&store)).await.unwrap();
+        let keys: Vec<_> = drained.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["a", "b"]);
+        restarted.push(Some(&store), "c", paid(3)).await.unwrap();
+        restarted.remove(Some(&store), "c").await.unwrap();
+        assert!(restarted.drain(Some(&store)).await.unwrap().is_empty());
+        assert!(store.load().await.unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
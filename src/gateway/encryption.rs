@@ -0,0 +1,130 @@
+use std::fmt;
+
+use alloy::primitives::{Address, B256};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use zeroize::ZeroizeOnDrop;
+
+use crate::invoice::ZeroizedVec;
+
+use super::error::GatewayError;
+
+/// PBKDF2-HMAC-SHA256 rounds used to derive a key from a passphrase.
+const PASSPHRASE_ROUNDS: u32 = 600_000;
+/// Length of the random nonce stored in front of every ciphertext.
+const NONCE_LEN: usize = 12;
+
+/// ## WalletEncryption
+///
+/// Encrypts invoice wallet keys at rest with ChaCha20-Poly1305. When set in
+/// the configuration, `Invoice.wallet` holds the encrypted key, bound to the
+/// invoice address, and is only decrypted transiently to sign sweeps.
+///
+/// Keep the key or passphrase: without it the invoice wallets of stored or
+/// exported invoices cannot be recovered.
+#[derive(Clone, ZeroizeOnDrop)]
+pub struct WalletEncryption {
+    key: [u8; 32],
+}
+
+impl WalletEncryption {
+    /// Uses a 32 byte key, e.g. from a secrets manager.
+    pub fn from_key(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Derives the key from `passphrase` and `salt` with PBKDF2-HMAC-SHA256.
+    /// The same passphrase and salt must be used to decrypt.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = [0u8; 32];
+        let password = passphrase.as_bytes();
+        pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password, salt, PASSPHRASE_ROUNDS, &mut key);
+        Self { key }
+    }
+
+    /// Encrypts the wallet key of the invoice at `address`. The output is the
+    /// random nonce followed by the ciphertext.
+    pub(crate) fn encrypt(
+        &self,
+        address: Address,
+        wallet: &[u8],
+    ) -> Result<ZeroizedVec, GatewayError> {
+        let nonce = B256::random();
+        let nonce = Nonce::from_slice(&nonce[..NONCE_LEN]);
+        let payload = Payload {
+            msg: wallet,
+            aad: address.as_slice(),
+        };
+        let ciphertext = self
+            .cipher()
+            .encrypt(nonce, payload)
+            .map_err(|e| GatewayError::WalletEncryption(e.to_string()))?;
+        Ok(ZeroizedVec {
+            inner: [nonce.as_slice(), &ciphertext].concat(),
+        })
+    }
+
+    /// Decrypts the wallet key of the invoice at `address`.
+    pub(crate) fn decrypt(
+        &self,
+        address: Address,
+        encrypted: &[u8],
+    ) -> Result<ZeroizedVec, GatewayError> {
+        if encrypted.len() < NONCE_LEN {
+            return Err(GatewayError::WalletEncryption("ciphertext too short".to_string()));
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: address.as_slice(),
+        };
+        let inner = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                GatewayError::WalletEncryption("wrong key or tampered wallet".to_string())
+            })?;
+        Ok(ZeroizedVec { inner })
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&self.key))
+    }
+}
+
+impl fmt::Debug for WalletEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WalletEncryption { .. }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wallet_round_trips_for_its_address() {
+        let encryption = WalletEncryption::from_key([7u8; 32]);
+        let address = Address::repeat_byte(0xAB);
+        let encrypted = encryption.encrypt(address, &[1u8; 32]).unwrap();
+        assert_ne!(encrypted.inner[NONCE_LEN..], [1u8; 32]);
+        assert_eq!(encryption.decrypt(address, &encrypted).unwrap().inner, vec![1u8; 32]);
+    }
+
+    #[test]
+    fn wrong_key_or_address_fails() {
+        let encryption = WalletEncryption::from_passphrase("correct horse", b"salt");
+        let address = Address::repeat_byte(0xAB);
+        let encrypted = encryption.encrypt(address, &[1u8; 32]).unwrap();
+        let other = WalletEncryption::from_passphrase("battery staple", b"salt");
+        assert!(other.decrypt(address, &encrypted).is_err());
+        assert!(encryption.decrypt(Address::repeat_byte(0xCD), &encrypted).is_err());
+        assert!(encryption.decrypt(address, &encrypted[..4]).is_err());
+    }
+
+    #[test]
+    fn debug_hides_the_key() {
+        let encryption = WalletEncryption::from_key([7u8; 32]);
+        assert_eq!(format!("{encryption:?}"), "WalletEncryption { .. }");
+    }
+}
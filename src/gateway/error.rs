@@ -6,4 +6,58 @@ pub enum GatewayError {
     NotFound,
     #[error("No RPC URLs provided")]
     NoRpcUrls,
+    #[error("Address label registration failed: {0}")]
+    LabelRegistration(String),
+    #[error("Failed to deliver paid invoice: {0}")]
+    Reflector(String),
+    #[error("Failed to derive invoice wallet: {0}")]
+    WalletDerivation(String),
+    #[error("Wallet encryption failed: {0}")]
+    WalletEncryption(String),
+    #[error("Invalid RPC URL: {0}")]
+    InvalidRpcUrl(String),
+    #[error("RPC request failed: {0}")]
+    Rpc(String),
+    #[error("Invalid amount {0}")]
+    InvalidAmount(String),
+    #[error("Invalid invoice request: {0}")]
+    InvalidInvoiceRequest(String),
+    #[error("All unique amounts for this invoice amount are taken by open invoices")]
+    UniqueAmountsExhausted,
+    #[error("Invalid invoice metadata: {0}")]
+    Metadata(String),
+    #[error("Invoice CSV export or import failed: {0}")]
+    Csv(String),
+    #[error("Audit log failed: {0}")]
+    Audit(String),
+    #[error("Event journal failed: {0}")]
+    Journal(String),
+    #[error("Pricing failed: {0}")]
+    Pricing(String),
+    #[error("Fee estimation failed: {0}")]
+    FeeEstimation(String),
+    #[error("Event signing failed: {0}")]
+    EventSigning(String),
+    #[error("Payment receipt signing failed: {0}")]
+    ReceiptSigning(String),
+    #[error("Invoice store failed: {0}")]
+    Store(String),
+    #[error("Chain id mismatch: expected {expected}, RPC reported {actual}")]
+    ChainMismatch { expected: u64, actual: u64 },
+    #[error("Invalid invoice wallet: {0}")]
+    InvalidWallet(String),
+    #[error("Failed to sign transaction: {0}")]
+    Signing(String),
+    #[error("Treasury splits add up to {0} basis points, more than 10000")]
+    InvalidTreasurySplits(u32),
+    #[error("RPC rate limit must allow at least one request per second and a burst of one")]
+    InvalidRateLimit,
+    #[error("A treasury transfer is already in flight")]
+    SweepInFlight,
+    #[error("Treasury transfer failed: {0}")]
+    Sweep(String),
+    #[error("Shutdown timed out with sweeps still in flight")]
+    ShutdownTimeout,
+    #[error("Not supported: {0}")]
+    Unsupported(&'static str),
 }
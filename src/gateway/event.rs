@@ -0,0 +1,182 @@
+use alloy::primitives::{Address, ChainId, U256};
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::invoice::{InvoiceError, InvoiceStatus};
+
+use super::audit::AuditEntry;
+
+/// ## GatewayEvent
+///
+/// Operational events raised by the gateway, delivered to every receiver
+/// obtained from [`PaymentGateway::subscribe_events`](super::PaymentGateway::subscribe_events)
+/// and every stream of [`PaymentGateway::events`](super::PaymentGateway::events).
+///
+/// Serializes to JSON tagged by `type`, e.g.
+/// `{"type":"ProviderSwitched","from":"...","to":"..."}`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum GatewayEvent {
+    /// Critical: the RPC reported a different chain id than the one cached at
+    /// startup. The sweep for `invoice_id` was aborted before signing.
+    ChainIdMismatch {
+        invoice_id: String,
+        expected: ChainId,
+        actual: ChainId,
+    },
+    /// A paid invoice settled with `status`, `Swept` or `Paid` in detect-only
+    /// mode, and was handed to the reflector. It is kept in the paid history,
+    /// see [`PaymentGateway::get_paid_invoices`](super::PaymentGateway::get_paid_invoices).
+    InvoicePaid {
+        invoice_id: String,
+        status: InvoiceStatus,
+    },
+    /// An invoice received funds below the requested amount. Throttled per
+    /// invoice by `partial_payment_throttle_seconds`; the paid invoice itself is
+    /// always delivered through the reflector.
+    PartialPayment {
+        invoice_id: String,
+        received: U256,
+        amount: U256,
+    },
+    /// An unpaid invoice holds `amount`, less than the configured dust
+    /// threshold of its asset. The dust is ignored by payment detection.
+    /// Throttled per invoice like `PartialPayment`.
+    DustReceived { invoice_id: String, amount: U256 },
+    /// An endpoint of the `sweep_quorum` did not confirm the payment of a
+    /// high-value invoice, which waits for the next poll cycle instead of
+    /// being swept. Throttled per invoice like `PartialPayment`.
+    QuorumDisagreement { invoice_id: String, rpc_url: String },
+    /// An unpaid invoice reached `percent` of its expiry window, as configured
+    /// by `expiry_reminders`. Each threshold is reported at most once.
+    ExpiryReminder {
+        invoice_id: String,
+        percent: u8,
+        expires: u64,
+    },
+    /// Funds found on an unpaid invoice when it expired were sent back to
+    /// `payer`, as configured by `refund_expired_payments`. `amount` is what
+    /// the payer receives after gas; `tx_hash` is the broadcast refund.
+    Refunded {
+        invoice_id: String,
+        payer: Address,
+        amount: U256,
+        tx_hash: String,
+    },
+    /// A paid invoice is `confirmations` blocks deep, out of the `required`.
+    /// Raised once per change while its payment, or its treasury transfer
+    /// afterwards, waits for confirmations.
+    Confirming {
+        invoice_id: String,
+        confirmations: u64,
+        required: u64,
+    },
+    /// The estimated gas cost `fee` of sweeping an invoice exceeds the
+    /// configured `max_sweep_fee_bps` of its `amount`. The sweep is deferred
+    /// until fees drop; raised once each time an invoice starts waiting.
+    SweepDeferred {
+        invoice_id: String,
+        fee: U256,
+        amount: U256,
+    },
+    /// Sweeping a paid invoice failed `attempts` times in a row, exhausting the
+    /// configured `sweep_retry` policy. The funds remain on the invoice wallet.
+    SweepFailed {
+        invoice_id: String,
+        attempts: u32,
+        error: InvoiceError,
+    },
+    /// The RPC URL `from` failed too often in a row and requests now go to
+    /// `to`. Only raised with `RpcSelection::Failover`.
+    ProviderSwitched { from: String, to: String },
+    /// Requests to `rpc_url` failed `failures` times in a row and the
+    /// `circuit_breaker` skips it for `retry_in_seconds`, after which it is
+    /// probed.
+    ProviderDegraded {
+        rpc_url: String,
+        failures: u32,
+        retry_in_seconds: u64,
+    },
+    /// A probe of `rpc_url` succeeded after a `ProviderDegraded` event and
+    /// requests go to it again.
+    ProviderRecovered { rpc_url: String },
+}
+
+/// ## InvoiceUpdate
+///
+/// Progress of a single invoice, yielded by
+/// [`PaymentGateway::subscribe`](super::PaymentGateway::subscribe), e.g. to
+/// drive the progress bar of a checkout page. Serializes to JSON tagged by
+/// `type` like [`GatewayEvent`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum InvoiceUpdate {
+    /// The invoice moved to `status`; `Paid` once the payment was detected.
+    Status { status: InvoiceStatus },
+    /// The payment, or the treasury transfer afterwards, is `confirmations`
+    /// blocks deep, out of the `required`.
+    Confirmations { confirmations: u64, required: u64 },
+    /// The invoice was delivered on the reflector and left the gateway.
+    Delivered,
+    Cancelled,
+}
+
+impl InvoiceUpdate {
+    /// The update reported for an audited change of an invoice.
+    pub(crate) fn of(entry: &AuditEntry) -> Option<InvoiceUpdate> {
+        match entry {
+            AuditEntry::StatusChanged { to, .. } => Some(InvoiceUpdate::Status { status: *to }),
+            AuditEntry::Expired => Some(InvoiceUpdate::Status {
+                status: InvoiceStatus::Expired,
+            }),
+            AuditEntry::Delivered { .. } => Some(InvoiceUpdate::Delivered),
+            AuditEntry::Cancelled => Some(InvoiceUpdate::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Whether no updates follow this one.
+    pub fn is_last(&self) -> bool {
+        match self {
+            InvoiceUpdate::Status { status } => *status == InvoiceStatus::Expired,
+            InvoiceUpdate::Confirmations { .. } => false,
+            InvoiceUpdate::Delivered | InvoiceUpdate::Cancelled => true,
+        }
+    }
+}
+
+/// Every message of `receiver`, skipping those a slow subscriber missed.
+pub(crate) fn receive<T: Clone + Send + 'static>(
+    receiver: broadcast::Receiver<T>,
+) -> impl Stream<Item = T> + Send + 'static {
+    stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(item) => return Some((item, receiver)),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Event subscriber lagged, {missed} events dropped");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_serialize_tagged_by_type() {
+        let event = GatewayEvent::PartialPayment {
+            invoice_id: "id".to_string(),
+            received: U256::from(1u64),
+            amount: U256::from(2u64),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "PartialPayment");
+        assert_eq!(json["invoice_id"], "id");
+        assert_eq!(serde_json::from_value::<GatewayEvent>(json).unwrap(), event);
+    }
+}
@@ -0,0 +1,144 @@
+use alloy::primitives::{Address, Signature};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::error::GatewayError;
+
+/// Header carrying the [`EventSigningKey`] signature of a webhook body.
+pub const EVENT_SIGNATURE_HEADER: &str = "X-AcceptEVM-Event-Signature";
+
+/// Key signing events that leave the process, configured as
+/// `event_signing_key`.
+///
+/// - `Hmac`: hex encoded HMAC-SHA256 keyed by a secret shared with consumers.
+/// - `Eip191`: `0x`-prefixed 65 byte EIP-191 personal message signature.
+///   Consumers only need the signer's address to verify it.
+#[derive(Clone, Debug)]
+pub enum EventSigningKey {
+    Hmac(String),
+    Eip191(PrivateKeySigner),
+}
+
+impl EventSigningKey {
+    /// Signs the bytes of a serialized event.
+    pub fn sign_payload(&self, payload: &[u8]) -> Result<String, GatewayError> {
+        match self {
+            EventSigningKey::Hmac(secret) => {
+                Ok(hex::encode(hmac(secret, payload).finalize().into_bytes()))
+            }
+            EventSigningKey::Eip191(signer) => signer
+                .sign_message_sync(payload)
+                .map(|signature| signature.to_string())
+                .map_err(|e| GatewayError::EventSigning(e.to_string())),
+        }
+    }
+
+    /// Serializes `event` to JSON and signs it.
+    pub fn sign<T: Serialize>(&self, event: &T) -> Result<SignedEvent, GatewayError> {
+        let payload =
+            serde_json::to_string(event).map_err(|e| GatewayError::EventSigning(e.to_string()))?;
+        let signature = self.sign_payload(payload.as_bytes())?;
+        Ok(SignedEvent { payload, signature })
+    }
+
+    /// The verifier consumers of this key's events use.
+    pub fn verifier(&self) -> EventVerifier {
+        match self {
+            EventSigningKey::Hmac(secret) => EventVerifier::Hmac(secret.clone()),
+            EventSigningKey::Eip191(signer) => EventVerifier::Eip191(signer.address()),
+        }
+    }
+}
+
+/// Checks signatures made by an [`EventSigningKey`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventVerifier {
+    /// The shared HMAC secret
+    Hmac(String),
+    /// The address of the EIP-191 signing key
+    Eip191(Address),
+}
+
+impl EventVerifier {
+    /// Whether `signature` was made over `payload` by the key of this
+    /// verifier. HMAC signatures are compared in constant time.
+    pub fn verify_payload(&self, payload: &[u8], signature: &str) -> bool {
+        match self {
+            EventVerifier::Hmac(secret) => hex::decode(signature)
+                .is_ok_and(|signature| hmac(secret, payload).verify_slice(&signature).is_ok()),
+            EventVerifier::Eip191(address) => signature
+                .parse::<Signature>()
+                .and_then(|signature| signature.recover_address_from_msg(payload))
+                .is_ok_and(|signer| signer == *address),
+        }
+    }
+
+    /// The event of `signed` when its signature is valid.
+    pub fn verify<T: DeserializeOwned>(&self, signed: &SignedEvent) -> Result<T, GatewayError> {
+        if !self.verify_payload(signed.payload.as_bytes(), &signed.signature) {
+            return Err(GatewayError::EventSigning("invalid event signature".to_string()));
+        }
+        serde_json::from_str(&signed.payload).map_err(|e| GatewayError::EventSigning(e.to_string()))
+    }
+}
+
+/// An event serialized to JSON together with the signature of exactly those
+/// bytes, so consumers verify it without re-serializing.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SignedEvent {
+    pub payload: String,
+    pub signature: String,
+}
+
+pub(crate) fn hmac(secret: &str, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::event::GatewayEvent;
+
+    fn event() -> GatewayEvent {
+        GatewayEvent::ProviderSwitched {
+            from: "http://a".to_string(),
+            to: "http://b".to_string(),
+        }
+    }
+
+    #[test]
+    fn hmac_signatures_match_webhook_signatures() {
+        let key = EventSigningKey::Hmac("key".to_string());
+        let payload = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(
+            key.sign_payload(payload).unwrap(),
+            crate::gateway::webhook_signature("key", payload)
+        );
+    }
+
+    #[test]
+    fn signed_events_verify_with_the_matching_key_only() {
+        let keys = [
+            EventSigningKey::Hmac("secret".to_string()),
+            EventSigningKey::Eip191(PrivateKeySigner::random()),
+        ];
+        for key in keys {
+            let signed = key.sign(&event()).unwrap();
+            assert_eq!(key.verifier().verify::<GatewayEvent>(&signed).unwrap(), event());
+
+            let mut tampered = signed.clone();
+            tampered.payload = tampered.payload.replace("http://b", "http://c");
+            assert!(key.verifier().verify::<GatewayEvent>(&tampered).is_err());
+        }
+        let signed = EventSigningKey::Eip191(PrivateKeySigner::random()).sign(&event()).unwrap();
+        let other = EventVerifier::Eip191(PrivateKeySigner::random().address());
+        assert!(!other.verify_payload(signed.payload.as_bytes(), &signed.signature));
+        assert!(!EventVerifier::Hmac("secret".to_string()).verify_payload(b"{}", "not hex"));
+    }
+}
@@ -0,0 +1,169 @@
+use std::{future::Future, pin::Pin};
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::{DynProvider, Provider};
+
+use super::error::GatewayError;
+
+/// Boxed future returned by [`FeeEstimator::estimate`].
+pub type FeeFuture = Pin<Box<dyn Future<Output = Result<NetworkFees, GatewayError>> + Send>>;
+
+/// ## TransactionType
+///
+/// Which kind of transaction sweeps are sent as.
+///
+/// - `Auto`: EIP-1559 when the latest block has a base fee, legacy otherwise.
+///   When the configured [`FeeEstimator`] fails, e.g. on chains where
+///   `eth_maxPriorityFeePerGas` errors, the legacy gas price is used instead.
+///   The default.
+/// - `Eip1559`: always asks the [`FeeEstimator`]; sweeps fail if it does.
+/// - `Legacy`: always sends legacy transactions at `eth_gasPrice`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransactionType {
+    #[default]
+    Auto,
+    Eip1559,
+    Legacy,
+}
+
+/// Fees a sweep is sent with: EIP-1559 fees, or the legacy gas price on
+/// networks without EIP-1559 support.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetworkFees {
+    Eip1559 {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+    },
+    Legacy {
+        gas_price: u128,
+    },
+}
+
+/// Estimates the fees of sweeps.
+///
+/// Configure one as `fee_estimator` for chains that need their own fee
+/// logic, e.g. a gas station API. The estimator gets a provider connected to
+/// the RPC the sweep is sent through. It is only asked on chains sweeping
+/// with EIP-1559, see [`TransactionType`].
+pub trait FeeEstimator: Send + Sync {
+    fn estimate(&self, provider: DynProvider) -> FeeFuture;
+}
+
+/// The default estimator: alloy's built-in EIP-1559 estimator, which
+/// averages the priority fees of recent blocks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProviderFeeEstimator;
+
+impl FeeEstimator for ProviderFeeEstimator {
+    fn estimate(&self, provider: DynProvider) -> FeeFuture {
+        Box::pin(async move {
+            let fees = provider
+                .estimate_eip1559_fees()
+                .await
+                .map_err(|e| GatewayError::Rpc(e.to_string()))?;
+            Ok(NetworkFees::Eip1559 {
+                max_fee_per_gas: fees.max_fee_per_gas,
+                max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+            })
+        })
+    }
+}
+
+/// Reads `eth_feeHistory` over the last `blocks` blocks. The priority fee is
+/// the median of the `reward_percentile` rewards of all non-empty blocks,
+/// falling back to `eth_maxPriorityFeePerGas` when every block was empty. The
+/// max fee is twice the next block's base fee plus the priority fee.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeHistoryEstimator {
+    pub blocks: u64,
+    pub reward_percentile: f64,
+}
+
+impl FeeEstimator for FeeHistoryEstimator {
+    fn estimate(&self, provider: DynProvider) -> FeeFuture {
+        let Self {
+            blocks,
+            reward_percentile,
+        } = *self;
+        Box::pin(async move {
+            let rpc = |e: alloy::transports::TransportError| GatewayError::Rpc(e.to_string());
+            let history = provider
+                .get_fee_history(blocks.max(1), BlockNumberOrTag::Latest, &[reward_percentile])
+                .await
+                .map_err(rpc)?;
+            let base_fee = history
+                .next_block_base_fee()
+                .filter(|base_fee| *base_fee > 0)
+                .ok_or_else(|| {
+                    GatewayError::FeeEstimation("no base fee in fee history".to_string())
+                })?;
+            let rewards: Vec<u128> = history
+                .reward
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|block| block.first().copied())
+                .collect();
+            let priority_fee = match median_reward(rewards) {
+                Some(reward) => reward,
+                None => provider.get_max_priority_fee_per_gas().await.map_err(rpc)?,
+            };
+            Ok(NetworkFees::Eip1559 {
+                max_fee_per_gas: base_fee.saturating_mul(2).saturating_add(priority_fee),
+                max_priority_fee_per_gas: priority_fee,
+            })
+        })
+    }
+}
+
+/// Always sends legacy transactions at `eth_gasPrice`, for chains such as
+/// BSC where EIP-1559 fees are not worth estimating.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LegacyFeeEstimator;
+
+impl FeeEstimator for LegacyFeeEstimator {
+    fn estimate(&self, provider: DynProvider) -> FeeFuture {
+        Box::pin(async move {
+            let gas_price =
+                provider.get_gas_price().await.map_err(|e| GatewayError::Rpc(e.to_string()))?;
+            Ok(NetworkFees::Legacy { gas_price })
+        })
+    }
+}
+
+/// Median of the non-zero block rewards; empty blocks report zero.
+fn median_reward(mut rewards: Vec<u128>) -> Option<u128> {
+    rewards.retain(|reward| *reward > 0);
+    rewards.sort_unstable();
+    rewards.get(rewards.len() / 2).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{mock_node::MockNode, test_chain::TestChain};
+    use alloy::providers::ProviderBuilder;
+
+    #[test]
+    fn median_skips_empty_blocks_and_outliers() {
+        assert_eq!(median_reward(vec![0, 3, 1_000, 2, 0]), Some(3));
+        assert_eq!(median_reward(vec![0, 0]), None);
+    }
+
+    #[tokio::test]
+    async fn fee_history_estimates_from_base_fee_and_rewards() {
+        let chain = TestChain::ethereum();
+        let node = MockNode::start_with_chain(chain.clone()).await;
+        let provider = ProviderBuilder::new().connect_http(node.url.parse().unwrap()).erased();
+        let estimator = FeeHistoryEstimator {
+            blocks: 10,
+            reward_percentile: 50.0,
+        };
+        assert_eq!(
+            estimator.estimate(provider).await.unwrap(),
+            NetworkFees::Eip1559 {
+                max_fee_per_gas: 2 * chain.base_fee + chain.priority_fee,
+                max_priority_fee_per_gas: chain.priority_fee,
+            }
+        );
+    }
+}
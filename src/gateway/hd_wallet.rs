@@ -0,0 +1,95 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use alloy::signers::local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner};
+use zeroize::Zeroizing;
+
+use super::error::GatewayError;
+
+/// ## HdWallet
+///
+/// Derives invoice wallets from a single BIP-39 mnemonic at the BIP-44 path
+/// `m/44'/60'/0'/0/{index}`, so only the mnemonic has to be backed up.
+///
+/// Invoices created in this mode store their derivation index instead of a
+/// private key. Indexes are handed out sequentially starting from the one given
+/// to [`HdWallet::starting_at`]; pass the highest index already in use plus one
+/// when restarting, so addresses are never reused.
+#[derive(Clone)]
+pub struct HdWallet {
+    phrase: Arc<Zeroizing<String>>,
+    next_index: Arc<AtomicU32>,
+}
+
+impl HdWallet {
+    /// Creates an HD wallet from a mnemonic phrase, starting at index 0.
+    ///
+    /// Returns an error if the phrase is not a valid English BIP-39 mnemonic.
+    pub fn new(phrase: impl Into<String>) -> Result<Self, GatewayError> {
+        let wallet = Self {
+            phrase: Arc::new(Zeroizing::new(phrase.into())),
+            next_index: Arc::new(AtomicU32::new(0)),
+        };
+        wallet.derive(0)?;
+        Ok(wallet)
+    }
+
+    /// Continues deriving invoice wallets from `index`.
+    pub fn starting_at(self, index: u32) -> Self {
+        self.next_index.store(index, Ordering::SeqCst);
+        self
+    }
+
+    /// Derives the invoice wallet at `index`.
+    pub fn derive(&self, index: u32) -> Result<PrivateKeySigner, GatewayError> {
+        MnemonicBuilder::<English>::default()
+            .phrase(self.phrase.as_str())
+            .index(index)
+            .and_then(|builder| builder.build())
+            .map_err(|e| GatewayError::WalletDerivation(e.to_string()))
+    }
+
+    /// Derives the wallet for the next unused index.
+    pub(crate) fn next(&self) -> Result<(u32, PrivateKeySigner), GatewayError> {
+        let index = self.next_index.fetch_add(1, Ordering::SeqCst);
+        Ok((index, self.derive(index)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn derives_standard_bip44_addresses() {
+        let wallet = HdWallet::new(PHRASE).unwrap();
+        // Well-known first accounts of the Hardhat/Anvil test mnemonic
+        assert_eq!(
+            wallet.derive(0).unwrap().address().to_string(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(
+            wallet.derive(1).unwrap().address().to_string(),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+    }
+
+    #[test]
+    fn next_hands_out_sequential_indexes() {
+        let wallet = HdWallet::new(PHRASE).unwrap().starting_at(5);
+        assert_eq!(wallet.next().unwrap().0, 5);
+        assert_eq!(wallet.clone().next().unwrap().0, 6);
+    }
+
+    #[test]
+    fn invalid_phrase_is_rejected() {
+        assert!(matches!(
+            HdWallet::new("not a mnemonic"),
+            Err(GatewayError::WalletDerivation(_))
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::invoice::Invoice;
+
+/// Sum of the invoices paid in one currency, see
+/// [`PaymentGateway::get_paid_totals`](super::PaymentGateway::get_paid_totals).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PaidTotal {
+    /// ERC20 token, or `None` for the native currency
+    pub token: Option<Address>,
+    /// Sum of the invoice amounts, in the smallest unit of `token`
+    pub amount: U256,
+    pub invoices: usize,
+}
+
+/// Invoices delivered on the reflector, in delivery order.
+#[derive(Default)]
+pub(crate) struct PaidHistory {
+    invoices: RwLock<VecDeque<(String, Invoice)>>,
+}
+
+impl PaidHistory {
+    /// Records a delivered invoice, dropping the oldest ones beyond `limit`.
+    pub(crate) async fn push(&self, key: &str, invoice: Invoice, limit: usize) {
+        let mut invoices = self.invoices.write().await;
+        invoices.push_back((key.to_string(), invoice));
+        while invoices.len() > limit {
+            invoices.pop_front();
+        }
+    }
+
+    /// The most recent delivery of the invoice `key`.
+    pub(crate) async fn get(&self, key: &str) -> Option<Invoice> {
+        let invoices = self.invoices.read().await;
+        invoices.iter().rev().find(|(id, _)| id == key).map(|(_, invoice)| invoice.clone())
+    }
+
+    /// Removes the invoices paid before `cutoff`, returning them.
+    pub(crate) async fn purge(&self, cutoff: u64) -> Vec<(String, Invoice)> {
+        let mut invoices = self.invoices.write().await;
+        let (purged, kept): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut *invoices)
+            .into_iter()
+            .partition(|(_, invoice)| invoice.paid_at_timestamp < cutoff);
+        *invoices = kept;
+        purged.into()
+    }
+
+    /// Invoices paid at or after `since` and before `until`, Unix seconds.
+    pub(crate) async fn between(&self, since: u64, until: u64) -> Vec<(String, Invoice)> {
+        self.invoices
+            .read()
+            .await
+            .iter()
+            .filter(|(_, invoice)| (since..until).contains(&invoice.paid_at_timestamp))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Sums `invoices` per currency, in order of first appearance. NFT invoices
+/// are left out.
+pub(crate) fn totals(invoices: &[(String, Invoice)]) -> Vec<PaidTotal> {
+    let mut totals: Vec<PaidTotal> = Vec::new();
+    for (_, invoice) in invoices.iter().filter(|(_, invoice)| invoice.nft.is_none()) {
+        match totals.iter_mut().find(|total| total.token == invoice.token) {
+            Some(total) => {
+                total.amount = total.amount.saturating_add(invoice.amount);
+                total.invoices += 1;
+            }
+            None => totals.push(PaidTotal {
+                token: invoice.token,
+                amount: invoice.amount,
+                invoices: 1,
+            }),
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paid(paid_at_timestamp: u64, token: Option<Address>, amount: u64) -> Invoice {
+        Invoice {
+            paid_at_timestamp,
+            token,
+            amount: U256::from(amount),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn history_is_queried_by_payment_time() {
+        let history = PaidHistory::default();
+        for (key, paid_at) in [("a", 10), ("b", 20), ("c", 30)] {
+            history.push(key, paid(paid_at, None, 1), 10).await;
+        }
+        let keys: Vec<_> = history.between(10, 30).await.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn oldest_invoices_are_dropped_beyond_limit() {
+        let history = PaidHistory::default();
+        for (key, paid_at) in [("a", 10), ("b", 20), ("c", 30)] {
+            history.push(key, paid(paid_at, None, 1), 2).await;
+        }
+        let keys: Vec<_> = history.between(0, u64::MAX).await.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn invoices_paid_before_cutoff_are_purged() {
+        let history = PaidHistory::default();
+        for (key, paid_at) in [("a", 10), ("b", 20), ("c", 5)] {
+            history.push(key, paid(paid_at, None, 1), 10).await;
+        }
+        let purged: Vec<_> = history.purge(20).await.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(purged, ["a", "c"]);
+        let keys: Vec<_> = history.between(0, u64::MAX).await.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["b"]);
+    }
+
+    #[test]
+    fn totals_are_summed_per_currency() {
+        let token = Some(Address::repeat_byte(0x01));
+        let invoices = [
+            ("a".to_string(), paid(1, None, 5)),
+            ("b".to_string(), paid(1, token, 7)),
+            ("c".to_string(), paid(1, None, 3)),
+        ];
+        assert_eq!(
+            totals(&invoices),
+            vec![
+                PaidTotal {
+                    token: None,
+                    amount: U256::from(8u64),
+                    invoices: 2,
+                },
+                PaidTotal {
+                    token,
+                    amount: U256::from(7u64),
+                    invoices: 1,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use std::{future::Future, pin::Pin};
+
+use alloy::primitives::U256;
+
+use crate::invoice::{Invoice, InvoiceError};
+
+/// Boxed future returned by the callbacks of [`GatewayHooks`].
+pub type HookFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Callbacks invoked at each step of an invoice's life, for custom metrics,
+/// auditing or business logic.
+///
+/// Every callback defaults to doing nothing, so implementations only override
+/// the ones they need. Callbacks are awaited where they are invoked, before
+/// the gateway moves on: long-running work should be spawned instead of
+/// holding up the poller.
+pub trait GatewayHooks: Send + Sync {
+    /// A new invoice was created.
+    fn on_invoice_created(&self, _invoice_id: String, _invoice: Invoice) -> HookFuture {
+        Box::pin(async {})
+    }
+
+    /// The poller read the balance of an invoice awaiting payment.
+    fn on_check(&self, _invoice_id: String, _balance: U256) -> HookFuture {
+        Box::pin(async {})
+    }
+
+    /// The payment of an invoice was detected and confirmed, before it is
+    /// swept or, in `detect_only` mode, delivered.
+    fn on_detected(&self, _invoice_id: String, _invoice: Invoice) -> HookFuture {
+        Box::pin(async {})
+    }
+
+    /// The treasury transfer `tx_hash` sweeping an invoice was broadcast,
+    /// including fee-bumped replacements and manual sweeps.
+    fn on_sweep_submitted(&self, _invoice_id: String, _tx_hash: String) -> HookFuture {
+        Box::pin(async {})
+    }
+
+    /// The sweep of an invoice reached the required confirmations.
+    fn on_sweep_confirmed(&self, _invoice_id: String, _invoice: Invoice) -> HookFuture {
+        Box::pin(async {})
+    }
+
+    /// Checking or sweeping an invoice failed; `error` is recorded as its
+    /// `last_error`.
+    fn on_error(&self, _invoice_id: String, _error: InvoiceError) -> HookFuture {
+        Box::pin(async {})
+    }
+}
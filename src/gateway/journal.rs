@@ -0,0 +1,281 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, OnceLock};
+
+use futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncBufReadExt;
+
+use super::{error::GatewayError, event::GatewayEvent, result::Result};
+
+/// One event of the journal.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct JournalRecord {
+    /// Position of the event in the journal, starting at 1
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub event: GatewayEvent,
+}
+
+/// ## EventJournal
+///
+/// Append-only journal of every [`GatewayEvent`] the gateway emits, written as
+/// one JSON [`JournalRecord`] per line to a single file. Each event gets the
+/// next sequence number, continuing from the last one in the file across
+/// restarts, so consumers that were offline can catch up with
+/// [`PaymentGateway::replay_events`](super::PaymentGateway::replay_events).
+///
+/// Events are queued before they are published and written in that order by a
+/// dedicated thread, so emitting never blocks the async runtime. The thread
+/// reads the last sequence number once when it starts, dropping a trailing
+/// line left half-written by a crash, and syncs the file to disk after every
+/// batch of writes. Failed writes are logged and never interrupt payment
+/// processing.
+///
+/// The journal is never rotated and grows with every event; archive or trim
+/// the file while the gateway is stopped. Replays stream the file instead of
+/// loading it whole.
+#[derive(Clone, Debug)]
+pub struct EventJournal {
+    pub path: PathBuf,
+    /// Queue of the writer thread, started on first use and shared by all
+    /// clones of this journal
+    writer: Arc<OnceLock<mpsc::Sender<Command>>>,
+}
+
+enum Command {
+    Append { event: GatewayEvent, timestamp_ms: u64 },
+    /// Answered once every earlier event is written
+    Flush(oneshot::Sender<()>),
+}
+
+impl EventJournal {
+    /// Journal at `path`, appending to the events already in it.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            writer: Arc::default(),
+        }
+    }
+
+    /// Queues `event`, emitted at `timestamp_ms`, to be written with the next
+    /// sequence number.
+    pub(crate) fn append(&self, event: &GatewayEvent, timestamp_ms: u64) -> Result<()> {
+        self.send(Command::Append {
+            event: event.clone(),
+            timestamp_ms,
+        })
+    }
+
+    /// Reads the records with a sequence number of at least `from_seq`,
+    /// oldest first, once the events queued so far are written.
+    pub async fn read(&self, from_seq: u64) -> Result<Vec<JournalRecord>> {
+        let (done, written) = oneshot::channel();
+        self.send(Command::Flush(done))?;
+        written.await.map_err(journal_error)?;
+        read_records(&self.path, from_seq).await
+    }
+
+    fn send(&self, command: Command) -> Result<()> {
+        let writer = self.writer.get_or_init(|| spawn_writer(self.path.clone()));
+        writer.send(command).map_err(|_| journal_error("the journal writer stopped"))
+    }
+}
+
+fn spawn_writer(path: PathBuf) -> mpsc::Sender<Command> {
+    let (sender, receiver) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("acceptevm-journal".to_string())
+        .spawn(move || write_events(path, receiver));
+    if let Err(e) = spawned {
+        // The receiver is gone, so every append reports the failure
+        tracing::error!("Failed to start the journal writer: {e}");
+    }
+    sender
+}
+
+/// Writes the queued events until every sender is dropped.
+fn write_events(path: PathBuf, commands: mpsc::Receiver<Command>) {
+    let mut journal: Option<(File, u64)> = None;
+    while let Ok(command) = commands.recv() {
+        let mut flushed = Vec::new();
+        let mut written = false;
+        for command in std::iter::once(command).chain(commands.try_iter()) {
+            match command {
+                Command::Append { event, timestamp_ms } => {
+                    match write_event(&path, &mut journal, event, timestamp_ms) {
+                        Ok(()) => written = true,
+                        Err(e) => tracing::error!("Failed to journal event: {e}"),
+                    }
+                }
+                Command::Flush(done) => flushed.push(done),
+            }
+        }
+        if let (true, Some((file, _))) = (written, &journal) {
+            if let Err(e) = file.sync_data() {
+                tracing::error!("Failed to sync the event journal: {e}");
+            }
+        }
+        for done in flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn write_event(
+    path: &Path,
+    journal: &mut Option<(File, u64)>,
+    event: GatewayEvent,
+    timestamp_ms: u64,
+) -> Result<()> {
+    let (file, last_seq) = match journal {
+        Some(journal) => journal,
+        None => journal.insert(recover(path)?),
+    };
+    let record = JournalRecord {
+        seq: *last_seq + 1,
+        timestamp_ms,
+        event,
+    };
+    let mut line = serde_json::to_vec(&record).map_err(journal_error)?;
+    line.push(b'\n');
+    file.write_all(&line).map_err(journal_error)?;
+    *last_seq = record.seq;
+    Ok(())
+}
+
+/// Opens the journal for appending and reads its last sequence number,
+/// truncating a trailing line that was only partly written.
+fn recover(path: &Path) -> Result<(File, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .map_err(journal_error)?;
+    let mut reader = BufReader::new(&file);
+    let (mut last_seq, mut complete_len) = (0, 0);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line).map_err(journal_error)?;
+        if read == 0 {
+            break;
+        }
+        match parse_line(&line) {
+            Some(record) => last_seq = record.seq,
+            None if !reader.fill_buf().map_err(journal_error)?.is_empty() => {
+                return Err(journal_error(format!("corrupt record after {last_seq}")));
+            }
+            None => {
+                tracing::warn!("Dropping a partly written record after {last_seq} from the journal");
+                file.set_len(complete_len).map_err(journal_error)?;
+                break;
+            }
+        }
+        complete_len += read as u64;
+    }
+    Ok((file, last_seq))
+}
+
+/// Parses a complete line; a line without its newline is partly written.
+fn parse_line(line: &[u8]) -> Option<JournalRecord> {
+    serde_json::from_slice(line.strip_suffix(b"\n")?).ok()
+}
+
+async fn read_records(path: &Path, from_seq: u64) -> Result<Vec<JournalRecord>> {
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(journal_error(e)),
+    };
+    let mut reader = tokio::io::BufReader::new(file);
+    let mut records = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await.map_err(journal_error)? == 0 {
+            return Ok(records);
+        }
+        let Some(complete) = line.strip_suffix(b"\n") else {
+            // Left by a crash and dropped on the next append
+            return Ok(records);
+        };
+        let record: JournalRecord = serde_json::from_slice(complete).map_err(journal_error)?;
+        if record.seq >= from_seq {
+            records.push(record);
+        }
+    }
+}
+
+fn journal_error(e: impl std::fmt::Display) -> GatewayError {
+    GatewayError::Journal(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_journal(name: &str) -> EventJournal {
+        let id = alloy::primitives::B256::random();
+        let path = std::env::temp_dir().join(format!("acceptevm-journal-{name}-{id}"));
+        EventJournal::new(path)
+    }
+
+    fn switched(to: &str) -> GatewayEvent {
+        GatewayEvent::ProviderSwitched {
+            from: "a".to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    fn seqs(records: &[JournalRecord]) -> Vec<u64> {
+        records.iter().map(|record| record.seq).collect()
+    }
+
+    #[tokio::test]
+    async fn events_are_numbered_and_replayed_from_a_sequence() {
+        let journal = temp_journal("replay");
+        journal.append(&switched("b"), 0).unwrap();
+        journal.append(&switched("c"), 0).unwrap();
+        journal.append(&switched("d"), 0).unwrap();
+
+        let records = journal.read(2).await.unwrap();
+        assert_eq!(seqs(&records), [2, 3]);
+        assert_eq!(records[0].event, switched("c"));
+        std::fs::remove_file(&journal.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn numbering_continues_after_a_restart() {
+        let journal = temp_journal("restart");
+        journal.append(&switched("b"), 0).unwrap();
+        journal.append(&switched("c"), 0).unwrap();
+        journal.read(1).await.unwrap();
+
+        let reopened = EventJournal::new(journal.path.clone());
+        reopened.append(&switched("d"), 0).unwrap();
+        assert_eq!(seqs(&reopened.read(3).await.unwrap()), [3]);
+        std::fs::remove_file(&journal.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_partly_written_last_record_is_dropped() {
+        let journal = temp_journal("partial");
+        journal.append(&switched("b"), 0).unwrap();
+        journal.append(&switched("c"), 0).unwrap();
+        journal.read(1).await.unwrap();
+        let mut file = OpenOptions::new().append(true).open(&journal.path).unwrap();
+        file.write_all(br#"{"seq":3,"timestamp_ms":17"#).unwrap();
+
+        let reopened = EventJournal::new(journal.path.clone());
+        assert_eq!(seqs(&reopened.read(1).await.unwrap()), [1, 2]);
+        reopened.append(&switched("d"), 0).unwrap();
+        let records = reopened.read(1).await.unwrap();
+        assert_eq!(seqs(&records), [1, 2, 3]);
+        assert_eq!(records[2].event, switched("d"));
+        std::fs::remove_file(&journal.path).unwrap();
+    }
+}
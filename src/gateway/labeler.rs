@@ -0,0 +1,103 @@
+use std::{future::Future, pin::Pin};
+
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use super::error::GatewayError;
+
+/// Boxed future returned by [`AddressLabeler::register`].
+pub type LabelFuture = Pin<Box<dyn Future<Output = Result<(), GatewayError>> + Send>>;
+
+/// Label describing a freshly generated deposit address.
+///
+/// Sent to the configured [`AddressLabeler`] when an invoice is created so
+/// treasury monitoring and chain-analytics tools can recognize gateway addresses.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddressLabel {
+    /// Deposit address of the invoice
+    pub address: Address,
+    /// Identifier of the invoice the address belongs to
+    pub invoice_id: String,
+    /// Amount requested by the invoice
+    pub amount: U256,
+    /// Invoice expiry time
+    pub expires: u64,
+    /// Arbitrary message attached to the invoice
+    pub message: Vec<u8>,
+}
+
+/// Hook for registering invoice deposit addresses with an external
+/// address-labeling service.
+///
+/// Registration happens in the background; failures are logged and never
+/// prevent the invoice from being created.
+pub trait AddressLabeler: Send + Sync {
+    fn register(&self, label: AddressLabel) -> LabelFuture;
+}
+
+/// Registers labels by POSTing them as JSON to an HTTP endpoint.
+#[derive(Clone, Debug)]
+pub struct WebhookLabeler {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookLabeler {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl AddressLabeler for WebhookLabeler {
+    fn register(&self, label: AddressLabel) -> LabelFuture {
+        let request = self.client.post(&self.url).json(&label);
+        Box::pin(async move {
+            request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| GatewayError::LabelRegistration(e.to_string()))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_serializes_address_as_hex() {
+        let label = AddressLabel {
+            address: Address::repeat_byte(0xAB),
+            invoice_id: "id".to_string(),
+            amount: U256::from(1u64),
+            expires: 10,
+            message: vec![],
+        };
+        let json = serde_json::to_value(&label).unwrap();
+        assert_eq!(
+            json["address"],
+            "0xabababababababababababababababababababab"
+        );
+        assert_eq!(json["invoice_id"], "id");
+    }
+
+    #[tokio::test]
+    async fn webhook_labeler_reports_unreachable_endpoint() {
+        let labeler = WebhookLabeler::new("http://127.0.0.1:1");
+        let result = labeler
+            .register(AddressLabel {
+                address: Address::ZERO,
+                invoice_id: "id".to_string(),
+                amount: U256::ZERO,
+                expires: 0,
+                message: vec![],
+            })
+            .await;
+        assert!(matches!(result, Err(GatewayError::LabelRegistration(_))));
+    }
+}
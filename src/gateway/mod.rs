@@ -1,49 +1,179 @@
+#[cfg(feature = "advanced")]
+mod advanced;
+mod address_index;
+pub mod amount;
+mod archive;
+pub mod audit;
+pub(crate) mod backup;
+pub mod check;
+pub mod circuit_breaker;
+pub mod clock;
+mod dead_letter;
+mod encryption;
 pub mod error;
+pub mod event;
+pub mod event_signing;
+pub mod fees;
+mod hd_wallet;
 mod hash;
+mod history;
+pub mod hooks;
+pub mod journal;
+pub mod labeler;
+pub mod nonce;
+pub mod payment_watch;
+pub mod poller;
+pub mod pricing;
+mod quorum;
+pub mod receipt;
+pub mod recovery;
+mod reflector;
+mod request;
 mod result;
+mod retention;
+mod retry;
+mod rpc;
+pub mod runtime;
+pub mod signer;
+pub mod sponsor;
+pub mod store;
+mod sweep_policy;
+mod time_index;
+mod unique_amounts;
 
 use std::{
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-    time::{SystemTime, UNIX_EPOCH},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
 };
 
 use ahash::AHashMap;
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use alloy::network::EthereumWallet;
+use alloy::primitives::B256;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::client::ClientBuilder;
 use alloy::signers::local::PrivateKeySigner;
-use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, watch, OnceCell, RwLock};
 
-pub use alloy::primitives::{Address, U256};
+pub use alloy::primitives::{Address, ChainId, U256};
+pub use amount::{Amount, InvoiceAmount};
+pub use circuit_breaker::CircuitBreaker;
+pub use archive::ExpiredRetention;
+pub use crate::web3::multicall::MULTICALL3;
+pub use crate::web3::rate_limit::RpcRateLimit;
+pub use crate::web3::transfers::splits::BASIS_POINTS;
+pub use crate::web3::transfers::forwarder::{
+    forwarder_address, forwarder_init_code, ForwarderMode, DETERMINISTIC_DEPLOYER,
+};
+pub use encryption::WalletEncryption;
+pub use hd_wallet::HdWallet;
+pub use history::PaidTotal;
+pub use poller::{PollerHandle, PollerState};
+pub use quorum::SweepQuorum;
+pub use retention::RetentionPolicy;
+pub use retry::SweepRetryPolicy;
+pub use rpc::RpcSelection;
+pub use sweep_policy::SweepPolicy;
+pub use unique_amounts::UniqueAmounts;
+pub use reflector::{webhook_signature, Reflector, SIGNATURE_HEADER};
+pub use request::InvoiceRequest;
 
-use crate::{
-    invoice::{self, Invoice},
-    web3::invoice_poller::poll_payments,
+use crate::chains::ChainPreset;
+use crate::web3::chain_id::check_expected_chain_id;
+use crate::web3::fee_cache::FeeCache;
+use crate::web3::rate_limit::{RateLimitLayer, TokenBucket};
+use crate::web3::transfers::native_transfers::invoice_signer;
+use crate::web3::transfers::token_transfers::token_decimals;
+use crate::invoice::{
+    self, encrypt_keystore, FiatQuote, Invoice, InvoiceOptions, InvoiceStatus, NftPayment,
+    PaymentOption,
 };
 
-use self::{error::GatewayError, hash::hash_now};
+use self::{
+    address_index::InvoiceAddressIndex,
+    archive::ExpiredArchive,
+    circuit_breaker::EndpointBreakers,
+    audit::{AuditEntry, AuditLog},
+    check::InvoiceCheck,
+    clock::{Clock, SystemClock},
+    dead_letter::DeadLetterQueue,
+    error::GatewayError,
+    event::{GatewayEvent, InvoiceUpdate},
+    event_signing::EventSigningKey,
+    fees::{FeeEstimator, ProviderFeeEstimator, TransactionType},
+    hash::hash_now,
+    history::{totals, PaidHistory},
+    hooks::{GatewayHooks, HookFuture},
+    journal::{EventJournal, JournalRecord},
+    labeler::{AddressLabel, AddressLabeler},
+    nonce::NonceManager,
+    payment_watch::{PaymentOutcome, PaymentWatches},
+    pricing::{fiat_to_units, PriceOracle},
+    recovery::RecoveryEntry,
+    rpc::RpcRotation,
+    runtime::{Runtime, TokioRuntime},
+    signer::{LocalSweepSigner, SweepSigner},
+    sponsor::{MemorySponsorStore, SponsorState, SponsorStore},
+    store::InvoiceStore,
+    time_index::InvoiceTimeIndex,
+};
 
 use result::Result;
 
+/// Decimals of the native currency assumed for fiat conversions and decimal
+/// amounts.
+const NATIVE_DECIMALS: u8 = 18;
+
+fn invalid_request(reason: &str) -> GatewayError {
+    GatewayError::InvalidInvoiceRequest(reason.to_string())
+}
+
+/// The pending invoice created with `external_id`, if any.
+fn find_external(
+    invoices: &AHashMap<String, Invoice>,
+    external_id: &str,
+) -> Option<(String, Invoice)> {
+    invoices
+        .iter()
+        .find(|(_, invoice)| invoice.external_id.as_deref() == Some(external_id))
+        .map(|(key, invoice)| (key.clone(), invoice.clone()))
+}
+
+/// The invoices of `keys`, in order, that are still pending and match `filter`.
+fn lookup(
+    invoices: &AHashMap<String, Invoice>,
+    keys: Vec<String>,
+    filter: impl Fn(&Invoice) -> bool,
+) -> Vec<(String, Invoice)> {
+    keys.into_iter()
+        .filter_map(|key| {
+            let invoice = invoices.get(&key).filter(|invoice| filter(invoice))?.clone();
+            Some((key, invoice))
+        })
+        .collect()
+}
+
 /// Wei is a type alias for `U256`, the smallest unit of the native currency.
 pub type Wei = U256;
 
-/// Retrieve the current unix time in seconds.
-pub fn get_unix_time_seconds() -> u64 {
-    let now = SystemTime::now();
-    match now.duration_since(UNIX_EPOCH) {
-        Ok(duration) => duration.as_secs(),
-        Err(_) => 0,
-    }
-}
+/// Number of events buffered for slow event subscribers before they start lagging.
+const EVENT_CAPACITY: usize = 1024;
+
+/// Async closure invoked with `(invoice_id, invoice)` for each paid invoice,
+/// used by [`Reflector::Callback`].
+pub type AsyncCallback =
+    Arc<dyn Fn(String, Invoice) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
 
 /// ## AcceptEVM
 ///
 /// The payment gateway is designed to be ran on the main thread, all of
 /// the functions are non-blocking asynchronous functions. The underlying polling
-/// mechanism is offloaded using `tokio::spawn`. All invoices are stored
-/// in-memory using an AHashMap. Therefore, it is your responsibility to
+/// mechanism is spawned on the configured `runtime`, tokio by default. All invoices are stored
+/// in-memory using an AHashMap. Configure an `invoice_store` to persist them and recover
+/// paid but unswept invoices after a crash; otherwise it is your responsibility to
 /// implement persistency for the invoices if you deem that this is required.
 ///
 /// The payment gateway creates addresses and waits for payments to be made to these addresses.
@@ -57,7 +187,8 @@ pub fn get_unix_time_seconds() -> u64 {
 /// the invoice was not transferred to the treasury, and you should handle this case accordingly. The invoice will
 /// always contain the wallet bytes that were used to create the invoice. You can use these bytes to recover the
 /// funds using `alloy::signers::local::PrivateKeySigner::from_bytes()`. It is therefore important to store this
-/// wallet in a safe location for either programmatic or manual recovery.
+/// wallet in a safe location for either programmatic or manual recovery. When an [`HdWallet`] is configured the
+/// invoice stores its `derivation_index` instead, and the key is recovered with [`HdWallet::derive`].
 ///
 /// Example:
 /// ```rust
@@ -68,16 +199,17 @@ pub fn get_unix_time_seconds() -> u64 {
 ///     let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
 ///     let gateway = PaymentGateway::new(
 ///         PaymentGatewayConfiguration {
-///             rpc_urls: vec![
-///                 "https://bsc-dataseed1.binance.org/".to_string(),
-///                 "https://bsc-dataseed2.binance.org/".to_string(),
-///             ],
-///             treasury_address: "0xdac17f958d2ee523a2206206994597c13d831ec7"
-///                 .parse::<Address>()?,
 ///             min_confirmations: 10,
-///             sender,
 ///             poller_delay_seconds: 10,
 ///             receipt_timeout_seconds: 60,
+///             ..PaymentGatewayConfiguration::new(
+///                 vec![
+///                     "https://bsc-dataseed1.binance.org/".to_string(),
+///                     "https://bsc-dataseed2.binance.org/".to_string(),
+///                 ],
+///                 "0xdac17f958d2ee523a2206206994597c13d831ec7".parse::<Address>()?,
+///                 sender,
+///             )
 ///         },
 ///     )?;
 ///
@@ -98,28 +230,284 @@ pub fn get_unix_time_seconds() -> u64 {
 pub struct PaymentGateway {
     pub config: PaymentGatewayConfiguration,
     pub invoices: Arc<RwLock<AHashMap<String, Invoice>>>,
-    rpc_index: Arc<AtomicUsize>,
+    rpc: Arc<RpcRotation>,
+    pub(crate) chain_id: Arc<OnceCell<ChainId>>,
+    events: broadcast::Sender<GatewayEvent>,
+    /// Updates of single invoices, see [`PaymentGateway::subscribe`]
+    invoice_updates: broadcast::Sender<(String, InvoiceUpdate)>,
+    /// Set while polling is paused, see [`PaymentGateway::pause_polling`]
+    pub(crate) polling_paused: Arc<watch::Sender<bool>>,
+    /// Invoices that expired unpaid, see [`PaymentGateway::get_expired_invoices`]
+    pub(crate) expired: Arc<ExpiredArchive>,
+    /// Invoices delivered on the reflector, see [`PaymentGateway::get_paid_invoices`]
+    pub(crate) paid: Arc<PaidHistory>,
+    /// Shared by all providers when `rpc_rate_limit` is set
+    rate_limiter: Option<Arc<TokenBucket>>,
+    /// Fee data reused for `fee_cache_seconds`
+    pub(crate) fee_cache: Arc<FeeCache>,
+    /// Invoices the reflector failed to deliver, see [`PaymentGateway::drain_dead_letters`]
+    dead_letters: Arc<DeadLetterQueue>,
+    /// Channels of [`PaymentGateway::await_payment`]
+    pub(crate) payment_watches: Arc<PaymentWatches>,
+    /// Open invoices by id, creation and expiry time, see
+    /// [`PaymentGateway::list_invoices`] and
+    /// [`PaymentGateway::get_invoices_created_between`]
+    pub(crate) invoice_times: Arc<InvoiceTimeIndex>,
+    /// Open invoices by asset and address, or by shared deposit amount
+    pub(crate) invoice_addresses: Arc<InvoiceAddressIndex>,
+    /// Held while a gas sponsor top-up picks its nonce and is broadcast, so
+    /// concurrent sweeps never send two top-ups with the same nonce
+    pub(crate) sponsor_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Client posting [`Reflector::Webhook`] deliveries
+    pub(crate) webhook_client: reqwest::Client,
+    /// The configured `sponsor_store`, or one in memory
+    pub(crate) sponsor_store: Arc<dyn SponsorStore>,
+    /// Circuits of the RPC endpoints under the configured `circuit_breaker`
+    pub(crate) breakers: Arc<EndpointBreakers>,
 }
 
 /// ## PaymentGatewayConfiguration
 ///
-/// - `rpc_urls`: a list of RPC provider URLs. Requests are distributed across them using round-robin.
+/// - `rpc_urls`: a list of RPC provider URLs. Requests are distributed across them according to `rpc_selection`.
+/// - `rpc_rate_limit`: optional [`RpcRateLimit`] token bucket shared by every RPC request of the gateway. When set, the poller no longer waits `poller_delay_seconds` between invoices but as long as the limit requires; the delay still applies between poll cycles.
+/// - `rpc_selection`: [`RpcSelection`] choosing between round-robin (the default) and failover to the next URL after repeated errors.
+/// - `expected_chain_id`: optional chain id the RPC URLs must serve. Verified when the poller starts and every `chain_check_interval_seconds` afterwards; on a mismatch the poller stops with [`GatewayError::ChainMismatch`].
+/// - `chain_check_interval_seconds`: how often a running poller re-checks `expected_chain_id`.
 /// - `treasury_address`: the address of the treasury for all paid invoices.
-/// - `min_confirmations`: the minimum amount of confirmations required before considering a transaction confirmed.
-/// - `sender`: an `UnboundedSender` from a tokio mpsc channel to receive paid invoices.
+/// - `treasury_splits`: `(address, basis_points)` payouts taken off every sweep before the remainder goes to `treasury_address`, e.g. `(platform, 500)` for a 5% platform fee. Not supported in forwarder mode.
+/// - `min_confirmations`: the minimum amount of confirmations required before considering a transaction confirmed. Invoices can override it through [`InvoiceOptions`].
+/// - `reflector`: where paid invoices are delivered, see [`Reflector`]. Tokio mpsc senders convert into it directly.
+/// - `webhook_timeout_seconds`: how long a single [`Reflector::Webhook`] delivery attempt may take, connecting included, before it fails and is retried. Deliveries are awaited by the poller, so this bounds how long an unresponsive endpoint holds it up.
 /// - `poller_delay_seconds`: how long to wait between checking invoices. This prevents potential rate limits.
 /// - `receipt_timeout_seconds`: how long to wait for a transaction receipt before timing out.
+/// - `replacement_timeout_seconds`: how long a sweep may stay unconfirmed before it is re-sent with the same nonce and bumped fees.
+/// - `price_oracle`: optional [`PriceOracle`](pricing::PriceOracle) converting fiat amounts for [`PaymentGateway::new_invoice_fiat`].
+/// - `address_labeler`: optional hook that registers every new deposit address with an external labeling service.
+/// - `hooks`: optional [`GatewayHooks`](hooks::GatewayHooks) called when an invoice is created, checked, detected as paid, swept or fails, for custom metrics, auditing or business logic.
+/// - `partial_payment_throttle_seconds`: minimum time between two `PartialPayment` events for the same invoice.
+/// - `dust_thresholds`: `(token, amount)` pairs, `None` for the native currency, below which the balance of an unpaid invoice in that asset is treated as dust: it is ignored by payment detection, partial payment events and refunds, and reported with a throttled `DustReceived` event instead. Invoices in assets without a threshold, and NFT invoices, count every unit.
+/// - `expiry_reminders`: percentages of the expiry window at which an `ExpiryReminder` event is raised for unpaid invoices.
+/// - `paid_history_limit`: how many invoices delivered on the reflector the gateway keeps for [`PaymentGateway::get_paid_invoices`], dropping the oldest first. Their wallet keys are kept only with `retain_swept_wallets`.
+/// - `expired_retention`: [`ExpiredRetention`] deciding how long invoices that expired unpaid stay in the expired archive, wallet keys included, for recovering late payments.
+/// - `retention`: [`RetentionPolicy`] deciding how long paid, expired and failed invoices, wallet keys included, are kept before the poller purges them with a `Purged` audit entry. Keeps them by default.
+/// - `refund_expired_payments`: return funds found on an invoice when it expires unpaid, e.g. an underpayment or a payment after expiry, to the payer that sent them, raising a `Refunded` event. Treasury splits do not apply to refunds. Invoices paid with NFTs, to forwarders or to the shared deposit address are not refunded.
+/// - `wallet_encryption`: optional [`WalletEncryption`]; when set, invoice wallet keys are stored encrypted and only decrypted to sign sweeps.
+/// - `audit_log`: optional [`AuditLog`](audit::AuditLog) file recording invoice creation, status transitions, sweeps and errors.
+/// - `event_journal`: optional [`EventJournal`](journal::EventJournal) file recording every [`GatewayEvent`] with a sequence number, for [`PaymentGateway::replay_events`].
+/// - `retain_swept_wallets`: keep the wallet key on swept invoices delivered on the reflector, as recovery data. By default the key is zeroized once the sweep is confirmed.
+/// - `sweep_signer`: optional [`SweepSigner`](signer::SweepSigner) creating invoice keys and signing their sweeps, e.g. backed by an HSM or a remote signing service. Defaults to a local private key per invoice; `hd_wallet`, `forwarder` and `unique_amounts` take precedence.
+/// - `hd_wallet`: optional [`HdWallet`]; when set, invoice wallets are derived from its mnemonic instead of generated randomly.
+/// - `gas_sponsor`: optional hot wallet that tops up token invoice wallets with exactly the gas their sweep needs. Any alloy signer converts into it, including remote ones such as `alloy::signers::aws::AwsSigner` so the key never lives in process memory.
+/// - `sweep_policy`: [`SweepPolicy`] deciding when paid invoices are swept: immediately, on a schedule or once their total crosses a threshold.
+/// - `sweep_batch_size`: how many paid invoices of a poll cycle are swept concurrently. Sweeps within a batch skip the poller delay between them. Gas sponsor top-ups within a batch are sent one after another; pair it with a [`LocalNonceManager`](nonce::LocalNonceManager) when a forwarder deployer sends on behalf of several invoices.
+/// - `deposit_lookback_blocks`: how many blocks back the poller looks for the transfers that paid an invoice, to record its `payer` and deposit transactions.
+/// - `trace_internal_transfers`: also attribute native payments sent by contracts, e.g. Safe wallets and smart accounts, which arrive as internal transactions, by searching the call traces of the blocks looked back on. Needs an RPC serving `debug_traceBlockByNumber` with the `callTracer`. Payments to the shared deposit address of `unique_amounts` are still only matched from plain transfers.
+/// - `multicall`: address of a Multicall3 contract, usually [`MULTICALL3`]. When set, the poller reads the balances of all unpaid invoices with one `eth_call` per cycle instead of one request per invoice.
+/// - `circuit_breaker`: optional [`CircuitBreaker`] skipping RPC URLs that keep failing, with exponential backoff, instead of polling them at full rate, and probing them for recovery. Polling pauses while every URL fails.
+/// - `sweep_retry`: [`SweepRetryPolicy`] with the backoff between failed sweeps and the number of attempts before giving up.
+/// - `fee_estimator`: [`FeeEstimator`](fees::FeeEstimator) computing the fees of sweeps. Defaults to alloy's block-average estimator; [`FeeHistoryEstimator`](fees::FeeHistoryEstimator) and [`LegacyFeeEstimator`](fees::LegacyFeeEstimator) are built in as well.
+/// - `transaction_type`: [`TransactionType`](fees::TransactionType) of sweeps. `Auto` by default, picking EIP-1559 or legacy from the latest block's base fee.
+/// - `sweep_gas_limit`: optional gas limit for each sweep transfer, used when gas estimation fails and as a floor for the estimate, e.g. for treasury contracts needing more than 21000 gas. Invoices can override it through [`InvoiceOptions`].
+/// - `detect_only`: when set, nothing is ever swept. Invoices are delivered as `Paid` once their payment is confirmed, with the funds and the wallet key left on the invoice, for merchants sweeping manually or paying into exchange deposit addresses.
+/// - `runtime`: [`Runtime`](runtime::Runtime) the poller and background tasks are spawned on and wait with. Defaults to tokio.
+/// - `clock`: [`Clock`](clock::Clock) telling the time for invoice expiry and the poller's schedules. Defaults to the system time; a [`MockClock`](clock::MockClock) makes time-based behavior testable.
+/// - `event_signing_key`: optional [`EventSigningKey`](event_signing::EventSigningKey), HMAC or EIP-191, signing events that leave the process: webhook bodies in the [`EVENT_SIGNATURE_HEADER`](event_signing::EVENT_SIGNATURE_HEADER) header and the `/events` stream of the `server` feature. Consumers check them with [`EventVerifier`](event_signing::EventVerifier).
+/// - `receipt_signing_key`: optional key signing a [`SignedReceipt`](receipt::SignedReceipt) for every swept invoice, set as its `payment_receipt` before delivery, so merchants can hand customers a verifiable proof of payment.
+/// - `invoice_store`: optional [`InvoiceStore`](store::InvoiceStore), e.g. a [`CsvInvoiceStore`](store::CsvInvoiceStore), persisting every open invoice with its wallet key. [`PaymentGateway::poll_payments`] loads it first, so sweeps of invoices paid before a crash resume automatically.
+/// - `dead_letter_store`: optional [`InvoiceStore`](store::InvoiceStore) persisting the paid invoices the reflector failed to deliver, e.g. after its receiver was dropped or its webhook retries ran out, until they are taken with [`PaymentGateway::drain_dead_letters`] or redelivered. Without one they are only queued in memory.
+/// - `sponsor_store`: optional [`SponsorStore`](sponsor::SponsorStore), e.g. a [`CsvSponsorStore`](sponsor::CsvSponsorStore), recording every gas sponsor top-up with its nonce before it is broadcast until its receipt arrives, with the value spent. A gateway restarted mid top-up, or a replica sharing the store, awaits that top-up instead of funding the wallet again. Without one the state is kept in memory.
+/// - `redeliver_dead_letters`: retry delivering dead-lettered invoices every poll cycle, see [`PaymentGateway::redeliver_dead_letters`].
+/// - `fee_cache_seconds`: how long fee data (EIP-1559 estimates or the legacy gas price) read for one sweep is reused by the next ones, cutting fee requests under load. `0`, the default, reads fresh fees for every sweep. The chain id is always cached for the lifetime of the gateway.
+/// - `max_gas_price`: optional ceiling in wei on the fee per gas (legacy gas price or EIP-1559 max fee) of sweeps. Above it, sweeps are deferred and the invoice waits in `PaidAwaitingSweep`.
+/// - `max_sweep_fee_bps`: optional ceiling on the estimated gas cost of a native sweep, in basis points of the invoice amount, e.g. `1000` for 10%. Above it, the sweep is deferred like with `max_gas_price` and a `SweepDeferred` event is raised instead of spending the payment on gas. Not applied to token, NFT and forwarder sweeps, whose gas is not paid in the invoice's asset, nor to refunds.
+/// - `nonce_manager`: optional [`NonceManager`](nonce::NonceManager) choosing transaction nonces; defaults to the pending transaction count.
+/// - `forwarder`: optional [`ForwarderMode`]; when set, invoice addresses are CREATE2 forwarders without private keys. Takes precedence over `hd_wallet`.
+/// - `sweep_quorum`: optional [`SweepQuorum`]; when set, payments of high-value invoices are only swept once every one of its RPC endpoints confirms the balance and confirmations, guarding against a single malicious or faulty endpoint.
+/// - `unique_amounts`: optional [`UniqueAmounts`]; when set, all invoices share one deposit address and are told apart by a unique amount. Takes precedence over `forwarder` and `hd_wallet`.
+///
+/// Use [`PaymentGatewayConfiguration::new`] together with struct update syntax to
+/// only spell out the settings that differ from the defaults.
 #[derive(Clone)]
 pub struct PaymentGatewayConfiguration {
     pub rpc_urls: Vec<String>,
+    pub rpc_selection: RpcSelection,
+    pub rpc_rate_limit: Option<RpcRateLimit>,
+    pub expected_chain_id: Option<ChainId>,
+    pub chain_check_interval_seconds: u64,
     pub treasury_address: Address,
+    pub treasury_splits: Vec<(Address, u16)>,
     pub poller_delay_seconds: u64,
-    pub sender: UnboundedSender<(String, Invoice)>,
+    pub reflector: Reflector,
+    pub webhook_timeout_seconds: u64,
     pub min_confirmations: u64,
     pub receipt_timeout_seconds: u64,
+    pub replacement_timeout_seconds: u64,
+    pub address_labeler: Option<Arc<dyn AddressLabeler>>,
+    pub hooks: Option<Arc<dyn GatewayHooks>>,
+    pub price_oracle: Option<Arc<dyn PriceOracle>>,
+    pub partial_payment_throttle_seconds: u64,
+    pub dust_thresholds: Vec<(Option<Address>, U256)>,
+    pub expiry_reminders: Vec<u8>,
+    pub paid_history_limit: usize,
+    pub expired_retention: ExpiredRetention,
+    pub retention: RetentionPolicy,
+    pub refund_expired_payments: bool,
+    pub wallet_encryption: Option<WalletEncryption>,
+    pub audit_log: Option<AuditLog>,
+    pub event_journal: Option<EventJournal>,
+    pub retain_swept_wallets: bool,
+    pub sweep_signer: Option<Arc<dyn SweepSigner>>,
+    pub hd_wallet: Option<HdWallet>,
+    pub forwarder: Option<ForwarderMode>,
+    pub gas_sponsor: Option<EthereumWallet>,
+    pub nonce_manager: Option<Arc<dyn NonceManager>>,
+    pub max_gas_price: Option<u128>,
+    pub max_sweep_fee_bps: Option<u16>,
+    pub fee_estimator: Arc<dyn FeeEstimator>,
+    pub transaction_type: TransactionType,
+    pub sweep_gas_limit: Option<u64>,
+    pub detect_only: bool,
+    pub runtime: Arc<dyn Runtime>,
+    pub clock: Arc<dyn Clock>,
+    pub event_signing_key: Option<EventSigningKey>,
+    pub receipt_signing_key: Option<PrivateKeySigner>,
+    pub invoice_store: Option<Arc<dyn InvoiceStore>>,
+    pub dead_letter_store: Option<Arc<dyn InvoiceStore>>,
+    pub sponsor_store: Option<Arc<dyn SponsorStore>>,
+    pub redeliver_dead_letters: bool,
+    pub fee_cache_seconds: u64,
+    pub sweep_retry: SweepRetryPolicy,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    pub sweep_batch_size: usize,
+    pub sweep_policy: SweepPolicy,
+    pub multicall: Option<Address>,
+    pub deposit_lookback_blocks: u64,
+    pub trace_internal_transfers: bool,
+    pub unique_amounts: Option<UniqueAmounts>,
+    pub sweep_quorum: Option<SweepQuorum>,
+}
+
+impl PaymentGatewayConfiguration {
+    /// Creates a configuration with the required settings and defaults for the rest:
+    /// 10 confirmations, a 10 second poller delay, a 60 second receipt timeout,
+    /// stuck sweeps replaced after 3 minutes, at most one `PartialPayment` event per invoice per minute and no expiry reminders.
+    pub fn new(
+        rpc_urls: Vec<String>,
+        treasury_address: Address,
+        reflector: impl Into<Reflector>,
+    ) -> Self {
+        Self {
+            rpc_urls,
+            rpc_selection: RpcSelection::RoundRobin,
+            rpc_rate_limit: None,
+            expected_chain_id: None,
+            chain_check_interval_seconds: 300,
+            treasury_address,
+            treasury_splits: Vec::new(),
+            poller_delay_seconds: 10,
+            reflector: reflector.into(),
+            webhook_timeout_seconds: 10,
+            min_confirmations: 10,
+            receipt_timeout_seconds: 60,
+            replacement_timeout_seconds: 180,
+            address_labeler: None,
+            hooks: None,
+            price_oracle: None,
+            partial_payment_throttle_seconds: 60,
+            dust_thresholds: Vec::new(),
+            expiry_reminders: Vec::new(),
+            paid_history_limit: 10_000,
+            expired_retention: ExpiredRetention::default(),
+            retention: RetentionPolicy::default(),
+            refund_expired_payments: false,
+            wallet_encryption: None,
+            audit_log: None,
+            event_journal: None,
+            retain_swept_wallets: false,
+            sweep_signer: None,
+            hd_wallet: None,
+            forwarder: None,
+            gas_sponsor: None,
+            nonce_manager: None,
+            max_gas_price: None,
+            max_sweep_fee_bps: None,
+            fee_estimator: Arc::new(ProviderFeeEstimator),
+            transaction_type: TransactionType::Auto,
+            sweep_gas_limit: None,
+            detect_only: false,
+            runtime: Arc::new(TokioRuntime),
+            clock: Arc::new(SystemClock),
+            event_signing_key: None,
+            receipt_signing_key: None,
+            invoice_store: None,
+            dead_letter_store: None,
+            sponsor_store: None,
+            redeliver_dead_letters: false,
+            fee_cache_seconds: 0,
+            sweep_retry: SweepRetryPolicy::default(),
+            circuit_breaker: None,
+            sweep_batch_size: 1,
+            sweep_policy: SweepPolicy::Immediate,
+            multicall: None,
+            deposit_lookback_blocks: 100,
+            trace_internal_transfers: false,
+            unique_amounts: None,
+            sweep_quorum: None,
+        }
+    }
+
+    /// Creates a configuration with the defaults of [`PaymentGatewayConfiguration::new`],
+    /// using the recommended confirmations of `preset`, a poller delay matching
+    /// its block time and its chain id as `expected_chain_id`.
+    pub fn for_chain(
+        preset: ChainPreset,
+        rpc_urls: Vec<String>,
+        treasury_address: Address,
+        reflector: impl Into<Reflector>,
+    ) -> Self {
+        Self {
+            min_confirmations: preset.recommended_confirmations,
+            poller_delay_seconds: preset.poller_delay_seconds(),
+            expected_chain_id: Some(preset.chain_id),
+            ..Self::new(rpc_urls, treasury_address, reflector)
+        }
+    }
 }
 
 impl PaymentGateway {
+    /// Creates a new payment gateway with sensible defaults for the network
+    /// described by `preset`, see [`PaymentGatewayConfiguration::for_chain`].
+    ///
+    /// Example:
+    /// ```rust
+    /// use acceptevm::{chains, gateway::{PaymentGateway, Address}};
+    ///
+    /// # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+    /// let gateway = PaymentGateway::for_chain(
+    ///     chains::BSC,
+    ///     vec!["https://bsc-dataseed1.binance.org/".to_string()],
+    ///     "0xdac17f958d2ee523a2206206994597c13d831ec7".parse::<Address>()?,
+    ///     sender,
+    /// )?;
+    /// assert_eq!(gateway.config.min_confirmations, chains::BSC.recommended_confirmations);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_chain(
+        preset: ChainPreset,
+        rpc_urls: Vec<String>,
+        treasury_address: Address,
+        reflector: impl Into<Reflector>,
+    ) -> Result<Self> {
+        Self::new(PaymentGatewayConfiguration::for_chain(
+            preset,
+            rpc_urls,
+            treasury_address,
+            reflector,
+        ))
+    }
+
     /// Creates a new payment gateway.
     ///
     /// Returns an error if `rpc_urls` is empty.
@@ -132,33 +520,279 @@ impl PaymentGateway {
     /// let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
     /// let gateway = PaymentGateway::new(
     ///     PaymentGatewayConfiguration {
-    ///         rpc_urls: vec!["https://bsc-dataseed1.binance.org/".to_string()],
-    ///         treasury_address: "0xdac17f958d2ee523a2206206994597c13d831ec7"
-    ///             .parse::<Address>()?,
     ///         min_confirmations: 10,
-    ///         sender,
     ///         poller_delay_seconds: 10,
     ///         receipt_timeout_seconds: 60,
+    ///         ..PaymentGatewayConfiguration::new(
+    ///             vec!["https://bsc-dataseed1.binance.org/".to_string()],
+    ///             "0xdac17f958d2ee523a2206206994597c13d831ec7".parse::<Address>()?,
+    ///             sender,
+    ///         )
     ///     },
     /// )?;
     /// # Ok(())
     /// # }
     /// ```
     pub fn new(configuration: PaymentGatewayConfiguration) -> Result<PaymentGateway> {
-        if configuration.rpc_urls.is_empty() {
+        let no_quorum_urls = configuration
+            .sweep_quorum
+            .as_ref()
+            .is_some_and(|quorum| quorum.rpc_urls.is_empty());
+        if configuration.rpc_urls.is_empty() || no_quorum_urls {
             return Err(GatewayError::NoRpcUrls);
         }
+        let split_points: u32 = configuration
+            .treasury_splits
+            .iter()
+            .map(|&(_, basis_points)| u32::from(basis_points))
+            .sum();
+        if split_points > u32::from(BASIS_POINTS) {
+            return Err(GatewayError::InvalidTreasurySplits(split_points));
+        }
+        if configuration.forwarder.is_some() && !configuration.treasury_splits.is_empty() {
+            return Err(GatewayError::Unsupported("treasury splits in forwarder mode"));
+        }
+        let rate_limiter = match configuration.rpc_rate_limit {
+            Some(limit) if limit.requests_per_second == 0 || limit.burst == 0 => {
+                return Err(GatewayError::InvalidRateLimit);
+            }
+            Some(limit) => Some(Arc::new(TokenBucket::new(
+                limit,
+                configuration.runtime.clone(),
+            ))),
+            None => None,
+        };
+        let sponsor_store = match &configuration.sponsor_store {
+            Some(store) => store.clone(),
+            None => Arc::new(MemorySponsorStore::default()),
+        };
+        let webhook_timeout = Duration::from_secs(configuration.webhook_timeout_seconds);
+        let webhook_client = reflector::webhook_client(webhook_timeout)?;
         Ok(PaymentGateway {
             config: configuration,
             invoices: Arc::new(RwLock::new(AHashMap::new())),
-            rpc_index: Arc::new(AtomicUsize::new(0)),
+            rpc: Arc::new(RpcRotation::default()),
+            chain_id: Arc::new(OnceCell::new()),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            invoice_updates: broadcast::channel(EVENT_CAPACITY).0,
+            polling_paused: Arc::new(watch::Sender::new(false)),
+            expired: Arc::default(),
+            paid: Arc::default(),
+            rate_limiter,
+            fee_cache: Arc::default(),
+            dead_letters: Arc::default(),
+            payment_watches: Arc::default(),
+            invoice_times: Arc::default(),
+            invoice_addresses: Arc::default(),
+            sponsor_lock: Arc::default(),
+            webhook_client,
+            sponsor_store,
+            breakers: Arc::default(),
         })
     }
 
-    /// Returns the next RPC URL using round-robin selection.
+    /// Current Unix time in seconds according to the configured `clock`.
+    pub(crate) fn now_seconds(&self) -> u64 {
+        self.config.clock.unix_time_seconds()
+    }
+
+    /// Current Unix time in milliseconds according to the configured `clock`.
+    pub(crate) fn now_millis(&self) -> u64 {
+        self.config.clock.unix_time_millis()
+    }
+
+    /// Returns the chain id cached when the poller started, if any.
+    ///
+    /// Every sweep verifies that the RPC still reports this chain id before
+    /// signing, and aborts with a [`GatewayEvent::ChainIdMismatch`] otherwise.
+    pub fn cached_chain_id(&self) -> Option<ChainId> {
+        self.chain_id.get().copied()
+    }
+
+    /// Fetches the chain id from the next RPC URL and checks it against the
+    /// configured `expected_chain_id`.
+    ///
+    /// Returns [`GatewayError::ChainMismatch`] when the RPC serves another
+    /// chain, so a misconfigured gateway can fail before accepting payments.
+    pub async fn verify_chain_id(&self) -> Result<ChainId> {
+        let url = self
+            .next_rpc_url()
+            .parse()
+            .map_err(|e: url::ParseError| GatewayError::InvalidRpcUrl(e.to_string()))?;
+        let provider = self.connect(url);
+        check_expected_chain_id(self, &provider).await
+    }
+
+    /// Subscribes to operational [`GatewayEvent`]s.
+    ///
+    /// Only events raised after subscribing are received.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<GatewayEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stream of the operational [`GatewayEvent`]s raised after this call,
+    /// for use with `futures::StreamExt` combinators such as `filter` or
+    /// `take_until`. Every stream observes all events independently; one that
+    /// falls more than a buffer of events behind skips the missed ones.
+    pub fn events(&self) -> impl Stream<Item = GatewayEvent> + Send + 'static {
+        event::receive(self.events.subscribe())
+    }
+
+    /// Stream of the [`InvoiceUpdate`]s of the invoice `invoice_id` from now
+    /// on: its status transitions, the confirmations of its payment and its
+    /// delivery. Ends after the last update, once the invoice was delivered,
+    /// cancelled or expired.
+    pub fn subscribe(
+        &self,
+        invoice_id: &str,
+    ) -> impl Stream<Item = InvoiceUpdate> + Send + 'static {
+        let invoice_id = invoice_id.to_string();
+        let updates = event::receive(self.invoice_updates.subscribe())
+            .filter_map(move |(key, update)| {
+                std::future::ready((key == invoice_id).then_some(update))
+            })
+            .boxed();
+        futures::stream::unfold((updates, false), |(mut updates, ended)| async move {
+            if ended {
+                return None;
+            }
+            let update = updates.next().await?;
+            let ended = update.is_last();
+            Some((update, (updates, ended)))
+        })
+    }
+
+    /// Adds a stored invoice to the lookup indexes.
+    pub(crate) fn index_invoice(&self, key: &str, invoice: &Invoice) {
+        self.invoice_times.insert(key, invoice);
+        self.invoice_addresses.insert(key, invoice);
+    }
+
+    /// Removes an invoice taken out of storage from the lookup indexes.
+    pub(crate) fn unindex_invoice(&self, key: &str, invoice: &Invoice) {
+        self.invoice_times.remove(key, invoice);
+        self.invoice_addresses.remove(key, invoice);
+    }
+
+    /// Reports an update of the invoice `key` to its subscribers.
+    pub(crate) fn publish_update(&self, key: &str, update: InvoiceUpdate) {
+        // Having no subscribers is not an error
+        let _ = self.invoice_updates.send((key.to_string(), update));
+    }
+
+    /// Events recorded in the configured `event_journal` from sequence number
+    /// `from_seq` on, oldest first, e.g. for a consumer catching up on the
+    /// events it missed while offline. Pass the last sequence number it
+    /// handled plus one.
+    pub async fn replay_events(&self, from_seq: u64) -> Result<Vec<JournalRecord>> {
+        let journal = self
+            .config
+            .event_journal
+            .as_ref()
+            .ok_or(GatewayError::Unsupported("replaying events without an event_journal"))?;
+        journal.read(from_seq).await
+    }
+
+    /// Awaits the callback of the configured `hooks`, if any, that `hook` picks.
+    pub(crate) async fn run_hook(&self, hook: impl FnOnce(&dyn GatewayHooks) -> HookFuture) {
+        if let Some(hooks) = &self.config.hooks {
+            hook(hooks.as_ref()).await;
+        }
+    }
+
+    /// Records an event in the configured `event_journal`, if any, and
+    /// publishes it to all current subscribers. Journal failures are only
+    /// logged.
+    pub(crate) fn emit(&self, event: GatewayEvent) {
+        if let Some(journal) = &self.config.event_journal {
+            if let Err(e) = journal.append(&event, self.now_millis()) {
+                tracing::error!("Failed to journal event: {e}");
+            }
+        }
+        // Having no subscribers is not an error
+        let _ = self.events.send(event);
+    }
+
+    /// Connects a provider to `url` whose requests pass through the
+    /// configured `rpc_rate_limit`.
+    pub(crate) fn connect(&self, url: url::Url) -> impl Provider + Clone {
+        let client = ClientBuilder::default()
+            .layer(RateLimitLayer(self.rate_limiter.clone()))
+            .http(url);
+        ProviderBuilder::new().connect_client(client)
+    }
+
+    /// Appends to the configured audit log, if any, and reports the change to
+    /// the invoice's subscribers. Failures are only logged.
+    pub(crate) async fn audit(&self, invoice_id: &str, entry: AuditEntry) {
+        if let Some(update) = InvoiceUpdate::of(&entry) {
+            self.publish_update(invoice_id, update);
+        }
+        let Some(log) = &self.config.audit_log else {
+            return;
+        };
+        if let Err(e) = log.append(invoice_id, entry, self.now_millis()).await {
+            tracing::error!("Failed to write audit record: {e}");
+        }
+    }
+
+    /// Pauses the poller once it finishes the invoice it is processing, halting
+    /// all of its RPC traffic, e.g. during provider maintenance. The poller task and
+    /// its notification state are kept; a paused poller only drains or shuts
+    /// down once resumed, but can be stopped or aborted.
+    pub fn pause_polling(&self) {
+        self.polling_paused.send_replace(true);
+    }
+
+    /// Resumes a poller paused by [`PaymentGateway::pause_polling`].
+    pub fn resume_polling(&self) {
+        self.polling_paused.send_replace(false);
+    }
+
+    /// Whether polling is currently paused.
+    pub fn is_polling_paused(&self) -> bool {
+        *self.polling_paused.borrow()
+    }
+
+    /// Returns the RPC URL for the next request according to `rpc_selection`.
+    /// URLs whose circuit the `circuit_breaker` opened are skipped while any
+    /// other one is closed.
     pub fn next_rpc_url(&self) -> &str {
-        let idx = self.rpc_index.fetch_add(1, Ordering::Relaxed) % self.config.rpc_urls.len();
-        &self.config.rpc_urls[idx]
+        let urls = &self.config.rpc_urls;
+        let index = self.rpc.next(self.config.rpc_selection, urls.len());
+        if self.config.circuit_breaker.is_some() {
+            let closed = (0..urls.len())
+                .map(|offset| &urls[(index + offset) % urls.len()])
+                .find(|url| !self.breakers.is_open(url));
+            if let Some(url) = closed {
+                return url;
+            }
+        }
+        &urls[index]
+    }
+
+    /// Records a failed request to `url`, failing over to the next URL once
+    /// the current one failed too often in a row.
+    pub(crate) fn report_rpc_failure(&self, url: &str) {
+        let urls = &self.config.rpc_urls;
+        let Some(index) = urls.iter().position(|u| u == url) else {
+            return;
+        };
+        if let Some((from, to)) = self.rpc.failed(self.config.rpc_selection, urls.len(), index) {
+            tracing::warn!("RPC URL '{}' keeps failing, switching to '{}'", urls[from], urls[to]);
+            self.emit(GatewayEvent::ProviderSwitched {
+                from: urls[from].clone(),
+                to: urls[to].clone(),
+            });
+        }
+    }
+
+    /// Records a successful request to `url`.
+    pub(crate) fn report_rpc_success(&self, url: &str) {
+        let urls = &self.config.rpc_urls;
+        if let Some(index) = urls.iter().position(|u| u == url) {
+            self.rpc.succeeded(urls.len(), index);
+        }
     }
 
     /// Retrieves all invoices as a list of `(id, invoice)` tuples.
@@ -174,6 +808,72 @@ impl PaymentGateway {
         Ok(invoices)
     }
 
+    /// Retrieves one page of invoices as `(id, invoice)` tuples, ordered by id.
+    ///
+    /// Returns at most `limit` invoices, starting after the id `after`; pass
+    /// `None` for the first page and the last id of a page for the next one.
+    /// Pages are read from an ordered id index, so only the invoices on the
+    /// requested page are visited and cloned, letting web backends page
+    /// through large invoice sets cheaply.
+    pub async fn list_invoices(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<(String, Invoice)>> {
+        let invoices = self.invoices.read().await;
+        let page = self
+            .invoice_times
+            .page(after, limit)
+            .into_iter()
+            .filter_map(|key| {
+                let invoice = invoices.get(&key)?.clone();
+                Some((key, invoice))
+            })
+            .collect();
+        Ok(page)
+    }
+
+    /// Retrieves all invoices currently in the given status as `(id, invoice)` tuples.
+    pub async fn get_invoices_by_status(
+        &self,
+        status: InvoiceStatus,
+    ) -> Result<Vec<(String, Invoice)>> {
+        let invoices = self
+            .invoices
+            .read()
+            .await
+            .iter()
+            .filter(|(_, invoice)| invoice.status == status)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Ok(invoices)
+    }
+
+    /// Retrieves the invoices created at or after `from` and before `to`, Unix
+    /// seconds, oldest first. Backed by a time index, so only the invoices in
+    /// range are visited.
+    pub async fn get_invoices_created_between(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(String, Invoice)>> {
+        let invoices = self.invoices.read().await;
+        let keys = self.invoice_times.created_within(from..to);
+        Ok(lookup(&invoices, keys, |invoice| (from..to).contains(&invoice.created_at)))
+    }
+
+    /// Retrieves the invoices expiring before `timestamp`, Unix seconds,
+    /// soonest first, including those already expired but not yet moved to the
+    /// expired archive by the poller.
+    pub async fn get_invoices_expiring_before(
+        &self,
+        timestamp: u64,
+    ) -> Result<Vec<(String, Invoice)>> {
+        let invoices = self.invoices.read().await;
+        let keys = self.invoice_times.expiring_within(0..timestamp);
+        Ok(lookup(&invoices, keys, |invoice| invoice.expires < timestamp))
+    }
+
     /// Retrieve an invoice from the payment gateway by its ID.
     pub async fn get_invoice(&self, key: &str) -> Result<Invoice> {
         self.invoices
@@ -184,47 +884,757 @@ impl PaymentGateway {
             .ok_or(GatewayError::NotFound)
     }
 
+    /// Retrieve a pending invoice by the `external_id` it was created with.
+    pub async fn get_invoice_by_external_id(
+        &self,
+        external_id: &str,
+    ) -> Result<(String, Invoice)> {
+        find_external(&*self.invoices.read().await, external_id).ok_or(GatewayError::NotFound)
+    }
+
+    /// Withdraws an invoice: it is removed from the gateway and no longer polled.
+    ///
+    /// Returns the invoice wallet bytes, decrypted when `wallet_encryption` is
+    /// set, so that any funds that were already received can still be
+    /// recovered. Invoices derived from an [`HdWallet`] return empty bytes;
+    /// their key is recovered from the derivation index.
+    pub async fn cancel_invoice(&self, key: &str) -> Result<invoice::ZeroizedVec> {
+        let invoice = self
+            .invoices
+            .write()
+            .await
+            .remove(key)
+            .ok_or(GatewayError::NotFound)?;
+        self.unindex_invoice(key, &invoice);
+        self.audit(key, AuditEntry::Cancelled).await;
+        self.discard_saved_invoice(key).await;
+        self.payment_watches.cancelled(key);
+        self.invoice_wallet(&invoice)
+    }
+
+    /// Invoices that expired unpaid and are still kept by `expired_retention`.
+    /// Their wallets can be exported with [`PaymentGateway::export_keystore`]
+    /// to recover funds that arrived after expiry.
+    pub async fn get_expired_invoices(&self) -> Result<Vec<(String, Invoice)>> {
+        Ok(self.expired.all().await)
+    }
+
+    /// Waits until the invoice `key` is paid, expires or is cancelled, for at
+    /// most `timeout`, so applications awaiting a single payment need not
+    /// filter the reflector. Resolves right away for invoices that already
+    /// settled, as long as they are still pending, archived or in the paid
+    /// history. `Paid` is reported once the payment is confirmed, before the
+    /// sweep; the poller has to be running.
+    ///
+    /// Returns [`GatewayError::NotFound`] for unknown invoices.
+    pub async fn await_payment(&self, key: &str, timeout: Duration) -> Result<PaymentOutcome> {
+        // Watched before looking the invoice up, so no change slips between
+        let mut receiver = self.payment_watches.watch(key);
+        let pending = self.invoices.read().await.get(key).map(PaymentOutcome::of);
+        let settled = match pending {
+            Some(settled) => Ok(settled),
+            None => match self.expired.get(key).await {
+                Some(invoice) => Ok(Some(PaymentOutcome::Expired(invoice))),
+                None => match self.paid.get(key).await {
+                    Some(invoice) => Ok(Some(PaymentOutcome::Paid(invoice))),
+                    None => Err(GatewayError::NotFound),
+                },
+            },
+        };
+        let outcome = match settled {
+            Ok(Some(outcome)) => Ok(outcome),
+            Err(e) => Err(e),
+            Ok(None) => {
+                let runtime = self.config.runtime.as_ref();
+                match runtime::timeout(runtime, timeout, receiver.wait_for(Option::is_some)).await
+                {
+                    Some(Ok(outcome)) => Ok(outcome.clone().unwrap_or(PaymentOutcome::TimedOut)),
+                    Some(Err(_)) => Err(GatewayError::NotFound),
+                    None => Ok(PaymentOutcome::TimedOut),
+                }
+            }
+        };
+        drop(receiver);
+        self.payment_watches.release(key);
+        outcome
+    }
+
+    /// Retrieve an expired invoice from the expired archive by its ID.
+    pub async fn get_expired_invoice(&self, key: &str) -> Result<Invoice> {
+        self.expired.get(key).await.ok_or(GatewayError::NotFound)
+    }
+
+    /// Invoices delivered on the reflector that were paid at or after `since`
+    /// and before `until`, in Unix seconds, oldest first. Only the last
+    /// `paid_history_limit` delivered invoices are kept.
+    pub async fn get_paid_invoices(
+        &self,
+        since: u64,
+        until: u64,
+    ) -> Result<Vec<(String, Invoice)>> {
+        Ok(self.paid.between(since, until).await)
+    }
+
+    /// Sums the invoices of [`PaymentGateway::get_paid_invoices`] per currency,
+    /// e.g. to answer what was received this week. NFT invoices are left out.
+    pub async fn get_paid_totals(&self, since: u64, until: u64) -> Result<Vec<PaidTotal>> {
+        Ok(totals(&self.paid.between(since, until).await))
+    }
+
+    /// Purges the paid, expired and failed invoices the configured `retention`
+    /// policy no longer keeps, wallet keys included, recording a `Purged`
+    /// audit entry for each. Returns their ids. The poller runs this every
+    /// `cleanup_interval_seconds`.
+    pub async fn purge_invoices(&self) -> Result<Vec<String>> {
+        let retention = self.config.retention;
+        let now = self.now_seconds();
+        let mut purged = Vec::new();
+        if let Some(seconds) = retention.paid_seconds {
+            purged.extend(self.paid.purge(now.saturating_sub(seconds)).await);
+        }
+        if let Some(seconds) = retention.expired_seconds {
+            purged.extend(self.expired.purge(now.saturating_sub(seconds)).await);
+        }
+        let failed: Vec<(String, Invoice)> = {
+            let mut invoices = self.invoices.write().await;
+            let keys: Vec<String> = invoices
+                .iter()
+                .filter(|(_, invoice)| retention.purges_failed(invoice, now))
+                .map(|(key, _)| key.clone())
+                .collect();
+            keys.into_iter()
+                .filter_map(|key| invoices.remove(&key).map(|invoice| (key, invoice)))
+                .collect()
+        };
+        for (key, invoice) in &failed {
+            self.unindex_invoice(key, invoice);
+            self.discard_saved_invoice(key).await;
+        }
+        purged.extend(failed);
+        for (key, invoice) in &purged {
+            let entry = AuditEntry::Purged {
+                status: invoice.status,
+            };
+            self.audit(key, entry).await;
+        }
+        Ok(purged.into_iter().map(|(key, _)| key).collect())
+    }
+
+    /// Paid invoices the reflector failed to deliver, oldest payment first.
+    /// They are kept in the `dead_letter_store`, if one is configured, until
+    /// drained or redelivered.
+    pub async fn get_dead_letters(&self) -> Result<Vec<(String, Invoice)>> {
+        let store = self.config.dead_letter_store.as_deref();
+        self.dead_letters.snapshot(store).await
+    }
+
+    /// Value spent and refunded by the gas sponsor and its top-ups not seen
+    /// mined yet, as recorded in the `sponsor_store`.
+    pub async fn sponsor_state(&self) -> Result<SponsorState> {
+        self.sponsor_store.load().await
+    }
+
+    /// Takes the paid invoices the reflector failed to deliver, oldest payment
+    /// first, e.g. to settle them out of band, and removes them from the
+    /// `dead_letter_store`.
+    pub async fn drain_dead_letters(&self) -> Result<Vec<(String, Invoice)>> {
+        let store = self.config.dead_letter_store.as_deref();
+        self.dead_letters.drain(store).await
+    }
+
+    /// Delivers the dead-lettered invoices on the reflector again, oldest
+    /// payment first, once it is reachable again, e.g. a webhook endpoint
+    /// that came back. Stops at the first failed delivery, leaving it and the
+    /// later invoices queued, and returns how many were delivered.
+    ///
+    /// With `redeliver_dead_letters` the poller calls this every poll cycle.
+    pub async fn redeliver_dead_letters(&self) -> Result<usize> {
+        let store = self.config.dead_letter_store.as_deref();
+        let mut delivered = 0;
+        for (key, invoice) in self.dead_letters.snapshot(store).await? {
+            if let Err(e) = self.reflect(&key, invoice).await {
+                tracing::warn!("Redelivering invoice {key} failed: {e}");
+                break;
+            }
+            self.dead_letters.remove(store, &key).await?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    /// Delivers a settled invoice on the reflector, signed with the
+    /// `event_signing_key` where the reflector supports it.
+    pub(crate) async fn reflect(&self, key: &str, invoice: Invoice) -> Result<()> {
+        let config = &self.config;
+        let runtime = config.runtime.as_ref();
+        let signing_key = config.event_signing_key.as_ref();
+        config
+            .reflector
+            .reflect(runtime, signing_key, &self.webhook_client, key.to_string(), invoice)
+            .await
+    }
+
+    /// Queues an invoice the reflector failed to deliver, see
+    /// [`PaymentGateway::drain_dead_letters`].
+    pub(crate) async fn dead_letter(&self, key: &str, invoice: Invoice) {
+        let store = self.config.dead_letter_store.as_deref();
+        if let Err(e) = self.dead_letters.push(store, key, invoice).await {
+            tracing::error!("Failed to save undelivered invoice {key}: {e}");
+        }
+    }
+
+    /// Moves an invoice that expired unpaid from the pending invoices to the
+    /// expired archive. Invoices cancelled meanwhile are not archived.
+    pub(crate) async fn expire_invoice(&self, key: &str, mut invoice: Invoice) {
+        let Some(removed) = self.invoices.write().await.remove(key) else {
+            return;
+        };
+        self.unindex_invoice(key, &removed);
+        invoice.transition(InvoiceStatus::Expired, self.now_millis());
+        self.payment_watches.notify(key, &invoice);
+        let retention = self.config.expired_retention;
+        self.expired
+            .insert(key, invoice, retention, self.now_seconds())
+            .await;
+        self.audit(key, AuditEntry::Expired).await;
+        self.discard_saved_invoice(key).await;
+    }
+
+    /// Saves `invoice` to the `invoice_store`, if one is configured.
+    pub(crate) async fn save_invoice(&self, key: &str, invoice: &Invoice) -> Result<()> {
+        match &self.config.invoice_store {
+            Some(store) => store.save(key.to_string(), invoice.clone()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Removes an invoice from the `invoice_store`, logging failures.
+    pub(crate) async fn discard_saved_invoice(&self, key: &str) {
+        if let Some(store) = &self.config.invoice_store {
+            if let Err(e) = store.remove(key.to_string()).await {
+                tracing::error!("Failed to remove invoice {key} from the invoice store: {e}");
+            }
+        }
+    }
+
+    /// Loads the invoices of the `invoice_store` that the gateway does not
+    /// hold yet, e.g. after a crash or restart, and returns the ids of the
+    /// paid invoices whose sweep the poller resumes. A sweep interrupted
+    /// before it was broadcast goes back to `Paid` and the invoice balance is
+    /// checked again; broadcast sweeps keep waiting for their confirmations.
+    ///
+    /// [`PaymentGateway::poll_payments`] calls this before polling starts.
+    pub async fn recover_invoices(&self) -> Result<Vec<String>> {
+        let Some(store) = &self.config.invoice_store else {
+            return Ok(Vec::new());
+        };
+        let saved = store.load().await?;
+        let mut resumed = Vec::new();
+        let mut interrupted = Vec::new();
+        {
+            let mut invoices = self.invoices.write().await;
+            for (key, mut invoice) in saved {
+                if invoices.contains_key(&key) {
+                    continue;
+                }
+                if invoice.status == InvoiceStatus::Sweeping {
+                    invoice.transition(InvoiceStatus::Paid, self.now_millis());
+                    interrupted.push((key.clone(), invoice.clone()));
+                }
+                if !matches!(invoice.status, InvoiceStatus::Pending | InvoiceStatus::SweepFailed) {
+                    resumed.push(key.clone());
+                }
+                self.index_invoice(&key, &invoice);
+                invoices.insert(key, invoice);
+            }
+        }
+        for (key, invoice) in interrupted {
+            let changed = AuditEntry::StatusChanged {
+                from: InvoiceStatus::Sweeping,
+                to: InvoiceStatus::Paid,
+            };
+            self.audit(&key, changed).await;
+            self.save_invoice(&key, &invoice).await?;
+        }
+        Ok(resumed)
+    }
+
+    /// The plaintext wallet key of `invoice`, decrypting it when it is stored
+    /// encrypted.
+    pub(crate) fn invoice_wallet(&self, invoice: &Invoice) -> Result<invoice::ZeroizedVec> {
+        if !invoice.wallet_encrypted {
+            return Ok(invoice.wallet.clone());
+        }
+        let encryption = self.config.wallet_encryption.as_ref().ok_or_else(|| {
+            GatewayError::WalletEncryption("wallet is encrypted but no key is configured".into())
+        })?;
+        encryption.decrypt(invoice.to, &invoice.wallet)
+    }
+
+    /// Exports the wallet of an invoice as an encrypted Web3 keystore, like
+    /// [`Invoice::to_keystore`], but also for HD-derived invoices and wallets
+    /// encrypted at rest. Expired invoices are looked up in the expired archive.
+    pub async fn export_keystore(&self, invoice_id: &str, password: &str) -> Result<String> {
+        let invoice = match self.get_invoice(invoice_id).await {
+            Err(GatewayError::NotFound) => self.get_expired_invoice(invoice_id).await?,
+            invoice => invoice?,
+        };
+        if invoice.derivation_index.is_none() && self.config.sweep_signer.is_some() {
+            return Err(GatewayError::InvalidWallet(
+                "the invoice key is held by the sweep signer".to_string(),
+            ));
+        }
+        let signer = invoice_signer(self, &invoice)
+            .map_err(|e| GatewayError::InvalidWallet(e.to_string()))?;
+        encrypt_keystore(&signer.to_bytes(), password)
+            .map_err(|e| GatewayError::InvalidWallet(e.to_string()))
+    }
+
+    /// Sweeps an invoice to the treasury right away instead of waiting for the
+    /// poller, e.g. after its automatic sweeps failed.
+    ///
+    /// Returns the hash of the treasury transfer. A running poller picks the
+    /// transfer up and delivers the invoice once it is confirmed.
+    pub async fn sweep_invoice(&self, key: &str) -> Result<String> {
+        crate::web3::invoice_poller::sweep_invoice(self, key).await
+    }
+
+    /// Checks the balance and confirmations of an invoice right away instead
+    /// of waiting for the poller, e.g. when a customer reports having paid.
+    ///
+    /// The check only reads the chain: a running poller still detects the
+    /// payment, sweeps and delivers the invoice. Invoices paid to the shared
+    /// deposit address of `unique_amounts` cannot be checked on their own.
+    pub async fn check_invoice_now(&self, key: &str) -> Result<InvoiceCheck> {
+        crate::web3::invoice_poller::check_invoice_now(self, key).await
+    }
+
+    /// Lists the wallet of every invoice the gateway still knows, pending,
+    /// failed to sweep or expired, with its current on-chain balance, so
+    /// stranded funds can be audited periodically and rescued with
+    /// [`PaymentGateway::export_keystore`]. Invoices paid to the shared deposit
+    /// address of `unique_amounts` have no wallet of their own and are left out.
+    ///
+    /// Fails with [`GatewayError::Rpc`] when a balance cannot be read.
+    pub async fn recovery_report(&self) -> Result<Vec<RecoveryEntry>> {
+        crate::web3::invoice_poller::recovery_report(self).await
+    }
+
     /// Spawns an asynchronous task that checks all the pending invoices
-    /// for this gateway.
-    pub async fn poll_payments(&self) {
-        let gateway = self.clone();
-        tokio::spawn(poll_payments(gateway));
+    /// for this gateway, after loading those of the `invoice_store`.
+    ///
+    /// The returned [`PollerHandle`] can stop, restart or drain the poller.
+    pub async fn poll_payments(&self) -> PollerHandle {
+        match self.recover_invoices().await {
+            Ok(resumed) if !resumed.is_empty() => {
+                tracing::warn!("Resuming {} paid invoices from the invoice store", resumed.len());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to load the invoice store: {e}"),
+        }
+        PollerHandle::spawn(self.clone())
     }
 
     /// Creates a new invoice for this gateway.
     ///
-    /// When this invoice is paid it will be sent through the sender channel.
+    /// When this invoice is paid it will be delivered through the configured reflector.
     ///
-    /// The `amount` parameter is in the smallest unit of the currency (wei for ETH).
+    /// The `amount` parameter is in the smallest unit of the currency (wei for ETH),
+    /// or a decimal string such as `"0.05"` in whole units of the currency.
     /// The `message` parameter accepts an array of bytes for arbitrary data.
     /// The `expires_in_seconds` parameter sets how long the invoice is valid.
     pub async fn new_invoice(
         &self,
-        amount: Wei,
+        amount: impl Into<InvoiceAmount>,
+        message: Vec<u8>,
+        expires_in_seconds: u64,
+    ) -> Result<(String, Invoice)> {
+        self.new_invoice_with_options(amount, message, expires_in_seconds, Default::default())
+            .await
+    }
+
+    /// Creates a new invoice payable in the ERC20 `token`.
+    ///
+    /// The `amount` parameter is in the smallest unit of the token, or a
+    /// decimal string such as `"12.50"` resolved against the token's
+    /// `decimals()`. Sweeping the token needs native gas on the invoice
+    /// wallet; configure a `gas_sponsor` so it is topped up automatically.
+    ///
+    /// Token invoices are not supported in forwarder mode, as forwarders only
+    /// move the native currency.
+    pub async fn new_token_invoice(
+        &self,
+        token: Address,
+        amount: impl Into<InvoiceAmount>,
+        message: Vec<u8>,
+        expires_in_seconds: u64,
+    ) -> Result<(String, Invoice)> {
+        let options = InvoiceOptions {
+            token: Some(token),
+            ..Default::default()
+        };
+        self.new_invoice_with_options(amount, message, expires_in_seconds, options)
+            .await
+    }
+
+    /// Creates a new invoice with the given [`InvoiceOptions`], e.g. a token,
+    /// how often the poller checks it for payment or how many confirmations
+    /// it requires. Decimal string amounts are resolved against the decimals
+    /// of `options.token`.
+    pub async fn new_invoice_with_options(
+        &self,
+        amount: impl Into<InvoiceAmount>,
         message: Vec<u8>,
         expires_in_seconds: u64,
+        options: InvoiceOptions,
     ) -> Result<(String, Invoice)> {
-        let signer = PrivateKeySigner::random();
+        let request = InvoiceRequest::new(amount, expires_in_seconds)
+            .message(message)
+            .options(options);
+        self.create_invoice(request).await
+    }
+
+    /// Creates a new invoice from an [`InvoiceRequest`].
+    ///
+    /// Fails with [`GatewayError::InvalidInvoiceRequest`] when the request is
+    /// inconsistent, e.g. expires immediately, and with
+    /// [`GatewayError::ChainMismatch`] when it is meant for another chain.
+    ///
+    /// Creation is idempotent per `external_id`: while an invoice with the
+    /// same external id is pending, it is returned as-is instead of creating
+    /// another one, whatever the rest of the request says.
+    pub async fn create_invoice(&self, request: InvoiceRequest) -> Result<(String, Invoice)> {
+        let InvoiceRequest {
+            amount,
+            message,
+            expires_in_seconds,
+            options,
+        } = request;
+        if let Some(external_id) = options.external_id.as_deref() {
+            if let Some(existing) = find_external(&*self.invoices.read().await, external_id) {
+                return Ok(existing);
+            }
+        }
+        let amount = match amount {
+            InvoiceAmount::Units(units) => units,
+            InvoiceAmount::Decimal(decimal) => {
+                let decimals = self.asset_decimals(options.token).await?;
+                Amount::from_decimal_str(&decimal, decimals)?.value
+            }
+        };
+        let invoice = self.build_invoice(amount, message, expires_in_seconds, options).await?;
+        self.insert_invoice(invoice).await
+    }
+
+    /// Creates a new invoice carrying typed `metadata`, e.g. an order
+    /// reference, instead of raw message bytes.
+    ///
+    /// The metadata is stored JSON encoded in [`Invoice::message`] and read
+    /// back with [`Invoice::metadata`].
+    pub async fn new_invoice_with_metadata<M: Serialize>(
+        &self,
+        amount: impl Into<InvoiceAmount>,
+        metadata: &M,
+        expires_in_seconds: u64,
+        options: InvoiceOptions,
+    ) -> Result<(String, Invoice)> {
+        let message =
+            serde_json::to_vec(metadata).map_err(|e| GatewayError::Metadata(e.to_string()))?;
+        self.new_invoice_with_options(amount, message, expires_in_seconds, options)
+            .await
+    }
+
+    /// Creates a new invoice over `amount_cents` of the fiat `currency`, e.g.
+    /// `"USD"`, payable in `token` or the native currency when `None`.
+    ///
+    /// The amount is converted with the configured `price_oracle` at creation
+    /// and both the fiat amount and the locked rate are kept in
+    /// [`Invoice::fiat`]. The native currency is assumed to have 18 decimals;
+    /// token decimals are read from the token contract.
+    pub async fn new_invoice_fiat(
+        &self,
+        amount_cents: u64,
+        currency: &str,
+        token: Option<Address>,
+        message: Vec<u8>,
+        expires_in_seconds: u64,
+    ) -> Result<(String, Invoice)> {
+        let oracle = self
+            .config
+            .price_oracle
+            .as_ref()
+            .ok_or(GatewayError::Unsupported("fiat invoices without a price oracle"))?;
+        let decimals = self.asset_decimals(token).await?;
+        let price = oracle.price(token, currency).await?;
+        let amount = fiat_to_units(amount_cents, decimals, price)?;
+        let quote = FiatQuote {
+            amount_cents,
+            currency: currency.to_string(),
+            rate: price.value,
+            rate_decimals: price.decimals,
+        };
+        let options = InvoiceOptions {
+            token,
+            ..Default::default()
+        };
         let invoice = Invoice {
-            to: signer.address(),
-            wallet: invoice::ZeroizedVec {
-                inner: signer.credential().to_bytes().to_vec(),
-            },
+            fiat: Some(quote),
+            ..self.build_invoice(amount, message, expires_in_seconds, options).await?
+        };
+        self.insert_invoice(invoice).await
+    }
+
+    /// Decimals of `token`, read from the token contract, or of the native
+    /// currency when `None`.
+    async fn asset_decimals(&self, token: Option<Address>) -> Result<u8> {
+        let Some(token) = token else {
+            return Ok(NATIVE_DECIMALS);
+        };
+        let url = self
+            .next_rpc_url()
+            .parse()
+            .map_err(|e: url::ParseError| GatewayError::InvalidRpcUrl(e.to_string()))?;
+        let provider = self.connect(url);
+        token_decimals(&provider, token)
+            .await
+            .map_err(|e| GatewayError::Rpc(e.to_string()))
+    }
+
+    /// Creates a new invoice that is paid once the ERC-721 token or the
+    /// quantity of an ERC-1155 token described by `nft` arrives at the
+    /// invoice address. The token is swept to the treasury with
+    /// `safeTransferFrom`; treasury splits do not apply.
+    ///
+    /// Sweeping needs native gas on the invoice wallet; configure a
+    /// `gas_sponsor` so it is topped up automatically. Not supported in
+    /// forwarder mode.
+    pub async fn new_nft_invoice(
+        &self,
+        nft: NftPayment,
+        message: Vec<u8>,
+        expires_in_seconds: u64,
+    ) -> Result<(String, Invoice)> {
+        if self.config.forwarder.is_some() {
+            return Err(GatewayError::Unsupported("NFT invoices in forwarder mode"));
+        }
+        if self.config.unique_amounts.is_some() {
+            return Err(GatewayError::Unsupported("NFT invoices with unique amounts"));
+        }
+        let invoice = Invoice {
+            nft: Some(nft),
+            ..self
+                .build_invoice(nft.quantity(), message, expires_in_seconds, Default::default())
+                .await?
+        };
+        self.insert_invoice(invoice).await
+    }
+
+    /// Builds a pending invoice with a fresh deposit address.
+    async fn build_invoice(
+        &self,
+        amount: U256,
+        message: Vec<u8>,
+        expires_in_seconds: u64,
+        options: InvoiceOptions,
+    ) -> Result<Invoice> {
+        let uses_tokens = options.token.is_some()
+            || options.alternatives.iter().any(|option| option.token.is_some());
+        if uses_tokens && self.config.forwarder.is_some() {
+            return Err(GatewayError::Unsupported("token invoices in forwarder mode"));
+        }
+        if uses_tokens && self.config.unique_amounts.is_some() {
+            return Err(GatewayError::Unsupported("token invoices with unique amounts"));
+        }
+        self.validate_options(&options).await?;
+        let created_at = self.now_seconds();
+        let expires = match expires_in_seconds {
+            0 => None,
+            seconds => created_at.checked_add(seconds),
+        }
+        .ok_or_else(|| invalid_request("expires_in_seconds must be positive and in range"))?;
+        let payment_options = match options.alternatives.is_empty() {
+            true => Vec::new(),
+            false => std::iter::once(PaymentOption {
+                token: options.token,
+                amount,
+            })
+            .chain(options.alternatives)
+            .collect(),
+        };
+        let (to, wallet, derivation_index, forwarder_salt) = self.new_deposit_address().await?;
+        let wallet_encrypted = self.config.wallet_encryption.is_some() && !wallet.is_empty();
+        Ok(Invoice {
+            to,
+            wallet,
+            wallet_encrypted,
+            derivation_index,
+            forwarder_salt,
+            token: options.token,
             amount,
             message,
             paid_at_timestamp: 0,
-            expires: get_unix_time_seconds() + expires_in_seconds,
+            created_at,
+            expires,
             hash: None,
             nonce: None,
-        };
+            settlement: None,
+            status: InvoiceStatus::Pending,
+            transitions: Vec::new(),
+            last_error: None,
+            sweep_attempts: 0,
+            next_sweep_at: 0,
+            check_interval_seconds: options.check_interval_seconds,
+            min_confirmations: options.min_confirmations,
+            deposit_block: None,
+            fiat: None,
+            payment_options,
+            paid_with: None,
+            nft: None,
+            shared_deposit: self.config.unique_amounts.is_some(),
+            payer: None,
+            deposits: Vec::new(),
+            deposit_block_number: None,
+            treasury: options.treasury,
+            external_id: options.external_id,
+            gas_limit: options.gas_limit,
+            payment_receipt: None,
+        })
+    }
 
-        let invoice_id = hash_now(signer.address().0.as_slice());
-        self.invoices
-            .write()
-            .await
-            .insert(invoice_id.clone(), invoice.clone());
+    /// Checks the per-invoice settings of a request against the gateway.
+    async fn validate_options(&self, options: &InvoiceOptions) -> Result<()> {
+        if let Some(treasury) = options.treasury {
+            if treasury.is_zero() {
+                return Err(invalid_request("treasury must not be the zero address"));
+            }
+            if self.config.forwarder.is_some() || self.config.unique_amounts.is_some() {
+                return Err(GatewayError::Unsupported(
+                    "per-invoice treasuries with forwarders or unique amounts",
+                ));
+            }
+        }
+        if options.external_id.as_ref().is_some_and(|id| id.trim().is_empty()) {
+            return Err(invalid_request("external_id must not be empty"));
+        }
+        if options.gas_limit == Some(0) {
+            return Err(invalid_request("gas_limit must not be zero"));
+        }
+        if let Some(expected) = options.chain_id {
+            let actual = match self.config.expected_chain_id.or(self.cached_chain_id()) {
+                Some(chain_id) => chain_id,
+                None => self.verify_chain_id().await?,
+            };
+            if actual != expected {
+                return Err(GatewayError::ChainMismatch { expected, actual });
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores a new invoice and registers its deposit address label.
+    ///
+    /// Invoices paid to the shared deposit address get their unique amount
+    /// here, while the invoices are locked, so no two open invoices share one.
+    async fn insert_invoice(&self, mut invoice: Invoice) -> Result<(String, Invoice)> {
+        let mut invoices = self.invoices.write().await;
+        // A concurrent request with the same external id won the race
+        if let Some(external_id) = invoice.external_id.as_deref() {
+            if let Some(existing) = find_external(&invoices, external_id) {
+                return Ok(existing);
+            }
+        }
+        let invoice_id = match self.config.unique_amounts {
+            Some(unique) if invoice.shared_deposit => {
+                // Only invoices still awaiting their payment hold an amount
+                let taken = invoices
+                    .values()
+                    .filter(|open| open.shared_deposit && open.status == InvoiceStatus::Pending)
+                    .map(|open| open.amount)
+                    .collect();
+                invoice.amount = unique
+                    .allocate(invoice.amount, &taken)
+                    .ok_or(GatewayError::UniqueAmountsExhausted)?;
+                // The address alone does not tell shared invoices apart
+                hash_now(&[invoice.to.as_slice(), B256::random().as_slice()].concat())
+            }
+            _ => hash_now(invoice.to.0.as_slice()),
+        };
+        invoices.insert(invoice_id.clone(), invoice.clone());
+        self.index_invoice(&invoice_id, &invoice);
+        drop(invoices);
+        if let Err(e) = self.save_invoice(&invoice_id, &invoice).await {
+            self.invoices.write().await.remove(&invoice_id);
+            self.unindex_invoice(&invoice_id, &invoice);
+            return Err(e);
+        }
+        let created = AuditEntry::Created {
+            to: invoice.to,
+            amount: invoice.amount,
+            token: invoice.token,
+        };
+        self.audit(&invoice_id, created).await;
+        self.register_address_label(&invoice_id, &invoice);
+        let (id, created) = (invoice_id.clone(), invoice.clone());
+        self.run_hook(|hooks| hooks.on_invoice_created(id, created)).await;
         Ok((invoice_id, invoice))
     }
+
+    /// Generates the deposit address of a new invoice according to the
+    /// configured address mode.
+    ///
+    /// Returns the address and whichever of wallet key, HD derivation index or
+    /// forwarder salt is needed to move its funds later.
+    async fn new_deposit_address(
+        &self,
+    ) -> Result<(Address, invoice::ZeroizedVec, Option<u32>, Option<B256>)> {
+        if let Some(unique) = &self.config.unique_amounts {
+            return Ok((unique.address, invoice::ZeroizedVec::default(), None, None));
+        }
+        if let Some(forwarder) = &self.config.forwarder {
+            let salt = B256::random();
+            let address =
+                forwarder_address(forwarder.factory, self.config.treasury_address, salt);
+            return Ok((address, invoice::ZeroizedVec::default(), None, Some(salt)));
+        }
+        // In HD mode only the derivation index is stored, never the key
+        if let Some(hd_wallet) = &self.config.hd_wallet {
+            let (index, signer) = hd_wallet.next()?;
+            return Ok((
+                signer.address(),
+                invoice::ZeroizedVec::default(),
+                Some(index),
+                None,
+            ));
+        }
+        let key = match &self.config.sweep_signer {
+            Some(signer) => signer.new_key().await?,
+            None => LocalSweepSigner.new_key().await?,
+        };
+        let wallet = match &self.config.wallet_encryption {
+            Some(encryption) => encryption.encrypt(key.address, &key.key)?,
+            None => key.key,
+        };
+        Ok((key.address, wallet, None, None))
+    }
+
+    /// Hands the deposit address to the configured labeler in the background.
+    fn register_address_label(&self, invoice_id: &str, invoice: &Invoice) {
+        let Some(labeler) = self.config.address_labeler.clone() else {
+            return;
+        };
+        let label = AddressLabel {
+            address: invoice.to,
+            invoice_id: invoice_id.to_string(),
+            amount: invoice.amount,
+            expires: invoice.expires,
+            message: invoice.message.clone(),
+        };
+        self.config.runtime.spawn(Box::pin(async move {
+            if let Err(e) = labeler.register(label).await {
+                tracing::warn!("Failed to register deposit address label: {e}");
+            }
+        }));
+    }
 }
 
 #[cfg(test)]
@@ -235,26 +1645,46 @@ mod tests {
     fn make_gateway(urls: Vec<String>) -> PaymentGateway {
         let (tx, _rx) = mpsc::unbounded_channel();
         PaymentGateway::new(PaymentGatewayConfiguration {
-            rpc_urls: urls,
-            treasury_address: Address::ZERO,
             poller_delay_seconds: 0,
             min_confirmations: 0,
             receipt_timeout_seconds: 5,
-            sender: tx,
+            ..PaymentGatewayConfiguration::new(urls, Address::ZERO, tx)
         })
         .expect("gateway creation must not fail")
     }
 
+    #[tokio::test]
+    async fn event_streams_observe_events_independently() {
+        use futures::StreamExt;
+
+        let gateway = make_gateway(vec!["http://localhost:8545".to_string()]);
+        let all = gateway.events();
+        let switches = gateway.events().filter(|event| {
+            std::future::ready(matches!(event, GatewayEvent::ProviderSwitched { .. }))
+        });
+        let switched = GatewayEvent::ProviderSwitched {
+            from: "http://a".to_string(),
+            to: "http://b".to_string(),
+        };
+        gateway.emit(GatewayEvent::ExpiryReminder {
+            invoice_id: "id".to_string(),
+            percent: 50,
+            expires: 0,
+        });
+        gateway.emit(switched.clone());
+
+        assert_eq!(all.take(2).collect::<Vec<_>>().await.len(), 2);
+        assert_eq!(std::pin::pin!(switches).next().await, Some(switched));
+    }
+
     #[test]
     fn no_rpc_urls_returns_error() {
         let (tx, _rx) = mpsc::unbounded_channel::<(String, crate::invoice::Invoice)>();
         let result = PaymentGateway::new(PaymentGatewayConfiguration {
-            rpc_urls: vec![],
-            treasury_address: Address::ZERO,
             poller_delay_seconds: 0,
             min_confirmations: 0,
             receipt_timeout_seconds: 5,
-            sender: tx,
+            ..PaymentGatewayConfiguration::new(vec![], Address::ZERO, tx)
         });
         assert!(
             result.is_err(),
@@ -262,6 +1692,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn treasury_splits_over_whole_amount_return_error() {
+        let (tx, _rx) = mpsc::unbounded_channel::<(String, crate::invoice::Invoice)>();
+        let result = PaymentGateway::new(PaymentGatewayConfiguration {
+            treasury_splits: vec![
+                (Address::repeat_byte(1), 6_000),
+                (Address::repeat_byte(2), 5_000),
+            ],
+            ..PaymentGatewayConfiguration::new(vec!["http://a.com".into()], Address::ZERO, tx)
+        });
+        assert!(matches!(result, Err(GatewayError::InvalidTreasurySplits(11_000))));
+    }
+
     #[test]
     fn round_robin_cycles_all_urls() {
         let gw = make_gateway(vec![
@@ -284,14 +1727,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn get_unix_time_seconds_is_reasonable() {
-        let t = get_unix_time_seconds();
-        // Must be after 2024-01-01 (unix 1704067200) and before year 2100
-        assert!(t > 1_704_067_200, "time must be after 2024-01-01");
-        assert!(t < 4_102_444_800, "time must be before 2100-01-01");
-    }
-
     #[tokio::test]
     async fn new_invoice_appears_in_map() {
         let gw = make_gateway(vec!["http://x.com".to_string()]);
@@ -302,6 +1737,108 @@ mod tests {
         assert_eq!(gw.invoices.read().await.len(), 1);
     }
 
+    struct ChannelLabeler(mpsc::UnboundedSender<labeler::AddressLabel>);
+
+    impl AddressLabeler for ChannelLabeler {
+        fn register(&self, label: labeler::AddressLabel) -> labeler::LabelFuture {
+            let sender = self.0.clone();
+            Box::pin(async move {
+                sender.send(label).ok();
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn new_invoice_registers_address_label() {
+        let (label_tx, mut label_rx) = mpsc::unbounded_channel();
+        let mut gw = make_gateway(vec!["http://x.com".to_string()]);
+        gw.config.address_labeler = Some(Arc::new(ChannelLabeler(label_tx)));
+
+        let (id, invoice) = gw.new_invoice(U256::from(7u64), vec![1], 60).await.unwrap();
+        let label = tokio::time::timeout(std::time::Duration::from_secs(1), label_rx.recv())
+            .await
+            .expect("label must be registered")
+            .expect("channel open");
+        assert_eq!(label.address, invoice.to);
+        assert_eq!(label.invoice_id, id);
+        assert_eq!(label.amount, U256::from(7u64));
+    }
+
+    #[tokio::test]
+    async fn cancel_invoice_removes_and_returns_wallet() {
+        let gw = make_gateway(vec!["http://x.com".to_string()]);
+        let (id, invoice) = gw.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        let wallet = gw.cancel_invoice(&id).await.unwrap();
+        assert_eq!(wallet.inner, invoice.wallet.inner);
+        assert!(gw.get_invoice(&id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn open_invoices_are_indexed_by_address() {
+        let gw = make_gateway(vec!["http://x.com".to_string()]);
+        let (id, invoice) = gw.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        assert_eq!(gw.invoice_addresses.by_address(None, invoice.to), Some(id.clone()));
+
+        gw.cancel_invoice(&id).await.unwrap();
+        assert_eq!(gw.invoice_addresses.by_address(None, invoice.to), None);
+    }
+
+    #[tokio::test]
+    async fn export_keystore_decrypts_wallet_encrypted_at_rest() {
+        let mut gw = make_gateway(vec!["http://x.com".to_string()]);
+        gw.config.wallet_encryption = Some(WalletEncryption::from_key([7; 32]));
+        let (id, invoice) = gw.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        assert!(invoice.to_keystore("pw").is_err());
+        let keystore: serde_json::Value =
+            serde_json::from_str(&gw.export_keystore(&id, "pw").await.unwrap()).unwrap();
+        assert_eq!(keystore["address"], alloy::hex::encode(invoice.to));
+    }
+
+    #[tokio::test]
+    async fn cancel_unknown_invoice_not_found() {
+        let gw = make_gateway(vec!["http://x.com".to_string()]);
+        let result = gw.cancel_invoice("nonexistent").await;
+        assert!(matches!(result, Err(GatewayError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn get_invoices_by_status_filters() {
+        let gw = make_gateway(vec!["http://x.com".to_string()]);
+        let (id, _) = gw.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        gw.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        gw.invoices.write().await.get_mut(&id).unwrap().status = InvoiceStatus::Paid;
+
+        let paid = gw.get_invoices_by_status(InvoiceStatus::Paid).await.unwrap();
+        assert_eq!(paid.len(), 1);
+        assert_eq!(paid[0].0, id);
+        let pending = gw.get_invoices_by_status(InvoiceStatus::Pending).await.unwrap();
+        assert_eq!(pending.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_invoices_pages_in_id_order() {
+        let gw = make_gateway(vec!["http://x.com".to_string()]);
+        for _ in 0..5 {
+            gw.new_invoice(U256::from(1u64), vec![], 60).await.unwrap();
+        }
+        let mut ids: Vec<String> = gw.invoices.read().await.keys().cloned().collect();
+        ids.sort();
+
+        let first = gw.list_invoices(None, 2).await.unwrap();
+        let second = gw.list_invoices(Some(&first[1].0), 2).await.unwrap();
+        let last = gw.list_invoices(Some(&second[1].0), 2).await.unwrap();
+        let paged: Vec<String> = first
+            .iter()
+            .chain(&second)
+            .chain(&last)
+            .map(|(id, _)| id.clone())
+            .collect();
+        assert_eq!(paged, ids);
+        assert_eq!(last.len(), 1);
+        assert!(gw.list_invoices(Some(&last[0].0), 2).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn get_invoice_not_found() {
         let gw = make_gateway(vec!["http://x.com".to_string()]);
@@ -0,0 +1,73 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+use alloy::primitives::Address;
+
+/// Chooses the nonce of every transaction the gateway sends.
+///
+/// The gateway reads the pending transaction count of the sending address and
+/// passes it in; implementations may return it as is or track nonces locally,
+/// e.g. when several transactions from one address are sent within a block.
+pub trait NonceManager: Send + Sync {
+    fn next_nonce(&self, address: Address, pending_count: u64) -> u64;
+}
+
+/// Uses the pending transaction count reported by the RPC. This is the
+/// default when no nonce manager is configured.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PendingNonceManager;
+
+impl NonceManager for PendingNonceManager {
+    fn next_nonce(&self, _address: Address, pending_count: u64) -> u64 {
+        pending_count
+    }
+}
+
+/// Hands out increasing nonces per address, never going below the pending
+/// transaction count. Suited for shared senders such as the gas sponsor that
+/// may send again before their previous transaction shows up as pending.
+#[derive(Debug, Default)]
+pub struct LocalNonceManager {
+    next: Mutex<AHashMap<Address, u64>>,
+}
+
+impl NonceManager for LocalNonceManager {
+    fn next_nonce(&self, address: Address, pending_count: u64) -> u64 {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = next.entry(address).or_insert(pending_count);
+        let nonce = (*entry).max(pending_count);
+        *entry = nonce + 1;
+        nonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_manager_returns_pending_count() {
+        assert_eq!(PendingNonceManager.next_nonce(Address::ZERO, 7), 7);
+        assert_eq!(PendingNonceManager.next_nonce(Address::ZERO, 7), 7);
+    }
+
+    #[test]
+    fn local_manager_increments_per_address() {
+        let manager = LocalNonceManager::default();
+        let a = Address::repeat_byte(0x01);
+        let b = Address::repeat_byte(0x02);
+        assert_eq!(manager.next_nonce(a, 3), 3);
+        assert_eq!(manager.next_nonce(a, 3), 4);
+        assert_eq!(manager.next_nonce(b, 0), 0);
+    }
+
+    #[test]
+    fn local_manager_catches_up_with_chain() {
+        let manager = LocalNonceManager::default();
+        let a = Address::repeat_byte(0x01);
+        assert_eq!(manager.next_nonce(a, 0), 0);
+        // Transactions sent elsewhere moved the pending count ahead
+        assert_eq!(manager.next_nonce(a, 5), 5);
+        assert_eq!(manager.next_nonce(a, 5), 6);
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+use tokio::sync::watch;
+
+use crate::invoice::{Invoice, InvoiceStatus};
+
+/// How an invoice awaited with
+/// [`PaymentGateway::await_payment`](super::PaymentGateway::await_payment) settled.
+#[derive(Clone, Debug)]
+pub enum PaymentOutcome {
+    /// The payment was confirmed. The invoice is the one of that moment, so
+    /// its sweep may still be underway.
+    Paid(Invoice),
+    /// The invoice expired unpaid.
+    Expired(Invoice),
+    /// The invoice was cancelled while it was awaited.
+    Cancelled,
+    /// Neither happened within the timeout.
+    TimedOut,
+}
+
+impl PaymentOutcome {
+    /// The outcome an invoice in its current status settled with, if any.
+    pub(crate) fn of(invoice: &Invoice) -> Option<PaymentOutcome> {
+        match invoice.status {
+            InvoiceStatus::Pending => None,
+            InvoiceStatus::Expired => Some(PaymentOutcome::Expired(invoice.clone())),
+            _ => Some(PaymentOutcome::Paid(invoice.clone())),
+        }
+    }
+}
+
+/// One watch channel per awaited invoice, created on demand and dropped once
+/// the invoice settled or nobody awaits it anymore.
+#[derive(Default)]
+pub(crate) struct PaymentWatches {
+    senders: Mutex<AHashMap<String, watch::Sender<Option<PaymentOutcome>>>>,
+}
+
+impl PaymentWatches {
+    /// Receives the outcome of the invoice `key`.
+    pub(crate) fn watch(&self, key: &str) -> watch::Receiver<Option<PaymentOutcome>> {
+        let mut senders = self.senders.lock().expect("payment watches poisoned");
+        senders
+            .entry(key.to_string())
+            .or_insert_with(|| watch::Sender::new(None))
+            .subscribe()
+    }
+
+    /// Reports the new state of an invoice to those awaiting it.
+    pub(crate) fn notify(&self, key: &str, invoice: &Invoice) {
+        if let Some(outcome) = PaymentOutcome::of(invoice) {
+            self.settle(key, outcome);
+        }
+    }
+
+    pub(crate) fn cancelled(&self, key: &str) {
+        self.settle(key, PaymentOutcome::Cancelled);
+    }
+
+    /// Drops the channel of `key` once its last receiver is gone.
+    pub(crate) fn release(&self, key: &str) {
+        let mut senders = self.senders.lock().expect("payment watches poisoned");
+        if senders.get(key).is_some_and(|sender| sender.receiver_count() == 0) {
+            senders.remove(key);
+        }
+    }
+
+    fn settle(&self, key: &str, outcome: PaymentOutcome) {
+        let sender = self.senders.lock().expect("payment watches poisoned").remove(key);
+        if let Some(sender) = sender {
+            // Receivers still see the last value after the sender is dropped
+            sender.send_replace(Some(outcome));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn watchers_see_the_outcome_and_channels_are_dropped() {
+        let watches = PaymentWatches::default();
+        let mut receiver = watches.watch("a");
+        watches.notify("a", &Invoice::default());
+        assert!(receiver.borrow().is_none());
+
+        let paid = Invoice {
+            status: InvoiceStatus::Paid,
+            ..Default::default()
+        };
+        watches.notify("a", &paid);
+        let outcome = receiver.wait_for(Option::is_some).await.unwrap().clone();
+        assert!(matches!(outcome, Some(PaymentOutcome::Paid(_))));
+        assert!(watches.senders.lock().unwrap().is_empty());
+
+        let receiver = watches.watch("b");
+        drop(receiver);
+        watches.release("b");
+        assert!(watches.senders.lock().unwrap().is_empty());
+    }
+}
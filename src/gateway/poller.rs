@@ -0,0 +1,190 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use futures::future::{AbortHandle, Abortable, Aborted};
+use tokio::sync::watch;
+
+use crate::invoice::InvoiceStatus;
+use crate::web3::invoice_poller::poll_payments;
+
+use super::{audit::AuditEntry, error::GatewayError, runtime, PaymentGateway};
+
+/// The poller task spawned on the configured runtime.
+struct PollerTask {
+    abort: AbortHandle,
+    finished: Arc<AtomicBool>,
+    /// Result of the task, sent once the poller future has been dropped.
+    /// Closed without a value when the task panicked.
+    result: oneshot::Receiver<Result<Result<(), GatewayError>, Aborted>>,
+}
+
+impl PollerTask {
+    fn spawn(gateway: PaymentGateway, state: Arc<watch::Sender<PollerState>>) -> Self {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (sender, result) = oneshot::channel();
+        let finished = Arc::new(AtomicBool::new(false));
+        let runtime = gateway.config.runtime.clone();
+        let poller = Abortable::new(poll_payments(gateway, state), registration);
+        let done = finished.clone();
+        runtime.spawn(Box::pin(async move {
+            let result = poller.await;
+            done.store(true, Ordering::Release);
+            let _ = sender.send(result);
+        }));
+        Self {
+            abort,
+            finished,
+            result,
+        }
+    }
+}
+
+/// Lifecycle state of the payment poller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PollerState {
+    /// Detecting payments and sweeping paid invoices.
+    Running,
+    /// Finishing in-flight sweeps; no new payments are detected.
+    Draining,
+    /// The poller task has exited. Once reached from `Draining`, no sweep is
+    /// in flight and the gateway is safe to terminate.
+    Stopped,
+}
+
+/// Handle to the polling task spawned by [`PaymentGateway::poll_payments`].
+///
+/// Dropping the handle leaves the poller running in the background.
+pub struct PollerHandle {
+    gateway: PaymentGateway,
+    state: Arc<watch::Sender<PollerState>>,
+    task: Option<PollerTask>,
+}
+
+impl PollerHandle {
+    pub(crate) fn spawn(gateway: PaymentGateway) -> Self {
+        let mut handle = Self {
+            gateway,
+            state: Arc::new(watch::Sender::new(PollerState::Stopped)),
+            task: None,
+        };
+        handle.start();
+        handle
+    }
+
+    /// Returns the current poller state.
+    pub fn state(&self) -> PollerState {
+        *self.state.borrow()
+    }
+
+    /// Whether the poller task is alive, including while it drains.
+    pub fn is_running(&self) -> bool {
+        self.task
+            .as_ref()
+            .is_some_and(|task| !task.finished.load(Ordering::Acquire))
+    }
+
+    /// Starts the poller if it is stopped, or cancels an ongoing drain.
+    pub fn start(&mut self) {
+        let previous = self.state.send_replace(PollerState::Running);
+        if previous == PollerState::Stopped {
+            self.task = Some(PollerTask::spawn(self.gateway.clone(), self.state.clone()));
+        }
+    }
+
+    /// Stops the poller after the invoice it is currently processing and
+    /// waits for the task to exit. In-flight sweeps are left as they are and
+    /// resume confirming once the poller is started again.
+    pub async fn stop(&mut self) {
+        self.state.send_replace(PollerState::Stopped);
+        let _ = self.wait().await;
+    }
+
+    /// Stops detecting new payments, keeps confirming sweeps that were already
+    /// broadcast, and returns once none are left and the poller has stopped.
+    pub async fn drain(&mut self) {
+        self.state.send_if_modified(|state| {
+            let running = *state == PollerState::Running;
+            if running {
+                *state = PollerState::Draining;
+            }
+            running
+        });
+        let _ = self.wait().await;
+    }
+
+    /// Cancels the poller task immediately, without waiting for the invoice it
+    /// is processing. A sweep interrupted before broadcasting is retried once
+    /// the poller is started again.
+    pub async fn abort(&mut self) {
+        self.state.send_replace(PollerState::Stopped);
+        if let Some(task) = self.task.take() {
+            task.abort.abort();
+            // Cancellation only completes once the poller future is dropped
+            let _ = task.result.await;
+        }
+        let mut reset = Vec::new();
+        for (key, invoice) in self.gateway.invoices.write().await.iter_mut() {
+            if invoice.status == InvoiceStatus::Sweeping {
+                invoice.transition(InvoiceStatus::Paid, self.gateway.now_millis());
+                reset.push((key.clone(), invoice.clone()));
+            }
+        }
+        for (key, invoice) in reset {
+            let changed = AuditEntry::StatusChanged {
+                from: InvoiceStatus::Sweeping,
+                to: InvoiceStatus::Paid,
+            };
+            self.gateway.audit(&key, changed).await;
+            if let Err(e) = self.gateway.save_invoice(&key, &invoice).await {
+                tracing::error!("Failed to save invoice {key}: {e}");
+            }
+        }
+    }
+
+    /// Shuts the poller down for process exit: drains it, so sweeps awaiting
+    /// confirmations are finished and their paid invoices delivered to the
+    /// reflector, and aborts it if that takes longer than `timeout`.
+    ///
+    /// Returns [`GatewayError::ShutdownTimeout`] when sweeps were still in
+    /// flight at the deadline; they resume once a poller is started again.
+    pub async fn shutdown(mut self, timeout: Duration) -> Result<(), GatewayError> {
+        let runtime = self.gateway.config.runtime.clone();
+        if runtime::timeout(runtime.as_ref(), timeout, self.drain()).await.is_some() {
+            tracing::info!("Poller shut down gracefully");
+            return Ok(());
+        }
+        tracing::warn!("Poller did not drain within {timeout:?}, aborting");
+        self.abort().await;
+        Err(GatewayError::ShutdownTimeout)
+    }
+
+    /// Waits for the poller to exit on its own and returns the error that
+    /// stopped it, e.g. [`GatewayError::ChainMismatch`] when the RPC serves
+    /// another chain than `expected_chain_id`. Returns `Ok` once the poller
+    /// was stopped or drained.
+    pub async fn join(&mut self) -> Result<(), GatewayError> {
+        self.wait().await
+    }
+
+    async fn wait(&mut self) -> Result<(), GatewayError> {
+        // Keep the task while waiting so a cancelled wait can still abort it
+        let result = match self.task.as_mut() {
+            Some(task) => match (&mut task.result).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(Aborted)) => Ok(()),
+                Err(_) => {
+                    tracing::error!("Poller task failed");
+                    Ok(())
+                }
+            },
+            None => Ok(()),
+        };
+        self.task = None;
+        self.state.send_replace(PollerState::Stopped);
+        result
+    }
+}
@@ -0,0 +1,224 @@
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use ahash::AHashMap;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    clock::{Clock, SystemClock},
+    error::GatewayError,
+};
+
+/// Boxed future returned by [`PriceOracle::price`].
+pub type PriceFuture = Pin<Box<dyn Future<Output = Result<Price, GatewayError>> + Send>>;
+
+/// Fiat price of one whole unit of an asset, e.g. `Price { value: 250_012_000_000, decimals: 8 }`
+/// for 2500.12 USD per ETH.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Price {
+    /// Price scaled by `10^decimals`
+    pub value: U256,
+    pub decimals: u8,
+}
+
+/// Source of exchange rates for fiat-denominated invoices.
+pub trait PriceOracle: Send + Sync {
+    /// Returns the price of one whole unit of `asset` in `currency`, e.g.
+    /// `"USD"`. A `None` asset is the native currency.
+    fn price(&self, asset: Option<Address>, currency: &str) -> PriceFuture;
+}
+
+sol! {
+    interface IAggregatorV3 {
+        function decimals() external view returns (uint8);
+        function latestRoundData()
+            external
+            view
+            returns (
+                uint80 roundId,
+                int256 answer,
+                uint256 startedAt,
+                uint256 updatedAt,
+                uint80 answeredInRound
+            );
+    }
+}
+
+/// Reads prices from Chainlink price feeds on chain.
+///
+/// Every `(asset, currency)` pair needs its feed registered with
+/// [`ChainlinkOracle::with_feed`]. Answers older than `max_age_seconds` are
+/// rejected as stale, judged by the system time unless another clock is set
+/// with [`ChainlinkOracle::with_clock`].
+#[derive(Clone)]
+pub struct ChainlinkOracle {
+    rpc_url: String,
+    feeds: AHashMap<(Option<Address>, String), Address>,
+    max_age_seconds: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl fmt::Debug for ChainlinkOracle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainlinkOracle")
+            .field("rpc_url", &self.rpc_url)
+            .field("feeds", &self.feeds)
+            .field("max_age_seconds", &self.max_age_seconds)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChainlinkOracle {
+    /// Creates an oracle reading feeds through `rpc_url`, rejecting answers
+    /// older than an hour.
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            feeds: AHashMap::new(),
+            max_age_seconds: 3600,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Registers the `feed` contract pricing `asset` in `currency`.
+    pub fn with_feed(mut self, asset: Option<Address>, currency: &str, feed: Address) -> Self {
+        self.feeds
+            .insert((asset, currency.to_ascii_uppercase()), feed);
+        self
+    }
+
+    /// Sets how old the latest answer of a feed may be.
+    pub fn with_max_age(mut self, max_age_seconds: u64) -> Self {
+        self.max_age_seconds = max_age_seconds;
+        self
+    }
+
+    /// Sets the clock the age of answers is judged by, normally the gateway's
+    /// `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl PriceOracle for ChainlinkOracle {
+    fn price(&self, asset: Option<Address>, currency: &str) -> PriceFuture {
+        let feed = self
+            .feeds
+            .get(&(asset, currency.to_ascii_uppercase()))
+            .copied();
+        let currency = currency.to_string();
+        let rpc_url = self.rpc_url.clone();
+        let max_age_seconds = self.max_age_seconds;
+        let clock = self.clock.clone();
+        Box::pin(async move {
+            let feed = feed.ok_or_else(|| {
+                GatewayError::Pricing(format!("no price feed for {asset:?} in {currency}"))
+            })?;
+            let url = rpc_url
+                .parse()
+                .map_err(|e: url::ParseError| GatewayError::InvalidRpcUrl(e.to_string()))?;
+            let provider = ProviderBuilder::new().connect_http(url);
+
+            let decimals = call(&provider, feed, IAggregatorV3::decimalsCall {}).await?;
+            let round = call(&provider, feed, IAggregatorV3::latestRoundDataCall {}).await?;
+            let age = clock.unix_time_seconds().saturating_sub(round.updatedAt.to::<u64>());
+            if age > max_age_seconds {
+                return Err(GatewayError::Pricing(format!(
+                    "price feed {feed} is stale, last updated {age}s ago"
+                )));
+            }
+            let value = U256::try_from(round.answer)
+                .ok()
+                .filter(|value| !value.is_zero())
+                .ok_or_else(|| {
+                    GatewayError::Pricing(format!("price feed {feed} answered {}", round.answer))
+                })?;
+            Ok(Price { value, decimals })
+        })
+    }
+}
+
+async fn call<C: SolCall>(
+    provider: &impl Provider,
+    to: Address,
+    call: C,
+) -> Result<C::Return, GatewayError> {
+    let request = TransactionRequest::default()
+        .to(to)
+        .input(call.abi_encode().into());
+    let output = provider
+        .call(request)
+        .await
+        .map_err(|e| GatewayError::Rpc(e.to_string()))?;
+    C::abi_decode_returns(&output).map_err(|e| GatewayError::Pricing(e.to_string()))
+}
+
+/// Converts `amount_cents` of fiat into the smallest unit of an asset with
+/// `decimals` decimals, rounding up so the invoice never asks for less than
+/// the fiat amount.
+pub(crate) fn fiat_to_units(
+    amount_cents: u64,
+    decimals: u8,
+    price: Price,
+) -> Result<U256, GatewayError> {
+    if price.value.is_zero() {
+        return Err(GatewayError::Pricing("price is zero".to_string()));
+    }
+    let scale = |exp: u8| U256::from(10u64).checked_pow(U256::from(exp));
+    let numerator = scale(decimals)
+        .zip(scale(price.decimals))
+        .and_then(|(units, price_scale)| units.checked_mul(price_scale))
+        .and_then(|scaled| scaled.checked_mul(U256::from(amount_cents)))
+        .ok_or_else(|| GatewayError::Pricing("fiat amount overflows".to_string()))?;
+    let denominator = price
+        .value
+        .checked_mul(U256::from(100u64))
+        .ok_or_else(|| GatewayError::Pricing("price overflows".to_string()))?;
+    Ok(numerator.div_ceil(denominator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_cents_to_wei_at_locked_rate() {
+        // 2000.00 USD per ETH with 8 decimals
+        let price = Price {
+            value: U256::from(200_000_000_000u64),
+            decimals: 8,
+        };
+        // 50.00 USD is 0.025 ETH
+        assert_eq!(
+            fiat_to_units(5_000, 18, price).unwrap(),
+            U256::from(25_000_000_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn rounds_up_to_whole_units() {
+        // 3.00 USD per token with 6 decimals, 0.01 USD is 3333.33 units
+        let price = Price {
+            value: U256::from(300u64),
+            decimals: 2,
+        };
+        assert_eq!(fiat_to_units(1, 6, price).unwrap(), U256::from(3_334u64));
+    }
+
+    #[test]
+    fn zero_price_is_rejected() {
+        let price = Price {
+            value: U256::ZERO,
+            decimals: 8,
+        };
+        assert!(matches!(
+            fiat_to_units(100, 18, price),
+            Err(GatewayError::Pricing(_))
+        ));
+    }
+}
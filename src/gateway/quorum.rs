@@ -0,0 +1,67 @@
+use alloy::primitives::{Address, U256};
+
+use crate::invoice::Invoice;
+
+/// ## SweepQuorum
+///
+/// Independent RPC endpoints that all have to confirm a high-value payment
+/// before it is swept, so a single malicious or faulty endpoint cannot make
+/// the gateway accept a payment that never happened.
+///
+/// - `rpc_urls`: the endpoints asked, usually 2 or 3 from other providers
+///   than the gateway's `rpc_urls`.
+/// - `thresholds`: minimum invoice amount per asset, `None` for the native
+///   currency, from which payments are verified. Invoices in assets without a
+///   threshold, and NFT invoices, are not verified.
+///
+/// Each endpoint has to report a balance covering the invoice amount and, for
+/// invoices requiring confirmations, a block at least that many blocks past
+/// the payment. Otherwise the sweep waits for the next poll cycle and a
+/// `QuorumDisagreement` event names the endpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SweepQuorum {
+    pub rpc_urls: Vec<String>,
+    pub thresholds: Vec<(Option<Address>, U256)>,
+}
+
+impl SweepQuorum {
+    /// Verifies native currency payments of at least `min_amount` wei.
+    pub fn new(rpc_urls: Vec<String>, min_amount: U256) -> Self {
+        Self {
+            rpc_urls,
+            thresholds: vec![(None, min_amount)],
+        }
+    }
+
+    /// Whether the payment of `invoice` has to be verified.
+    pub(crate) fn applies(&self, invoice: &Invoice) -> bool {
+        invoice.nft.is_none()
+            && self
+                .thresholds
+                .iter()
+                .any(|(token, threshold)| *token == invoice.token && invoice.amount >= *threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_invoices_above_their_asset_threshold_are_verified() {
+        let token = Some(Address::repeat_byte(0x70));
+        let quorum = SweepQuorum {
+            thresholds: vec![(None, U256::from(100u64)), (token, U256::from(5u64))],
+            ..SweepQuorum::new(Vec::new(), U256::ZERO)
+        };
+        let invoice = |token, amount: u64| Invoice {
+            token,
+            amount: U256::from(amount),
+            ..Default::default()
+        };
+        assert!(quorum.applies(&invoice(None, 100)));
+        assert!(!quorum.applies(&invoice(None, 99)));
+        assert!(quorum.applies(&invoice(token, 5)));
+        assert!(!quorum.applies(&invoice(Some(Address::ZERO), 1_000)));
+    }
+}
@@ -0,0 +1,144 @@
+use alloy::primitives::{Address, Signature, B256, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use alloy::sol;
+use alloy::sol_types::{Eip712Domain, SolStruct};
+use serde::{Deserialize, Serialize};
+
+use super::error::GatewayError;
+
+/// Name of the EIP-712 domain receipts are signed in.
+pub const RECEIPT_DOMAIN_NAME: &str = "AcceptEVM";
+/// Version of the EIP-712 domain receipts are signed in.
+pub const RECEIPT_DOMAIN_VERSION: &str = "1";
+
+sol! {
+    struct PaymentReceipt {
+        string invoiceId;
+        address payer;
+        uint256 amount;
+        address token;
+        bytes32 sweepTxHash;
+    }
+}
+
+/// Proof of payment handed to customers, signed as EIP-712 typed data
+/// `PaymentReceipt(string invoiceId,address payer,uint256 amount,address token,bytes32 sweepTxHash)`
+/// by the gateway's `receipt_signing_key`.
+///
+/// The domain is named [`RECEIPT_DOMAIN_NAME`] with version
+/// [`RECEIPT_DOMAIN_VERSION`] and the chain id of the payment. Anyone knowing
+/// the gateway's signer address can verify the receipt, e.g. with
+/// [`SignedReceipt::verify`] or a wallet's `eth_signTypedData` tooling.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SignedReceipt {
+    pub invoice_id: String,
+    /// Sender of the payment; the zero address when it was not identified
+    pub payer: Address,
+    pub amount: U256,
+    /// Token or NFT contract paid in; `None`, signed as the zero address,
+    /// for the native currency
+    pub token: Option<Address>,
+    /// Hash of the treasury transfer that swept the payment
+    pub sweep_tx_hash: B256,
+    pub chain_id: Option<u64>,
+    /// `0x`-prefixed 65 byte signature of the typed data
+    pub signature: String,
+}
+
+impl SignedReceipt {
+    /// Signs a receipt with `signer`.
+    pub(crate) fn sign(
+        signer: &PrivateKeySigner,
+        invoice_id: &str,
+        payer: Address,
+        amount: U256,
+        token: Option<Address>,
+        sweep_tx_hash: B256,
+        chain_id: Option<u64>,
+    ) -> Result<Self, GatewayError> {
+        let mut receipt = Self {
+            invoice_id: invoice_id.to_string(),
+            payer,
+            amount,
+            token,
+            sweep_tx_hash,
+            chain_id,
+            signature: String::new(),
+        };
+        receipt.signature = signer
+            .sign_hash_sync(&receipt.signing_hash())
+            .map_err(|e| GatewayError::ReceiptSigning(e.to_string()))?
+            .to_string();
+        Ok(receipt)
+    }
+
+    /// The EIP-712 hash the signature is made over.
+    pub fn signing_hash(&self) -> B256 {
+        let typed = PaymentReceipt {
+            invoiceId: self.invoice_id.clone(),
+            payer: self.payer,
+            amount: self.amount,
+            token: self.token.unwrap_or(Address::ZERO),
+            sweepTxHash: self.sweep_tx_hash,
+        };
+        typed.eip712_signing_hash(&self.domain())
+    }
+
+    /// The EIP-712 domain of the receipt.
+    pub fn domain(&self) -> Eip712Domain {
+        Eip712Domain::new(
+            Some(RECEIPT_DOMAIN_NAME.into()),
+            Some(RECEIPT_DOMAIN_VERSION.into()),
+            self.chain_id.map(U256::from),
+            None,
+            None,
+        )
+    }
+
+    /// The address that signed the receipt.
+    pub fn recover_signer(&self) -> Result<Address, GatewayError> {
+        self.signature
+            .parse::<Signature>()
+            .and_then(|signature| signature.recover_address_from_prehash(&self.signing_hash()))
+            .map_err(|e| GatewayError::ReceiptSigning(e.to_string()))
+    }
+
+    /// Whether the receipt is unaltered and was signed by `signer`.
+    pub fn verify(&self, signer: Address) -> bool {
+        self.recover_signer().is_ok_and(|recovered| recovered == signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(signer: &PrivateKeySigner) -> SignedReceipt {
+        SignedReceipt::sign(
+            signer,
+            "invoice",
+            Address::repeat_byte(1),
+            U256::from(1_000u64),
+            None,
+            B256::repeat_byte(2),
+            Some(1),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn receipts_verify_with_the_signer_only() {
+        let signer = PrivateKeySigner::random();
+        let receipt = sign(&signer);
+        assert!(receipt.verify(signer.address()));
+        assert!(!receipt.verify(PrivateKeySigner::random().address()));
+
+        let mut tampered = receipt.clone();
+        tampered.amount = U256::from(1_000_000u64);
+        assert!(!tampered.verify(signer.address()));
+        let mut other_chain = receipt;
+        other_chain.chain_id = Some(137);
+        assert!(!other_chain.verify(signer.address()));
+    }
+}
@@ -0,0 +1,22 @@
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::invoice::InvoiceStatus;
+
+/// One invoice wallet of
+/// [`PaymentGateway::recovery_report`](super::PaymentGateway::recovery_report).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RecoveryEntry {
+    pub invoice_id: String,
+    /// Address of the invoice wallet
+    pub address: Address,
+    /// `Expired` for invoices of the expired archive
+    pub status: InvoiceStatus,
+    /// Asset of the invoice, `None` for the native currency
+    pub token: Option<Address>,
+    /// Current balance of the wallet in the invoice's asset
+    pub balance: U256,
+    /// Current native balance of the wallet for token and NFT invoices, e.g.
+    /// gas topped up for a sweep that never went out
+    pub native_balance: Option<U256>,
+}
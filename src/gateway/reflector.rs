@@ -0,0 +1,348 @@
+use std::time::Duration;
+
+use hmac::Mac;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{Sender, UnboundedSender};
+
+use crate::invoice::Invoice;
+
+use super::event_signing::{hmac, EventSigningKey, EVENT_SIGNATURE_HEADER};
+use super::{error::GatewayError, runtime::Runtime, AsyncCallback};
+
+/// ## Reflector
+///
+/// Destination for paid invoices. The poller dispatches every confirmed
+/// `(invoice_id, invoice)` pair to whichever variant is configured.
+///
+/// - `Sender`: an unbounded tokio mpsc channel.
+/// - `TokioSender`: a bounded tokio mpsc channel. The poller waits for capacity,
+///   so a slow consumer applies backpressure instead of growing memory.
+/// - `Broadcast`: a tokio broadcast channel fanning every paid invoice out to
+///   each of its receivers, e.g. accounting, fulfillment and notifications.
+///   Subscribe before starting the poller; a receiver lagging more than the
+///   channel capacity behind misses the oldest invoices. Delivery fails while
+///   there is no receiver.
+/// - `Callback`: an async closure that the poller awaits for each paid invoice.
+/// - `Webhook`: POSTs the paid invoice as JSON to `url`, signed with an
+///   HMAC-SHA256 of the body keyed by `secret` in the [`SIGNATURE_HEADER`] header.
+///   With an `event_signing_key` the body is also signed with it in the
+///   [`EVENT_SIGNATURE_HEADER`] header. The invoice wallet is never included in
+///   the payload. Failed deliveries are retried with backoff.
+///
+/// Invoices that cannot be delivered go to the gateway's dead-letter queue,
+/// see [`PaymentGateway::drain_dead_letters`](super::PaymentGateway::drain_dead_letters).
+#[derive(Clone)]
+pub enum Reflector {
+    Sender(UnboundedSender<(String, Invoice)>),
+    TokioSender(Sender<(String, Invoice)>),
+    Broadcast(broadcast::Sender<(String, Invoice)>),
+    Callback(AsyncCallback),
+    Webhook { url: String, secret: String },
+}
+
+/// Header carrying the hex encoded HMAC-SHA256 signature of a webhook body.
+pub const SIGNATURE_HEADER: &str = "X-AcceptEVM-Signature";
+
+/// Maximum number of delivery attempts for a single webhook event.
+const WEBHOOK_MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubled after every failed attempt.
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Longest wait for the webhook endpoint to accept the connection.
+const WEBHOOK_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Computes the hex encoded HMAC-SHA256 signature of a webhook body.
+///
+/// Webhook consumers can recompute this over the raw request body and compare
+/// it with the [`SIGNATURE_HEADER`] value to authenticate the request.
+pub fn webhook_signature(secret: &str, body: &[u8]) -> String {
+    hex::encode(hmac(secret, body).finalize().into_bytes())
+}
+
+/// HTTP client shared by every webhook delivery of a gateway. Each attempt
+/// gives up after `timeout`, so an endpoint that hangs cannot stall the poller.
+pub(crate) fn webhook_client(timeout: Duration) -> Result<reqwest::Client, GatewayError> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(timeout.min(WEBHOOK_CONNECT_TIMEOUT))
+        .build()
+        .map_err(|e| GatewayError::Reflector(e.to_string()))
+}
+
+impl Reflector {
+    /// Delivers a paid invoice to the configured destination, posting
+    /// webhooks with `client`.
+    pub(crate) async fn reflect(
+        &self,
+        runtime: &dyn Runtime,
+        signing_key: Option<&EventSigningKey>,
+        client: &reqwest::Client,
+        id: String,
+        invoice: Invoice,
+    ) -> Result<(), GatewayError> {
+        match self {
+            Reflector::Sender(sender) => sender
+                .send((id, invoice))
+                .map_err(|e| GatewayError::Reflector(e.to_string())),
+            Reflector::TokioSender(sender) => sender
+                .send((id, invoice))
+                .await
+                .map_err(|e| GatewayError::Reflector(e.to_string())),
+            Reflector::Broadcast(sender) => sender
+                .send((id, invoice))
+                .map(|_| ())
+                .map_err(|e| GatewayError::Reflector(e.to_string())),
+            Reflector::Callback(callback) => {
+                callback(id, invoice).await;
+                Ok(())
+            }
+            Reflector::Webhook { url, secret } => {
+                post_webhook(runtime, signing_key, client, url, secret, &id, &invoice).await
+            }
+        }
+    }
+}
+
+/// Serializes a paid invoice without its private key material.
+fn webhook_body(id: &str, invoice: &Invoice) -> Result<Vec<u8>, GatewayError> {
+    let invoice = invoice
+        .redacted()
+        .map_err(|e| GatewayError::Reflector(e.to_string()))?;
+    serde_json::to_vec(&serde_json::json!({ "invoice_id": id, "invoice": invoice }))
+        .map_err(|e| GatewayError::Reflector(e.to_string()))
+}
+
+async fn post_webhook(
+    runtime: &dyn Runtime,
+    signing_key: Option<&EventSigningKey>,
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    id: &str,
+    invoice: &Invoice,
+) -> Result<(), GatewayError> {
+    let body = webhook_body(id, invoice)?;
+    let signature = webhook_signature(secret, &body);
+    let event_signature = signing_key.map(|key| key.sign_payload(&body)).transpose()?;
+
+    let mut delay = WEBHOOK_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature);
+        if let Some(event_signature) = &event_signature {
+            request = request.header(EVENT_SIGNATURE_HEADER, event_signature);
+        }
+        let result = request
+            .body(body.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match result {
+            Ok(_) => return Ok(()),
+            Err(e) if attempt >= WEBHOOK_MAX_ATTEMPTS => {
+                return Err(GatewayError::Reflector(e.to_string()))
+            }
+            Err(e) => {
+                tracing::warn!("Webhook delivery attempt {attempt} failed, retrying: {e}");
+                runtime.sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+impl From<UnboundedSender<(String, Invoice)>> for Reflector {
+    fn from(sender: UnboundedSender<(String, Invoice)>) -> Self {
+        Reflector::Sender(sender)
+    }
+}
+
+impl From<Sender<(String, Invoice)>> for Reflector {
+    fn from(sender: Sender<(String, Invoice)>) -> Self {
+        Reflector::TokioSender(sender)
+    }
+}
+
+impl From<broadcast::Sender<(String, Invoice)>> for Reflector {
+    fn from(sender: broadcast::Sender<(String, Invoice)>) -> Self {
+        Reflector::Broadcast(sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::runtime::TokioRuntime;
+    use tokio::sync::mpsc;
+
+    fn client() -> reqwest::Client {
+        webhook_client(Duration::from_secs(10)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn unbounded_sender_delivers() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let reflector = Reflector::from(tx);
+        reflector
+            .reflect(&TokioRuntime, None, &client(), "id".to_string(), Invoice::default())
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap().0, "id");
+    }
+
+    #[tokio::test]
+    async fn bounded_sender_delivers() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let reflector = Reflector::from(tx);
+        reflector
+            .reflect(&TokioRuntime, None, &client(), "id".to_string(), Invoice::default())
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap().0, "id");
+    }
+
+    #[tokio::test]
+    async fn broadcast_reaches_every_receiver() {
+        let (tx, mut accounting) = broadcast::channel(4);
+        let mut fulfillment = tx.subscribe();
+        let reflector = Reflector::from(tx);
+        reflector
+            .reflect(&TokioRuntime, None, &client(), "id".to_string(), Invoice::default())
+            .await
+            .unwrap();
+        assert_eq!(accounting.recv().await.unwrap().0, "id");
+        assert_eq!(fulfillment.recv().await.unwrap().0, "id");
+    }
+
+    #[tokio::test]
+    async fn broadcast_without_receivers_returns_error() {
+        let (tx, rx) = broadcast::channel(4);
+        drop(rx);
+        let result = Reflector::Broadcast(tx)
+            .reflect(&TokioRuntime, None, &client(), "id".to_string(), Invoice::default())
+            .await;
+        assert!(matches!(result, Err(GatewayError::Reflector(_))));
+    }
+
+    #[tokio::test]
+    async fn callback_is_awaited() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let callback: AsyncCallback = std::sync::Arc::new(move |id, _invoice| {
+            let tx = tx.clone();
+            Box::pin(async move {
+                tx.send(id).ok();
+            })
+        });
+        Reflector::Callback(callback)
+            .reflect(&TokioRuntime, None, &client(), "id".to_string(), Invoice::default())
+            .await
+            .unwrap();
+        assert_eq!(rx.try_recv().unwrap(), "id");
+    }
+
+    #[test]
+    fn webhook_signature_known_vector() {
+        // Verified: echo -n "The quick brown fox jumps over the lazy dog" | openssl dgst -sha256 -hmac key
+        assert_eq!(
+            webhook_signature("key", b"The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn webhook_body_omits_wallet() {
+        let invoice = Invoice {
+            wallet: crate::invoice::ZeroizedVec {
+                inner: vec![1u8; 32],
+            },
+            ..Default::default()
+        };
+        let body: serde_json::Value =
+            serde_json::from_slice(&webhook_body("id", &invoice).unwrap()).unwrap();
+        assert_eq!(body["invoice_id"], "id");
+        assert!(body["invoice"].get("wallet").is_none());
+        assert!(body["invoice"].get("amount").is_some());
+    }
+
+    #[tokio::test]
+    async fn webhook_retries_and_signs() {
+        use axum::{http::HeaderMap, http::StatusCode, routing::post, Router};
+        use std::sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        };
+
+        let attempts = Arc::new(AtomicU32::new(0));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/",
+            post({
+                let attempts = attempts.clone();
+                move |headers: HeaderMap, body: axum::body::Bytes| async move {
+                    // Fail the first attempt to exercise the retry path
+                    if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return StatusCode::INTERNAL_SERVER_ERROR;
+                    }
+                    let signature = headers[SIGNATURE_HEADER].to_str().unwrap().to_string();
+                    let event_signature = headers[EVENT_SIGNATURE_HEADER].to_str().unwrap();
+                    tx.send((signature, event_signature.to_string(), body.to_vec())).ok();
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+
+        let reflector = Reflector::Webhook {
+            url,
+            secret: "secret".to_string(),
+        };
+        let key = EventSigningKey::Eip191(alloy::signers::local::PrivateKeySigner::random());
+        reflector
+            .reflect(&TokioRuntime, Some(&key), &client(), "id".to_string(), Invoice::default())
+            .await
+            .unwrap();
+
+        let (signature, event_signature, body) = rx.recv().await.unwrap();
+        assert_eq!(signature, webhook_signature("secret", &body));
+        assert!(key.verifier().verify_payload(&body, &event_signature));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn hanging_webhook_times_out() {
+        use axum::{routing::post, Router};
+
+        let app = Router::new().route("/", post(std::future::pending::<()>));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+
+        let reflector = Reflector::Webhook {
+            url,
+            secret: "secret".to_string(),
+        };
+        let client = webhook_client(Duration::from_millis(100)).unwrap();
+        let delivery =
+            reflector.reflect(&TokioRuntime, None, &client, "id".to_string(), Invoice::default());
+        let result = tokio::time::timeout(Duration::from_secs(5), delivery)
+            .await
+            .expect("every attempt must time out");
+        assert!(matches!(result, Err(GatewayError::Reflector(_))));
+    }
+
+    #[tokio::test]
+    async fn closed_channel_returns_error() {
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        let result = Reflector::TokioSender(tx)
+            .reflect(&TokioRuntime, None, &client(), "id".to_string(), Invoice::default())
+            .await;
+        assert!(matches!(result, Err(GatewayError::Reflector(_))));
+    }
+}
@@ -0,0 +1,134 @@
+use alloy::primitives::{Address, ChainId};
+use serde::Serialize;
+
+use crate::invoice::{InvoiceOptions, PaymentOption};
+
+use super::{amount::InvoiceAmount, error::GatewayError, result::Result};
+
+/// ## InvoiceRequest
+///
+/// Builder for [`PaymentGateway::create_invoice`](super::PaymentGateway::create_invoice),
+/// e.g.
+///
+/// ```rust
+/// # use acceptevm::gateway::{Address, InvoiceRequest};
+/// let request = InvoiceRequest::new("12.50", 3600)
+///     .token(Address::repeat_byte(0x01))
+///     .min_confirmations(3)
+///     .external_id("order-42");
+/// ```
+///
+/// The request is validated when the invoice is created; invalid settings
+/// are returned as [`GatewayError::InvalidInvoiceRequest`].
+#[derive(Clone, Debug)]
+pub struct InvoiceRequest {
+    pub(crate) amount: InvoiceAmount,
+    pub(crate) message: Vec<u8>,
+    pub(crate) expires_in_seconds: u64,
+    pub(crate) options: InvoiceOptions,
+}
+
+impl InvoiceRequest {
+    /// Requests `amount`, in the smallest unit or as a decimal string, payable
+    /// for `expires_in_seconds`.
+    pub fn new(amount: impl Into<InvoiceAmount>, expires_in_seconds: u64) -> Self {
+        Self {
+            amount: amount.into(),
+            message: Vec::new(),
+            expires_in_seconds,
+            options: InvoiceOptions::default(),
+        }
+    }
+
+    /// Starts from the given [`InvoiceOptions`].
+    pub fn options(mut self, options: InvoiceOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Arbitrary bytes attached to the invoice.
+    pub fn message(mut self, message: Vec<u8>) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Attaches JSON encoded `metadata`, read back with
+    /// [`Invoice::metadata`](crate::invoice::Invoice::metadata).
+    pub fn metadata<M: Serialize>(mut self, metadata: &M) -> Result<Self> {
+        self.message =
+            serde_json::to_vec(metadata).map_err(|e| GatewayError::Metadata(e.to_string()))?;
+        Ok(self)
+    }
+
+    /// ERC20 token the invoice is paid in instead of the native currency.
+    pub fn token(mut self, token: Address) -> Self {
+        self.options.token = Some(token);
+        self
+    }
+
+    /// Further asset the invoice can be paid in.
+    pub fn alternative(mut self, option: PaymentOption) -> Self {
+        self.options.alternatives.push(option);
+        self
+    }
+
+    /// Treasury this invoice is swept to instead of the gateway's.
+    pub fn treasury(mut self, treasury: Address) -> Self {
+        self.options.treasury = Some(treasury);
+        self
+    }
+
+    /// Confirmations required instead of the gateway's `min_confirmations`.
+    pub fn min_confirmations(mut self, confirmations: u64) -> Self {
+        self.options.min_confirmations = Some(confirmations);
+        self
+    }
+
+    /// Gas limit of each sweep transfer instead of the gateway's
+    /// `sweep_gas_limit`.
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.options.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Minimum time between balance checks of the invoice.
+    pub fn check_interval_seconds(mut self, seconds: u64) -> Self {
+        self.options.check_interval_seconds = Some(seconds);
+        self
+    }
+
+    /// Chain the invoice is meant for; creation fails if the gateway serves
+    /// another one.
+    pub fn chain_id(mut self, chain_id: ChainId) -> Self {
+        self.options.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Reference of the invoice in the calling system, e.g. an order id.
+    /// Retried requests with the same external id return the pending invoice
+    /// instead of creating a new one.
+    pub fn external_id(mut self, external_id: impl Into<String>) -> Self {
+        self.options.external_id = Some(external_id.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+
+    #[test]
+    fn builder_sets_options() {
+        let request = InvoiceRequest::new(U256::from(5u64), 60)
+            .treasury(Address::repeat_byte(0x01))
+            .min_confirmations(2)
+            .chain_id(56)
+            .external_id("order-1");
+        assert!(matches!(request.amount, InvoiceAmount::Units(amount) if amount == U256::from(5)));
+        assert_eq!(request.options.treasury, Some(Address::repeat_byte(0x01)));
+        assert_eq!(request.options.min_confirmations, Some(2));
+        assert_eq!(request.options.chain_id, Some(56));
+        assert_eq!(request.options.external_id.as_deref(), Some("order-1"));
+    }
+}
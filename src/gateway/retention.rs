@@ -0,0 +1,93 @@
+use crate::invoice::{Invoice, InvoiceStatus};
+
+/// ## RetentionPolicy
+///
+/// How long settled invoices are kept before the poller's periodic cleanup
+/// purges them, wallet keys included, every `cleanup_interval_seconds`:
+///
+/// - `paid_seconds`: invoices in the paid history, counted from their payment.
+/// - `expired_seconds`: invoices in the expired archive, counted from their
+///   expiry. Applies on top of `expired_retention`, also while no invoice
+///   expires.
+/// - `failed_seconds`: invoices whose sweep was given up on (`SweepFailed`),
+///   counted from when it was. Their funds are left on the wallet, so
+///   recover them first, e.g. from a
+///   [`recovery_report`](super::PaymentGateway::recovery_report).
+///
+/// `None` keeps the invoices, which is the default. Every purged invoice is
+/// recorded with a `Purged` audit entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub paid_seconds: Option<u64>,
+    pub expired_seconds: Option<u64>,
+    pub failed_seconds: Option<u64>,
+    pub cleanup_interval_seconds: u64,
+}
+
+impl Default for RetentionPolicy {
+    /// Keeps everything, checking hourly once a retention period is set.
+    fn default() -> Self {
+        Self {
+            paid_seconds: None,
+            expired_seconds: None,
+            failed_seconds: None,
+            cleanup_interval_seconds: 3600,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Whether any invoices are ever purged.
+    pub fn is_enabled(&self) -> bool {
+        self.paid_seconds.is_some()
+            || self.expired_seconds.is_some()
+            || self.failed_seconds.is_some()
+    }
+
+    /// Whether the pending `invoice` gave up on its sweep more than
+    /// `failed_seconds` before `now`.
+    pub(crate) fn purges_failed(&self, invoice: &Invoice, now: u64) -> bool {
+        let Some(retention) = self.failed_seconds else {
+            return false;
+        };
+        if invoice.status != InvoiceStatus::SweepFailed {
+            return false;
+        }
+        let failed_at = invoice
+            .transitions
+            .iter()
+            .rev()
+            .find(|transition| transition.to == InvoiceStatus::SweepFailed)
+            .map_or(invoice.created_at, |transition| transition.at_ms / 1000);
+        failed_at.saturating_add(retention) < now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invoice::StatusTransition;
+
+    #[test]
+    fn failed_invoices_are_purged_after_failed_seconds() {
+        let policy = RetentionPolicy {
+            failed_seconds: Some(100),
+            ..Default::default()
+        };
+        let failed = Invoice {
+            status: InvoiceStatus::SweepFailed,
+            transitions: vec![StatusTransition {
+                from: InvoiceStatus::Failed,
+                to: InvoiceStatus::SweepFailed,
+                at_ms: 1_000_000,
+            }],
+            ..Default::default()
+        };
+        assert!(!policy.purges_failed(&failed, 1_100));
+        assert!(policy.purges_failed(&failed, 1_101));
+
+        let pending = Invoice::default();
+        assert!(!policy.purges_failed(&pending, u64::MAX));
+        assert!(!RetentionPolicy::default().purges_failed(&failed, u64::MAX));
+    }
+}
@@ -0,0 +1,70 @@
+/// ## SweepRetryPolicy
+///
+/// Controls how failed sweeps are retried. After every failed attempt the
+/// invoice waits `initial_backoff_seconds`, doubled per consecutive failure and
+/// capped at `max_backoff_seconds`. Once `max_attempts` sweeps failed in a row
+/// the invoice moves to `SweepFailed` and a `SweepFailed` event is raised; its
+/// funds stay on the invoice wallet until swept manually.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SweepRetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for SweepRetryPolicy {
+    /// 10 attempts, backing off from 10 seconds up to an hour.
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            initial_backoff_seconds: 10,
+            max_backoff_seconds: 3600,
+        }
+    }
+}
+
+impl SweepRetryPolicy {
+    /// Seconds to wait before the next attempt after `attempts` consecutive failures.
+    pub fn backoff_seconds(&self, attempts: u32) -> u64 {
+        let doublings = attempts.saturating_sub(1).min(63);
+        self.initial_backoff_seconds
+            .saturating_mul(1u64 << doublings)
+            .min(self.max_backoff_seconds)
+    }
+
+    /// Whether `attempts` consecutive failures exhaust the policy.
+    pub fn is_exhausted(&self, attempts: u32) -> bool {
+        attempts >= self.max_attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_per_attempt() {
+        let policy = SweepRetryPolicy::default();
+        assert_eq!(policy.backoff_seconds(1), 10);
+        assert_eq!(policy.backoff_seconds(2), 20);
+        assert_eq!(policy.backoff_seconds(4), 80);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = SweepRetryPolicy::default();
+        assert_eq!(policy.backoff_seconds(9), 2560);
+        assert_eq!(policy.backoff_seconds(10), 3600);
+        assert_eq!(policy.backoff_seconds(u32::MAX), 3600);
+    }
+
+    #[test]
+    fn exhausted_after_max_attempts() {
+        let policy = SweepRetryPolicy {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        assert!(!policy.is_exhausted(2));
+        assert!(policy.is_exhausted(3));
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// ## RpcSelection
+///
+/// Decides which of the configured `rpc_urls` each request goes to.
+///
+/// - `RoundRobin`: requests are distributed across all URLs in turn.
+/// - `Failover`: every request goes to the current URL, starting with the
+///   first one. After `max_failures` consecutive errors or timeouts the gateway
+///   switches to the next URL and raises a `ProviderSwitched` event.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RpcSelection {
+    #[default]
+    RoundRobin,
+    Failover { max_failures: u32 },
+}
+
+/// Index of the RPC URL in use and the failures it accumulated.
+#[derive(Default)]
+pub(crate) struct RpcRotation {
+    index: AtomicUsize,
+    /// Consecutive failures of the current URL in failover mode
+    failures: AtomicU32,
+}
+
+impl RpcRotation {
+    /// Returns the index of the URL the next request goes to.
+    pub(crate) fn next(&self, selection: RpcSelection, len: usize) -> usize {
+        match selection {
+            RpcSelection::RoundRobin => self.index.fetch_add(1, Ordering::Relaxed) % len,
+            RpcSelection::Failover { .. } => self.index.load(Ordering::Relaxed) % len,
+        }
+    }
+
+    /// Records a failed request to the URL at `index`. Returns the indexes
+    /// switched from and to when the failure made the gateway fail over.
+    pub(crate) fn failed(
+        &self,
+        selection: RpcSelection,
+        len: usize,
+        index: usize,
+    ) -> Option<(usize, usize)> {
+        let RpcSelection::Failover { max_failures } = selection else {
+            return None;
+        };
+        let current = self.index.load(Ordering::Relaxed) % len;
+        // Late failures of a URL that was already switched away from
+        if current != index {
+            return None;
+        }
+        if self.failures.fetch_add(1, Ordering::Relaxed) + 1 < max_failures {
+            return None;
+        }
+        let next = (current + 1) % len;
+        self.index
+            .compare_exchange(current, next, Ordering::Relaxed, Ordering::Relaxed)
+            .ok()?;
+        self.failures.store(0, Ordering::Relaxed);
+        Some((current, next))
+    }
+
+    /// Records a successful request to the URL at `index`.
+    pub(crate) fn succeeded(&self, len: usize, index: usize) {
+        if self.index.load(Ordering::Relaxed) % len == index {
+            self.failures.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAILOVER: RpcSelection = RpcSelection::Failover { max_failures: 2 };
+
+    #[test]
+    fn failover_sticks_to_current_url() {
+        let rotation = RpcRotation::default();
+        assert_eq!(rotation.next(FAILOVER, 3), 0);
+        assert_eq!(rotation.next(FAILOVER, 3), 0);
+    }
+
+    #[test]
+    fn repeated_failures_switch_to_next_url() {
+        let rotation = RpcRotation::default();
+        assert_eq!(rotation.failed(FAILOVER, 2, 0), None);
+        assert_eq!(rotation.failed(FAILOVER, 2, 0), Some((0, 1)));
+        assert_eq!(rotation.next(FAILOVER, 2), 1);
+        // Late failures of the previous URL do not count
+        assert_eq!(rotation.failed(FAILOVER, 2, 0), None);
+        assert_eq!(rotation.failed(FAILOVER, 2, 1), None);
+        assert_eq!(rotation.failed(FAILOVER, 2, 1), Some((1, 0)));
+    }
+
+    #[test]
+    fn success_resets_failures() {
+        let rotation = RpcRotation::default();
+        assert_eq!(rotation.failed(FAILOVER, 2, 0), None);
+        rotation.succeeded(2, 0);
+        assert_eq!(rotation.failed(FAILOVER, 2, 0), None);
+        assert_eq!(rotation.next(FAILOVER, 2), 0);
+    }
+
+    #[test]
+    fn round_robin_never_fails_over() {
+        let rotation = RpcRotation::default();
+        for _ in 0..5 {
+            assert_eq!(rotation.failed(RpcSelection::RoundRobin, 2, 0), None);
+        }
+    }
+}
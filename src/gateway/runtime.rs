@@ -0,0 +1,62 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use futures::future::{select, Either};
+
+/// Boxed future of a background task handed to [`Runtime::spawn`].
+pub type TaskFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+/// Boxed future returned by [`Runtime::sleep`].
+pub type SleepFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Async runtime the gateway spawns its background tasks and waits on.
+///
+/// Configure one as `runtime` to run the poller under an executor other than
+/// tokio, e.g. async-std or smol. Note that alloy's HTTP transport is built
+/// on reqwest, which still needs a tokio reactor to be reachable, e.g.
+/// through `async-compat`.
+pub trait Runtime: Send + Sync {
+    /// Runs `task` in the background.
+    fn spawn(&self, task: TaskFuture);
+
+    /// Completes after `duration`.
+    fn sleep(&self, duration: Duration) -> SleepFuture;
+}
+
+/// The default runtime: `tokio::spawn` and `tokio::time::sleep`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, task: TaskFuture) {
+        tokio::spawn(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> SleepFuture {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Runs `future` for at most `duration`, returning `None` when it took longer.
+pub(crate) async fn timeout<F: Future>(
+    runtime: &dyn Runtime,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    match select(future, runtime.sleep(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn timeout_returns_output_of_fast_futures_only() {
+        let fast = timeout(&TokioRuntime, Duration::from_secs(5), async { 7 }).await;
+        assert_eq!(fast, Some(7));
+        let slow = TokioRuntime.sleep(Duration::from_secs(5));
+        assert_eq!(timeout(&TokioRuntime, Duration::from_millis(10), slow).await, None);
+    }
+}
@@ -0,0 +1,106 @@
+use std::{future::Future, pin::Pin};
+
+use alloy::consensus::TxEnvelope;
+use alloy::network::{Ethereum, EthereumWallet, NetworkTransactionBuilder};
+use alloy::primitives::{Address, B256};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+
+use crate::invoice::ZeroizedVec;
+
+use super::error::GatewayError;
+
+/// Boxed future returned by [`SweepSigner::new_key`].
+pub type KeyFuture = Pin<Box<dyn Future<Output = Result<SweepKey, GatewayError>> + Send>>;
+/// Boxed future returned by [`SweepSigner::sign`].
+pub type SignFuture = Pin<Box<dyn Future<Output = Result<TxEnvelope, GatewayError>> + Send>>;
+
+/// Deposit address of a new invoice together with the key material the
+/// signer needs to sign for it later.
+pub struct SweepKey {
+    pub address: Address,
+    /// Stored as `Invoice.wallet`. The private key itself for
+    /// [`LocalSweepSigner`], or a reference such as an HSM key id for signers
+    /// that keep keys elsewhere.
+    pub key: ZeroizedVec,
+}
+
+/// Creates invoice keys and signs the sweeps of their invoices.
+///
+/// Configure one as `sweep_signer` to keep invoice keys in an HSM or a remote
+/// signing service. Every invoice that is not HD-derived is then created and
+/// swept through it; without one, invoices hold a local private key.
+pub trait SweepSigner: Send + Sync {
+    /// Creates the key of a new invoice.
+    fn new_key(&self) -> KeyFuture;
+
+    /// Signs `tx`, sent from the invoice address, with the invoice `key` as
+    /// returned by [`SweepSigner::new_key`].
+    fn sign(&self, key: &[u8], tx: TransactionRequest) -> SignFuture;
+}
+
+/// The default signer: a random secp256k1 key per invoice, stored in the
+/// invoice and signed with in process.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalSweepSigner;
+
+impl LocalSweepSigner {
+    fn signer(key: &[u8]) -> Result<PrivateKeySigner, GatewayError> {
+        let key = B256::try_from(key).map_err(|e| GatewayError::InvalidWallet(e.to_string()))?;
+        PrivateKeySigner::from_bytes(&key).map_err(|e| GatewayError::InvalidWallet(e.to_string()))
+    }
+}
+
+impl SweepSigner for LocalSweepSigner {
+    fn new_key(&self) -> KeyFuture {
+        let signer = PrivateKeySigner::random();
+        let key = ZeroizedVec {
+            inner: signer.credential().to_bytes().to_vec(),
+        };
+        Box::pin(async move {
+            Ok(SweepKey {
+                address: signer.address(),
+                key,
+            })
+        })
+    }
+
+    fn sign(&self, key: &[u8], tx: TransactionRequest) -> SignFuture {
+        let signer = Self::signer(key);
+        Box::pin(async move {
+            let wallet = EthereumWallet::from(signer?);
+            <TransactionRequest as NetworkTransactionBuilder<Ethereum>>::build(tx, &wallet)
+                .await
+                .map_err(|e| GatewayError::Signing(e.to_string()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::consensus::transaction::SignerRecoverable;
+    use alloy::network::TransactionBuilder;
+    use alloy::primitives::U256;
+
+    #[tokio::test]
+    async fn local_signer_signs_for_its_key() {
+        let key = LocalSweepSigner.new_key().await.unwrap();
+        let tx = TransactionRequest::default()
+            .with_from(key.address)
+            .with_to(Address::repeat_byte(0x01))
+            .with_value(U256::from(1u64))
+            .with_nonce(0)
+            .with_chain_id(1)
+            .with_gas_limit(21_000)
+            .with_gas_price(1);
+        let envelope = LocalSweepSigner.sign(&key.key.inner, tx).await.unwrap();
+        assert_eq!(envelope.recover_signer().unwrap(), key.address);
+    }
+
+    #[tokio::test]
+    async fn local_signer_rejects_malformed_key() {
+        let result = LocalSweepSigner.sign(&[1, 2, 3], TransactionRequest::default()).await;
+        assert!(matches!(result, Err(GatewayError::InvalidWallet(_))));
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::error::GatewayError;
+
+/// Boxed future returned by [`SponsorStore`] methods.
+pub type SponsorFuture = Pin<Box<dyn Future<Output = Result<SponsorState, GatewayError>> + Send>>;
+/// Change applied to the state by [`SponsorStore::update`].
+pub type SponsorChange = Box<dyn FnOnce(&mut SponsorState) + Send>;
+
+/// A gas top-up of an invoice wallet by the gas sponsor that was not seen
+/// mined yet.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SponsorFunding {
+    /// Invoice wallet topped up
+    pub wallet: Address,
+    pub amount: U256,
+    /// Nonce of the sponsor reserved for the top-up
+    pub nonce: u64,
+    /// Hash of the signed top-up, set before it is broadcast
+    pub tx_hash: Option<B256>,
+    /// The signed top-up, broadcast again when it is not mined in time
+    pub raw_tx: Option<Bytes>,
+    /// Unix time in seconds at which the nonce was reserved
+    pub created_at: u64,
+}
+
+/// Accounting of the gas sponsor shared by every gateway using the same
+/// [`SponsorStore`].
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SponsorState {
+    /// Value of the top-ups that were mined
+    pub spent: U256,
+    /// Value of the top-ups that reverted and so stayed with the sponsor
+    pub refunded: U256,
+    /// Top-ups sent, or about to be sent, and not seen mined yet
+    pub pending: Vec<SponsorFunding>,
+}
+
+/// How a pending funding ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FundingOutcome {
+    Mined,
+    Reverted,
+    /// Never broadcast, or its nonce was taken by another transaction
+    Dropped,
+}
+
+impl SponsorState {
+    /// The pending funding of `wallet`, if any.
+    pub fn pending_for(&self, wallet: Address) -> Option<&SponsorFunding> {
+        self.pending.iter().find(|funding| funding.wallet == wallet)
+    }
+
+    /// Records a funding of `wallet` unless one is pending already, and
+    /// returns its nonce: the lowest one from `chain_nonce` on that no
+    /// pending funding holds.
+    pub(crate) fn reserve(
+        &mut self,
+        wallet: Address,
+        amount: U256,
+        chain_nonce: u64,
+        now: u64,
+    ) -> Option<u64> {
+        if self.pending_for(wallet).is_some() {
+            return None;
+        }
+        let nonce = (chain_nonce..)
+            .find(|nonce| self.pending.iter().all(|funding| funding.nonce != *nonce))
+            .expect("a pending funding holds every nonce");
+        self.pending.push(SponsorFunding {
+            wallet,
+            amount,
+            nonce,
+            tx_hash: None,
+            raw_tx: None,
+            created_at: now,
+        });
+        Some(nonce)
+    }
+
+    /// Attaches the signed top-up to the funding of `wallet` with `nonce`.
+    pub(crate) fn signed(&mut self, wallet: Address, nonce: u64, tx_hash: B256, raw_tx: Bytes) {
+        let funding = self
+            .pending
+            .iter_mut()
+            .find(|funding| funding.wallet == wallet && funding.nonce == nonce);
+        if let Some(funding) = funding {
+            funding.tx_hash = Some(tx_hash);
+            funding.raw_tx = Some(raw_tx);
+        }
+    }
+
+    /// Removes the funding of `wallet` with `nonce`, accounting for its value.
+    pub(crate) fn settle(&mut self, wallet: Address, nonce: u64, outcome: FundingOutcome) {
+        let Some(index) = self
+            .pending
+            .iter()
+            .position(|funding| funding.wallet == wallet && funding.nonce == nonce)
+        else {
+            return;
+        };
+        let funding = self.pending.remove(index);
+        match outcome {
+            FundingOutcome::Mined => self.spent += funding.amount,
+            FundingOutcome::Reverted => self.refunded += funding.amount,
+            FundingOutcome::Dropped => {}
+        }
+    }
+}
+
+/// Durable storage of the [`SponsorState`], so restarted gateways and
+/// replicas sharing a gas sponsor neither fund an invoice wallet twice nor
+/// send two top-ups with the same nonce.
+///
+/// Configure one as `sponsor_store`. Every top-up is recorded with its nonce
+/// before it is broadcast and removed once its receipt arrives; a wallet
+/// with a pending top-up is not topped up again. Replicas need a store that
+/// applies [`SponsorStore::update`] atomically across processes, e.g. in a
+/// database transaction.
+pub trait SponsorStore: Send + Sync {
+    /// The saved state, empty when nothing was saved yet.
+    fn load(&self) -> SponsorFuture;
+
+    /// Applies `change` to the saved state and returns the new state. No
+    /// other update may run between reading and saving the state.
+    fn update(&self, change: SponsorChange) -> SponsorFuture;
+}
+
+/// Sponsor store keeping the state in memory, used without a `sponsor_store`.
+#[derive(Default)]
+pub(crate) struct MemorySponsorStore {
+    state: Arc<Mutex<SponsorState>>,
+}
+
+impl SponsorStore for MemorySponsorStore {
+    fn load(&self) -> SponsorFuture {
+        let state = self.state.clone();
+        Box::pin(async move { Ok(state.lock().await.clone()) })
+    }
+
+    fn update(&self, change: SponsorChange) -> SponsorFuture {
+        let state = self.state.clone();
+        Box::pin(async move {
+            let mut state = state.lock().await;
+            change(&mut state);
+            Ok(state.clone())
+        })
+    }
+}
+
+/// One line of a [`CsvSponsorStore`] file: a `spent` or `refunded` total in
+/// `amount`, or a `pending` funding.
+#[derive(Default, Deserialize, Serialize)]
+struct Row {
+    kind: String,
+    wallet: Option<Address>,
+    amount: U256,
+    nonce: Option<u64>,
+    tx_hash: Option<B256>,
+    raw_tx: Option<Bytes>,
+    created_at: Option<u64>,
+}
+
+/// Sponsor store in a CSV file with the totals on the first lines and one
+/// line per pending funding.
+///
+/// Updates are atomic within the process and rewrite the file through a
+/// temporary file, so a crash never leaves it half written. It serves a
+/// single gateway across restarts, not replicas.
+#[derive(Clone)]
+pub struct CsvSponsorStore {
+    path: PathBuf,
+    /// Contents of the file, read on first use
+    state: Arc<Mutex<Option<SponsorState>>>,
+}
+
+impl CsvSponsorStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            state: Arc::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn read(&self) -> Result<SponsorState, GatewayError> {
+        let csv = match tokio::fs::read(&self.path).await {
+            Ok(csv) => csv,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+            Err(e) => return Err(store_error(e)),
+        };
+        let mut state = SponsorState::default();
+        for row in csv::Reader::from_reader(csv.as_slice()).deserialize() {
+            let row: Row = row.map_err(store_error)?;
+            match row.kind.as_str() {
+                "spent" => state.spent = row.amount,
+                "refunded" => state.refunded = row.amount,
+                "pending" => state.pending.push(SponsorFunding {
+                    wallet: row.wallet.ok_or_else(|| store_error("pending row without wallet"))?,
+                    amount: row.amount,
+                    nonce: row.nonce.ok_or_else(|| store_error("pending row without nonce"))?,
+                    tx_hash: row.tx_hash,
+                    raw_tx: row.raw_tx,
+                    created_at: row.created_at.unwrap_or_default(),
+                }),
+                kind => return Err(store_error(format!("unknown row kind '{kind}'"))),
+            }
+        }
+        Ok(state)
+    }
+
+    async fn write(&self, state: &SponsorState) -> Result<(), GatewayError> {
+        let total = |kind: &str, amount| Row {
+            kind: kind.to_string(),
+            amount,
+            ..Default::default()
+        };
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let rows = [total("spent", state.spent), total("refunded", state.refunded)]
+            .into_iter()
+            .chain(state.pending.iter().map(|funding| Row {
+                kind: "pending".to_string(),
+                wallet: Some(funding.wallet),
+                amount: funding.amount,
+                nonce: Some(funding.nonce),
+                tx_hash: funding.tx_hash,
+                raw_tx: funding.raw_tx.clone(),
+                created_at: Some(funding.created_at),
+            }));
+        for row in rows {
+            writer.serialize(row).map_err(store_error)?;
+        }
+        let csv = writer.into_inner().map_err(store_error)?;
+
+        let mut temporary = self.path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        tokio::fs::write(&temporary, csv).await.map_err(store_error)?;
+        tokio::fs::rename(&temporary, &self.path).await.map_err(store_error)
+    }
+}
+
+impl SponsorStore for CsvSponsorStore {
+    fn load(&self) -> SponsorFuture {
+        let store = self.clone();
+        Box::pin(async move {
+            let mut cached = store.state.lock().await;
+            match cached.as_ref() {
+                Some(state) => Ok(state.clone()),
+                None => Ok(cached.insert(store.read().await?).clone()),
+            }
+        })
+    }
+
+    fn update(&self, change: SponsorChange) -> SponsorFuture {
+        let store = self.clone();
+        Box::pin(async move {
+            let mut cached = store.state.lock().await;
+            let mut state = match cached.take() {
+                Some(state) => state,
+                None => store.read().await?,
+            };
+            change(&mut state);
+            // On failure the file is read again next time rather than trusting the cache
+            store.write(&state).await?;
+            Ok(cached.insert(state).clone())
+        })
+    }
+}
+
+fn store_error(e: impl std::fmt::Display) -> GatewayError {
+    GatewayError::Store(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: Address = Address::repeat_byte(0x01);
+    const OTHER: Address = Address::repeat_byte(0x02);
+
+    #[test]
+    fn fundings_take_the_lowest_free_nonce() {
+        let mut state = SponsorState::default();
+        assert_eq!(state.reserve(WALLET, U256::from(5u64), 3, 0), Some(3));
+        assert_eq!(state.reserve(WALLET, U256::from(5u64), 3, 0), None);
+        assert_eq!(state.reserve(OTHER, U256::from(7u64), 3, 0), Some(4));
+
+        // A dropped funding frees its nonce for the next one
+        state.settle(WALLET, 3, FundingOutcome::Dropped);
+        assert_eq!(state.reserve(WALLET, U256::from(5u64), 3, 0), Some(3));
+        state.settle(WALLET, 3, FundingOutcome::Mined);
+        state.settle(OTHER, 4, FundingOutcome::Reverted);
+        assert!(state.pending.is_empty());
+        assert_eq!(state.spent, U256::from(5u64));
+        assert_eq!(state.refunded, U256::from(7u64));
+    }
+
+    #[tokio::test]
+    async fn csv_store_persists_totals_and_pending_fundings() {
+        let path =
+            std::env::temp_dir().join(format!("acceptevm-sponsor-{}.csv", std::process::id()));
+        let store = CsvSponsorStore::new(&path);
+        assert_eq!(store.load().await.unwrap(), SponsorState::default());
+
+        store
+            .update(Box::new(|state| {
+                state.reserve(WALLET, U256::from(5u64), 0, 10);
+                state.reserve(OTHER, U256::from(7u64), 0, 10);
+                state.signed(WALLET, 0, B256::repeat_byte(0xAA), Bytes::from_static(&[1, 2]));
+                state.settle(OTHER, 1, FundingOutcome::Mined);
+            }))
+            .await
+            .unwrap();
+
+        // A new store reads what the first one wrote
+        let loaded = CsvSponsorStore::new(&path).load().await.unwrap();
+        assert_eq!(loaded.spent, U256::from(7u64));
+        assert_eq!(loaded.refunded, U256::ZERO);
+        assert_eq!(
+            loaded.pending,
+            vec![SponsorFunding {
+                wallet: WALLET,
+                amount: U256::from(5u64),
+                nonce: 0,
+                tx_hash: Some(B256::repeat_byte(0xAA)),
+                raw_tx: Some(Bytes::from_static(&[1, 2])),
+                created_at: 10,
+            }]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
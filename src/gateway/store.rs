@@ -0,0 +1,163 @@
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use ahash::AHashMap;
+use tokio::sync::Mutex;
+
+use crate::invoice::Invoice;
+
+use super::backup::{read_csv, write_csv};
+use super::error::GatewayError;
+
+/// Boxed future returned by [`InvoiceStore::save`] and [`InvoiceStore::remove`].
+pub type SaveFuture = Pin<Box<dyn Future<Output = Result<(), GatewayError>> + Send>>;
+/// Boxed future returned by [`InvoiceStore::load`].
+pub type LoadFuture =
+    Pin<Box<dyn Future<Output = Result<Vec<(String, Invoice)>, GatewayError>> + Send>>;
+
+/// Durable storage of open invoices, wallet keys included, so a gateway that
+/// crashed or restarted resumes where it stopped.
+///
+/// Configure one as `invoice_store`. The gateway saves an invoice when it is
+/// created and whenever the poller changes it, and removes it once it was
+/// delivered, cancelled or expired. A detected payment is saved before the
+/// sweep starts, and invoice creation fails when the new invoice cannot be
+/// saved, so no wallet key ever exists only in memory.
+pub trait InvoiceStore: Send + Sync {
+    /// Saves the current state of an invoice, replacing the previous one.
+    fn save(&self, invoice_id: String, invoice: Invoice) -> SaveFuture;
+
+    /// Removes an invoice; removing an unknown invoice is not an error.
+    fn remove(&self, invoice_id: String) -> SaveFuture;
+
+    /// Every saved invoice.
+    fn load(&self) -> LoadFuture;
+}
+
+/// Invoice store in a CSV file in the format of
+/// [`PaymentGateway::export_csv`](super::PaymentGateway::export_csv).
+///
+/// The file is rewritten through a temporary file on every change, so a
+/// crash never leaves it half written. Suited to the invoice volume of a
+/// single shop; larger deployments implement [`InvoiceStore`] on a database.
+#[derive(Clone)]
+pub struct CsvInvoiceStore {
+    path: PathBuf,
+    /// Contents of the file, read on first use
+    invoices: Arc<Mutex<Option<AHashMap<String, Invoice>>>>,
+}
+
+impl CsvInvoiceStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            invoices: Arc::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn read(&self) -> Result<Vec<(String, Invoice)>, GatewayError> {
+        match tokio::fs::try_exists(&self.path).await {
+            Ok(true) => read_csv(&self.path).await,
+            Ok(false) => Ok(Vec::new()),
+            Err(e) => Err(store_error(e)),
+        }
+    }
+
+    async fn update(
+        &self,
+        change: impl FnOnce(&mut AHashMap<String, Invoice>),
+    ) -> Result<(), GatewayError> {
+        let mut cached = self.invoices.lock().await;
+        let invoices = match cached.as_mut() {
+            Some(invoices) => invoices,
+            None => cached.insert(self.read().await?.into_iter().collect()),
+        };
+        change(invoices);
+        let snapshot = invoices
+            .iter()
+            .map(|(id, invoice)| (id.clone(), invoice.clone()))
+            .collect();
+
+        let mut temporary = self.path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let written = match write_csv(Path::new(&temporary), snapshot).await {
+            Ok(_) => tokio::fs::rename(&temporary, &self.path).await.map_err(store_error),
+            Err(e) => Err(e),
+        };
+        if written.is_err() {
+            // Read the file again next time rather than trusting the cache
+            *cached = None;
+        }
+        written
+    }
+}
+
+impl InvoiceStore for CsvInvoiceStore {
+    fn save(&self, invoice_id: String, invoice: Invoice) -> SaveFuture {
+        let store = self.clone();
+        Box::pin(async move {
+            store
+                .update(|invoices| {
+                    invoices.insert(invoice_id, invoice);
+                })
+                .await
+        })
+    }
+
+    fn remove(&self, invoice_id: String) -> SaveFuture {
+        let store = self.clone();
+        Box::pin(async move {
+            store
+                .update(|invoices| {
+                    invoices.remove(&invoice_id);
+                })
+                .await
+        })
+    }
+
+    fn load(&self) -> LoadFuture {
+        let store = self.clone();
+        Box::pin(async move { store.read().await })
+    }
+}
+
+fn store_error(e: impl std::fmt::Display) -> GatewayError {
+    GatewayError::Store(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invoice::InvoiceStatus;
+
+    #[tokio::test]
+    async fn csv_store_saves_and_removes_invoices() {
+        let path = std::env::temp_dir().join(format!("acceptevm-store-{}.csv", std::process::id()));
+        let store = CsvInvoiceStore::new(&path);
+        assert!(store.load().await.unwrap().is_empty());
+
+        let paid = Invoice {
+            status: InvoiceStatus::Paid,
+            ..Default::default()
+        };
+        store.save("a".to_string(), Invoice::default()).await.unwrap();
+        store.save("b".to_string(), Invoice::default()).await.unwrap();
+        store.save("a".to_string(), paid).await.unwrap();
+        store.remove("b".to_string()).await.unwrap();
+
+        // A new store reads what the first one wrote
+        let loaded = CsvInvoiceStore::new(&path).load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].0, "a");
+        assert_eq!(loaded[0].1.status, InvoiceStatus::Paid);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
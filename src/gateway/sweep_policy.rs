@@ -0,0 +1,19 @@
+use alloy::primitives::U256;
+
+/// ## SweepPolicy
+///
+/// Decides when the poller sweeps paid invoices to the treasury. Invoices
+/// held back by the policy stay `Paid` and keep their funds until swept.
+///
+/// - `Immediate`: sweep as soon as a payment is detected.
+/// - `Scheduled`: sweep once per `every_seconds` window, aligned to Unix time
+///   like a cron schedule, e.g. `3600` sweeps in the first poll cycle of every hour.
+/// - `Threshold`: sweep once the balances of all paid invoices add up to
+///   `min_total`. Balances are summed as-is, so use it with invoices of a single currency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SweepPolicy {
+    #[default]
+    Immediate,
+    Scheduled { every_seconds: u64 },
+    Threshold { min_total: U256 },
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+use std::ops::{Bound, Range};
+use std::sync::Mutex;
+
+use crate::invoice::Invoice;
+
+/// Ids of the open invoices ordered by id, by creation and by expiry time, so
+/// pages and time range queries only visit the invoices they return.
+///
+/// Time entries are `(timestamp, id)`, both timestamps being fixed once an
+/// invoice is created. Callers look the ids up in the invoice map, which
+/// stays the source of truth.
+#[derive(Default)]
+pub(crate) struct InvoiceTimeIndex {
+    inner: Mutex<TimeIndex>,
+}
+
+#[derive(Default)]
+struct TimeIndex {
+    ids: BTreeSet<String>,
+    created: BTreeSet<(u64, String)>,
+    expiring: BTreeSet<(u64, String)>,
+}
+
+impl InvoiceTimeIndex {
+    pub(crate) fn insert(&self, key: &str, invoice: &Invoice) {
+        let mut index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        index.ids.insert(key.to_string());
+        index.created.insert((invoice.created_at, key.to_string()));
+        index.expiring.insert((invoice.expires, key.to_string()));
+    }
+
+    pub(crate) fn remove(&self, key: &str, invoice: &Invoice) {
+        let mut index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        index.ids.remove(key);
+        index.created.remove(&(invoice.created_at, key.to_string()));
+        index.expiring.remove(&(invoice.expires, key.to_string()));
+    }
+
+    /// Up to `limit` ids in order, starting after the id `after` or else at
+    /// the first one.
+    pub(crate) fn page(&self, after: Option<&str>, limit: usize) -> Vec<String> {
+        let index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let start = match after {
+            Some(after) => Bound::Excluded(after),
+            None => Bound::Unbounded,
+        };
+        index
+            .ids
+            .range::<str, _>((start, Bound::Unbounded))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Ids of the invoices created within `range`, oldest first.
+    pub(crate) fn created_within(&self, range: Range<u64>) -> Vec<String> {
+        let index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        within(&index.created, range)
+    }
+
+    /// Ids of the invoices expiring within `range`, soonest first.
+    pub(crate) fn expiring_within(&self, range: Range<u64>) -> Vec<String> {
+        let index = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        within(&index.expiring, range)
+    }
+}
+
+fn within(entries: &BTreeSet<(u64, String)>, range: Range<u64>) -> Vec<String> {
+    if range.is_empty() {
+        return Vec::new();
+    }
+    entries
+        .range((range.start, String::new())..(range.end, String::new()))
+        .map(|(_, key)| key.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice(created_at: u64, expires: u64) -> Invoice {
+        Invoice {
+            created_at,
+            expires,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ranges_are_half_open_and_ordered_by_time() {
+        let index = InvoiceTimeIndex::default();
+        index.insert("b", &invoice(20, 100));
+        index.insert("a", &invoice(10, 300));
+        index.insert("c", &invoice(30, 200));
+
+        assert_eq!(index.created_within(10..30), ["a", "b"]);
+        assert_eq!(index.created_within(0..u64::MAX), ["a", "b", "c"]);
+        assert_eq!(index.expiring_within(0..300), ["b", "c"]);
+        let (from, to) = (30, 10);
+        assert!(index.created_within(from..to).is_empty());
+    }
+
+    #[test]
+    fn removed_invoices_leave_the_index() {
+        let index = InvoiceTimeIndex::default();
+        let created = invoice(10, 100);
+        index.insert("a", &created);
+        index.remove("a", &created);
+
+        assert!(index.created_within(0..u64::MAX).is_empty());
+        assert!(index.expiring_within(0..u64::MAX).is_empty());
+        assert!(index.page(None, 10).is_empty());
+    }
+
+    #[test]
+    fn pages_start_after_the_cursor() {
+        let index = InvoiceTimeIndex::default();
+        for key in ["c", "a", "b"] {
+            index.insert(key, &invoice(10, 100));
+        }
+
+        assert_eq!(index.page(None, 2), ["a", "b"]);
+        assert_eq!(index.page(Some("b"), 2), ["c"]);
+        // A cursor that was removed meanwhile still orders the rest
+        assert_eq!(index.page(Some("aa"), 5), ["b", "c"]);
+        assert!(index.page(Some("c"), 2).is_empty());
+    }
+}
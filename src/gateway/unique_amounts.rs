@@ -0,0 +1,79 @@
+use ahash::AHashSet;
+use alloy::primitives::{Address, U256};
+
+/// ## UniqueAmounts
+///
+/// Shared deposit address mode: every invoice is paid to the same `address`
+/// and told apart by a unique amount. Each new invoice gets the smallest
+/// free suffix of `unit` added to its amount, e.g. `1 ETH + 123 * unit`, and
+/// the poller matches incoming transfers to the invoice with exactly that
+/// amount.
+///
+/// - `address`: the shared deposit address. The gateway holds no key for it
+///   and never sweeps it, so use an address you control, e.g. the treasury.
+/// - `unit`: value of one suffix step in wei.
+/// - `max_suffix`: how many suffixes are available. Creating an invoice
+///   fails once all amounts for its base amount are taken by open invoices.
+/// - `lookback_blocks`: how many blocks before the latest confirmed one the
+///   poller scans when it starts.
+///
+/// Only native currency payments can be matched this way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UniqueAmounts {
+    pub address: Address,
+    pub unit: U256,
+    pub max_suffix: u64,
+    pub lookback_blocks: u64,
+}
+
+impl UniqueAmounts {
+    /// Suffixes in steps of 10^6 wei up to 999999, i.e. at most 0.000001 ETH
+    /// on top of the requested amount, with a lookback of 100 blocks.
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            unit: U256::from(1_000_000u64),
+            max_suffix: 999_999,
+            lookback_blocks: 100,
+        }
+    }
+
+    /// Picks the smallest suffixed amount for `base` that is not in `taken`.
+    pub(crate) fn allocate(&self, base: U256, taken: &AHashSet<U256>) -> Option<U256> {
+        (1..=self.max_suffix)
+            .map(|suffix| base.checked_add(self.unit.checked_mul(U256::from(suffix))?))
+            .map_while(|amount| amount)
+            .find(|amount| !taken.contains(amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unique(max_suffix: u64) -> UniqueAmounts {
+        UniqueAmounts {
+            unit: U256::from(10u64),
+            max_suffix,
+            ..UniqueAmounts::new(Address::ZERO)
+        }
+    }
+
+    #[test]
+    fn smallest_free_suffix_is_allocated() {
+        let base = U256::from(1_000u64);
+        let mut taken = AHashSet::new();
+        assert_eq!(unique(3).allocate(base, &taken), Some(U256::from(1_010u64)));
+        taken.insert(U256::from(1_010u64));
+        taken.insert(U256::from(1_030u64));
+        assert_eq!(unique(3).allocate(base, &taken), Some(U256::from(1_020u64)));
+    }
+
+    #[test]
+    fn allocation_fails_when_suffixes_run_out() {
+        let base = U256::from(1_000u64);
+        let taken = [1_010u64, 1_020].map(U256::from).into_iter().collect();
+        assert_eq!(unique(2).allocate(base, &taken), None);
+        assert_eq!(unique(2).allocate(U256::MAX, &AHashSet::new()), None);
+    }
+}
@@ -0,0 +1,576 @@
+//! gRPC API exposing a gateway to microservices, as defined in
+//! `proto/acceptevm.proto`.
+//!
+//! | RPC             | Action                                                   |
+//! |-----------------|----------------------------------------------------------|
+//! | `CreateInvoice` | Create an invoice from a [`proto::CreateInvoiceRequest`] |
+//! | `GetInvoice`    | Get a pending invoice                                    |
+//! | `CancelInvoice` | Cancel a pending invoice                                 |
+//! | `WatchPayments` | Stream [`proto::PaymentEvent`]s                          |
+//!
+//! Invoices are returned without their wallet key. Paid invoices are only
+//! streamed by `WatchPayments` when the gateway's reflector comes from
+//! [`PaymentEvents::reflector`].
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use alloy::primitives::{Address, U256};
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tonic::codegen::{http, Body, BoxFuture, StdError};
+use tonic::server::{Grpc, NamedService, ServerStreamingService, UnaryService};
+use tonic::{Request, Response, Status};
+use tonic_prost::ProstCodec;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::event::GatewayEvent;
+use crate::gateway::{InvoiceRequest, PaymentGateway, Reflector};
+use crate::invoice::{Invoice, InvoiceOptions};
+
+/// Protobuf messages of `proto/acceptevm.proto`.
+pub mod proto {
+    /// Request of `CreateInvoice`. Exactly one of `amount` and `units` is required.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CreateInvoiceRequest {
+        /// Decimal amount, e.g. `"1.5"`, in the native currency or `token`
+        #[prost(string, optional, tag = "1")]
+        pub amount: Option<String>,
+        /// Amount in the smallest unit, as a decimal string
+        #[prost(string, optional, tag = "2")]
+        pub units: Option<String>,
+        #[prost(uint64, tag = "3")]
+        pub expires_in_seconds: u64,
+        /// ERC20 token the invoice is paid in
+        #[prost(string, optional, tag = "4")]
+        pub token: Option<String>,
+        /// Stored as the invoice message
+        #[prost(string, optional, tag = "5")]
+        pub message: Option<String>,
+        #[prost(string, optional, tag = "6")]
+        pub external_id: Option<String>,
+        #[prost(string, optional, tag = "7")]
+        pub treasury: Option<String>,
+        #[prost(uint64, optional, tag = "8")]
+        pub min_confirmations: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InvoiceId {
+        #[prost(string, tag = "1")]
+        pub invoice_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InvoiceReply {
+        #[prost(string, tag = "1")]
+        pub invoice_id: String,
+        #[prost(message, optional, tag = "2")]
+        pub invoice: Option<Invoice>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CancelInvoiceReply {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WatchPaymentsRequest {
+        /// Only events of this invoice; all events when unset
+        #[prost(string, optional, tag = "1")]
+        pub invoice_id: Option<String>,
+    }
+
+    /// An invoice without its wallet key. Addresses are `0x` hex strings and
+    /// amounts decimal strings of the smallest unit.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Invoice {
+        #[prost(string, tag = "1")]
+        pub to: String,
+        #[prost(string, tag = "2")]
+        pub amount: String,
+        #[prost(string, optional, tag = "3")]
+        pub token: Option<String>,
+        #[prost(bytes = "vec", tag = "4")]
+        pub message: Vec<u8>,
+        #[prost(uint64, tag = "5")]
+        pub created_at: u64,
+        #[prost(uint64, tag = "6")]
+        pub expires: u64,
+        #[prost(string, tag = "7")]
+        pub status: String,
+        #[prost(string, optional, tag = "8")]
+        pub hash: Option<String>,
+        #[prost(uint64, tag = "9")]
+        pub paid_at_timestamp: u64,
+        #[prost(string, optional, tag = "10")]
+        pub payer: Option<String>,
+        #[prost(string, optional, tag = "11")]
+        pub external_id: Option<String>,
+    }
+
+    /// An invoice delivered on the gateway's reflector.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PaidInvoice {
+        #[prost(string, tag = "1")]
+        pub invoice_id: String,
+        #[prost(message, optional, tag = "2")]
+        pub invoice: Option<Invoice>,
+    }
+
+    /// A [`GatewayEvent`](crate::gateway::event::GatewayEvent), its fields
+    /// given as the JSON the gateway serializes it to.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GatewayEvent {
+        /// Name of the event, e.g. `PartialPayment`
+        #[prost(string, tag = "1")]
+        pub r#type: String,
+        #[prost(string, optional, tag = "2")]
+        pub invoice_id: Option<String>,
+        #[prost(string, tag = "3")]
+        pub json: String,
+    }
+
+    /// Message streamed by `WatchPayments`.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PaymentEvent {
+        #[prost(oneof = "payment_event::Kind", tags = "1, 2")]
+        pub kind: Option<payment_event::Kind>,
+    }
+
+    pub mod payment_event {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Kind {
+            /// An invoice was delivered on the reflector
+            #[prost(message, tag = "1")]
+            Paid(super::PaidInvoice),
+            /// An operational event of the gateway
+            #[prost(message, tag = "2")]
+            Gateway(super::GatewayEvent),
+        }
+    }
+}
+
+/// Name of the gRPC service.
+pub const SERVICE_NAME: &str = "acceptevm.v1.PaymentGateway";
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::PaymentEvent, Status>> + Send>>;
+
+/// Feeds paid invoices into the `WatchPayments` streams.
+///
+/// Configure [`PaymentEvents::reflector`] as the gateway's `reflector`, or
+/// call [`PaymentEvents::publish`] from your own reflector callback.
+#[derive(Clone)]
+pub struct PaymentEvents {
+    sender: broadcast::Sender<proto::PaidInvoice>,
+}
+
+impl PaymentEvents {
+    /// Buffers up to `capacity` events for slow subscribers.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity.max(1)).0,
+        }
+    }
+
+    /// Reflector publishing every paid invoice to the `WatchPayments` streams.
+    pub fn reflector(&self) -> Reflector {
+        let events = self.clone();
+        Reflector::Callback(Arc::new(move |invoice_id, invoice| {
+            events.publish(invoice_id, &invoice);
+            Box::pin(async {})
+        }))
+    }
+
+    /// Publishes a paid invoice to current `WatchPayments` subscribers.
+    pub fn publish(&self, invoice_id: String, invoice: &Invoice) {
+        let _ = self.sender.send(proto::PaidInvoice {
+            invoice_id,
+            invoice: Some(invoice_message(invoice)),
+        });
+    }
+}
+
+impl Default for PaymentEvents {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+/// The gRPC service, serving `gateway`. Add it to a
+/// [`tonic::transport::Server`] or run it with [`serve`].
+#[derive(Clone)]
+pub struct PaymentGatewayService {
+    gateway: PaymentGateway,
+    events: PaymentEvents,
+}
+
+impl PaymentGatewayService {
+    pub fn new(gateway: PaymentGateway, events: PaymentEvents) -> Self {
+        Self { gateway, events }
+    }
+
+    async fn create_invoice(
+        &self,
+        request: proto::CreateInvoiceRequest,
+    ) -> Result<proto::InvoiceReply, Status> {
+        let expires_in_seconds = request.expires_in_seconds;
+        let invoice_request = match (request.amount, request.units) {
+            (Some(amount), None) => InvoiceRequest::new(amount, expires_in_seconds),
+            (None, Some(units)) => {
+                let units = U256::from_str_radix(&units, 10)
+                    .map_err(|e| Status::invalid_argument(format!("invalid units: {e}")))?;
+                InvoiceRequest::new(units, expires_in_seconds)
+            }
+            _ => {
+                let message = "exactly one of amount and units is required";
+                return Err(Status::invalid_argument(message));
+            }
+        };
+        let invoice_request = invoice_request
+            .options(InvoiceOptions {
+                token: request.token.as_deref().map(address).transpose()?,
+                external_id: request.external_id,
+                treasury: request.treasury.as_deref().map(address).transpose()?,
+                min_confirmations: request.min_confirmations,
+                ..Default::default()
+            })
+            .message(request.message.unwrap_or_default().into_bytes());
+        let (invoice_id, invoice) = self.gateway.create_invoice(invoice_request).await?;
+        Ok(invoice_reply(invoice_id, &invoice))
+    }
+
+    async fn get_invoice(&self, request: proto::InvoiceId) -> Result<proto::InvoiceReply, Status> {
+        let invoice = self.gateway.get_invoice(&request.invoice_id).await?;
+        Ok(invoice_reply(request.invoice_id, &invoice))
+    }
+
+    /// The wallet key returned by the gateway is not sent over gRPC; funds
+    /// that already reached a cancelled invoice cannot be recovered through
+    /// the API.
+    async fn cancel_invoice(
+        &self,
+        request: proto::InvoiceId,
+    ) -> Result<proto::CancelInvoiceReply, Status> {
+        self.gateway.cancel_invoice(&request.invoice_id).await?;
+        Ok(proto::CancelInvoiceReply {})
+    }
+
+    fn watch_payments(&self, request: proto::WatchPaymentsRequest) -> EventStream {
+        use proto::payment_event::Kind;
+
+        let paid = receive(self.events.sender.subscribe()).map(|paid| Ok(Kind::Paid(paid)));
+        let gateway = receive(self.gateway.subscribe_events())
+            .map(|event| event_message(&event).map(Kind::Gateway));
+        let events = stream::select(paid, gateway).filter_map(move |kind| {
+            let wanted = match (&kind, &request.invoice_id) {
+                (_, None) | (Err(_), _) => true,
+                (Ok(Kind::Paid(paid)), Some(invoice_id)) => &paid.invoice_id == invoice_id,
+                (Ok(Kind::Gateway(event)), Some(invoice_id)) => {
+                    event.invoice_id.as_ref() == Some(invoice_id)
+                }
+            };
+            let event = kind.map(|kind| proto::PaymentEvent { kind: Some(kind) });
+            std::future::ready(wanted.then_some(event))
+        });
+        Box::pin(events)
+    }
+}
+
+/// Items of `receiver`, skipping the ones missed by falling behind.
+fn receive<T: Clone + Send + 'static>(receiver: broadcast::Receiver<T>) -> impl Stream<Item = T> {
+    BroadcastStream::new(receiver).filter_map(|item| async move {
+        match item {
+            Ok(item) => Some(item),
+            Err(e) => {
+                tracing::warn!("Event subscriber lagged: {e}");
+                None
+            }
+        }
+    })
+}
+
+fn address(value: &str) -> Result<Address, Status> {
+    Address::from_str(value)
+        .map_err(|e| Status::invalid_argument(format!("invalid address '{value}': {e}")))
+}
+
+fn invoice_reply(invoice_id: String, invoice: &Invoice) -> proto::InvoiceReply {
+    proto::InvoiceReply {
+        invoice_id,
+        invoice: Some(invoice_message(invoice)),
+    }
+}
+
+/// `invoice` without its wallet key.
+fn invoice_message(invoice: &Invoice) -> proto::Invoice {
+    proto::Invoice {
+        to: invoice.to.to_string(),
+        amount: invoice.amount.to_string(),
+        token: invoice.token.map(|token| token.to_string()),
+        message: invoice.message.clone(),
+        created_at: invoice.created_at,
+        expires: invoice.expires,
+        status: format!("{:?}", invoice.status),
+        hash: invoice.hash.clone(),
+        paid_at_timestamp: invoice.paid_at_timestamp,
+        payer: invoice.payer.map(|payer| payer.to_string()),
+        external_id: invoice.external_id.clone(),
+    }
+}
+
+fn event_message(event: &GatewayEvent) -> Result<proto::GatewayEvent, Status> {
+    let json = serde_json::to_value(event).map_err(|e| Status::internal(e.to_string()))?;
+    let field = |name: &str| json.get(name).and_then(Value::as_str).map(str::to_string);
+    Ok(proto::GatewayEvent {
+        r#type: field("type").unwrap_or_default(),
+        invoice_id: field("invoice_id"),
+        json: json.to_string(),
+    })
+}
+
+impl From<GatewayError> for Status {
+    fn from(error: GatewayError) -> Self {
+        let message = error.to_string();
+        match error {
+            GatewayError::NotFound => Status::not_found(message),
+            GatewayError::InvalidInvoiceRequest(_)
+            | GatewayError::InvalidAmount(_)
+            | GatewayError::Metadata(_)
+            | GatewayError::ChainMismatch { .. }
+            | GatewayError::Unsupported(_) => Status::invalid_argument(message),
+            GatewayError::UniqueAmountsExhausted => Status::resource_exhausted(message),
+            _ => Status::internal(message),
+        }
+    }
+}
+
+/// Adapts one RPC of the service to tonic's method traits.
+struct Method<F>(PaymentGatewayService, F);
+
+impl<Req, Res, F, Fut> UnaryService<Req> for Method<F>
+where
+    F: Fn(PaymentGatewayService, Req) -> Fut,
+    Fut: std::future::Future<Output = Result<Res, Status>> + Send + 'static,
+{
+    type Response = Res;
+    type Future = BoxFuture<Response<Res>, Status>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        let reply = (self.1)(self.0.clone(), request.into_inner());
+        Box::pin(async move { reply.await.map(Response::new) })
+    }
+}
+
+impl<F> ServerStreamingService<proto::WatchPaymentsRequest> for Method<F>
+where
+    F: Fn(&PaymentGatewayService, proto::WatchPaymentsRequest) -> EventStream,
+{
+    type Response = proto::PaymentEvent;
+    type ResponseStream = EventStream;
+    type Future = BoxFuture<Response<EventStream>, Status>;
+
+    fn call(&mut self, request: Request<proto::WatchPaymentsRequest>) -> Self::Future {
+        let events = (self.1)(&self.0, request.into_inner());
+        Box::pin(async move { Ok(Response::new(events)) })
+    }
+}
+
+impl<B> tonic::codegen::Service<http::Request<B>> for PaymentGatewayService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let service = self.clone();
+        let method = request.uri().path().strip_prefix("/acceptevm.v1.PaymentGateway/");
+        match method {
+            Some("CreateInvoice") => Box::pin(async move {
+                let method = Method(service, |service: PaymentGatewayService, request| async move {
+                    service.create_invoice(request).await
+                });
+                Ok(Grpc::new(ProstCodec::default()).unary(method, request).await)
+            }),
+            Some("GetInvoice") => Box::pin(async move {
+                let method = Method(service, |service: PaymentGatewayService, request| async move {
+                    service.get_invoice(request).await
+                });
+                Ok(Grpc::new(ProstCodec::default()).unary(method, request).await)
+            }),
+            Some("CancelInvoice") => Box::pin(async move {
+                let method = Method(service, |service: PaymentGatewayService, request| async move {
+                    service.cancel_invoice(request).await
+                });
+                Ok(Grpc::new(ProstCodec::default()).unary(method, request).await)
+            }),
+            Some("WatchPayments") => Box::pin(async move {
+                let method = Method(service, PaymentGatewayService::watch_payments);
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(grpc.server_streaming(method, request).await)
+            }),
+            _ => Box::pin(async move { Ok(Status::unimplemented("unknown method").into_http()) }),
+        }
+    }
+}
+
+impl NamedService for PaymentGatewayService {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+/// Serves the gRPC API on `listener` until the server fails.
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    gateway: PaymentGateway,
+    events: PaymentEvents,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(PaymentGatewayService::new(gateway, events))
+        .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+        .await
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tonic::codegen::http::uri::PathAndQuery;
+    use tonic::transport::Channel;
+
+    use super::*;
+    use crate::gateway::PaymentGatewayConfiguration;
+    use crate::test_utils::mock_node::MockNode;
+
+    const TREASURY: Address = Address::repeat_byte(0xDB);
+
+    async fn start(node: &MockNode) -> (tonic::client::Grpc<Channel>, PaymentGateway) {
+        let events = PaymentEvents::default();
+        let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+            poller_delay_seconds: 0,
+            min_confirmations: 0,
+            ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, events.reflector())
+        })
+        .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, gateway.clone(), events));
+        let channel = tonic::transport::Endpoint::from_shared(url).unwrap().connect().await.unwrap();
+        (tonic::client::Grpc::new(channel), gateway)
+    }
+
+    async fn unary<Req, Res>(
+        client: &mut tonic::client::Grpc<Channel>,
+        path: &'static str,
+        request: Req,
+    ) -> Result<Res, Status>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Res: prost::Message + Default + Send + Sync + 'static,
+    {
+        client.ready().await.unwrap();
+        let path = PathAndQuery::from_static(path);
+        let codec = ProstCodec::default();
+        Ok(client.unary(Request::new(request), path, codec).await?.into_inner())
+    }
+
+    #[tokio::test]
+    async fn invoices_are_created_fetched_and_cancelled() {
+        let node = MockNode::start().await;
+        let (mut client, gateway) = start(&node).await;
+
+        let request = proto::CreateInvoiceRequest {
+            amount: Some("1.5".to_string()),
+            expires_in_seconds: 3600,
+            external_id: Some("order-9".to_string()),
+            ..Default::default()
+        };
+        let created: proto::InvoiceReply =
+            unary(&mut client, "/acceptevm.v1.PaymentGateway/CreateInvoice", request)
+                .await
+                .unwrap();
+        let invoice = created.invoice.unwrap();
+        assert_eq!(invoice.amount, "1500000000000000000");
+        assert_eq!(invoice.external_id.as_deref(), Some("order-9"));
+        assert_eq!(invoice.status, "Pending");
+
+        let id = proto::InvoiceId {
+            invoice_id: created.invoice_id.clone(),
+        };
+        let fetched: proto::InvoiceReply =
+            unary(&mut client, "/acceptevm.v1.PaymentGateway/GetInvoice", id.clone())
+                .await
+                .unwrap();
+        assert_eq!(fetched.invoice, Some(invoice));
+
+        let _: proto::CancelInvoiceReply =
+            unary(&mut client, "/acceptevm.v1.PaymentGateway/CancelInvoice", id.clone())
+                .await
+                .unwrap();
+        assert!(gateway.get_invoice(&created.invoice_id).await.is_err());
+        let missing: Result<proto::InvoiceReply, _> =
+            unary(&mut client, "/acceptevm.v1.PaymentGateway/GetInvoice", id).await;
+        assert_eq!(missing.unwrap_err().code(), tonic::Code::NotFound);
+
+        let invalid = proto::CreateInvoiceRequest {
+            expires_in_seconds: 3600,
+            ..Default::default()
+        };
+        let invalid: Result<proto::InvoiceReply, _> =
+            unary(&mut client, "/acceptevm.v1.PaymentGateway/CreateInvoice", invalid).await;
+        assert_eq!(invalid.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn watch_payments_streams_the_paid_invoice() {
+        let node = MockNode::start().await;
+        let (mut client, gateway) = start(&node).await;
+        let amount = U256::from(1_000_000_000_000_000_000u128);
+        let (other, other_invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+        let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+
+        client.ready().await.unwrap();
+        let request = proto::WatchPaymentsRequest {
+            invoice_id: Some(id.clone()),
+        };
+        let path = PathAndQuery::from_static("/acceptevm.v1.PaymentGateway/WatchPayments");
+        let mut events = client
+            .server_streaming(Request::new(request), path, ProstCodec::default())
+            .await
+            .unwrap()
+            .into_inner();
+
+        node.set_balance(other_invoice.to, amount);
+        node.set_balance(invoice.to, amount);
+        let poller = gateway.poll_payments().await;
+        let paid = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let event: proto::PaymentEvent = events.message().await.unwrap().unwrap();
+                match event.kind.unwrap() {
+                    proto::payment_event::Kind::Paid(paid) => break paid,
+                    proto::payment_event::Kind::Gateway(event) => {
+                        assert_eq!(event.invoice_id.as_deref(), Some(id.as_str()));
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+        assert_eq!(paid.invoice_id, id);
+        assert_ne!(paid.invoice_id, other);
+        let paid_invoice = paid.invoice.unwrap();
+        assert_eq!(paid_invoice.to, invoice.to.to_string());
+        assert_eq!(paid_invoice.amount, amount.to_string());
+    }
+}
@@ -0,0 +1,64 @@
+/// The audit log follows an invoice from creation through payment and sweep
+/// to delivery.
+use std::time::Duration;
+
+use alloy::primitives::{Address, B256, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::audit::{AuditEntry, AuditLog};
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xAD);
+
+#[tokio::test]
+async fn test_audit_log_records_invoice_lifecycle() {
+    let node = MockNode::start().await;
+    let path = std::env::temp_dir().join(format!("acceptevm-audit-{}.jsonl", B256::random()));
+    let audit_log = AuditLog::new(&path);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        audit_log: Some(audit_log.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    let (cancelled, _) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    gateway.cancel_invoice(&cancelled).await.unwrap();
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    let records = audit_log.read().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(records.iter().any(|r| r.invoice_id == cancelled && r.entry == AuditEntry::Cancelled));
+    let entries: Vec<_> = records
+        .into_iter()
+        .filter(|r| r.invoice_id == id)
+        .map(|r| r.entry)
+        .collect();
+    assert!(matches!(entries[0], AuditEntry::Created { to, .. } if to == invoice.to));
+    assert!(entries.contains(&AuditEntry::StatusChanged {
+        from: InvoiceStatus::Pending,
+        to: InvoiceStatus::Paid,
+    }));
+    assert!(entries
+        .iter()
+        .any(|entry| matches!(entry, AuditEntry::SweepBroadcast { .. })));
+    assert_eq!(
+        entries.last(),
+        Some(&AuditEntry::Delivered {
+            status: InvoiceStatus::Swept
+        })
+    );
+}
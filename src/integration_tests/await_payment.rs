@@ -0,0 +1,63 @@
+/// `await_payment` resolves with the outcome of a single invoice.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::payment_watch::PaymentOutcome;
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xDB);
+
+#[tokio::test]
+async fn test_await_payment_resolves_when_paid() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    let (other, _) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+
+    let waiting = tokio::spawn({
+        let gateway = gateway.clone();
+        let id = id.clone();
+        async move { gateway.await_payment(&id, Duration::from_secs(10)).await }
+    });
+    node.set_balance(invoice.to, amount);
+    let poller = gateway.poll_payments().await;
+    let outcome = waiting.await.unwrap().unwrap();
+    assert!(matches!(outcome, PaymentOutcome::Paid(paid) if paid.status == InvoiceStatus::Paid));
+
+    // The unpaid invoice times out, the settled one resolves immediately
+    let unpaid = gateway.await_payment(&other, Duration::from_millis(200)).await;
+    assert!(matches!(unpaid, Ok(PaymentOutcome::TimedOut)));
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+    let settled = gateway.await_payment(&id, Duration::ZERO).await;
+    assert!(matches!(settled, Ok(PaymentOutcome::Paid(_))));
+}
+
+#[tokio::test]
+async fn test_await_payment_reports_expiry_and_cancellation() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let (expiring, _) = gateway.new_invoice(U256::from(1_000u64), vec![], 3600).await.unwrap();
+    let (cancelled, _) = gateway.new_invoice(U256::from(1_000u64), vec![], 3600).await.unwrap();
+    gateway.invoices.write().await.get_mut(&expiring).unwrap().expires = 1;
+
+    let waiting = tokio::spawn({
+        let gateway = gateway.clone();
+        let cancelled = cancelled.clone();
+        async move { gateway.await_payment(&cancelled, Duration::from_secs(10)).await }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    gateway.cancel_invoice(&cancelled).await.unwrap();
+    assert!(matches!(waiting.await.unwrap(), Ok(PaymentOutcome::Cancelled)));
+
+    let poller = gateway.poll_payments().await;
+    let outcome = gateway.await_payment(&expiring, Duration::from_secs(10)).await;
+    assert!(matches!(outcome, Ok(PaymentOutcome::Expired(_))));
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    let unknown = gateway.await_payment("unknown", Duration::from_secs(1)).await;
+    assert!(matches!(unknown, Err(GatewayError::NotFound)));
+}
@@ -0,0 +1,50 @@
+/// Paid invoices found in one poll cycle are swept together in batches of
+/// `sweep_batch_size` instead of one poller delay apart.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x9E);
+
+#[tokio::test]
+async fn test_paid_invoices_swept_as_one_batch() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 1,
+        min_confirmations: 0,
+        sweep_batch_size: 3,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    for _ in 0..3 {
+        let (_, invoice) = gateway
+            .new_invoice(amount, vec![], 3600)
+            .await
+            .expect("invoice creation must succeed");
+        node.set_balance(invoice.to, amount);
+    }
+
+    let _handle = gateway.poll_payments().await;
+
+    let mut broadcasts = Vec::new();
+    for _ in 0..3 {
+        let (_, confirmed) = timeout(Duration::from_secs(30), rx.recv())
+            .await
+            .expect("timed out waiting for confirmation")
+            .expect("channel closed");
+        let settlement = confirmed.settlement.expect("settlement must be recorded");
+        broadcasts.push(settlement.timings.broadcast_at_ms);
+    }
+
+    // Sequential sweeps would be at least one poller delay apart
+    let spread = broadcasts.iter().max().unwrap() - broadcasts.iter().min().unwrap();
+    assert!(spread < 1000, "sweeps were spread over {spread}ms");
+    assert_eq!(node.sent_txs().len(), 3);
+}
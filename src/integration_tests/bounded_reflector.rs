@@ -0,0 +1,46 @@
+/// A gateway configured with a bounded tokio mpsc channel (`Reflector::TokioSender`)
+/// must deliver paid invoices exactly like the unbounded default.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration, Reflector};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x44);
+
+#[tokio::test]
+async fn test_bounded_reflector_receives_paid_invoice() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::channel(1);
+    let config = PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        ..PaymentGatewayConfiguration::new(
+            vec![node.url.clone()],
+            TREASURY,
+            Reflector::TokioSender(tx),
+        )
+    };
+    let gateway = PaymentGateway::new(config).unwrap();
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    gateway.poll_payments().await;
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    assert_eq!(confirmed_id, id);
+    assert!(confirmed.hash.is_some());
+}
@@ -0,0 +1,36 @@
+/// A cancelled invoice must no longer be polled, even if it gets funded.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x5C);
+
+#[tokio::test]
+async fn test_cancelled_invoice_is_not_swept() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    let wallet = gateway
+        .cancel_invoice(&id)
+        .await
+        .expect("cancel must succeed");
+    assert_eq!(wallet.inner.len(), 32, "wallet bytes must be returned");
+
+    // Funds arriving after cancellation stay on the invoice address
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let result = timeout(Duration::from_secs(2), rx.recv()).await;
+    assert!(result.is_err(), "cancelled invoice must not be confirmed");
+    assert_eq!(node.get_balance(invoice.to), amount);
+    assert_eq!(node.get_treasury_balance(TREASURY), U256::ZERO);
+}
@@ -0,0 +1,50 @@
+/// If the RPC endpoint suddenly reports a different chain id than the one
+/// cached at startup, the sweep must be aborted before signing and a
+/// `ChainIdMismatch` event raised.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::event::GatewayEvent;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x55);
+
+#[tokio::test]
+async fn test_chain_id_switch_aborts_sweep() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    let mut events = gateway.subscribe_events();
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    gateway.poll_payments().await;
+
+    // Let the poller cache the chain id, then swap the endpoint's network
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(gateway.cached_chain_id(), Some(1));
+    node.set_chain_id(56);
+    node.set_balance(invoice.to, amount);
+
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for mismatch event")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::ChainIdMismatch {
+            invoice_id: id,
+            expected: 1,
+            actual: 56,
+        }
+    );
+
+    // Nothing may have been swept or confirmed
+    assert!(node.any_tx_hash().is_none(), "no tx may be broadcast");
+    assert!(timeout(Duration::from_secs(1), rx.recv()).await.is_err());
+}
@@ -0,0 +1,59 @@
+/// Sweeps follow the fee model and confirmation depth of each chain fixture.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::test_utils::{
+    gateway_helpers::make_chain_gateway, mock_node::MockNode, test_chain::TestChain,
+};
+
+const TREASURY: Address = Address::repeat_byte(0x3E);
+
+/// Funds an invoice on `chain`, waits for the sweep broadcast and returns the
+/// type of the transaction, then mines the fixture's confirmation depth and
+/// checks the invoice is delivered.
+async fn sweep_on(chain: TestChain) -> u8 {
+    let node = MockNode::start_with_chain(chain.clone()).await;
+    let (gateway, mut rx) = make_chain_gateway(&node, &chain, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let tx_type = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(receipt) = node.state.lock().unwrap().receipts.values().next() {
+                return receipt.tx_type;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("sweep must be broadcast");
+
+    // Not delivered before the fixture's confirmation depth is reached
+    assert!(rx.try_recv().is_err(), "{} sweep confirmed too early", chain.name);
+    node.mine_blocks(chain.min_confirmations);
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    tx_type
+}
+
+#[tokio::test]
+async fn test_ethereum_fixture_sweeps_with_eip1559_fees() {
+    assert_eq!(sweep_on(TestChain::ethereum()).await, 2);
+}
+
+#[tokio::test]
+async fn test_bsc_fixture_sweeps_with_legacy_fees() {
+    assert_eq!(sweep_on(TestChain::bsc()).await, 0);
+}
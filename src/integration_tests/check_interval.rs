@@ -0,0 +1,65 @@
+/// Invoices with a check interval are checked for payment at most once per
+/// interval, while invoices without one are checked every poll cycle.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::InvoiceOptions;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xC4);
+
+#[tokio::test]
+async fn test_invoice_with_interval_is_not_rechecked_early() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(10u128.pow(17));
+    let (fast_id, fast) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let options = InvoiceOptions {
+        check_interval_seconds: Some(3600),
+        ..Default::default()
+    };
+    let (slow_id, slow) = gateway
+        .new_invoice_with_options(amount, vec![], 3600, options)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(slow.check_interval_seconds, Some(3600));
+
+    gateway.poll_payments().await;
+    // Let the first cycle check both invoices while still unpaid
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    node.set_balance(fast.to, amount);
+    node.set_balance(slow.to, amount);
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, fast_id);
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(rx.try_recv().is_err(), "slow invoice must wait for its next check");
+    assert!(gateway.get_invoice(&slow_id).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_options_set_token_and_interval() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let options = InvoiceOptions {
+        token: Some(Address::repeat_byte(0x70)),
+        check_interval_seconds: Some(60),
+        ..Default::default()
+    };
+    let (_, invoice) = gateway
+        .new_invoice_with_options(U256::from(1u64), vec![], 3600, options)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.token, Some(Address::repeat_byte(0x70)));
+    assert_eq!(invoice.check_interval_seconds, Some(60));
+}
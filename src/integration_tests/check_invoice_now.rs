@@ -0,0 +1,42 @@
+/// `check_invoice_now` reads an invoice's balance on demand, without a
+/// running poller and without changing the invoice.
+use alloy::primitives::{Address, U256};
+
+use crate::gateway::error::GatewayError;
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xCB);
+
+#[tokio::test]
+async fn test_check_reports_balance_without_polling() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+
+    node.set_balance(invoice.to, amount / U256::from(2u64));
+    let check = gateway.check_invoice_now(&id).await.unwrap();
+    assert_eq!(check.status, InvoiceStatus::Pending);
+    assert_eq!(check.received, amount / U256::from(2u64));
+    assert_eq!(check.amount, amount);
+    assert!(!check.paid);
+    assert_eq!(check.confirmations, None);
+
+    node.set_balance(invoice.to, amount);
+    let check = gateway.check_invoice_now(&id).await.unwrap();
+    assert!(check.paid);
+    // Detection and the sweep are left to the poller
+    let unchanged = gateway.get_invoice(&id).await.unwrap();
+    assert_eq!(unchanged.status, InvoiceStatus::Pending);
+}
+
+#[tokio::test]
+async fn test_check_of_unknown_invoice_fails() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    assert!(matches!(
+        gateway.check_invoice_now("missing").await,
+        Err(GatewayError::NotFound)
+    ));
+}
@@ -0,0 +1,128 @@
+/// With a circuit breaker the poller stops polling a failing RPC at full rate,
+/// backs off and resumes once a probe succeeds. Failing endpoints are skipped
+/// while another one answers.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{
+    event::GatewayEvent, CircuitBreaker, PaymentGateway, PaymentGatewayConfiguration,
+};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xF0);
+const DEAD_URL: &str = "http://127.0.0.1:1";
+
+#[tokio::test]
+async fn test_failing_rpc_is_backed_off_and_probed_for_recovery() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        circuit_breaker: Some(CircuitBreaker {
+            failure_threshold: 3,
+            initial_backoff_seconds: 1,
+            max_backoff_seconds: 1,
+        }),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+    let mut events = gateway.subscribe_events();
+
+    let amount = U256::from(10u128.pow(17));
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_offline(true);
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for the degraded provider")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::ProviderDegraded {
+            rpc_url: node.url.clone(),
+            failures: 3,
+            retry_in_seconds: 1,
+        }
+    );
+
+    // While open, the poller sends at most one probe per backoff
+    let requests = node.request_count();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(node.request_count() - requests <= 2);
+
+    node.set_offline(false);
+    node.set_balance(invoice.to, amount);
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for the recovered provider")
+        .expect("event channel closed");
+    assert_eq!(event, GatewayEvent::ProviderRecovered { rpc_url: node.url.clone() });
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_failing_endpoint_is_skipped_while_another_answers() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        // Longer than the test, so only skipping the endpoint lets it pass
+        circuit_breaker: Some(CircuitBreaker {
+            failure_threshold: 2,
+            initial_backoff_seconds: 600,
+            max_backoff_seconds: 600,
+        }),
+        ..PaymentGatewayConfiguration::new(
+            vec![DEAD_URL.to_string(), node.url.clone()],
+            TREASURY,
+            tx,
+        )
+    })
+    .expect("gateway creation must not fail");
+    let mut events = gateway.subscribe_events();
+
+    let amount = U256::from(10u128.pow(17));
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for the degraded provider")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::ProviderDegraded {
+            rpc_url: DEAD_URL.to_string(),
+            failures: 2,
+            retry_in_seconds: 600,
+        }
+    );
+    for _ in 0..3 {
+        assert_eq!(gateway.next_rpc_url(), node.url);
+    }
+
+    node.set_balance(invoice.to, amount);
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("the healthy endpoint must keep being polled")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    // The healthy endpoint never opened
+    assert!(!std::iter::from_fn(|| events.try_recv().ok())
+        .any(|event| matches!(event, GatewayEvent::ProviderDegraded { .. })));
+}
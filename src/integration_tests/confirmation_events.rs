@@ -0,0 +1,51 @@
+/// `Confirming` events report the depth of a treasury transfer once per new
+/// block until it reaches the required confirmations.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::event::GatewayEvent;
+use crate::test_utils::{gateway_helpers::make_gateway_with_confirmations, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xC7);
+
+#[tokio::test]
+async fn test_confirming_events_follow_new_blocks() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_gateway_with_confirmations(vec![node.url.clone()], TREASURY, 3);
+    let mut events = gateway.subscribe_events();
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+
+    let poller = gateway.poll_payments().await;
+    let mut progress = Vec::new();
+    while progress.len() < 3 {
+        let event = timeout(Duration::from_secs(10), events.recv())
+            .await
+            .expect("timed out waiting for Confirming")
+            .expect("event channel closed");
+        if let GatewayEvent::Confirming {
+            invoice_id,
+            confirmations,
+            required,
+        } = event
+        {
+            assert_eq!(invoice_id, id);
+            assert_eq!(required, 3);
+            progress.push(confirmations);
+            // Several poll cycles pass before the next block
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            node.mine_blocks(1);
+        }
+    }
+    let (paid_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for the swept invoice")
+        .unwrap();
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    assert_eq!(progress, vec![0, 1, 2]);
+    assert_eq!(paid_id, id);
+}
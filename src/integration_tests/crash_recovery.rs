@@ -0,0 +1,86 @@
+/// A gateway with an `invoice_store` resumes the sweeps of invoices that were
+/// paid when the previous process stopped.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::store::{CsvInvoiceStore, InvoiceStore};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD9);
+
+fn store_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("acceptevm-{name}-{}.csv", std::process::id()))
+}
+
+#[tokio::test]
+async fn test_paid_invoice_is_swept_after_restart() {
+    let node = MockNode::start().await;
+    let path = store_path("crash-recovery");
+    let store = CsvInvoiceStore::new(&path);
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+
+    let (id, invoice) = {
+        let (mut gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+        gateway.config.invoice_store = Some(Arc::new(store.clone()));
+        let (id, mut invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+        // The process stops right after the payment was detected
+        invoice.transition(InvoiceStatus::Paid, gateway.now_millis());
+        store.save(id.clone(), invoice.clone()).await.unwrap();
+        (id, invoice)
+    };
+    node.set_balance(invoice.to, amount);
+
+    let (mut gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.invoice_store = Some(Arc::new(CsvInvoiceStore::new(&path)));
+    let poller = gateway.poll_payments().await;
+    let (swept_id, swept) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(swept_id, id);
+    assert_eq!(swept.status, InvoiceStatus::Swept);
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    // Delivered invoices leave the store
+    assert!(CsvInvoiceStore::new(&path).load().await.unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_interrupted_sweep_is_recovered_as_paid() {
+    let node = MockNode::start().await;
+    let path = store_path("interrupted-sweep");
+    let store = CsvInvoiceStore::new(&path);
+    let (mut gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.invoice_store = Some(Arc::new(store.clone()));
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (pending_id, _) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    let (sweeping_id, mut sweeping) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    sweeping.transition(InvoiceStatus::Paid, gateway.now_millis());
+    sweeping.transition(InvoiceStatus::Sweeping, gateway.now_millis());
+    store.save(sweeping_id.clone(), sweeping).await.unwrap();
+
+    let (mut restarted, _rx) = make_single_node_gateway(&node, TREASURY);
+    restarted.config.invoice_store = Some(Arc::new(CsvInvoiceStore::new(&path)));
+    assert_eq!(restarted.recover_invoices().await.unwrap(), vec![sweeping_id.clone()]);
+    assert_eq!(
+        restarted.get_invoice(&pending_id).await.unwrap().status,
+        InvoiceStatus::Pending
+    );
+    assert_eq!(
+        restarted.get_invoice(&sweeping_id).await.unwrap().status,
+        InvoiceStatus::Paid
+    );
+    // Invoices already held are not loaded twice
+    assert!(restarted.recover_invoices().await.unwrap().is_empty());
+
+    let saved = store.load().await.unwrap();
+    let (_, recovered) = saved.iter().find(|(id, _)| *id == sweeping_id).unwrap();
+    assert_eq!(recovered.status, InvoiceStatus::Paid);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,54 @@
+/// The poller is spawned on, and sleeps through, the configured runtime.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::runtime::{Runtime, SleepFuture, TaskFuture};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD8);
+
+/// Delegates to tokio while counting what the gateway asks for.
+#[derive(Clone, Default)]
+struct CountingRuntime {
+    spawns: Arc<AtomicUsize>,
+    sleeps: Arc<AtomicUsize>,
+}
+
+impl Runtime for CountingRuntime {
+    fn spawn(&self, task: TaskFuture) {
+        self.spawns.fetch_add(1, Ordering::SeqCst);
+        tokio::spawn(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> SleepFuture {
+        self.sleeps.fetch_add(1, Ordering::SeqCst);
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+#[tokio::test]
+async fn test_poller_runs_on_the_configured_runtime() {
+    let node = MockNode::start().await;
+    let runtime = CountingRuntime::default();
+    let (mut gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.runtime = Arc::new(runtime.clone());
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (_, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    let poller = gateway.poll_payments().await;
+    let (_, swept) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(swept.status, InvoiceStatus::Swept);
+
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(runtime.spawns.load(Ordering::SeqCst), 1);
+    assert!(runtime.sleeps.load(Ordering::SeqCst) > 0);
+}
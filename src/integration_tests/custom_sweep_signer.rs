@@ -0,0 +1,71 @@
+/// A configured `SweepSigner` creates the keys of new invoices and signs
+/// their sweeps, so the invoice only stores the signer's key reference.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::signer::{KeyFuture, LocalSweepSigner, SignFuture, SweepKey, SweepSigner};
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::ZeroizedVec;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x5F);
+
+/// Stands in for an HSM: keys stay in its vault and invoices get an index.
+#[derive(Default)]
+struct VaultSigner {
+    keys: Mutex<Vec<Vec<u8>>>,
+    signatures: Mutex<usize>,
+}
+
+impl SweepSigner for VaultSigner {
+    fn new_key(&self) -> KeyFuture {
+        let secret = PrivateKeySigner::random();
+        let mut keys = self.keys.lock().unwrap();
+        let key = ZeroizedVec {
+            inner: vec![keys.len() as u8],
+        };
+        keys.push(secret.credential().to_bytes().to_vec());
+        let address = secret.address();
+        Box::pin(async move { Ok(SweepKey { address, key }) })
+    }
+
+    fn sign(&self, key: &[u8], tx: TransactionRequest) -> SignFuture {
+        *self.signatures.lock().unwrap() += 1;
+        let secret = self.keys.lock().unwrap()[key[0] as usize].clone();
+        LocalSweepSigner.sign(&secret, tx)
+    }
+}
+
+#[tokio::test]
+async fn test_sweep_is_signed_by_configured_signer() {
+    let node = MockNode::start().await;
+    let signer = Arc::new(VaultSigner::default());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        sweep_signer: Some(signer.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    assert_eq!(invoice.wallet.inner, vec![0]);
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+    let (paid_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(paid_id, id);
+    assert!(node.get_treasury_balance(TREASURY) > U256::ZERO);
+    assert_eq!(*signer.signatures.lock().unwrap(), 1);
+}
@@ -0,0 +1,79 @@
+/// Paid invoices the reflector cannot take go to the dead-letter queue instead
+/// of being lost.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::store::CsvInvoiceStore;
+use crate::invoice::{Invoice, InvoiceStatus};
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xDA);
+
+#[tokio::test]
+async fn test_undeliverable_invoice_is_dead_lettered_and_persisted() {
+    let node = MockNode::start().await;
+    let path = std::env::temp_dir().join(format!("acceptevm-dead-{}.csv", std::process::id()));
+    let (mut gateway, rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.dead_letter_store = Some(Arc::new(CsvInvoiceStore::new(&path)));
+    drop(rx);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    let poller = gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_dead_letters().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("timed out waiting for the dead letter");
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    // Another process finds the invoice in the store
+    let (mut restarted, _rx) = make_single_node_gateway(&node, TREASURY);
+    restarted.config.dead_letter_store = Some(Arc::new(CsvInvoiceStore::new(&path)));
+    let drained = restarted.drain_dead_letters().await.unwrap();
+    assert_eq!(drained.len(), 1);
+    assert_eq!(drained[0].0, id);
+    assert_eq!(drained[0].1.status, InvoiceStatus::Swept);
+    assert!(restarted.get_dead_letters().await.unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_poller_redelivers_dead_letters() {
+    let node = MockNode::start().await;
+    let (mut gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.redeliver_dead_letters = true;
+    let paid = Invoice {
+        status: InvoiceStatus::Paid,
+        ..Default::default()
+    };
+    gateway.dead_letter("first", paid.clone()).await;
+    gateway.dead_letter("second", paid).await;
+
+    let poller = gateway.poll_payments().await;
+    for expected in ["first", "second"] {
+        let (id, _) = timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timed out waiting for redelivery")
+            .expect("channel closed");
+        assert_eq!(id, expected);
+    }
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+    assert!(gateway.get_dead_letters().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_failed_redelivery_keeps_the_queue() {
+    let node = MockNode::start().await;
+    let (gateway, rx) = make_single_node_gateway(&node, TREASURY);
+    drop(rx);
+    gateway.dead_letter("id", Invoice::default()).await;
+    assert_eq!(gateway.redeliver_dead_letters().await.unwrap(), 0);
+    assert_eq!(gateway.get_dead_letters().await.unwrap().len(), 1);
+}
@@ -0,0 +1,41 @@
+/// Invoice amounts given as decimal strings are resolved against the native
+/// currency's 18 decimals or the token's `decimals()`.
+use alloy::primitives::{Address, U256};
+
+use crate::gateway::error::GatewayError;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x1E);
+const TOKEN: Address = Address::repeat_byte(0x70);
+
+#[tokio::test]
+async fn test_decimal_amounts_use_asset_decimals() {
+    let node = MockNode::start().await;
+    node.set_token_decimals(TOKEN, 6);
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let (_, invoice) = gateway
+        .new_token_invoice(TOKEN, "12.50", vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.amount, U256::from(12_500_000u64));
+
+    let (_, invoice) = gateway
+        .new_invoice("0.05", vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.amount, U256::from(50_000_000_000_000_000u64));
+}
+
+#[tokio::test]
+async fn test_decimal_amount_finer_than_token_is_rejected() {
+    let node = MockNode::start().await;
+    node.set_token_decimals(TOKEN, 2);
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let result = gateway
+        .new_token_invoice(TOKEN, "12.505", vec![], 3600)
+        .await;
+    assert!(matches!(result, Err(GatewayError::InvalidAmount(_))));
+    assert!(gateway.get_all_invoices().await.unwrap().is_empty());
+}
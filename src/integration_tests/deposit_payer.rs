@@ -0,0 +1,169 @@
+/// Paid invoices record who paid them and every transfer that contributed
+/// to the payment before they are delivered.
+use std::time::Duration;
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolCall;
+use tokio::time::timeout;
+
+use crate::test_utils::gateway_helpers::{funded_gas_sponsor, make_configured_gateway};
+use crate::test_utils::mock_node::MockNode;
+use crate::web3::transfers::token_transfers::IERC20;
+
+const TREASURY: Address = Address::repeat_byte(0x1E);
+const TOKEN: Address = Address::repeat_byte(0x70);
+const ONE_ETH: u128 = 1_000_000_000_000_000_000;
+
+/// A wallet holding one ETH for gas.
+fn funded_payer(node: &MockNode) -> PrivateKeySigner {
+    let payer = PrivateKeySigner::random();
+    node.set_balance(payer.address(), U256::from(ONE_ETH));
+    payer
+}
+
+#[tokio::test]
+async fn test_native_payment_records_payer_and_transaction() {
+    let node = MockNode::start().await;
+    node.mine_blocks(3);
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let amount = U256::from(ONE_ETH / 10);
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    let payer = funded_payer(&node);
+    let payer_address = payer.address();
+    let payment = TransactionRequest::default().with_to(invoice.to).with_value(amount);
+    let tx_hash = node.send_from(payer, payment).await;
+    gateway.poll_payments().await;
+
+    let (paid_id, paid) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(paid_id, id);
+    assert_eq!(paid.payer, Some(payer_address));
+    assert_eq!(paid.deposits.len(), 1);
+    assert_eq!(paid.deposits[0].tx_hash, tx_hash);
+    assert_eq!(paid.deposits[0].amount, amount);
+    assert_eq!(paid.deposit_block_number, Some(node.block_number()));
+}
+
+#[tokio::test]
+async fn test_payment_in_several_transfers_lists_each_deposit() {
+    let node = MockNode::start().await;
+    node.mine_blocks(3);
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let amount = U256::from(ONE_ETH / 10);
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    // Two payers split the invoice across two blocks
+    let first_payer = funded_payer(&node);
+    let first_address = first_payer.address();
+    let first_leg = amount / U256::from(4u64);
+    let payment = TransactionRequest::default().with_to(invoice.to).with_value(first_leg);
+    let first_hash = node.send_from(first_payer, payment).await;
+    let first_block = node.block_number();
+    node.mine_blocks(1);
+    let second_leg = amount - first_leg;
+    let payment = TransactionRequest::default().with_to(invoice.to).with_value(second_leg);
+    let second_hash = node.send_from(funded_payer(&node), payment).await;
+    gateway.poll_payments().await;
+
+    let (_, paid) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    let legs: Vec<_> = paid
+        .deposits
+        .iter()
+        .map(|deposit| (deposit.tx_hash, deposit.amount, deposit.block_number))
+        .collect();
+    assert_eq!(
+        legs,
+        vec![
+            (first_hash, first_leg, first_block),
+            (second_hash, second_leg, first_block + 1)
+        ]
+    );
+    assert_eq!(paid.payer, Some(first_address));
+    assert_eq!(paid.deposit_block_number, Some(first_block + 1));
+}
+
+#[tokio::test]
+async fn test_token_payment_records_payer_from_transfer_logs() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let amount = U256::from(250_000_000u64);
+    let (_, invoice) = gateway
+        .new_token_invoice(TOKEN, amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    let payer = funded_payer(&node);
+    let payer_address = payer.address();
+    node.set_token_balance(TOKEN, payer_address, amount);
+    let transfer = IERC20::transferCall {
+        to: invoice.to,
+        amount,
+    };
+    let payment = TransactionRequest::default()
+        .with_to(TOKEN)
+        .with_input(transfer.abi_encode());
+    let tx_hash = node.send_from(payer, payment).await;
+    gateway.poll_payments().await;
+
+    let (_, paid) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(paid.payer, Some(payer_address));
+    assert_eq!(paid.deposits.len(), 1);
+    assert_eq!(paid.deposits[0].tx_hash, tx_hash);
+    assert_eq!(paid.deposits[0].amount, amount);
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), amount);
+}
+
+#[tokio::test]
+async fn test_payment_from_contract_wallet_is_found_in_call_traces() {
+    let node = MockNode::start().await;
+    node.mine_blocks(3);
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.trace_internal_transfers = true
+    });
+    let amount = U256::from(ONE_ETH / 10);
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    // An owner executes a Safe transaction that pays the invoice internally
+    let safe = Address::repeat_byte(0x5A);
+    let execution = TransactionRequest::default().with_to(safe).with_value(U256::ZERO);
+    let tx_hash = node.send_from(funded_payer(&node), execution).await;
+    node.add_internal_transfer(tx_hash, safe, invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (_, paid) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(paid.payer, Some(safe));
+    assert_eq!(paid.deposits.len(), 1);
+    assert_eq!(paid.deposits[0].tx_hash, tx_hash);
+    assert_eq!(paid.deposits[0].amount, amount);
+    assert_eq!(paid.deposit_block_number, Some(node.block_number()));
+}
@@ -0,0 +1,38 @@
+/// In detect-only mode paid invoices are delivered as `Paid` without any
+/// treasury transfer, leaving the funds on the invoice address.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD7);
+
+#[tokio::test]
+async fn test_paid_invoice_is_delivered_without_sweeping() {
+    let node = MockNode::start().await;
+    let (mut gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.detect_only = true;
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (key, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (delivered_key, delivered) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(delivered_key, key);
+    assert_eq!(delivered.status, InvoiceStatus::Paid);
+    assert!(delivered.paid_at_timestamp > 0);
+    assert!(delivered.settlement.is_none());
+    assert!(!delivered.wallet.inner.is_empty());
+
+    assert!(node.sent_txs().is_empty());
+    assert_eq!(node.get_balance(invoice.to), amount);
+    assert_eq!(node.get_balance(TREASURY), U256::ZERO);
+    assert!(gateway.get_invoice(&key).await.is_err());
+}
@@ -0,0 +1,59 @@
+/// Balances below the configured dust threshold are reported as dust and
+/// never count as a partial payment.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{event::GatewayEvent, PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x5D);
+
+#[tokio::test]
+async fn test_dust_is_reported_separately_and_ignored() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        dust_thresholds: vec![(None, U256::from(1_000_000u64))],
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+    let mut events = gateway.subscribe_events();
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let dust = U256::from(1_000u64);
+    node.set_balance(invoice.to, dust);
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("timed out waiting for DustReceived")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::DustReceived {
+            invoice_id: id.clone(),
+            amount: dust,
+        }
+    );
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(events.try_recv().is_err(), "dust is no partial payment");
+    let pending = gateway.get_invoice(&id).await.expect("invoice must still exist");
+    assert_eq!(pending.status, InvoiceStatus::Pending);
+
+    // Payments above the threshold are detected as usual
+    node.set_balance(invoice.to, amount + dust);
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
@@ -0,0 +1,85 @@
+/// A configured `expected_chain_id` stops the poller as soon as the RPC
+/// serves another chain, at startup or later on.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::{error::GatewayError, PollerState};
+use crate::test_utils::{gateway_helpers::make_configured_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xE1);
+
+#[tokio::test]
+async fn test_wrong_chain_fails_at_startup() {
+    let node = MockNode::start_with_chain_id(56).await;
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.expected_chain_id = Some(1);
+        config.chain_check_interval_seconds = 0;
+    });
+
+    let result = gateway.verify_chain_id().await;
+    assert!(matches!(
+        result,
+        Err(GatewayError::ChainMismatch {
+            expected: 1,
+            actual: 56
+        })
+    ));
+
+    let mut handle = gateway.poll_payments().await;
+    let result = timeout(Duration::from_secs(5), handle.join())
+        .await
+        .expect("poller must stop on a chain mismatch");
+    assert!(matches!(result, Err(GatewayError::ChainMismatch { .. })));
+    assert_eq!(handle.state(), PollerState::Stopped);
+}
+
+#[tokio::test]
+async fn test_matching_chain_accepts_payments() {
+    let node = MockNode::start_with_chain_id(56).await;
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.expected_chain_id = Some(56);
+        config.chain_check_interval_seconds = 0;
+    });
+    assert_eq!(gateway.verify_chain_id().await.unwrap(), 56);
+
+    let amount = U256::from(10u128.pow(17));
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_chain_switch_while_running_stops_poller() {
+    let node = MockNode::start_with_chain_id(56).await;
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.expected_chain_id = Some(56);
+        config.chain_check_interval_seconds = 0;
+    });
+
+    let mut handle = gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(handle.is_running());
+
+    node.set_chain_id(1);
+    let result = timeout(Duration::from_secs(5), handle.join())
+        .await
+        .expect("poller must stop once the chain changes");
+    assert!(matches!(
+        result,
+        Err(GatewayError::ChainMismatch {
+            expected: 56,
+            actual: 1
+        })
+    ));
+}
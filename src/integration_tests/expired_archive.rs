@@ -0,0 +1,46 @@
+/// Invoices that expire unpaid move to the expired archive with their wallet
+/// key, so funds arriving after expiry can still be recovered.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xCD);
+
+#[tokio::test]
+async fn test_expired_invoice_is_archived_with_its_wallet() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let (id, invoice) = gateway
+        .new_invoice(U256::from(1_000u64), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    gateway.invoices.write().await.get_mut(&id).unwrap().expires = gateway.now_seconds() - 1;
+
+    gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_expired_invoice(&id).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("expired invoice must be archived");
+
+    assert!(gateway.get_invoice(&id).await.is_err());
+    let archived = gateway.get_expired_invoice(&id).await.unwrap();
+    assert_eq!(archived.to, invoice.to);
+    assert_eq!(archived.wallet.inner, invoice.wallet.inner);
+    assert_eq!(archived.status, InvoiceStatus::Expired);
+    assert_eq!(gateway.get_expired_invoices().await.unwrap().len(), 1);
+
+    // The wallet of an expired invoice can still be exported
+    let keystore = gateway.export_keystore(&id, "pw").await.unwrap();
+    assert!(keystore.contains(&alloy::primitives::hex::encode(invoice.to)));
+
+    let path = std::env::temp_dir().join(format!("acceptevm-expired-{id}.csv"));
+    assert_eq!(gateway.export_expired_csv(&path).await.unwrap(), 1);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,95 @@
+/// Funds found on an invoice when it expires unpaid are sent back to the
+/// payer when `refund_expired_payments` is set, and left alone otherwise.
+use std::time::Duration;
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{event::GatewayEvent, PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x7E);
+const PLATFORM: Address = Address::repeat_byte(0x7F);
+const ONE_ETH: u128 = 1_000_000_000_000_000_000;
+
+/// Creates an invoice for one ETH, underpays it by half and lets it expire.
+async fn expire_underpaid(
+    node: &MockNode,
+    refund_expired_payments: bool,
+) -> (PaymentGateway, String, Address, Address) {
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        treasury_splits: vec![(PLATFORM, 500)],
+        refund_expired_payments,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let (id, invoice) = gateway
+        .new_invoice(U256::from(ONE_ETH), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let payer = PrivateKeySigner::random();
+    let payer_address = payer.address();
+    node.set_balance(payer_address, U256::from(ONE_ETH));
+    let payment = TransactionRequest::default()
+        .with_to(invoice.to)
+        .with_value(U256::from(ONE_ETH / 2));
+    node.send_from(payer, payment).await;
+
+    gateway.invoices.write().await.get_mut(&id).unwrap().expires = gateway.now_seconds() - 1;
+    (gateway, id, invoice.to, payer_address)
+}
+
+#[tokio::test]
+async fn test_expired_invoice_funds_are_refunded_to_payer() {
+    let node = MockNode::start().await;
+    let (gateway, id, deposit_address, payer) = expire_underpaid(&node, true).await;
+    let mut events = gateway.subscribe_events();
+    let payer_balance = node.get_balance(payer);
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for refund")
+        .expect("event channel closed");
+    let GatewayEvent::Refunded {
+        invoice_id,
+        payer: refunded_to,
+        amount,
+        ..
+    } = event
+    else {
+        panic!("unexpected event {event:?}");
+    };
+    assert_eq!(invoice_id, id);
+    assert_eq!(refunded_to, payer);
+    assert!(amount > U256::ZERO);
+    assert_eq!(node.get_balance(payer), payer_balance + amount);
+    assert!(node.get_balance(deposit_address) < U256::from(ONE_ETH / 2));
+    // Refunds skip treasury splits
+    assert_eq!(node.get_balance(PLATFORM), U256::ZERO);
+    assert!(gateway.get_invoice(&id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_expired_invoice_funds_stay_without_refunds() {
+    let node = MockNode::start().await;
+    let (gateway, id, deposit_address, _) = expire_underpaid(&node, false).await;
+    gateway.poll_payments().await;
+
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_invoice(&id).await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("expired invoice must be dropped");
+    assert_eq!(node.get_balance(deposit_address), U256::from(ONE_ETH / 2));
+}
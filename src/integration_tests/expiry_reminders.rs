@@ -0,0 +1,58 @@
+/// Unpaid invoices raise an `ExpiryReminder` event once per configured
+/// threshold of their expiry window.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{event::GatewayEvent, PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x6B);
+
+#[tokio::test]
+async fn test_unpaid_invoice_raises_expiry_reminders() {
+    let node = MockNode::start().await;
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        expiry_reminders: vec![50, 90],
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+    let mut events = gateway.subscribe_events();
+
+    let (id, _) = gateway
+        .new_invoice(U256::from(1_000u64), vec![], 1000)
+        .await
+        .expect("invoice creation must succeed");
+
+    // Pretend the invoice was created 60% of its window ago
+    let now = gateway.now_seconds();
+    {
+        let mut map = gateway.invoices.write().await;
+        let invoice = map.get_mut(&id).unwrap();
+        invoice.created_at = now - 600;
+        invoice.expires = now + 400;
+    }
+
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("timed out waiting for expiry reminder")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::ExpiryReminder {
+            invoice_id: id.clone(),
+            percent: 50,
+            expires: now + 400,
+        }
+    );
+
+    // The same threshold is never reported twice
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(events.try_recv().is_err(), "reminder must not repeat");
+}
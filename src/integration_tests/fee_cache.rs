@@ -0,0 +1,52 @@
+/// Fee data read for one sweep is reused by the following sweeps for
+/// `fee_cache_seconds`.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xD4);
+const GWEI: u128 = 1_000_000_000;
+
+/// Sweeps two invoices, raising the gas price in between, and returns the
+/// gas price each sweep paid.
+async fn sweep_two(fee_cache_seconds: u64) -> (u128, u128) {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        fee_cache_seconds,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+    gateway.poll_payments().await;
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let mut fees = Vec::new();
+    for gas_price in [GWEI, 2 * GWEI] {
+        node.state.lock().unwrap().chain.gas_price = gas_price;
+        let (_, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+        node.set_balance(invoice.to, amount);
+        let (_, swept) = timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timed out waiting for confirmation")
+            .expect("channel closed");
+        fees.push(swept.settlement.unwrap().max_fee_per_gas);
+    }
+    (fees[0], fees[1])
+}
+
+#[tokio::test]
+async fn test_cached_fees_are_reused() {
+    assert_eq!(sweep_two(60).await, (GWEI, GWEI));
+}
+
+#[tokio::test]
+async fn test_fees_are_read_per_sweep_by_default() {
+    assert_eq!(sweep_two(0).await, (GWEI, 2 * GWEI));
+}
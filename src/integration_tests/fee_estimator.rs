@@ -0,0 +1,64 @@
+/// Sweeps are sent with the fees of the configured `fee_estimator`, and fall
+/// back to the legacy gas price when it fails.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use alloy::providers::DynProvider;
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::fees::{FeeEstimator, FeeFuture, NetworkFees};
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::{mock_node::MockNode, test_chain::TestChain};
+
+const TREASURY: Address = Address::repeat_byte(0xD5);
+const GWEI: u128 = 1_000_000_000;
+
+/// Quotes a fixed gas price, like a gas station API would, or fails.
+struct GasStation(Option<u128>);
+
+impl FeeEstimator for GasStation {
+    fn estimate(&self, _provider: DynProvider) -> FeeFuture {
+        let quote = self.0;
+        Box::pin(async move {
+            quote
+                .map(|gas_price| NetworkFees::Legacy { gas_price })
+                .ok_or_else(|| GatewayError::FeeEstimation("gas station is down".to_string()))
+        })
+    }
+}
+
+/// Sweeps one invoice with `estimator` and returns the gas price it paid.
+async fn sweep_with(estimator: GasStation) -> u128 {
+    let node = MockNode::start_with_chain(TestChain::ethereum()).await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        fee_estimator: Arc::new(estimator),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+    gateway.poll_payments().await;
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (_, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    let (_, swept) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    swept.settlement.unwrap().max_fee_per_gas
+}
+
+#[tokio::test]
+async fn test_sweep_uses_custom_estimator() {
+    assert_eq!(sweep_with(GasStation(Some(3 * GWEI))).await, 3 * GWEI);
+}
+
+#[tokio::test]
+async fn test_failed_estimator_falls_back_to_gas_price() {
+    assert_eq!(sweep_with(GasStation(None)).await, TestChain::ethereum().gas_price);
+}
@@ -0,0 +1,99 @@
+/// Fiat-denominated invoices are converted at creation with the configured
+/// price oracle, here Chainlink feeds served by the mock node.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+
+use crate::gateway::{clock::MockClock, error::GatewayError, pricing::ChainlinkOracle};
+use crate::test_utils::gateway_helpers::{make_configured_gateway, make_single_node_gateway};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xF1);
+const TOKEN: Address = Address::repeat_byte(0x72);
+const ETH_USD_FEED: Address = Address::repeat_byte(0xFE);
+const TOKEN_USD_FEED: Address = Address::repeat_byte(0xFD);
+/// Time the gateway and oracle clocks start at
+const NOW: u64 = 1_700_000_000;
+
+/// USD feeds of the native currency and `TOKEN` on `node`, aged by `clock`.
+fn usd_oracle(node: &MockNode, clock: &MockClock) -> Arc<ChainlinkOracle> {
+    let oracle = ChainlinkOracle::new(node.url.clone())
+        .with_feed(None, "USD", ETH_USD_FEED)
+        .with_feed(Some(TOKEN), "USD", TOKEN_USD_FEED)
+        .with_clock(Arc::new(clock.clone()));
+    Arc::new(oracle)
+}
+
+#[tokio::test]
+async fn test_native_fiat_invoice_locks_rate() {
+    let node = MockNode::start().await;
+    // 2000.00 USD per ETH
+    let rate = U256::from(200_000_000_000u64);
+    node.set_price_feed(ETH_USD_FEED, rate, 8, NOW);
+    let clock = MockClock::new(NOW);
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.clock = Arc::new(clock.clone());
+        config.price_oracle = Some(usd_oracle(&node, &clock));
+    });
+
+    let (_, invoice) = gateway
+        .new_invoice_fiat(5_000, "usd", None, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.amount, U256::from(25_000_000_000_000_000u64)); // 0.025 ETH
+
+    let fiat = invoice.fiat.expect("fiat quote must be stored");
+    assert_eq!(fiat.amount_cents, 5_000);
+    assert_eq!(fiat.currency, "usd");
+    assert_eq!(fiat.rate, rate);
+    assert_eq!(fiat.rate_decimals, 8);
+}
+
+#[tokio::test]
+async fn test_token_fiat_invoice_uses_token_decimals() {
+    let node = MockNode::start().await;
+    node.set_token_decimals(TOKEN, 6);
+    node.set_price_feed(TOKEN_USD_FEED, U256::from(100_000_000u64), 8, NOW);
+    let clock = MockClock::new(NOW);
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.clock = Arc::new(clock.clone());
+        config.price_oracle = Some(usd_oracle(&node, &clock));
+    });
+
+    let (_, invoice) = gateway
+        .new_invoice_fiat(1_234, "USD", Some(TOKEN), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.token, Some(TOKEN));
+    assert_eq!(invoice.amount, U256::from(12_340_000u64)); // 12.34 tokens
+}
+
+#[tokio::test]
+async fn test_stale_or_missing_prices_are_rejected() {
+    let node = MockNode::start().await;
+    node.set_price_feed(ETH_USD_FEED, U256::from(1u64), 8, NOW);
+    let clock = MockClock::new(NOW);
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.clock = Arc::new(clock.clone());
+        config.price_oracle = Some(usd_oracle(&node, &clock));
+    });
+
+    // Fresh by the gateway clock, then two hours pass without an update
+    assert!(gateway.new_invoice_fiat(100, "USD", None, vec![], 3600).await.is_ok());
+    clock.advance(Duration::from_secs(7200));
+    let stale = gateway.new_invoice_fiat(100, "USD", None, vec![], 3600).await;
+    assert!(matches!(stale, Err(GatewayError::Pricing(_))));
+    let unknown = gateway.new_invoice_fiat(100, "EUR", None, vec![], 3600).await;
+    assert!(matches!(unknown, Err(GatewayError::Pricing(_))));
+    assert_eq!(gateway.invoices.read().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_fiat_invoice_requires_oracle() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let result = gateway.new_invoice_fiat(100, "USD", None, vec![], 3600).await;
+    assert!(matches!(result, Err(GatewayError::Unsupported(_))));
+}
@@ -0,0 +1,55 @@
+/// In forwarder mode invoices have no private key; a paid invoice is settled
+/// by deploying its CREATE2 forwarder from the deployer account.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{forwarder_address, ForwarderMode, PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x2F);
+
+#[tokio::test]
+async fn test_paid_forwarder_invoice_deploys_forwarder() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let forwarder = ForwarderMode::new(PrivateKeySigner::random());
+    let deployer = forwarder.deployer.address();
+    let factory = forwarder.factory;
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        forwarder: Some(forwarder),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let salt = invoice.forwarder_salt.expect("forwarder invoices carry a salt");
+    assert!(invoice.wallet.is_empty(), "forwarder invoices have no key");
+    assert_eq!(invoice.to, forwarder_address(factory, TREASURY, salt));
+
+    node.set_balance(invoice.to, amount);
+    node.set_balance(deployer, amount);
+    gateway.poll_payments().await;
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    assert_eq!(settlement.swept_amount, amount, "deployer pays the gas");
+
+    let state = node.state.lock().unwrap();
+    let receipt = state.receipts.values().next().expect("deployment must be sent");
+    assert_eq!(receipt.from, deployer);
+    assert_eq!(receipt.to, factory);
+}
@@ -0,0 +1,54 @@
+/// Sweep transfers use the configured gas limit as a floor for the estimate,
+/// and on its own when estimation fails.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::InvoiceRequest;
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD6);
+const AMOUNT: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+/// Pays and sweeps one invoice created from `request`, returning the gas
+/// limit of the sweep transfer.
+async fn sweep_gas(sweep_gas_limit: Option<u64>, request: InvoiceRequest, fail: bool) -> u64 {
+    let node = MockNode::start().await;
+    node.state.lock().unwrap().fail_gas_estimation = fail;
+    let (mut gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    gateway.config.sweep_gas_limit = sweep_gas_limit;
+
+    let (_, invoice) = gateway.create_invoice(request).await.unwrap();
+    node.set_balance(invoice.to, AMOUNT);
+    gateway.poll_payments().await;
+    let (_, swept) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(swept.status, InvoiceStatus::Swept);
+    let sent = node.sent_txs();
+    assert_eq!(sent.len(), 1);
+    sent[0].gas_limit
+}
+
+fn request() -> InvoiceRequest {
+    InvoiceRequest::new(AMOUNT, 3600)
+}
+
+#[tokio::test]
+async fn test_estimate_is_used_by_default() {
+    assert_eq!(sweep_gas(None, request(), false).await, 21_000);
+}
+
+#[tokio::test]
+async fn test_gateway_gas_limit_raises_the_estimate() {
+    assert_eq!(sweep_gas(Some(50_000), request(), false).await, 50_000);
+}
+
+#[tokio::test]
+async fn test_invoice_gas_limit_is_used_when_estimation_fails() {
+    let request = request().gas_limit(30_000);
+    assert_eq!(sweep_gas(Some(50_000), request, true).await, 30_000);
+}
@@ -0,0 +1,53 @@
+/// Sweeps are deferred while network fees exceed the configured
+/// `max_gas_price` and go through once fees drop below it.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x9B);
+const GWEI: u128 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_sweep_deferred_until_gas_price_drops() {
+    // Legacy chain quoting 1 gwei
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        max_gas_price: Some(GWEI / 2),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let deferred = gateway.get_invoice(&id).await.expect("invoice must still exist");
+    assert_eq!(deferred.status, InvoiceStatus::PaidAwaitingSweep);
+    assert!(deferred.hash.is_none());
+    assert!(deferred.last_error.is_none());
+    assert!(rx.try_recv().is_err());
+    assert_eq!(node.get_balance(TREASURY), U256::ZERO);
+
+    node.state.lock().unwrap().chain.gas_price = GWEI / 4;
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+    assert!(node.get_balance(TREASURY) > U256::ZERO);
+}
@@ -0,0 +1,117 @@
+/// `GatewayHooks` are called at every step of an invoice's life.
+use std::{sync::Arc, time::Duration};
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{
+    hooks::{GatewayHooks, HookFuture},
+    PaymentGateway, PaymentGatewayConfiguration,
+};
+use crate::invoice::{Invoice, InvoiceError, InvoiceErrorSource};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xF0);
+
+/// Reports the name of every callback it receives.
+struct RecordingHooks(mpsc::UnboundedSender<String>);
+
+impl RecordingHooks {
+    fn record(&self, call: String) -> HookFuture {
+        let _ = self.0.send(call);
+        Box::pin(async {})
+    }
+}
+
+impl GatewayHooks for RecordingHooks {
+    fn on_invoice_created(&self, invoice_id: String, _invoice: Invoice) -> HookFuture {
+        self.record(format!("created {invoice_id}"))
+    }
+
+    fn on_check(&self, invoice_id: String, balance: U256) -> HookFuture {
+        self.record(format!("check {invoice_id} {balance}"))
+    }
+
+    fn on_detected(&self, invoice_id: String, invoice: Invoice) -> HookFuture {
+        self.record(format!("detected {invoice_id} {}", invoice.amount))
+    }
+
+    fn on_sweep_submitted(&self, invoice_id: String, _tx_hash: String) -> HookFuture {
+        self.record(format!("submitted {invoice_id}"))
+    }
+
+    fn on_sweep_confirmed(&self, invoice_id: String, invoice: Invoice) -> HookFuture {
+        self.record(format!("confirmed {invoice_id} {:?}", invoice.status))
+    }
+
+    fn on_error(&self, invoice_id: String, error: InvoiceError) -> HookFuture {
+        self.record(format!("error {invoice_id} {:?}", error.source))
+    }
+}
+
+fn hooked_gateway(node: &MockNode) -> (PaymentGateway, mpsc::UnboundedReceiver<String>) {
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let (calls_tx, calls) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        hooks: Some(Arc::new(RecordingHooks(calls_tx))),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+    (gateway, calls)
+}
+
+/// Receives the next call, skipping balance checks unless `expected` is one.
+async fn expect_call(calls: &mut mpsc::UnboundedReceiver<String>, expected: &str) {
+    loop {
+        let call = timeout(Duration::from_secs(10), calls.recv())
+            .await
+            .unwrap_or_else(|_| panic!("timed out waiting for '{expected}'"))
+            .expect("hooks dropped");
+        if call == expected {
+            return;
+        }
+        assert!(call.starts_with("check "), "expected '{expected}', got '{call}'");
+    }
+}
+
+#[tokio::test]
+async fn test_hooks_follow_an_invoice_from_creation_to_sweep() {
+    let node = MockNode::start().await;
+    let (gateway, mut calls) = hooked_gateway(&node);
+
+    let amount = U256::from(10u128.pow(17));
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    expect_call(&mut calls, &format!("created {id}")).await;
+
+    gateway.poll_payments().await;
+    expect_call(&mut calls, &format!("check {id} 0")).await;
+
+    node.set_balance(invoice.to, amount);
+    expect_call(&mut calls, &format!("check {id} {amount}")).await;
+    expect_call(&mut calls, &format!("detected {id} {amount}")).await;
+    expect_call(&mut calls, &format!("submitted {id}")).await;
+    expect_call(&mut calls, &format!("confirmed {id} Swept")).await;
+}
+
+#[tokio::test]
+async fn test_failed_balance_check_is_reported_to_on_error() {
+    let node = MockNode::start().await;
+    let (gateway, mut calls) = hooked_gateway(&node);
+
+    let (id, _) = gateway
+        .new_invoice(U256::from(1000u64), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    expect_call(&mut calls, &format!("created {id}")).await;
+
+    node.set_offline(true);
+    gateway.poll_payments().await;
+    let source = InvoiceErrorSource::BalanceCheck;
+    expect_call(&mut calls, &format!("error {id} {source:?}")).await;
+}
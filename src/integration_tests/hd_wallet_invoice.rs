@@ -0,0 +1,46 @@
+/// Invoices derived from an HD wallet store only their index and are still
+/// swept by re-deriving the key.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{HdWallet, PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x4D);
+const PHRASE: &str = "test test test test test test test test test test test junk";
+
+#[tokio::test]
+async fn test_hd_derived_invoice_is_swept() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let hd_wallet = HdWallet::new(PHRASE).unwrap().starting_at(3);
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        hd_wallet: Some(hd_wallet.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.derivation_index, Some(3));
+    assert!(invoice.wallet.is_empty(), "HD invoices must not store a key");
+    assert_eq!(invoice.to, hd_wallet.derive(3).unwrap().address());
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    assert!(confirmed.hash.is_some());
+    assert!(node.get_treasury_balance(TREASURY) > U256::ZERO);
+}
@@ -0,0 +1,52 @@
+/// Retried invoice requests with the same external id return the pending
+/// invoice instead of minting a new wallet.
+use alloy::primitives::{Address, U256};
+
+use crate::gateway::{error::GatewayError, InvoiceRequest};
+use crate::invoice::InvoiceOptions;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD2);
+
+#[tokio::test]
+async fn test_retried_request_returns_pending_invoice() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let request = InvoiceRequest::new(U256::from(1_000u64), 3600).external_id("order-7");
+
+    let (id, invoice) = gateway.create_invoice(request.clone()).await.unwrap();
+    let (retried_id, retried) = gateway.create_invoice(request).await.unwrap();
+    assert_eq!(retried_id, id);
+    assert_eq!(retried.to, invoice.to);
+
+    // The positional API takes the external id through its options
+    let options = InvoiceOptions {
+        external_id: Some("order-7".to_string()),
+        ..Default::default()
+    };
+    let (option_id, _) = gateway
+        .new_invoice_with_options(U256::from(1_000u64), vec![], 3600, options)
+        .await
+        .unwrap();
+    assert_eq!(option_id, id);
+    assert_eq!(gateway.get_all_invoices().await.unwrap().len(), 1);
+
+    let (found_id, _) = gateway.get_invoice_by_external_id("order-7").await.unwrap();
+    assert_eq!(found_id, id);
+    assert!(matches!(
+        gateway.get_invoice_by_external_id("order-8").await,
+        Err(GatewayError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn test_external_id_is_free_again_once_invoice_leaves() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let request = InvoiceRequest::new(U256::from(1_000u64), 3600).external_id("order-9");
+
+    let (id, _) = gateway.create_invoice(request.clone()).await.unwrap();
+    gateway.cancel_invoice(&id).await.unwrap();
+    let (new_id, _) = gateway.create_invoice(request).await.unwrap();
+    assert_ne!(new_id, id);
+}
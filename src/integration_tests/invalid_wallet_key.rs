@@ -7,7 +7,6 @@ use alloy::primitives::{Address, U256};
 use tokio::time::timeout;
 
 use crate::invoice::{Invoice, ZeroizedVec};
-use crate::gateway::get_unix_time_seconds;
 use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
 
 const TREASURY: Address = Address::repeat_byte(0x66);
@@ -27,10 +26,8 @@ async fn test_corrupted_wallet_does_not_crash_poller() {
         wallet: bad_wallet,
         amount,
         message: vec![],
-        expires: get_unix_time_seconds() + 3600,
-        paid_at_timestamp: 0,
-        hash: None,
-        nonce: None,
+        expires: gateway.now_seconds() + 3600,
+        ..Default::default()
     };
 
     // Inject the bad invoice directly into the gateway's invoice map
@@ -67,10 +64,8 @@ async fn test_valid_invoice_after_bad_one_still_confirms() {
         wallet: bad_wallet,
         amount,
         message: vec![],
-        expires: get_unix_time_seconds() + 3600,
-        paid_at_timestamp: 0,
-        hash: None,
-        nonce: None,
+        expires: gateway.now_seconds() + 3600,
+        ..Default::default()
     };
     {
         let mut map = gateway.invoices.write().await;
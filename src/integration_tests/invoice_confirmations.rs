@@ -0,0 +1,57 @@
+/// Invoices can require more confirmations than the gateway default, both
+/// for the incoming payment and for the treasury transfer.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::InvoiceOptions;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD3);
+const CONFIRMATIONS: u64 = 5;
+
+#[tokio::test]
+async fn test_invoice_confirmations_override_gateway_default() {
+    let node = MockNode::start().await;
+    // The gateway itself requires no confirmations
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(10u128.pow(18));
+    let options = InvoiceOptions {
+        min_confirmations: Some(CONFIRMATIONS),
+        ..Default::default()
+    };
+    let (id, invoice) = gateway
+        .new_invoice_with_options(amount, vec![], 3600, options)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    // The payment is seen but not swept before it is deep enough
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let pending = gateway.get_invoice(&id).await.expect("invoice must exist");
+    assert!(pending.deposit_block.is_some());
+    assert!(node.any_tx_hash().is_none(), "payment swept too early");
+
+    node.mine_blocks(CONFIRMATIONS);
+    timeout(Duration::from_secs(10), async {
+        while node.any_tx_hash().is_none() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("sweep must be broadcast once the payment is confirmed");
+
+    // The treasury transfer needs the same depth before delivery
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(rx.try_recv().is_err(), "sweep confirmed too early");
+    node.mine_blocks(CONFIRMATIONS);
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
@@ -0,0 +1,74 @@
+/// The most recent processing error is kept on the invoice and returned by
+/// `get_invoice`, classified by source and kind.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::PaymentGateway;
+use crate::invoice::{
+    Invoice, InvoiceError, InvoiceErrorKind, InvoiceErrorSource, SweepStage, ZeroizedVec,
+};
+use crate::test_utils::{
+    gateway_helpers::{make_gateway, make_single_node_gateway},
+    mock_node::MockNode,
+};
+
+const TREASURY: Address = Address::repeat_byte(0x8E);
+
+async fn wait_for_error(gateway: &PaymentGateway, id: &str) -> InvoiceError {
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let stored = gateway.get_invoice(id).await.expect("invoice must remain");
+            if let Some(error) = stored.last_error {
+                return error;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("error must be recorded")
+}
+
+#[tokio::test]
+async fn test_unreachable_rpc_records_balance_check_error() {
+    let (gateway, _rx) = make_gateway(vec!["http://127.0.0.1:1".to_string()], TREASURY);
+    let (id, _) = gateway
+        .new_invoice(U256::from(1_000u64), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    gateway.poll_payments().await;
+
+    let error = wait_for_error(&gateway, &id).await;
+    assert_eq!(error.source, InvoiceErrorSource::BalanceCheck);
+    assert_eq!(error.kind, InvoiceErrorKind::Rpc);
+    assert!(error.timestamp > 0);
+}
+
+#[tokio::test]
+async fn test_invalid_key_records_classified_sweep_error() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let address = Address::repeat_byte(0x8F);
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    gateway.invoices.write().await.insert(
+        "bad".to_string(),
+        Invoice {
+            to: address,
+            wallet: ZeroizedVec { inner: vec![0xAB; 5] },
+            amount,
+            expires: gateway.now_seconds() + 3600,
+            ..Default::default()
+        },
+    );
+    node.set_balance(address, amount);
+
+    gateway.poll_payments().await;
+
+    let error = wait_for_error(&gateway, "bad").await;
+    assert_eq!(error.source, InvoiceErrorSource::Sweep(SweepStage::Sign));
+    assert_eq!(error.kind, InvoiceErrorKind::InvalidWallet);
+    assert!(!error.message.is_empty());
+}
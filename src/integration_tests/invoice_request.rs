@@ -0,0 +1,73 @@
+/// Invoices created from an `InvoiceRequest` keep their per-invoice settings
+/// and invalid requests are rejected instead of producing broken invoices.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::{error::GatewayError, InvoiceRequest};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x71);
+const MERCHANT: Address = Address::repeat_byte(0x72);
+
+#[tokio::test]
+async fn test_invoice_is_swept_to_its_own_treasury() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let request = InvoiceRequest::new(amount, 3600)
+        .treasury(MERCHANT)
+        .external_id("order-1");
+    let (_, invoice) = gateway.create_invoice(request).await.unwrap();
+    assert_eq!(invoice.treasury, Some(MERCHANT));
+    assert_eq!(invoice.external_id.as_deref(), Some("order-1"));
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+    let (_, delivered) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(delivered.status, InvoiceStatus::Swept);
+    assert!(node.get_balance(MERCHANT) > U256::ZERO);
+    assert_eq!(node.get_balance(TREASURY), U256::ZERO);
+}
+
+#[tokio::test]
+async fn test_invalid_requests_are_rejected() {
+    let node = MockNode::start_with_chain_id(56).await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let amount = U256::from(1_000u64);
+
+    let result = gateway.create_invoice(InvoiceRequest::new(amount, 0)).await;
+    assert!(matches!(result, Err(GatewayError::InvalidInvoiceRequest(_))));
+
+    let request = InvoiceRequest::new(amount, 3600).treasury(Address::ZERO);
+    let result = gateway.create_invoice(request).await;
+    assert!(matches!(result, Err(GatewayError::InvalidInvoiceRequest(_))));
+
+    let request = InvoiceRequest::new(amount, 3600).external_id("");
+    let result = gateway.create_invoice(request).await;
+    assert!(matches!(result, Err(GatewayError::InvalidInvoiceRequest(_))));
+
+    let request = InvoiceRequest::new(amount, 3600).gas_limit(0);
+    let result = gateway.create_invoice(request).await;
+    assert!(matches!(result, Err(GatewayError::InvalidInvoiceRequest(_))));
+
+    let request = InvoiceRequest::new(amount, 3600).chain_id(1);
+    let result = gateway.create_invoice(request).await;
+    assert!(matches!(
+        result,
+        Err(GatewayError::ChainMismatch {
+            expected: 1,
+            actual: 56
+        })
+    ));
+
+    let request = InvoiceRequest::new(amount, 3600).chain_id(56);
+    assert!(gateway.create_invoice(request).await.is_ok());
+    assert_eq!(gateway.get_all_invoices().await.unwrap().len(), 1);
+}
@@ -0,0 +1,85 @@
+/// The poller keeps `Invoice::status` up to date so integrators can query
+/// invoices by lifecycle stage.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{
+    gateway_helpers::{make_gateway_with_confirmations, make_single_node_gateway},
+    mock_node::MockNode,
+};
+
+const TREASURY: Address = Address::repeat_byte(0x57);
+
+#[tokio::test]
+async fn test_delivered_invoice_is_swept() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.status, InvoiceStatus::Pending);
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (_, confirmed) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+
+    let steps: Vec<_> = confirmed.transitions.iter().map(|t| (t.from, t.to)).collect();
+    assert_eq!(
+        steps,
+        vec![
+            (InvoiceStatus::Pending, InvoiceStatus::Paid),
+            (InvoiceStatus::Paid, InvoiceStatus::Sweeping),
+            (InvoiceStatus::Sweeping, InvoiceStatus::Confirming),
+            (InvoiceStatus::Confirming, InvoiceStatus::Swept),
+        ]
+    );
+    assert!(confirmed.transitions.windows(2).all(|w| w[0].at_ms <= w[1].at_ms));
+}
+
+#[tokio::test]
+async fn test_unconfirmed_sweep_is_confirming() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_gateway_with_confirmations(vec![node.url.clone()], TREASURY, 5);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let (unfunded_id, _) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    // The sweep is broadcast but can never reach 5 confirmations
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let confirming = gateway
+        .get_invoices_by_status(InvoiceStatus::Confirming)
+        .await
+        .unwrap();
+    assert_eq!(confirming.len(), 1);
+    assert_eq!(confirming[0].0, id);
+    assert!(confirming[0].1.hash.is_some());
+
+    let pending = gateway
+        .get_invoices_by_status(InvoiceStatus::Pending)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].0, unfunded_id);
+}
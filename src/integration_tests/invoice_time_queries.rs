@@ -0,0 +1,58 @@
+/// Invoices can be queried by creation and expiry time.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::sync::mpsc;
+
+use crate::gateway::clock::MockClock;
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::Invoice;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xCF);
+/// 2030-01-01, far from the system time
+const START: u64 = 1_893_456_000;
+
+fn ids(invoices: Vec<(String, Invoice)>) -> Vec<String> {
+    invoices.into_iter().map(|(id, _)| id).collect()
+}
+
+#[tokio::test]
+async fn test_invoices_are_queried_by_creation_and_expiry_time() {
+    let node = MockNode::start().await;
+    let clock = MockClock::new(START);
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        clock: Arc::new(clock.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let mut created = Vec::new();
+    for expires_in in [3600, 600, 1800] {
+        let (id, _) = gateway
+            .new_invoice(U256::from(1_000u64), vec![], expires_in)
+            .await
+            .expect("invoice creation must succeed");
+        created.push(id);
+        clock.advance(Duration::from_secs(100));
+    }
+
+    let first_two = gateway.get_invoices_created_between(START, START + 200).await.unwrap();
+    assert_eq!(ids(first_two), created[..2]);
+    let none = gateway.get_invoices_created_between(START + 300, START + 400).await.unwrap();
+    assert!(none.is_empty());
+
+    // Expiring at START + 3600, START + 700 and START + 2000
+    let expiring = gateway.get_invoices_expiring_before(START + 2001).await.unwrap();
+    assert_eq!(ids(expiring), [created[1].clone(), created[2].clone()]);
+    let expiring = gateway.get_invoices_expiring_before(START + 2000).await.unwrap();
+    assert_eq!(ids(expiring), [created[1].clone()]);
+
+    gateway.cancel_invoice(&created[1]).await.unwrap();
+    let expiring = gateway.get_invoices_expiring_before(START + 2001).await.unwrap();
+    assert_eq!(ids(expiring), [created[2].clone()]);
+    let all = gateway.get_invoices_created_between(0, u64::MAX).await.unwrap();
+    assert_eq!(ids(all), [created[0].clone(), created[2].clone()]);
+}
@@ -0,0 +1,93 @@
+/// `subscribe` yields the progress of one invoice and ends with it.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use futures::StreamExt;
+use tokio::time::timeout;
+
+use crate::gateway::event::InvoiceUpdate;
+use crate::invoice::{InvoiceOptions, InvoiceStatus};
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xDC);
+
+#[tokio::test]
+async fn test_subscription_follows_one_invoice_to_delivery() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let options = InvoiceOptions {
+        min_confirmations: Some(2),
+        ..Default::default()
+    };
+    let (id, invoice) = gateway
+        .new_invoice_with_options(amount, vec![], 3600, options)
+        .await
+        .unwrap();
+    let (other, unpaid) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+
+    let updates = tokio::spawn(gateway.subscribe(&id).collect::<Vec<_>>());
+    node.set_balance(invoice.to, amount);
+    node.set_balance(unpaid.to, U256::from(1u64));
+    let poller = gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    node.mine_blocks(2);
+    // The treasury transfer needs the same depth
+    timeout(Duration::from_secs(10), async {
+        while node.any_tx_hash().is_none() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("sweep must be broadcast once the payment is confirmed");
+    node.mine_blocks(2);
+    let updates = timeout(Duration::from_secs(10), updates)
+        .await
+        .expect("timed out waiting for the last update")
+        .unwrap();
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    assert_eq!(
+        updates.first(),
+        Some(&InvoiceUpdate::Confirmations {
+            confirmations: 0,
+            required: 2
+        })
+    );
+    assert!(updates.contains(&InvoiceUpdate::Confirmations {
+        confirmations: 2,
+        required: 2
+    }));
+    let statuses: Vec<_> = updates
+        .iter()
+        .filter_map(|update| match update {
+            InvoiceUpdate::Status { status } => Some(*status),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            InvoiceStatus::Paid,
+            InvoiceStatus::Sweeping,
+            InvoiceStatus::Confirming,
+            InvoiceStatus::Swept,
+        ]
+    );
+    assert_eq!(updates.last(), Some(&InvoiceUpdate::Delivered));
+    assert!(gateway.get_invoice(&other).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_subscription_ends_on_cancellation() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let (id, _) = gateway.new_invoice(U256::from(1_000u64), vec![], 3600).await.unwrap();
+
+    let updates = gateway.subscribe(&id);
+    gateway.cancel_invoice(&id).await.unwrap();
+    let updates = timeout(Duration::from_secs(5), updates.collect::<Vec<_>>())
+        .await
+        .expect("the stream must end");
+    assert_eq!(updates, vec![InvoiceUpdate::Cancelled]);
+}
@@ -0,0 +1,90 @@
+/// `sweep_invoice` triggers the treasury transfer on demand, reviving
+/// invoices whose automatic sweeps were given up on.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{
+    error::GatewayError, PaymentGateway, PaymentGatewayConfiguration, SweepRetryPolicy,
+};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xA4);
+
+#[tokio::test]
+async fn test_manual_sweep_revives_failed_invoice() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        sweep_retry: SweepRetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        },
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+
+    // One wei cannot cover the gas of its sweep
+    let (id, invoice) = gateway
+        .new_invoice(U256::from(1u64), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, U256::from(1u64));
+
+    let _handle = gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_invoice(&id).await.unwrap().status != InvoiceStatus::SweepFailed {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("automatic sweep must be given up on");
+
+    node.set_balance(invoice.to, U256::from(1_000_000_000_000_000_000u128));
+    let hash = gateway.sweep_invoice(&id).await.expect("manual sweep must succeed");
+    assert!(matches!(
+        gateway.sweep_invoice(&id).await,
+        Err(GatewayError::SweepInFlight)
+    ));
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.hash, Some(hash));
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+    assert!(node.get_balance(TREASURY) > U256::ZERO);
+}
+
+#[tokio::test]
+async fn test_manual_sweep_reports_failures() {
+    let node = MockNode::start().await;
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration::new(
+        vec![node.url.clone()],
+        TREASURY,
+        tx,
+    ))
+    .expect("gateway creation must succeed");
+
+    assert!(matches!(
+        gateway.sweep_invoice("missing").await,
+        Err(GatewayError::NotFound)
+    ));
+
+    let (id, _) = gateway
+        .new_invoice(U256::from(1_000u64), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert!(matches!(
+        gateway.sweep_invoice(&id).await,
+        Err(GatewayError::Sweep(_))
+    ));
+    let failed = gateway.get_invoice(&id).await.expect("invoice must be kept");
+    assert_eq!(failed.status, InvoiceStatus::Pending);
+    assert!(failed.last_error.is_some());
+}
@@ -0,0 +1,94 @@
+/// A `MockClock` drives invoice expiry and the timestamps of status
+/// transitions, so time-based behavior is tested without waiting for it.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::clock::MockClock;
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xCF);
+/// 2030-01-01, far from the system time
+const START: u64 = 1_893_456_000;
+
+#[tokio::test]
+async fn test_invoice_expires_when_the_clock_is_advanced() {
+    let node = MockNode::start().await;
+    let clock = MockClock::new(START);
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        clock: Arc::new(clock.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let (id, invoice) = gateway
+        .new_invoice(U256::from(1_000u64), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.created_at, START);
+    assert_eq!(invoice.expires, START + 3600);
+
+    gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(gateway.get_invoice(&id).await.unwrap().status, InvoiceStatus::Pending);
+
+    clock.advance(Duration::from_secs(3601));
+    let expired = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Ok(expired) = gateway.get_expired_invoice(&id).await {
+                return expired;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("invoice must expire once the clock passes its expiry");
+    let transition = expired.transitions.last().unwrap();
+    assert_eq!(transition.to, InvoiceStatus::Expired);
+    assert_eq!(transition.at_ms, (START + 3601) * 1000);
+}
+
+#[tokio::test]
+async fn test_transitions_are_timestamped_by_the_clock() {
+    let node = MockNode::start().await;
+    let clock = MockClock::new(START);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        clock: Arc::new(clock.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    clock.advance(Duration::from_secs(60));
+    let poller = gateway.poll_payments().await;
+    let (_, swept) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for the sweep")
+        .unwrap();
+    poller.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    let statuses: Vec<_> = swept.transitions.iter().map(|transition| transition.to).collect();
+    assert_eq!(
+        statuses,
+        [
+            InvoiceStatus::Paid,
+            InvoiceStatus::Sweeping,
+            InvoiceStatus::Confirming,
+            InvoiceStatus::Swept,
+        ]
+    );
+    let at_ms = (START + 60) * 1000;
+    assert!(swept.transitions.iter().all(|transition| transition.at_ms == at_ms));
+}
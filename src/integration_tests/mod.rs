@@ -10,3 +10,68 @@ mod round_robin_rpc;
 mod treasury_address_sweep;
 mod receipt_timeout;
 mod invalid_wallet_key;
+mod bounded_reflector;
+mod chain_id_switch;
+mod cancelled_invoice;
+mod invoice_status;
+mod sweep_stages;
+mod partial_payment_events;
+mod expiry_reminders;
+mod poller_handle;
+mod chain_presets;
+mod hd_wallet_invoice;
+mod invoice_last_error;
+mod forwarder_mode;
+mod token_invoice_gas_topup;
+mod nonce_management;
+mod gas_price_ceiling;
+mod stuck_transaction;
+mod sweep_retry;
+mod batch_sweeping;
+mod sweep_policy;
+mod treasury_splits;
+mod manual_sweep;
+mod polling_pause;
+mod multicall_balances;
+mod check_interval;
+mod invoice_confirmations;
+mod rpc_failover;
+mod expected_chain_id;
+mod fiat_invoice;
+mod multi_token_invoice;
+mod nft_invoice;
+mod decimal_amounts;
+mod unique_amounts;
+mod deposit_payer;
+mod wallet_encryption;
+mod custom_sweep_signer;
+mod swept_wallet_scrubbing;
+mod audit_trail;
+mod invoice_request;
+mod expired_refund;
+mod expired_archive;
+mod paid_history;
+mod idempotent_invoices;
+mod rpc_rate_limit;
+mod fee_cache;
+mod fee_estimator;
+mod gas_limit;
+mod detect_only;
+mod custom_runtime;
+mod crash_recovery;
+mod dead_letters;
+mod await_payment;
+mod invoice_updates;
+mod confirmation_events;
+mod sweep_fee_limit;
+mod dust_threshold;
+mod check_invoice_now;
+mod event_journal;
+mod recovery_report;
+mod mock_clock;
+mod payment_receipts;
+mod sweep_quorum;
+mod circuit_breaker;
+mod gateway_hooks;
+mod invoice_time_queries;
+mod retention_policy;
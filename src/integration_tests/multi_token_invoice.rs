@@ -0,0 +1,89 @@
+/// Invoices accepting several assets are swept in whichever one is paid in
+/// full, and record which option was used.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::PaymentGateway;
+use crate::invoice::{Invoice, InvoiceOptions, PaymentOption};
+use crate::test_utils::gateway_helpers::{funded_gas_sponsor, make_configured_gateway};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x4D);
+const USDC: Address = Address::repeat_byte(0x73);
+const USDT: Address = Address::repeat_byte(0x74);
+
+/// Creates an invoice over 0.05 ETH, 100 USDC or 100 USDT.
+async fn new_multi_token_invoice(gateway: &PaymentGateway) -> (String, Invoice) {
+    let options = InvoiceOptions {
+        alternatives: vec![
+            PaymentOption {
+                token: Some(USDC),
+                amount: U256::from(100_000_000u64),
+            },
+            PaymentOption {
+                token: Some(USDT),
+                amount: U256::from(100_000_000u64),
+            },
+        ],
+        ..Default::default()
+    };
+    gateway
+        .new_invoice_with_options(U256::from(5 * 10u128.pow(16)), vec![], 3600, options)
+        .await
+        .expect("invoice creation must succeed")
+}
+
+#[tokio::test]
+async fn test_invoice_paid_with_alternative_token() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let (id, invoice) = new_multi_token_invoice(&gateway).await;
+    assert_eq!(invoice.payment_options.len(), 3);
+    assert_eq!(invoice.payment_options[0].token, None);
+
+    let usdt_amount = U256::from(100_000_000u64);
+    node.set_token_balance(USDT, invoice.to, usdt_amount);
+    gateway.poll_payments().await;
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    assert_eq!(
+        confirmed.paid_with,
+        Some(PaymentOption {
+            token: Some(USDT),
+            amount: usdt_amount,
+        })
+    );
+    assert_eq!(confirmed.token, Some(USDT));
+    assert_eq!(confirmed.amount, usdt_amount);
+    assert_eq!(node.get_token_balance(USDT, TREASURY), usdt_amount);
+}
+
+#[tokio::test]
+async fn test_invoice_paid_with_native_option() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let (id, invoice) = new_multi_token_invoice(&gateway).await;
+
+    // A partial token payment does not count
+    node.set_token_balance(USDC, invoice.to, U256::from(1u64));
+    node.set_balance(invoice.to, invoice.amount);
+    gateway.poll_payments().await;
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    assert_eq!(confirmed.token, None);
+    assert_eq!(confirmed.paid_with.map(|option| option.token), Some(None));
+}
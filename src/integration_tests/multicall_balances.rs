@@ -0,0 +1,83 @@
+/// With a Multicall contract configured the poller reads every unpaid
+/// invoice balance with a single `eth_call` per cycle.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::MULTICALL3;
+use crate::test_utils::gateway_helpers::{funded_gas_sponsor, make_configured_gateway};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x3C);
+const TOKEN: Address = Address::repeat_byte(0x71);
+
+#[tokio::test]
+async fn test_balances_of_all_invoices_are_read_in_one_call() {
+    let node = MockNode::start().await;
+    // A long delay between individual balance checks would stall the cycle
+    // after the first invoice if balances were not read in bulk
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.poller_delay_seconds = 60;
+        config.multicall = Some(MULTICALL3);
+    });
+
+    for _ in 0..25 {
+        let (id, _) = gateway
+            .new_invoice(U256::from(1_000u64), vec![], 3600)
+            .await
+            .expect("invoice creation must succeed");
+        // Expired and unfunded invoices are pruned as soon as their balance is read
+        gateway.invoices.write().await.get_mut(&id).unwrap().expires = 1;
+    }
+
+    let requests = node.request_count();
+    gateway.poll_payments().await;
+    timeout(Duration::from_secs(5), async {
+        while !gateway.invoices.read().await.is_empty() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("all expired invoices must be pruned in one cycle");
+
+    // One eth_chainId and one eth_call for the whole cycle
+    assert!(node.request_count() - requests <= 2);
+}
+
+#[tokio::test]
+async fn test_native_and_token_payments_are_detected_through_multicall() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.gas_sponsor = Some(sponsor.into());
+        config.multicall = Some(MULTICALL3);
+    });
+
+    let native_amount = U256::from(10u128.pow(17));
+    let token_amount = U256::from(5_000_000u64);
+    let (native_id, native) = gateway
+        .new_invoice(native_amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let (token_id, token) = gateway
+        .new_token_invoice(TOKEN, token_amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(native.to, native_amount);
+    node.set_token_balance(TOKEN, token.to, token_amount);
+
+    gateway.poll_payments().await;
+    let mut confirmed = Vec::new();
+    for _ in 0..2 {
+        let (id, _) = timeout(Duration::from_secs(15), rx.recv())
+            .await
+            .expect("timed out waiting for confirmation")
+            .expect("channel closed");
+        confirmed.push(id);
+    }
+    assert!(confirmed.contains(&native_id));
+    assert!(confirmed.contains(&token_id));
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), token_amount);
+    assert!(node.get_treasury_balance(TREASURY) > U256::ZERO);
+}
@@ -0,0 +1,83 @@
+/// NFT invoices are paid once the token id, or enough of an ERC-1155 id,
+/// reaches the invoice address, and are swept with `safeTransferFrom`.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::NftPayment;
+use crate::test_utils::gateway_helpers::{funded_gas_sponsor, make_configured_gateway};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x1E);
+const COLLECTION: Address = Address::repeat_byte(0x72);
+const BUYER: Address = Address::repeat_byte(0xB0);
+
+#[tokio::test]
+async fn test_erc721_invoice_is_swept_to_treasury() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let token_id = U256::from(42u64);
+    node.set_nft_owner(COLLECTION, token_id, BUYER);
+
+    let nft = NftPayment::erc721(COLLECTION, token_id);
+    let (id, invoice) = gateway
+        .new_nft_invoice(nft, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.nft, Some(nft));
+
+    gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(rx.try_recv().is_err(), "invoice must not be paid before the NFT arrives");
+
+    node.set_nft_owner(COLLECTION, token_id, invoice.to);
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    assert_eq!(node.get_nft_owner(COLLECTION, token_id), Some(TREASURY));
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    assert_eq!(settlement.swept_amount, U256::from(1u64));
+}
+
+#[tokio::test]
+async fn test_erc1155_invoice_requires_full_quantity() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    let token_id = U256::from(7u64);
+
+    let nft = NftPayment::erc1155(COLLECTION, token_id, U256::from(3u64));
+    let (id, invoice) = gateway
+        .new_nft_invoice(nft, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.amount, U256::from(3u64));
+
+    node.set_erc1155_balance(COLLECTION, token_id, invoice.to, U256::from(2u64));
+    gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(rx.try_recv().is_err(), "a partial quantity must not pay the invoice");
+
+    node.set_erc1155_balance(COLLECTION, token_id, invoice.to, U256::from(3u64));
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    assert_eq!(
+        node.get_erc1155_balance(COLLECTION, token_id, TREASURY),
+        U256::from(3u64)
+    );
+    assert_eq!(
+        node.get_erc1155_balance(COLLECTION, token_id, invoice.to),
+        U256::ZERO
+    );
+}
@@ -0,0 +1,77 @@
+/// Sweeps use the pending transaction count of the invoice wallet, through
+/// the configured nonce manager, instead of assuming a fresh wallet.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{nonce::NonceManager, PaymentGateway, PaymentGatewayConfiguration};
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x9A);
+
+/// Records every nonce request and defers to the pending count.
+#[derive(Default)]
+struct RecordingNonceManager {
+    calls: Mutex<Vec<(Address, u64)>>,
+}
+
+impl NonceManager for RecordingNonceManager {
+    fn next_nonce(&self, address: Address, pending_count: u64) -> u64 {
+        self.calls.lock().unwrap().push((address, pending_count));
+        pending_count
+    }
+}
+
+#[tokio::test]
+async fn test_sweep_uses_pending_nonce_of_used_wallet() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    // The wallet already sent three transactions, e.g. refunds
+    node.state.lock().unwrap().nonces.insert(invoice.to, 3);
+    node.set_balance(invoice.to, amount);
+
+    gateway.poll_payments().await;
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.nonce, Some(3));
+}
+
+#[tokio::test]
+async fn test_configured_nonce_manager_is_consulted() {
+    let node = MockNode::start().await;
+    let manager = Arc::new(RecordingNonceManager::default());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        nonce_manager: Some(manager.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    gateway.poll_payments().await;
+    timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    assert_eq!(manager.calls.lock().unwrap()[0], (invoice.to, 0));
+}
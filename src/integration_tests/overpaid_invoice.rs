@@ -70,3 +70,44 @@ async fn test_overpaid_invoice_sweeps_full_balance_to_treasury() {
         "treasury should hold at least the invoice amount"
     );
 }
+
+#[tokio::test]
+async fn test_settlement_reports_received_and_swept_amounts() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    // Customer double-sends
+    let received = amount * U256::from(2u64);
+    node.set_balance(invoice.to, received);
+
+    gateway.poll_payments().await;
+
+    let (_, confirmed) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    // Mock node: 21000 gas at 1 gwei
+    let gas_cost = U256::from(21_000u64) * U256::from(1_000_000_000u64);
+    assert_eq!(settlement.invoice_amount, amount);
+    assert_eq!(settlement.received_amount, received);
+    assert_eq!(settlement.swept_amount, received - gas_cost);
+    assert_eq!(node.get_treasury_balance(TREASURY), settlement.swept_amount);
+    assert_eq!(settlement.sweep_gas_used, Some(21_000));
+    assert_eq!(settlement.sweep_effective_gas_price, Some(1_000_000_000));
+    assert_eq!(settlement.net_amount_to_treasury, Some(received - gas_cost));
+
+    let receipt = settlement.payouts[0]
+        .receipt
+        .expect("receipt must be recorded");
+    assert_eq!(format!("{:?}", receipt.hash), settlement.payouts[0].hash);
+    assert_eq!(receipt.gas_used, 21_000);
+    assert!(receipt.status);
+}
@@ -0,0 +1,51 @@
+/// Invoices delivered on the reflector stay queryable in the paid history,
+/// with totals per currency.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::PaidTotal;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xD1);
+
+#[tokio::test]
+async fn test_delivered_invoices_are_kept_in_paid_history() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    let since = gateway.now_seconds();
+
+    let amounts = [1_000_000_000_000_000_000u128, 2_000_000_000_000_000_000];
+    for amount in amounts {
+        let (_, invoice) = gateway
+            .new_invoice(U256::from(amount), vec![], 3600)
+            .await
+            .expect("invoice creation must succeed");
+        node.set_balance(invoice.to, U256::from(amount));
+    }
+    gateway.poll_payments().await;
+    let mut delivered = Vec::new();
+    for _ in amounts {
+        let (id, _) = timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("timed out waiting for confirmation")
+            .expect("channel closed");
+        delivered.push(id);
+    }
+
+    let until = gateway.now_seconds() + 1;
+    let paid = gateway.get_paid_invoices(since, until).await.unwrap();
+    let ids: Vec<_> = paid.iter().map(|(id, _)| id.clone()).collect();
+    assert_eq!(ids, delivered);
+    assert!(paid.iter().all(|(_, invoice)| invoice.wallet.inner.is_empty()));
+    assert_eq!(
+        gateway.get_paid_totals(since, until).await.unwrap(),
+        vec![PaidTotal {
+            token: None,
+            amount: U256::from(amounts[0] + amounts[1]),
+            invoices: 2,
+        }]
+    );
+    assert!(gateway.get_paid_invoices(until, until + 60).await.unwrap().is_empty());
+}
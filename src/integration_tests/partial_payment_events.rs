@@ -0,0 +1,54 @@
+/// Underpaid invoices raise `PartialPayment` events, throttled per invoice,
+/// while the final paid invoice is always delivered.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::event::GatewayEvent;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x5A);
+
+#[tokio::test]
+async fn test_partial_payments_are_throttled_but_final_state_delivered() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+    let mut events = gateway.subscribe_events();
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    let first = amount / U256::from(4u64);
+    node.set_balance(invoice.to, first);
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("timed out waiting for partial payment event")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::PartialPayment {
+            invoice_id: id.clone(),
+            received: first,
+            amount,
+        }
+    );
+
+    // A second trickle within the throttle window is suppressed
+    node.set_balance(invoice.to, amount / U256::from(2u64));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(events.try_recv().is_err(), "intermediate event must be throttled");
+
+    // The final state is always delivered
+    node.set_balance(invoice.to, amount);
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
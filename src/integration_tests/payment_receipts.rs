@@ -0,0 +1,46 @@
+/// Swept invoices carry a receipt signed by the gateway's
+/// `receipt_signing_key`, verifiable with the signer's address alone.
+use std::time::Duration;
+
+use alloy::primitives::{Address, B256, U256};
+use alloy::signers::local::PrivateKeySigner;
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xEB);
+
+#[tokio::test]
+async fn test_swept_invoice_carries_a_signed_receipt() {
+    let node = MockNode::start().await;
+    let signer = PrivateKeySigner::random();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_signing_key: Some(signer.clone()),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+    let (paid_id, paid) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for the paid invoice")
+        .unwrap();
+    assert_eq!(paid_id, id);
+    assert_eq!(paid.status, InvoiceStatus::Swept);
+
+    let receipt = paid.payment_receipt.expect("swept invoice must carry a receipt");
+    assert_eq!(receipt.invoice_id, id);
+    assert_eq!(receipt.amount, amount);
+    assert_eq!(receipt.token, None);
+    assert_eq!(receipt.sweep_tx_hash, paid.hash.unwrap().parse::<B256>().unwrap());
+    assert!(receipt.verify(signer.address()));
+    assert!(!receipt.verify(PrivateKeySigner::random().address()));
+}
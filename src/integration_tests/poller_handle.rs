@@ -0,0 +1,187 @@
+/// The `PollerHandle` returned by `poll_payments` stops, aborts, restarts,
+/// drains and shuts down the poller.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::{error::GatewayError, PollerState};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{
+    gateway_helpers::{make_gateway_with_confirmations, make_single_node_gateway},
+    mock_node::MockNode,
+};
+
+const TREASURY: Address = Address::repeat_byte(0x7C);
+
+#[tokio::test]
+async fn test_stopped_poller_ignores_payments_until_restarted() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let mut handle = gateway.poll_payments().await;
+    assert_eq!(handle.state(), PollerState::Running);
+    timeout(Duration::from_secs(5), handle.stop())
+        .await
+        .expect("stop must return promptly");
+    assert_eq!(handle.state(), PollerState::Stopped);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let result = timeout(Duration::from_secs(1), rx.recv()).await;
+    assert!(result.is_err(), "stopped poller must not detect payments");
+
+    handle.start();
+    assert_eq!(handle.state(), PollerState::Running);
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation after restart")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_abort_cancels_poller_and_allows_restart() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let mut handle = gateway.poll_payments().await;
+    assert!(handle.is_running());
+    timeout(Duration::from_secs(1), handle.abort())
+        .await
+        .expect("abort must not wait for the poll cycle");
+    assert!(!handle.is_running());
+    assert_eq!(handle.state(), PollerState::Stopped);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    handle.start();
+    assert!(handle.is_running());
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation after restart")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_drain_finishes_in_flight_sweeps_and_refuses_new_detections() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_gateway_with_confirmations(vec![node.url.clone()], TREASURY, 5);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (swept_id, swept) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(swept.to, amount);
+
+    let mut handle = gateway.poll_payments().await;
+
+    // Wait until the sweep was broadcast and awaits confirmations
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_invoice(&swept_id).await.unwrap().hash.is_none() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("sweep must be broadcast");
+
+    let (late_id, late) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(late.to, amount);
+
+    let drain = tokio::spawn(async move {
+        handle.drain().await;
+        handle
+    });
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(!drain.is_finished(), "drain must wait for the in-flight sweep");
+
+    node.mine_blocks(10);
+    let handle = timeout(Duration::from_secs(15), drain)
+        .await
+        .expect("drain must finish once the sweep confirms")
+        .unwrap();
+    assert_eq!(handle.state(), PollerState::Stopped);
+
+    let (confirmed_id, _) = rx.try_recv().expect("in-flight sweep must be delivered");
+    assert_eq!(confirmed_id, swept_id);
+
+    let late = gateway.get_invoice(&late_id).await.unwrap();
+    assert_eq!(late.status, InvoiceStatus::Pending);
+    assert!(late.hash.is_none(), "no new sweep may start while draining");
+}
+
+#[tokio::test]
+async fn test_shutdown_delivers_in_flight_sweeps() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_gateway_with_confirmations(vec![node.url.clone()], TREASURY, 5);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let handle = gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_invoice(&id).await.unwrap().hash.is_none() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("sweep must be broadcast");
+
+    node.mine_blocks(10);
+    handle
+        .shutdown(Duration::from_secs(15))
+        .await
+        .expect("shutdown must finish the in-flight sweep");
+    let (confirmed_id, _) = rx.try_recv().expect("paid invoice must be delivered");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_shutdown_aborts_after_timeout() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_gateway_with_confirmations(vec![node.url.clone()], TREASURY, 5);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let handle = gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_invoice(&id).await.unwrap().hash.is_none() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("sweep must be broadcast");
+
+    // The sweep never reaches its confirmations
+    let result = timeout(Duration::from_secs(5), handle.shutdown(Duration::from_secs(1)))
+        .await
+        .expect("shutdown must honor its timeout");
+    assert!(matches!(result, Err(GatewayError::ShutdownTimeout)));
+    let pending = gateway.get_invoice(&id).await.expect("invoice must be kept");
+    assert!(pending.hash.is_some());
+}
@@ -0,0 +1,58 @@
+/// Pausing the poller halts its RPC traffic without stopping the task;
+/// resuming picks up where it left off.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::PollerState;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xA5);
+
+#[tokio::test]
+async fn test_paused_poller_sends_no_requests_until_resumed() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+
+    let handle = gateway.poll_payments().await;
+    gateway.pause_polling();
+    assert!(gateway.is_polling_paused());
+
+    // Let the poller finish whatever it was doing when paused
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let requests = node.request_count();
+    node.set_balance(invoice.to, amount);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    assert_eq!(node.request_count(), requests, "paused poller must not call the RPC");
+    assert!(rx.try_recv().is_err());
+    assert!(handle.is_running());
+    assert_eq!(handle.state(), PollerState::Running);
+
+    gateway.resume_polling();
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation after resume")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_paused_poller_can_be_stopped() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let mut handle = gateway.poll_payments().await;
+    gateway.pause_polling();
+    timeout(Duration::from_secs(5), handle.stop())
+        .await
+        .expect("stop must not wait for resume");
+    assert!(!handle.is_running());
+}
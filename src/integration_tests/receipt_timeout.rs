@@ -19,12 +19,10 @@ async fn test_short_receipt_timeout_does_not_panic() {
     let node = MockNode::start().await;
     let (tx, mut rx) = mpsc::unbounded_channel();
     let config = PaymentGatewayConfiguration {
-        rpc_urls: vec![node.url.clone()],
-        treasury_address: TREASURY,
         poller_delay_seconds: 0,
         min_confirmations: 0,
         receipt_timeout_seconds: 1, // very short but non-zero
-        sender: tx,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
     };
     let gateway = PaymentGateway::new(config).unwrap();
 
@@ -51,12 +49,10 @@ async fn test_zero_receipt_timeout_does_not_panic() {
     let node = MockNode::start().await;
     let (tx, mut rx) = mpsc::unbounded_channel();
     let config = PaymentGatewayConfiguration {
-        rpc_urls: vec![node.url.clone()],
-        treasury_address: TREASURY,
         poller_delay_seconds: 0,
         min_confirmations: 0,
         receipt_timeout_seconds: 0, // instant timeout
-        sender: tx,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
     };
     let gateway = PaymentGateway::new(config).unwrap();
 
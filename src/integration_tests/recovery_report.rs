@@ -0,0 +1,52 @@
+/// `recovery_report` lists the wallets of pending, failed and expired
+/// invoices with their current balances, so stranded funds can be rescued.
+use alloy::primitives::{Address, U256};
+
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0xCE);
+const TOKEN: Address = Address::repeat_byte(0x70);
+
+#[tokio::test]
+async fn test_report_lists_wallets_with_balances() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+    let amount = U256::from(1_000_000u64);
+    let (pending_id, pending) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    let (token_id, token) = gateway
+        .new_token_invoice(TOKEN, amount, vec![], 3600)
+        .await
+        .unwrap();
+    let (expired_id, expired) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    let mut invoices = gateway.invoices.write().await;
+    invoices.get_mut(&token_id).unwrap().status = InvoiceStatus::SweepFailed;
+    drop(invoices);
+    gateway.expire_invoice(&expired_id, expired.clone()).await;
+
+    node.set_balance(pending.to, U256::from(5u64));
+    node.set_token_balance(TOKEN, token.to, amount);
+    node.set_balance(token.to, U256::from(7u64));
+    // Paid after expiry
+    node.set_balance(expired.to, amount);
+
+    let report = gateway.recovery_report().await.unwrap();
+    assert_eq!(report.len(), 3);
+    let entry = |id: &str| report.iter().find(|entry| entry.invoice_id == id).unwrap();
+
+    let pending_entry = entry(&pending_id);
+    assert_eq!(pending_entry.address, pending.to);
+    assert_eq!(pending_entry.status, InvoiceStatus::Pending);
+    assert_eq!(pending_entry.balance, U256::from(5u64));
+    assert_eq!(pending_entry.native_balance, None);
+
+    let token_entry = entry(&token_id);
+    assert_eq!(token_entry.status, InvoiceStatus::SweepFailed);
+    assert_eq!(token_entry.token, Some(TOKEN));
+    assert_eq!(token_entry.balance, amount);
+    assert_eq!(token_entry.native_balance, Some(U256::from(7u64)));
+
+    let expired_entry = entry(&expired_id);
+    assert_eq!(expired_entry.status, InvoiceStatus::Expired);
+    assert_eq!(expired_entry.balance, amount);
+}
@@ -0,0 +1,80 @@
+/// A `RetentionPolicy` purges paid and expired invoices once their retention
+/// period passed.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, B256, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::audit::{AuditEntry, AuditLog};
+use crate::gateway::clock::MockClock;
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration, RetentionPolicy};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xCF);
+/// 2030-01-01, far from the system time
+const START: u64 = 1_893_456_000;
+
+#[tokio::test]
+async fn test_settled_invoices_are_purged_after_their_retention() {
+    let node = MockNode::start().await;
+    let clock = MockClock::new(START);
+    let path = std::env::temp_dir().join(format!("acceptevm-audit-{}.jsonl", B256::random()));
+    let audit_log = AuditLog::new(&path);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        clock: Arc::new(clock.clone()),
+        audit_log: Some(audit_log.clone()),
+        retention: RetentionPolicy {
+            paid_seconds: Some(600),
+            expired_seconds: Some(600),
+            failed_seconds: None,
+            cleanup_interval_seconds: 0,
+        },
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let (paid, _) = gateway.new_invoice(U256::ZERO, vec![], 3600).await.unwrap();
+    let (expired, _) = gateway.new_invoice(U256::from(1_000u64), vec![], 60).await.unwrap();
+    gateway.poll_payments().await;
+    timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for the paid invoice")
+        .expect("channel closed");
+
+    clock.advance(Duration::from_secs(61));
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_expired_invoice(&expired).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("invoice must expire once the clock passes its expiry");
+    // Both are still within their retention
+    assert_eq!(gateway.get_paid_invoices(0, u64::MAX).await.unwrap().len(), 1);
+
+    clock.advance(Duration::from_secs(600));
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_expired_invoice(&expired).await.is_ok()
+            || !gateway.get_paid_invoices(0, u64::MAX).await.unwrap().is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("invoices must be purged once their retention passed");
+
+    let records = audit_log.read().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let purged = |id: &str, status| {
+        records
+            .iter()
+            .any(|r| r.invoice_id == id && r.entry == AuditEntry::Purged { status })
+    };
+    assert!(purged(&paid, InvoiceStatus::Paid));
+    assert!(purged(&expired, InvoiceStatus::Expired));
+}
@@ -25,12 +25,10 @@ async fn test_round_robin_index_wraps() {
         "http://fake-b.invalid".to_string(),
     ];
     let config = PaymentGatewayConfiguration {
-        rpc_urls: urls.clone(),
-        treasury_address: TREASURY,
         poller_delay_seconds: 0,
         min_confirmations: 0,
         receipt_timeout_seconds: 1,
-        sender: tx,
+        ..PaymentGatewayConfiguration::new(urls.clone(), TREASURY, tx)
     };
     let gateway = PaymentGateway::new(config).unwrap();
 
@@ -59,12 +57,14 @@ async fn test_round_robin_single_real_node_three_urls() {
     let node = MockNode::start().await;
     let (tx, mut rx) = mpsc::unbounded_channel();
     let config = PaymentGatewayConfiguration {
-        rpc_urls: vec![node.url.clone(), node.url.clone(), node.url.clone()],
-        treasury_address: TREASURY,
         poller_delay_seconds: 0,
         min_confirmations: 0,
         receipt_timeout_seconds: 5,
-        sender: tx,
+        ..PaymentGatewayConfiguration::new(
+            vec![node.url.clone(), node.url.clone(), node.url.clone()],
+            TREASURY,
+            tx,
+        )
     };
     let gateway = PaymentGateway::new(config).unwrap();
 
@@ -0,0 +1,96 @@
+/// In failover mode the gateway sticks to one RPC URL and only moves on to
+/// the next one after repeated failures.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{
+    event::GatewayEvent, PaymentGateway, PaymentGatewayConfiguration, RpcSelection,
+};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xF0);
+/// Nothing listens on port 1, so every request fails
+const DEAD_URL: &str = "http://127.0.0.1:1";
+
+#[tokio::test]
+async fn test_failing_primary_is_replaced_by_next_url() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        rpc_selection: RpcSelection::Failover { max_failures: 2 },
+        ..PaymentGatewayConfiguration::new(
+            vec![DEAD_URL.to_string(), node.url.clone()],
+            TREASURY,
+            tx,
+        )
+    })
+    .expect("gateway creation must not fail");
+    let mut events = gateway.subscribe_events();
+
+    let amount = U256::from(10u128.pow(17));
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+    assert_eq!(gateway.next_rpc_url(), DEAD_URL);
+    gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for the provider switch")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::ProviderSwitched {
+            from: DEAD_URL.to_string(),
+            to: node.url.clone(),
+        }
+    );
+    assert_eq!(gateway.next_rpc_url(), node.url);
+
+    let (confirmed_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+}
+
+#[tokio::test]
+async fn test_healthy_primary_receives_every_request() {
+    let primary = MockNode::start().await;
+    let backup = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        rpc_selection: RpcSelection::Failover { max_failures: 2 },
+        ..PaymentGatewayConfiguration::new(
+            vec![primary.url.clone(), backup.url.clone()],
+            TREASURY,
+            tx,
+        )
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(10u128.pow(17));
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    primary.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert!(primary.request_count() > 0);
+    assert_eq!(backup.request_count(), 0);
+}
@@ -0,0 +1,64 @@
+/// Every RPC request of the gateway passes through the configured token
+/// bucket, which replaces the poller delay between invoices.
+use std::time::{Duration, Instant};
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{
+    error::GatewayError, PaymentGateway, PaymentGatewayConfiguration, RpcRateLimit,
+};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xD3);
+const REQUESTS_PER_SECOND: u32 = 20;
+
+#[tokio::test]
+async fn test_requests_stay_within_rate_limit() {
+    let node = MockNode::start().await;
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        // Without the limit the poller would wait a minute per invoice
+        poller_delay_seconds: 60,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        rpc_rate_limit: Some(RpcRateLimit {
+            requests_per_second: REQUESTS_PER_SECOND,
+            burst: 1,
+        }),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    for _ in 0..3 {
+        let (_, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+        node.set_balance(invoice.to, amount);
+    }
+    let started = Instant::now();
+    let before = node.request_count();
+    gateway.poll_payments().await;
+
+    // All three are checked and swept in the first cycle
+    timeout(Duration::from_secs(10), async {
+        while node.sent_txs().len() < 3 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("sweeps must not wait for the poller delay");
+
+    let allowed = 1.0 + f64::from(REQUESTS_PER_SECOND) * started.elapsed().as_secs_f64();
+    let sent = node.request_count() - before;
+    assert!(sent as f64 <= allowed + 1.0, "{sent} requests, {allowed} allowed");
+}
+
+#[tokio::test]
+async fn test_zero_rate_limit_is_rejected() {
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let result = PaymentGateway::new(PaymentGatewayConfiguration {
+        rpc_rate_limit: Some(RpcRateLimit::per_second(0)),
+        ..PaymentGatewayConfiguration::new(vec!["http://localhost".into()], TREASURY, tx)
+    });
+    assert!(matches!(result, Err(GatewayError::InvalidRateLimit)));
+}
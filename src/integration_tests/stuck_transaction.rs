@@ -0,0 +1,76 @@
+/// A sweep stuck in the mempool is left alone until the replacement timeout
+/// passes, then re-sent with the same nonce and escalating fees.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_configured_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x9C);
+
+#[tokio::test]
+async fn test_pending_sweep_not_replaced_before_timeout() {
+    let node = MockNode::start().await;
+    node.hold_txs(true);
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.replacement_timeout_seconds = 3600
+    });
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    assert_eq!(node.sent_txs().len(), 1);
+    let pending = gateway.get_invoice(&id).await.expect("invoice must exist");
+    assert_eq!(pending.status, InvoiceStatus::Confirming);
+    assert!(pending.last_error.is_none());
+}
+
+#[tokio::test]
+async fn test_stuck_sweep_replaced_with_bumped_fees() {
+    let node = MockNode::start().await;
+    node.hold_txs(true);
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.replacement_timeout_seconds = 1
+    });
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+    timeout(Duration::from_secs(15), async {
+        while node.sent_txs().len() < 3 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("stuck sweep must be replaced");
+
+    let sent = node.sent_txs();
+    assert!(sent.iter().all(|tx| tx.from == invoice.to && tx.nonce == sent[0].nonce));
+    // Every replacement outbids the one before it
+    assert!(sent.windows(2).all(|pair| pair[1].fee_per_gas > pair[0].fee_per_gas));
+
+    node.hold_txs(false);
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+    let last = node.sent_txs().pop().expect("replacement must be sent");
+    assert_eq!(confirmed.hash, Some(format!("{:?}", last.hash)));
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    assert_eq!(settlement.max_fee_per_gas, last.fee_per_gas);
+}
@@ -0,0 +1,67 @@
+/// Sweeps costing more than `max_sweep_fee_bps` of the invoice amount are
+/// deferred with a single `SweepDeferred` event until fees drop.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{event::GatewayEvent, PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x9F);
+const GWEI: u128 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_sweep_deferred_while_fee_exceeds_share_of_amount() {
+    // Legacy chain quoting 1 gwei, a sweep costs 21000 gwei
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        max_sweep_fee_bps: Some(1_000),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+    let mut events = gateway.subscribe_events();
+
+    // 10% of the amount is 10000 gwei
+    let amount = U256::from(100_000 * GWEI);
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("timed out waiting for SweepDeferred")
+        .expect("event channel closed");
+    assert_eq!(
+        event,
+        GatewayEvent::SweepDeferred {
+            invoice_id: id.clone(),
+            fee: U256::from(21_000 * GWEI),
+            amount,
+        }
+    );
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let deferred = gateway.get_invoice(&id).await.expect("invoice must still exist");
+    assert_eq!(deferred.status, InvoiceStatus::PaidAwaitingSweep);
+    assert!(deferred.hash.is_none());
+    assert!(rx.try_recv().is_err());
+    // Deferring again on later polls raises no further event
+    assert!(events.try_recv().is_err());
+
+    node.state.lock().unwrap().chain.gas_price = GWEI / 4;
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+    assert!(node.get_balance(TREASURY) > U256::ZERO);
+}
@@ -0,0 +1,102 @@
+/// Paid invoices are held back until the configured sweep policy permits
+/// sweeping them.
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::hooks::{GatewayHooks, HookFuture};
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration, SweepPolicy};
+use crate::invoice::{Invoice, InvoiceStatus};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x9F);
+
+/// Counts the payments detected.
+struct DetectionCounter(Arc<AtomicU32>);
+
+impl GatewayHooks for DetectionCounter {
+    fn on_detected(&self, _invoice_id: String, _invoice: Invoice) -> HookFuture {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+}
+
+#[tokio::test]
+async fn test_threshold_policy_sweeps_once_total_is_reached() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let detected = Arc::new(AtomicU32::new(0));
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        sweep_policy: SweepPolicy::Threshold {
+            min_total: amount * U256::from(2u64),
+        },
+        hooks: Some(Arc::new(DetectionCounter(detected.clone()))),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+
+    let (first_id, first) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let (_, second) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(first.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let held = gateway.get_invoice(&first_id).await.expect("invoice must exist");
+    assert_eq!(held.status, InvoiceStatus::Paid);
+    assert!(node.sent_txs().is_empty());
+    // Held for many cycles, but detected only once
+    assert_eq!(detected.load(Ordering::SeqCst), 1);
+
+    node.set_balance(second.to, amount);
+    for _ in 0..2 {
+        let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+            .await
+            .expect("timed out waiting for confirmation")
+            .expect("channel closed");
+        assert_eq!(confirmed.status, InvoiceStatus::Swept);
+    }
+    assert_eq!(node.sent_txs().len(), 2);
+    assert_eq!(detected.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_scheduled_policy_sweeps_in_next_window() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        sweep_policy: SweepPolicy::Scheduled { every_seconds: 2 },
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+}
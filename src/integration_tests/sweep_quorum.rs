@@ -0,0 +1,66 @@
+/// High-value payments are only swept once every endpoint of the sweep
+/// quorum confirms them, so one lying endpoint cannot fake a payment.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{event::GatewayEvent, PaymentGateway, PaymentGatewayConfiguration, SweepQuorum};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x9A);
+
+#[tokio::test]
+async fn test_sweep_waits_until_the_quorum_agrees() {
+    let node = MockNode::start().await;
+    let witness = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        sweep_quorum: Some(SweepQuorum::new(vec![witness.url.clone()], amount)),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must succeed");
+    let mut events = gateway.subscribe_events();
+
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    // Only the primary endpoint reports the payment
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let disagreement = timeout(Duration::from_secs(5), async {
+        loop {
+            if let GatewayEvent::QuorumDisagreement { invoice_id, rpc_url } =
+                events.recv().await.unwrap()
+            {
+                return (invoice_id, rpc_url);
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for QuorumDisagreement");
+    assert_eq!(disagreement, (id.clone(), witness.url.clone()));
+    assert_eq!(gateway.get_invoice(&id).await.unwrap().status, InvoiceStatus::Pending);
+    assert!(node.sent_txs().is_empty());
+
+    witness.set_balance(invoice.to, amount);
+    let (paid_id, paid) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for the paid invoice")
+        .unwrap();
+    assert_eq!(paid_id, id);
+    assert_eq!(paid.status, InvoiceStatus::Swept);
+}
+
+#[test]
+fn test_quorum_without_endpoints_is_rejected() {
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let result = PaymentGateway::new(PaymentGatewayConfiguration {
+        sweep_quorum: Some(SweepQuorum::new(Vec::new(), U256::ZERO)),
+        ..PaymentGatewayConfiguration::new(vec!["http://a.com".into()], TREASURY, tx)
+    });
+    assert!(result.is_err());
+}
@@ -0,0 +1,97 @@
+/// Failed sweeps are retried with backoff and given up on, with a
+/// `SweepFailed` event, once the retry policy is exhausted.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::{event::GatewayEvent, SweepRetryPolicy};
+use crate::invoice::{InvoiceErrorKind, InvoiceStatus};
+use crate::test_utils::{gateway_helpers::make_configured_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x9D);
+
+#[tokio::test]
+async fn test_sweep_failed_after_retries_exhausted() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.sweep_retry = SweepRetryPolicy {
+            max_attempts: 3,
+            initial_backoff_seconds: 1,
+            max_backoff_seconds: 1,
+        }
+    });
+    let mut events = gateway.subscribe_events();
+
+    // One wei is paid but can never cover the gas of its sweep
+    let amount = U256::from(1u64);
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+
+    let event = timeout(Duration::from_secs(15), events.recv())
+        .await
+        .expect("timed out waiting for SweepFailed")
+        .expect("event channel closed");
+    let GatewayEvent::SweepFailed {
+        invoice_id,
+        attempts,
+        error,
+    } = event
+    else {
+        panic!("expected SweepFailed, got {event:?}");
+    };
+    assert_eq!(invoice_id, id);
+    assert_eq!(attempts, 3);
+    assert_eq!(error.kind, InvoiceErrorKind::InsufficientBalance);
+
+    let failed = gateway.get_invoice(&id).await.expect("invoice must be kept");
+    assert_eq!(failed.status, InvoiceStatus::SweepFailed);
+    assert_eq!(failed.sweep_attempts, 3);
+
+    // No further attempts once exhausted
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let failed = gateway.get_invoice(&id).await.expect("invoice must be kept");
+    assert_eq!(failed.sweep_attempts, 3);
+}
+
+#[tokio::test]
+async fn test_failed_sweep_backs_off_then_succeeds() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.sweep_retry = SweepRetryPolicy {
+            max_attempts: 10,
+            initial_backoff_seconds: 2,
+            max_backoff_seconds: 2,
+        }
+    });
+
+    let amount = U256::from(1u64);
+    let (id, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    let _handle = gateway.poll_payments().await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let failed = gateway.get_invoice(&id).await.expect("invoice must be kept");
+    assert_eq!(failed.status, InvoiceStatus::Failed);
+    assert_eq!(failed.sweep_attempts, 1);
+    assert!(failed.next_sweep_at > 0);
+
+    // Enough to cover gas for the next attempt
+    node.set_balance(invoice.to, U256::from(1_000_000_000_000_000_000u128));
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed.status, InvoiceStatus::Swept);
+    assert_eq!(confirmed.sweep_attempts, 0);
+}
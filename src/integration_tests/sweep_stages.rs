@@ -0,0 +1,74 @@
+/// The sweep pipeline records per-stage timings on the settlement record and
+/// tags failures with the stage they happened in.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::invoice::{Invoice, SweepStage, ZeroizedVec};
+use crate::test_utils::{gateway_helpers::make_single_node_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x58);
+
+#[tokio::test]
+async fn test_confirmed_sweep_records_timings() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (_, confirmed) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    assert!(settlement.timings.broadcast_at_ms > 0);
+    assert!(settlement.timings.confirm_ms.is_some());
+    assert!(settlement.error.is_none());
+}
+
+#[tokio::test]
+async fn test_invalid_key_fails_at_sign_stage() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let address = Address::repeat_byte(0x59);
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let bad_invoice = Invoice {
+        to: address,
+        wallet: ZeroizedVec { inner: vec![0xAB; 5] },
+        amount,
+        expires: gateway.now_seconds() + 3600,
+        ..Default::default()
+    };
+    gateway
+        .invoices
+        .write()
+        .await
+        .insert("bad".to_string(), bad_invoice);
+    node.set_balance(address, amount);
+
+    gateway.poll_payments().await;
+
+    // The poller keeps retrying, so wait until the first failure is recorded
+    let error = timeout(Duration::from_secs(5), async {
+        loop {
+            let stored = gateway.get_invoice("bad").await.expect("invoice must remain");
+            if let Some(error) = stored.settlement.and_then(|s| s.error) {
+                return error;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("stage error must be recorded");
+    assert_eq!(error.stage, SweepStage::Sign);
+    assert!(node.any_tx_hash().is_none(), "nothing may be broadcast");
+}
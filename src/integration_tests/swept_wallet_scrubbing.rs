@@ -0,0 +1,49 @@
+/// Swept invoices are delivered without their wallet key unless the gateway
+/// is configured to retain it.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x5C);
+
+async fn sweep_one(retain_swept_wallets: bool) -> (Vec<u8>, Vec<u8>) {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        retain_swept_wallets,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let (_, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (_, delivered) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(delivered.status, InvoiceStatus::Swept);
+    (invoice.wallet.inner.clone(), delivered.wallet.inner.clone())
+}
+
+#[tokio::test]
+async fn test_swept_invoice_is_delivered_without_wallet() {
+    let (_, delivered) = sweep_one(false).await;
+    assert!(delivered.is_empty());
+}
+
+#[tokio::test]
+async fn test_swept_wallet_is_retained_on_opt_out() {
+    let (created, delivered) = sweep_one(true).await;
+    assert_eq!(delivered, created);
+}
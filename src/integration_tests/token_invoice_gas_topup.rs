@@ -0,0 +1,198 @@
+/// ERC20 invoices are swept after the gas sponsor tops up the invoice wallet
+/// with exactly the estimated gas.
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::gateway::sponsor::{CsvSponsorStore, SponsorStore};
+use crate::invoice::{InvoiceErrorKind, InvoiceErrorSource, SweepStage};
+use crate::test_utils::gateway_helpers::{
+    funded_gas_sponsor, make_configured_gateway, make_single_node_gateway,
+};
+use crate::test_utils::mock_node::{MockNode, ScriptedReceipt};
+
+const TREASURY: Address = Address::repeat_byte(0x1E);
+const TOKEN: Address = Address::repeat_byte(0x70);
+/// 21000 gas at the mock node's 1 gwei gas price
+const TRANSFER_GAS_COST: u128 = 21_000 * 1_000_000_000;
+
+#[tokio::test]
+async fn test_token_invoice_is_swept_with_sponsored_gas() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+
+    let amount = U256::from(250_000_000u64); // 250 USDT with 6 decimals
+    let (id, invoice) = gateway
+        .new_token_invoice(TOKEN, amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(invoice.token, Some(TOKEN));
+
+    node.set_token_balance(TOKEN, invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let (confirmed_id, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(confirmed_id, id);
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), amount);
+    assert_eq!(node.get_token_balance(TOKEN, invoice.to), U256::ZERO);
+
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    assert_eq!(settlement.swept_amount, amount);
+    assert_eq!(settlement.sponsored_gas, U256::from(TRANSFER_GAS_COST));
+}
+
+#[tokio::test]
+async fn test_token_invoice_without_sponsor_reports_missing_gas() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_single_node_gateway(&node, TREASURY);
+
+    let amount = U256::from(1_000u64);
+    let (id, invoice) = gateway
+        .new_token_invoice(TOKEN, amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_token_balance(TOKEN, invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let error = timeout(Duration::from_secs(10), async {
+        loop {
+            let stored = gateway.get_invoice(&id).await.expect("invoice must remain");
+            if let Some(error) = stored.last_error {
+                return error;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("error must be recorded");
+    assert_eq!(error.source, InvoiceErrorSource::Sweep(SweepStage::Estimate));
+    assert_eq!(error.kind, InvoiceErrorKind::InsufficientBalance);
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), U256::ZERO);
+}
+
+#[tokio::test]
+async fn test_reverted_top_up_fails_the_sweep() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, _rx) =
+        make_configured_gateway(&node, TREASURY, |config| config.gas_sponsor = Some(sponsor.into()));
+    node.script_receipts([ScriptedReceipt::Reverted]);
+
+    let amount = U256::from(1_000u64);
+    let (id, invoice) = gateway.new_token_invoice(TOKEN, amount, vec![], 3600).await.unwrap();
+    node.set_token_balance(TOKEN, invoice.to, amount);
+    gateway.poll_payments().await;
+
+    let error = timeout(Duration::from_secs(10), async {
+        loop {
+            let stored = gateway.get_invoice(&id).await.expect("invoice must remain");
+            if let Some(error) = stored.last_error {
+                return error;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("error must be recorded");
+    assert_eq!(error.source, InvoiceErrorSource::Sweep(SweepStage::Estimate));
+    assert!(error.message.contains("reverted"), "{}", error.message);
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), U256::ZERO);
+}
+
+#[tokio::test]
+async fn test_pending_top_up_is_recorded_and_not_sent_again() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let sponsor_address = sponsor.address();
+    let path = std::env::temp_dir()
+        .join(format!("acceptevm-pending-top-up-{}.csv", std::process::id()));
+    let store = CsvSponsorStore::new(&path);
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.gas_sponsor = Some(sponsor.into());
+        config.sponsor_store = Some(Arc::new(store));
+        config.receipt_timeout_seconds = 1;
+        config.sweep_retry.initial_backoff_seconds = 0;
+        config.sweep_retry.max_backoff_seconds = 0;
+    });
+    // The top-up stays in the mempool until released
+    node.hold_txs(true);
+
+    let amount = U256::from(1_000u64);
+    let (id, invoice) = gateway.new_token_invoice(TOKEN, amount, vec![], 3600).await.unwrap();
+    node.set_token_balance(TOKEN, invoice.to, amount);
+    gateway.poll_payments().await;
+
+    timeout(Duration::from_secs(10), async {
+        while gateway.get_invoice(&id).await.unwrap().last_error.is_none() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the top-up must time out");
+    let saved = CsvSponsorStore::new(&path).load().await.unwrap();
+    let [funding] = saved.pending.as_slice() else {
+        panic!("one pending top-up must be saved: {saved:?}");
+    };
+    assert_eq!(funding.wallet, invoice.to);
+    assert_eq!(funding.nonce, 0);
+    assert!(funding.tx_hash.is_some() && funding.raw_tx.is_some());
+
+    // Retries await the saved top-up, broadcasting it again, until it is mined
+    node.hold_txs(false);
+    timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    let top_ups: Vec<_> = node
+        .sent_txs()
+        .into_iter()
+        .filter(|tx| tx.from == sponsor_address)
+        .collect();
+    assert!(top_ups.iter().all(|tx| Some(tx.hash) == funding.tx_hash), "{top_ups:?}");
+    let state = gateway.sponsor_state().await.unwrap();
+    assert!(state.pending.is_empty());
+    assert_eq!(state.spent, U256::from(TRANSFER_GAS_COST));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_sponsor_top_ups_of_a_batch_use_distinct_nonces() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let sponsor_address = sponsor.address();
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.gas_sponsor = Some(sponsor.into());
+        // Both invoices are swept concurrently with the default nonce manager
+        config.sweep_batch_size = 2;
+    });
+
+    let amount = U256::from(1_000u64);
+    for _ in 0..2 {
+        let (_, invoice) = gateway.new_token_invoice(TOKEN, amount, vec![], 3600).await.unwrap();
+        node.set_token_balance(TOKEN, invoice.to, amount);
+    }
+    gateway.poll_payments().await;
+    for _ in 0..2 {
+        timeout(Duration::from_secs(15), rx.recv())
+            .await
+            .expect("timed out waiting for confirmation")
+            .expect("channel closed");
+    }
+
+    let mut nonces: Vec<_> = node
+        .sent_txs()
+        .into_iter()
+        .filter(|tx| tx.from == sponsor_address)
+        .map(|tx| tx.nonce)
+        .collect();
+    nonces.sort();
+    assert_eq!(nonces, [0, 1]);
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), amount * U256::from(2u64));
+}
@@ -0,0 +1,89 @@
+/// Sweeps pay every configured treasury split before the treasury receives
+/// the remainder.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use tokio::time::timeout;
+
+use crate::test_utils::gateway_helpers::{funded_gas_sponsor, make_configured_gateway};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0xA1);
+const PLATFORM: Address = Address::repeat_byte(0xA2);
+const TOKEN: Address = Address::repeat_byte(0xA3);
+
+#[tokio::test]
+async fn test_native_sweep_is_split() {
+    let node = MockNode::start().await;
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.treasury_splits = vec![(PLATFORM, 500)]
+    });
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let (_, invoice) = gateway
+        .new_invoice(amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_balance(invoice.to, amount);
+
+    gateway.poll_payments().await;
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    let platform_share = settlement.swept_amount * U256::from(500u64) / U256::from(10_000u64);
+    assert_eq!(node.get_balance(PLATFORM), platform_share);
+    assert_eq!(
+        node.get_balance(TREASURY),
+        settlement.swept_amount - platform_share
+    );
+
+    assert_eq!(settlement.payouts.len(), 2);
+    assert_eq!(settlement.payouts[0].to, PLATFORM);
+    assert_eq!(settlement.payouts[1].to, TREASURY);
+    // The treasury transfer is sent last and tracked for confirmation
+    assert_eq!(confirmed.hash.as_deref(), Some(settlement.payouts[1].hash.as_str()));
+    // Both transfers are paid for by the invoice wallet
+    assert_eq!(settlement.sweep_gas_used, Some(42_000));
+    assert_eq!(
+        settlement.net_amount_to_treasury,
+        Some(node.get_balance(TREASURY))
+    );
+
+    let sent = node.sent_txs();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1].nonce, sent[0].nonce + 1);
+}
+
+#[tokio::test]
+async fn test_token_sweep_is_split() {
+    let node = MockNode::start().await;
+    let sponsor = funded_gas_sponsor(&node);
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.treasury_splits = vec![(PLATFORM, 500)];
+        config.gas_sponsor = Some(sponsor.into());
+    });
+
+    let amount = U256::from(250_000_000u64); // 250 USDT with 6 decimals
+    let (_, invoice) = gateway
+        .new_token_invoice(TOKEN, amount, vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    node.set_token_balance(TOKEN, invoice.to, amount);
+
+    gateway.poll_payments().await;
+
+    let (_, confirmed) = timeout(Duration::from_secs(15), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+
+    assert_eq!(node.get_token_balance(TOKEN, PLATFORM), U256::from(12_500_000u64));
+    assert_eq!(node.get_token_balance(TOKEN, TREASURY), U256::from(237_500_000u64));
+    let settlement = confirmed.settlement.expect("settlement must be recorded");
+    assert_eq!(settlement.swept_amount, amount);
+    assert_eq!(settlement.payouts.len(), 2);
+}
@@ -0,0 +1,100 @@
+/// In unique-amount mode invoices share one deposit address and are paid by
+/// a transfer of exactly their suffixed amount, found by scanning blocks.
+use std::time::Duration;
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use tokio::time::timeout;
+
+use crate::gateway::{error::GatewayError, UniqueAmounts};
+use crate::invoice::InvoiceStatus;
+use crate::test_utils::{gateway_helpers::make_configured_gateway, mock_node::MockNode};
+
+const TREASURY: Address = Address::repeat_byte(0x1E);
+const ONE_ETH: u128 = 1_000_000_000_000_000_000;
+
+/// Sends `value` wei from a freshly funded wallet to `to`.
+async fn pay(node: &MockNode, to: Address, value: U256) {
+    let payer = PrivateKeySigner::random();
+    node.set_balance(payer.address(), U256::from(2 * ONE_ETH));
+    let payment = TransactionRequest::default().with_to(to).with_value(value);
+    node.send_from(payer, payment).await;
+}
+
+#[tokio::test]
+async fn test_shared_deposit_is_matched_by_exact_amount() {
+    let node = MockNode::start().await;
+    node.mine_blocks(5);
+    let (gateway, mut rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.min_confirmations = 1;
+        config.unique_amounts = Some(UniqueAmounts::new(TREASURY));
+    });
+
+    let (_, first) = gateway
+        .new_invoice(U256::from(ONE_ETH), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let (second_id, second) = gateway
+        .new_invoice(U256::from(ONE_ETH), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    assert_eq!(first.to, TREASURY);
+    assert_eq!(second.to, TREASURY);
+    assert_eq!(first.amount, U256::from(ONE_ETH + 1_000_000));
+    assert_eq!(second.amount, U256::from(ONE_ETH + 2_000_000));
+
+    gateway.poll_payments().await;
+    // Neither the base amount nor the payment of another amount pays an invoice
+    pay(&node, TREASURY, U256::from(ONE_ETH)).await;
+    pay(&node, TREASURY, second.amount).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(rx.try_recv().is_err(), "payments must wait for a confirmation");
+    node.mine_blocks(1);
+
+    let (paid_id, paid) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(paid_id, second_id);
+    assert_eq!(paid.amount, second.amount);
+    assert_eq!(paid.deposits.len(), 1);
+    assert_eq!(paid.deposits[0].amount, second.amount);
+    assert!(paid.settlement.is_none(), "shared deposits are never swept");
+
+    let open = gateway.get_all_invoices().await.unwrap();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].1.amount, first.amount);
+}
+
+#[tokio::test]
+async fn test_shared_deposit_rejects_tokens_and_exhausted_amounts() {
+    let node = MockNode::start().await;
+    let (gateway, _rx) = make_configured_gateway(&node, TREASURY, |config| {
+        config.unique_amounts = Some(UniqueAmounts {
+            max_suffix: 1,
+            ..UniqueAmounts::new(TREASURY)
+        })
+    });
+
+    let result = gateway
+        .new_token_invoice(Address::repeat_byte(0x70), U256::from(1u64), vec![], 3600)
+        .await;
+    assert!(matches!(result, Err(GatewayError::Unsupported(_))));
+
+    let (first_id, first) = gateway
+        .new_invoice(U256::from(ONE_ETH), vec![], 3600)
+        .await
+        .expect("invoice creation must succeed");
+    let result = gateway.new_invoice(U256::from(ONE_ETH), vec![], 3600).await;
+    assert!(matches!(result, Err(GatewayError::UniqueAmountsExhausted)));
+
+    // Amounts of invoices no longer awaiting payment are free again
+    gateway.invoices.write().await.get_mut(&first_id).unwrap().status = InvoiceStatus::Paid;
+    let (_, reused) = gateway
+        .new_invoice(U256::from(ONE_ETH), vec![], 3600)
+        .await
+        .expect("the amount of a paid invoice must be reusable");
+    assert_eq!(reused.amount, first.amount);
+}
@@ -0,0 +1,53 @@
+/// Invoices created with wallet encryption configured store only the
+/// ciphertext of their key, yet are still swept to the treasury.
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
+use tokio::{sync::mpsc, time::timeout};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration, WalletEncryption};
+use crate::test_utils::mock_node::MockNode;
+
+const TREASURY: Address = Address::repeat_byte(0x5E);
+const ONE_ETH: u128 = 1_000_000_000_000_000_000;
+
+#[tokio::test]
+async fn test_encrypted_wallet_is_swept_and_cancel_returns_plaintext() {
+    let node = MockNode::start().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        wallet_encryption: Some(WalletEncryption::from_key([0x42; 32])),
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], TREASURY, tx)
+    })
+    .expect("gateway creation must not fail");
+
+    let amount = U256::from(ONE_ETH);
+    let (id, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+    assert!(invoice.wallet_encrypted);
+    let (other_id, other) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+
+    // The stored bytes are not a key for the deposit address.
+    let stored: Result<[u8; 32], _> = invoice.wallet.inner.as_slice().try_into();
+    assert!(stored
+        .ok()
+        .and_then(|key| PrivateKeySigner::from_bytes(&key.into()).ok())
+        .is_none_or(|signer| signer.address() != invoice.to));
+
+    // Cancelling hands back the decrypted key.
+    let wallet = gateway.cancel_invoice(&other_id).await.unwrap();
+    let key: [u8; 32] = wallet.inner.as_slice().try_into().unwrap();
+    assert_eq!(PrivateKeySigner::from_bytes(&key.into()).unwrap().address(), other.to);
+
+    node.set_balance(invoice.to, amount);
+    gateway.poll_payments().await;
+    let (paid_id, _) = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for confirmation")
+        .expect("channel closed");
+    assert_eq!(paid_id, id);
+    assert!(node.get_treasury_balance(TREASURY) > U256::ZERO);
+}
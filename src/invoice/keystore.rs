@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+
+use alloy::primitives::{hex, B256};
+use alloy::signers::local::PrivateKeySigner;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::Invoice;
+
+/// File name of the keystore within its temporary directory.
+const KEYSTORE_FILE: &str = "keystore.json";
+
+#[derive(Error, Debug)]
+pub enum KeystoreError {
+    #[error("Invoice holds no plaintext private key: {0}")]
+    NoPrivateKey(&'static str),
+    #[error("Invalid invoice wallet key")]
+    InvalidKey,
+    #[error("Keystore creation failed: {0}")]
+    Keystore(String),
+}
+
+impl Invoice {
+    /// Exports the invoice wallet as a Web3 Secret Storage (version 3)
+    /// keystore encrypted with `password`, which Geth, MetaMask and most
+    /// other wallets can import to recover stranded funds.
+    ///
+    /// Only invoices that store their plaintext key can be exported this way;
+    /// use `PaymentGateway::export_keystore` for HD-derived invoices and
+    /// wallets encrypted at rest.
+    pub fn to_keystore(&self, password: &str) -> Result<String, KeystoreError> {
+        if self.wallet_encrypted {
+            return Err(KeystoreError::NoPrivateKey("the wallet is encrypted at rest"));
+        }
+        if self.wallet.is_empty() {
+            return Err(KeystoreError::NoPrivateKey("the wallet is derived or keyless"));
+        }
+        let key = B256::try_from(self.wallet.as_slice()).map_err(|_| KeystoreError::InvalidKey)?;
+        encrypt_keystore(&key, password)
+    }
+}
+
+/// Encrypts the private `key` into a version 3 keystore JSON document with
+/// alloy's keystore support, the `eth-keystore` implementation Foundry uses,
+/// adding the `address` field Geth writes. It only writes keystores to files,
+/// so the document passes through a private temporary directory that is
+/// removed right away.
+pub(crate) fn encrypt_keystore(key: &B256, password: &str) -> Result<String, KeystoreError> {
+    let address = PrivateKeySigner::from_bytes(key)
+        .map_err(|_| KeystoreError::InvalidKey)?
+        .address();
+    let dir = TempDir::create().map_err(keystore_error)?;
+    PrivateKeySigner::encrypt_keystore(
+        dir.path(),
+        &mut rand::thread_rng(),
+        key,
+        password,
+        Some(KEYSTORE_FILE),
+    )
+    .map_err(keystore_error)?;
+    let keystore = std::fs::read(dir.path().join(KEYSTORE_FILE)).map_err(keystore_error)?;
+    let mut keystore: Value = serde_json::from_slice(&keystore).map_err(keystore_error)?;
+    if let Some(fields) = keystore.as_object_mut() {
+        fields.insert("address".to_string(), Value::String(hex::encode(address)));
+    }
+    Ok(keystore.to_string())
+}
+
+fn keystore_error(e: impl std::fmt::Display) -> KeystoreError {
+    KeystoreError::Keystore(e.to_string())
+}
+
+/// Directory readable by the owner only, removed with its contents on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn create() -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("acceptevm-keystore-{}", B256::random()));
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&path)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invoice::ZeroizedVec;
+
+    /// Decrypts a keystore with alloy's keystore support, as wallets do.
+    fn decrypt(keystore: &str, password: &str) -> Option<Vec<u8>> {
+        let dir = TempDir::create().unwrap();
+        let path = dir.path().join(KEYSTORE_FILE);
+        std::fs::write(&path, keystore).unwrap();
+        let signer = PrivateKeySigner::decrypt_keystore(&path, password).ok()?;
+        Some(signer.credential().to_bytes().to_vec())
+    }
+
+    /// The PBKDF2 test vector of the Web3 Secret Storage Definition.
+    #[test]
+    fn published_test_vector_decrypts() {
+        let keystore = r#"{
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                "kdf": "pbkdf2",
+                "kdfparams": {
+                    "c": 262144,
+                    "dklen": 32,
+                    "prf": "hmac-sha256",
+                    "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                },
+                "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "version": 3
+        }"#;
+        let key = hex::decode("7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d")
+            .unwrap();
+        assert_eq!(decrypt(keystore, "testpassword").unwrap(), key);
+        assert!(decrypt(keystore, "wrongtestpassword").is_none());
+    }
+
+    #[test]
+    fn keystore_decrypts_to_wallet_key() {
+        let signer = PrivateKeySigner::random();
+        let invoice = Invoice {
+            to: signer.address(),
+            wallet: ZeroizedVec {
+                inner: signer.credential().to_bytes().to_vec(),
+            },
+            ..Default::default()
+        };
+        let keystore = invoice.to_keystore("hunter2").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&keystore).unwrap();
+        assert_eq!(json["version"], 3);
+        assert_eq!(json["address"], hex::encode(signer.address()));
+        assert_eq!(json["id"].as_str().unwrap().len(), 36);
+
+        assert_eq!(decrypt(&keystore, "hunter2").unwrap(), invoice.wallet.inner);
+        assert!(decrypt(&keystore, "wrong").is_none());
+    }
+
+    #[test]
+    fn keyless_and_encrypted_wallets_are_not_exported() {
+        let keyless = Invoice::default();
+        assert!(matches!(
+            keyless.to_keystore("pw"),
+            Err(KeystoreError::NoPrivateKey(_))
+        ));
+        let encrypted = Invoice {
+            wallet: ZeroizedVec { inner: vec![1; 60] },
+            wallet_encrypted: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            encrypted.to_keystore("pw"),
+            Err(KeystoreError::NoPrivateKey(_))
+        ));
+    }
+}
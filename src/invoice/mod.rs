@@ -1,11 +1,22 @@
-use alloy::primitives::{Address, U256};
-use serde::{Deserialize, Serialize};
+use alloy::primitives::{Address, B256, U256};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 use zeroize::ZeroizeOnDrop;
 
+use crate::gateway::receipt::SignedReceipt;
+
+mod keystore;
+#[cfg(feature = "qr")]
+mod qr;
+
+pub use keystore::KeystoreError;
+pub(crate) use keystore::encrypt_keystore;
+#[cfg(feature = "qr")]
+pub use qr::QrCodeError;
+
 /// ## DANGER: Private Key Data is contained in this struct
 /// Zeroed memory on drop
-#[derive(ZeroizeOnDrop, Clone, Deserialize, Serialize, Debug)]
+#[derive(ZeroizeOnDrop, Clone, Default, Deserialize, Serialize, Debug)]
 pub struct ZeroizedVec {
     pub inner: Vec<u8>,
 }
@@ -25,16 +36,225 @@ impl DerefMut for ZeroizedVec {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+/// Lifecycle status of an invoice, updated by the poller.
+///
+/// Invoices move `Pending → Paid → Sweeping → Confirming → Swept`. Sweeps
+/// deferred by `max_gas_price` or `max_sweep_fee_bps` wait in `PaidAwaitingSweep`, failed ones in
+/// `Failed` until they are retried or given up on as `SweepFailed`, and
+/// unpaid invoices end as `Expired`. See [`InvoiceStatus::can_transition_to`]
+/// for every allowed step and [`Invoice::transitions`] for the recorded ones.
+#[derive(Clone, Copy, Default, Deserialize, Serialize, Debug, PartialEq, Eq, Hash)]
+pub enum InvoiceStatus {
+    /// Waiting for payment
+    #[default]
+    Pending,
+    /// Treasury transfer broadcast, waiting for the required confirmations
+    Confirming,
+    /// Payment detected
+    Paid,
+    /// Treasury transfer is being built and broadcast
+    Sweeping,
+    /// Treasury transfer confirmed
+    Swept,
+    /// Paid, but the sweep is deferred because network fees exceed the
+    /// configured `max_gas_price` or `max_sweep_fee_bps`; it is retried on
+    /// the next poll
+    PaidAwaitingSweep,
+    /// Invoice expired without payment
+    Expired,
+    /// The latest treasury transfer attempt failed; it is retried after a backoff
+    Failed,
+    /// Every retry of the treasury transfer failed; the funds remain on the
+    /// invoice wallet and the poller no longer sweeps it
+    SweepFailed,
+}
+
+impl InvoiceStatus {
+    /// Whether the lifecycle allows moving an invoice from this status to
+    /// `next`. A failed manual sweep returns the invoice to the status it was
+    /// swept from.
+    pub fn can_transition_to(self, next: InvoiceStatus) -> bool {
+        use InvoiceStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Paid | Sweeping)
+                | (Paid, Sweeping)
+                | (PaidAwaitingSweep, Sweeping | Confirming | Failed)
+                | (Sweeping, Confirming | Failed | PaidAwaitingSweep)
+                | (Sweeping, Pending | Paid | SweepFailed)
+                | (Failed, Paid | Sweeping | SweepFailed)
+                | (SweepFailed, Sweeping)
+                | (Confirming, Swept)
+                | (Pending | Paid | PaidAwaitingSweep | Failed | SweepFailed, Expired)
+        )
+    }
+
+    /// Whether the invoice is settled or expired and never changes again.
+    pub fn is_final(self) -> bool {
+        matches!(self, InvoiceStatus::Swept | InvoiceStatus::Expired)
+    }
+}
+
+/// A change of [`Invoice::status`], see [`Invoice::transitions`].
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct StatusTransition {
+    pub from: InvoiceStatus,
+    pub to: InvoiceStatus,
+    /// Unix time in milliseconds
+    pub at_ms: u64,
+}
+
+/// Stage of the sweep pipeline.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub enum SweepStage {
+    /// Reading balance, nonce and fees and building the transfer
+    Estimate,
+    /// Signing the transfer with the invoice wallet
+    Sign,
+    /// Submitting the signed transfer to the RPC
+    Broadcast,
+    /// Waiting for the transfer to reach the required confirmations
+    Confirm,
+}
+
+/// Error raised by a specific sweep stage.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub struct SweepStageError {
+    pub stage: SweepStage,
+    pub message: String,
+}
+
+/// Operation during which an invoice error occurred.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub enum InvoiceErrorSource {
+    /// Reading the balance of the invoice address
+    BalanceCheck,
+    /// A stage of the treasury transfer
+    Sweep(SweepStage),
+}
+
+/// Broad classification of an invoice error.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub enum InvoiceErrorKind {
+    /// The RPC could not be reached or rejected the request
+    Rpc,
+    /// Not enough funds to cover the transfer and its gas
+    InsufficientBalance,
+    /// The invoice wallet could not be restored
+    InvalidWallet,
+    /// The transaction could not be signed
+    Signing,
+    /// The RPC reported a different chain than the one the poller started on
+    ChainIdMismatch,
+    /// Network fees exceeded the configured `max_gas_price`
+    GasPriceAboveCeiling,
+    /// The sweep would have cost more than the configured `max_sweep_fee_bps`
+    SweepFeeAboveLimit,
+    /// Any other failure
+    Other,
+}
+
+/// Most recent error encountered while processing an invoice.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub struct InvoiceError {
+    pub source: InvoiceErrorSource,
+    pub kind: InvoiceErrorKind,
+    pub message: String,
+    /// Unix time in seconds at which the error occurred
+    pub timestamp: u64,
+}
+
+/// Duration of each sweep stage, in milliseconds.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq)]
+pub struct SweepTimings {
+    pub estimate_ms: u64,
+    pub sign_ms: u64,
+    pub broadcast_ms: u64,
+    /// Unix time in milliseconds at which the transfer was broadcast
+    pub broadcast_at_ms: u64,
+    /// Time from broadcast until the required confirmations were reached
+    pub confirm_ms: Option<u64>,
+}
+
+/// A single transfer of swept funds, to the treasury or a treasury split.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq)]
+pub struct Payout {
+    pub to: Address,
+    pub amount: U256,
+    /// Transaction hash of the transfer
+    pub hash: String,
+    /// Receipt of the transfer, once the sweep is confirmed
+    pub receipt: Option<SweepReceipt>,
+}
+
+/// Trimmed receipt of a mined sweep transfer.
+#[derive(Clone, Copy, Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct SweepReceipt {
+    pub hash: B256,
+    pub block_number: u64,
+    pub gas_used: u64,
+    /// Price per gas actually paid
+    pub effective_gas_price: u128,
+    /// Whether the transfer succeeded
+    pub status: bool,
+}
+
+/// Settlement record of the treasury transfer.
+///
+/// The balance is re-read at sweep time, so funds that arrived after the
+/// payment was detected are swept as well and show up in `received_amount`.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq)]
+pub struct Settlement {
+    /// Amount requested by the invoice
+    pub invoice_amount: U256,
+    /// Balance of the invoice address when the sweep was built
+    pub received_amount: U256,
+    /// Amount transferred to the treasury after gas costs
+    pub swept_amount: U256,
+    /// Native currency sent by the gas sponsor so the invoice wallet could pay the sweep gas
+    pub sponsored_gas: U256,
+    /// Legacy gas price or EIP-1559 max fee per gas of the latest sweep
+    pub max_fee_per_gas: u128,
+    /// EIP-1559 priority fee per gas of the latest sweep; zero for legacy txs
+    pub max_priority_fee_per_gas: u128,
+    /// Gas used by all transfers of the sweep, once confirmed
+    pub sweep_gas_used: Option<u64>,
+    /// Price per gas actually paid by the treasury transfer, once confirmed
+    pub sweep_effective_gas_price: Option<u128>,
+    /// Amount that landed in the treasury after gas and treasury splits,
+    /// once confirmed
+    pub net_amount_to_treasury: Option<U256>,
+    /// Transfers of the latest sweep; the treasury transfer comes last
+    pub payouts: Vec<Payout>,
+    /// Per-stage timing of the latest sweep
+    pub timings: SweepTimings,
+    /// Error of the latest failed sweep stage, if any
+    pub error: Option<SweepStageError>,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize, Debug)]
 pub struct Invoice {
     /// Recipient address
     pub to: Address,
-    /// Contains the keys to restore the wallet
+    /// Contains the keys to restore the wallet; empty for HD-derived invoices
+    /// and for invoices read from redacted JSON
+    #[serde(default)]
     pub wallet: ZeroizedVec,
-    /// Amount requested
+    /// Whether `wallet` is encrypted with the gateway's `wallet_encryption`
+    pub wallet_encrypted: bool,
+    /// BIP-44 index of the invoice wallet when derived from an `HdWallet`
+    pub derivation_index: Option<u32>,
+    /// CREATE2 salt of the invoice's forwarder when using `ForwarderMode`
+    pub forwarder_salt: Option<B256>,
+    /// ERC20 token contract the invoice is paid in; `None` for the native currency
+    pub token: Option<Address>,
+    /// Amount requested, in the smallest unit of the native currency or token
     pub amount: U256,
-    /// Arbitrary message attached to the invoice
+    /// Arbitrary message attached to the invoice; JSON encoded metadata for
+    /// invoices created with `new_invoice_with_metadata`, see [`Invoice::metadata`]
     pub message: Vec<u8>,
+    /// Invoice creation time
+    pub created_at: u64,
     /// Invoice expiry time
     pub expires: u64,
     /// Timestamp at which the invoice was paid
@@ -43,6 +263,239 @@ pub struct Invoice {
     pub hash: Option<String>,
     /// Nonce used for the treasury transfer (for replacement txs)
     pub nonce: Option<u64>,
+    /// Amounts received and swept by the latest treasury transfer
+    pub settlement: Option<Settlement>,
+    /// Current lifecycle status
+    pub status: InvoiceStatus,
+    /// Every status change since creation, oldest first
+    #[serde(default)]
+    pub transitions: Vec<StatusTransition>,
+    /// Most recent balance-check or sweep error, kept for diagnostics
+    pub last_error: Option<InvoiceError>,
+    /// Consecutive failed sweep attempts, reset once a sweep is broadcast
+    pub sweep_attempts: u32,
+    /// Unix time before which a failed sweep is not retried
+    pub next_sweep_at: u64,
+    /// Minimum time between balance checks; `None` checks every poll cycle
+    pub check_interval_seconds: Option<u64>,
+    /// Confirmations required for this invoice; `None` uses the gateway's `min_confirmations`
+    pub min_confirmations: Option<u64>,
+    /// Block at which the full payment was first seen, when confirmations are required
+    pub deposit_block: Option<u64>,
+    /// Fiat amount and exchange rate of invoices created with `new_invoice_fiat`
+    pub fiat: Option<FiatQuote>,
+    /// Every asset the invoice accepts when it has alternatives, the invoice's
+    /// own `token` and `amount` first; empty otherwise
+    pub payment_options: Vec<PaymentOption>,
+    /// Option the invoice was paid with. `token` and `amount` are updated to
+    /// match it once the payment is detected.
+    pub paid_with: Option<PaymentOption>,
+    /// NFT the invoice is paid with; `amount` is then the quantity expected
+    pub nft: Option<NftPayment>,
+    /// Paid to the deposit address shared by all invoices in unique-amount
+    /// mode, by a transfer of exactly `amount`
+    pub shared_deposit: bool,
+    /// Sender of the first transfer that paid the invoice, once detected
+    pub payer: Option<Address>,
+    /// Transfers that paid the invoice, oldest first
+    pub deposits: Vec<DepositRecord>,
+    /// Block of the transfer that completed the payment
+    pub deposit_block_number: Option<u64>,
+    /// Treasury the invoice is swept to; `None` uses the gateway's
+    #[serde(default)]
+    pub treasury: Option<Address>,
+    /// Reference of the invoice in the calling system, e.g. an order id
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// Gas limit of each sweep transfer; `None` uses the gateway's `sweep_gas_limit`
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    /// Signed proof of payment, once swept with a `receipt_signing_key`
+    #[serde(default)]
+    pub payment_receipt: Option<SignedReceipt>,
+}
+
+/// One transfer that paid into an invoice address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DepositRecord {
+    pub tx_hash: B256,
+    /// Sender of the transfer
+    pub from: Address,
+    /// Amount transferred, in the smallest unit of the invoice's asset
+    pub amount: U256,
+    pub block_number: u64,
+}
+
+/// Token standard of an [`NftPayment`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NftStandard {
+    /// A single token, paid once the invoice address owns it
+    Erc721,
+    /// A fungible quantity of a token id, paid once the invoice address holds it
+    Erc1155 { quantity: U256 },
+}
+
+/// A specific NFT expected as payment, see `PaymentGateway::new_nft_invoice`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NftPayment {
+    pub standard: NftStandard,
+    /// Token contract
+    pub contract: Address,
+    pub token_id: U256,
+}
+
+impl NftPayment {
+    pub fn erc721(contract: Address, token_id: U256) -> Self {
+        Self {
+            standard: NftStandard::Erc721,
+            contract,
+            token_id,
+        }
+    }
+
+    pub fn erc1155(contract: Address, token_id: U256, quantity: U256) -> Self {
+        Self {
+            standard: NftStandard::Erc1155 { quantity },
+            contract,
+            token_id,
+        }
+    }
+
+    /// Number of tokens expected, always one for ERC-721.
+    pub fn quantity(&self) -> U256 {
+        match self.standard {
+            NftStandard::Erc721 => U256::from(1u64),
+            NftStandard::Erc1155 { quantity } => quantity,
+        }
+    }
+}
+
+/// An asset an invoice can be paid in, with the amount requested in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PaymentOption {
+    /// ERC20 token contract; `None` for the native currency
+    pub token: Option<Address>,
+    /// Amount in the smallest unit of the native currency or token
+    pub amount: U256,
+}
+
+/// Fiat amount an invoice was created for and the rate locked in at creation.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FiatQuote {
+    /// Requested amount in cents of `currency`
+    pub amount_cents: u64,
+    /// Fiat currency code, e.g. `USD`
+    pub currency: String,
+    /// Fiat price of one whole unit of the invoice currency, scaled by `10^rate_decimals`
+    pub rate: U256,
+    pub rate_decimals: u8,
+}
+
+/// Optional settings for [`PaymentGateway::new_invoice_with_options`].
+///
+/// [`PaymentGateway::new_invoice_with_options`]: crate::gateway::PaymentGateway::new_invoice_with_options
+#[derive(Clone, Debug, Default)]
+pub struct InvoiceOptions {
+    /// ERC20 token contract the invoice is paid in; `None` for the native currency
+    pub token: Option<Address>,
+    /// Minimum time between balance checks of the invoice. `None` checks it
+    /// every poll cycle, which suits high-value invoices; low-value ones can
+    /// be checked e.g. once a minute to save RPC requests.
+    pub check_interval_seconds: Option<u64>,
+    /// Confirmations required for this invoice instead of the gateway's
+    /// `min_confirmations`. The payment must be this many blocks deep before
+    /// it is swept, and the treasury transfer this many blocks deep before
+    /// the invoice is delivered.
+    pub min_confirmations: Option<u64>,
+    /// Further assets the invoice can be paid in, e.g. USDC and USDT next to
+    /// ETH, each with its own amount. The poller checks all of them and the
+    /// first one paid in full is swept.
+    pub alternatives: Vec<PaymentOption>,
+    /// Treasury the invoice is swept to instead of the gateway's
+    /// `treasury_address`. Treasury splits still apply.
+    pub treasury: Option<Address>,
+    /// Chain the invoice is meant for. Creation fails when the gateway
+    /// serves another chain.
+    pub chain_id: Option<u64>,
+    /// Reference of the invoice in the calling system, e.g. an order id.
+    /// While an invoice with the same external id is pending, creating
+    /// another one returns the pending invoice instead.
+    pub external_id: Option<String>,
+    /// Gas limit of each sweep transfer instead of the gateway's
+    /// `sweep_gas_limit`, e.g. for a treasury contract with a costly
+    /// `receive` function.
+    pub gas_limit: Option<u64>,
+}
+
+impl Invoice {
+    /// Renders an [EIP-681](https://eips.ethereum.org/EIPS/eip-681) payment URI
+    /// for this invoice, e.g. `ethereum:0xAbC…@56?value=1000`.
+    ///
+    /// Wallets open it as a prefilled transfer of `amount` wei to the invoice
+    /// address on `chain_id`, so it can be used for deep links and QR codes.
+    /// Token invoices render a `transfer` call on the token contract instead.
+    pub fn payment_uri(&self, chain_id: u64) -> String {
+        match self.token {
+            Some(token) => format!(
+                "ethereum:{}@{}/transfer?address={}&uint256={}",
+                token, chain_id, self.to, self.amount
+            ),
+            None => format!("ethereum:{}@{}?value={}", self.to, chain_id, self.amount),
+        }
+    }
+
+    /// Serializes the invoice to JSON including its wallet key. Only store
+    /// this where the private key itself could be stored.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Serializes the invoice to JSON without its wallet key, for web
+    /// frontends, support tooling and webhooks.
+    pub fn to_redacted_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.redacted()?)
+    }
+
+    /// Moves the invoice to `status` and records the transition at `at_ms`,
+    /// Unix milliseconds. Transitions the lifecycle does not allow are logged
+    /// and refused.
+    pub(crate) fn transition(&mut self, status: InvoiceStatus, at_ms: u64) {
+        if self.status == status {
+            return;
+        }
+        if !self.status.can_transition_to(status) {
+            tracing::error!("Refusing invoice status change from {:?} to {status:?}", self.status);
+            return;
+        }
+        self.transitions.push(StatusTransition {
+            from: self.status,
+            to: status,
+            at_ms,
+        });
+        self.status = status;
+    }
+
+    /// The invoice as a JSON value without its wallet key.
+    pub(crate) fn redacted(&self) -> Result<serde_json::Value, serde_json::Error> {
+        let mut invoice = serde_json::to_value(self)?;
+        if let Some(fields) = invoice.as_object_mut() {
+            fields.remove("wallet");
+        }
+        Ok(invoice)
+    }
+
+    /// Reads an invoice from the JSON of [`Invoice::to_json`] or
+    /// [`Invoice::to_redacted_json`]. Redacted invoices come back without a
+    /// wallet key and cannot be swept from it.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Decodes the typed metadata stored in `message` by
+    /// `PaymentGateway::new_invoice_with_metadata`.
+    pub fn metadata<M: DeserializeOwned>(&self) -> Result<M, serde_json::Error> {
+        serde_json::from_slice(&self.message)
+    }
 }
 
 #[cfg(test)]
@@ -54,6 +507,34 @@ mod tests {
         ZeroizedVec { inner: bytes }
     }
 
+    #[test]
+    fn lifecycle_allows_only_forward_transitions() {
+        use InvoiceStatus::*;
+        assert!(Pending.can_transition_to(Paid));
+        assert!(Sweeping.can_transition_to(Confirming));
+        assert!(Failed.can_transition_to(SweepFailed));
+        assert!(Confirming.can_transition_to(Swept));
+        assert!(!Pending.can_transition_to(Swept));
+        assert!(!Confirming.can_transition_to(Expired));
+        for status in [Swept, Expired] {
+            assert!(status.is_final());
+            assert!(!status.can_transition_to(Pending));
+        }
+    }
+
+    #[test]
+    fn transitions_are_recorded_and_invalid_ones_refused() {
+        let mut invoice = Invoice::default();
+        invoice.transition(InvoiceStatus::Paid, 1_000);
+        invoice.transition(InvoiceStatus::Paid, 2_000);
+        invoice.transition(InvoiceStatus::Swept, 3_000);
+        assert_eq!(invoice.status, InvoiceStatus::Paid);
+        assert_eq!(invoice.transitions.len(), 1);
+        assert_eq!(invoice.transitions[0].from, InvoiceStatus::Pending);
+        assert_eq!(invoice.transitions[0].to, InvoiceStatus::Paid);
+        assert_eq!(invoice.transitions[0].at_ms, 1_000);
+    }
+
     #[test]
     fn zeroized_vec_deref_gives_inner_slice() {
         let v = make_vec(vec![1, 2, 3]);
@@ -83,9 +564,7 @@ mod tests {
             amount: U256::from(42u64),
             message: b"hello".to_vec(),
             expires: 9999,
-            paid_at_timestamp: 0,
-            hash: None,
-            nonce: None,
+            ..Default::default()
         };
         let clone = inv.clone();
         assert_eq!(inv.to, clone.to);
@@ -95,19 +574,79 @@ mod tests {
     }
 
     #[test]
-    fn invoice_default_state_fields() {
+    fn metadata_decodes_json_message() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Order {
+            id: u64,
+        }
+        let inv = Invoice {
+            message: br#"{"id":7}"#.to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(inv.metadata::<Order>().unwrap(), Order { id: 7 });
+        assert!(inv.metadata::<String>().is_err());
+    }
+
+    #[test]
+    fn json_round_trips_with_and_without_wallet() {
+        let inv = Invoice {
+            to: Address::repeat_byte(0xAB),
+            wallet: make_vec(vec![7u8; 32]),
+            amount: U256::from(42u64),
+            status: InvoiceStatus::Paid,
+            ..Default::default()
+        };
+        let full = Invoice::from_json(&inv.to_json().unwrap()).unwrap();
+        assert_eq!(full.wallet.inner, inv.wallet.inner);
+        assert_eq!(full.amount, inv.amount);
+
+        let redacted_json = inv.to_redacted_json().unwrap();
+        assert!(!redacted_json.contains("\"wallet\":"));
+        let redacted = Invoice::from_json(&redacted_json).unwrap();
+        assert!(redacted.wallet.inner.is_empty());
+        assert_eq!(redacted.to, inv.to);
+        assert_eq!(redacted.status, InvoiceStatus::Paid);
+    }
+
+    #[test]
+    fn payment_uri_follows_eip681() {
+        let inv = Invoice {
+            to: "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359".parse().unwrap(),
+            amount: U256::from(2_014_000_000_000_000_000u128),
+            ..Default::default()
+        };
+        assert_eq!(
+            inv.payment_uri(1),
+            "ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359@1?value=2014000000000000000"
+        );
+    }
+
+    #[test]
+    fn token_payment_uri_renders_transfer_call() {
         let inv = Invoice {
-            to: Address::ZERO,
-            wallet: make_vec(vec![]),
-            amount: U256::ZERO,
-            message: vec![],
-            expires: 0,
-            paid_at_timestamp: 0,
-            hash: None,
-            nonce: None,
+            to: Address::repeat_byte(0x11),
+            token: Some(Address::repeat_byte(0x22)),
+            amount: U256::from(5u64),
+            ..Default::default()
         };
+        assert_eq!(
+            inv.payment_uri(56),
+            format!(
+                "ethereum:{}@56/transfer?address={}&uint256=5",
+                Address::repeat_byte(0x22),
+                Address::repeat_byte(0x11)
+            )
+        );
+    }
+
+    #[test]
+    fn invoice_default_state_fields() {
+        let inv = Invoice::default();
         assert!(inv.hash.is_none());
         assert!(inv.nonce.is_none());
+        assert!(inv.settlement.is_none());
+        assert!(inv.last_error.is_none());
+        assert_eq!(inv.status, InvoiceStatus::Pending);
         assert_eq!(inv.paid_at_timestamp, 0);
     }
 }
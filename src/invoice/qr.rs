@@ -0,0 +1,65 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, Luma};
+use qrcode::{render::svg, QrCode};
+use thiserror::Error;
+
+use super::Invoice;
+
+/// Minimum edge length of rendered QR codes, in pixels.
+const QR_MIN_SIZE: u32 = 256;
+
+#[derive(Error, Debug)]
+pub enum QrCodeError {
+    #[error("Could not encode payment URI: {0}")]
+    Encode(#[from] qrcode::types::QrError),
+    #[error("Could not encode PNG: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+impl Invoice {
+    /// Renders the [`payment_uri`](Invoice::payment_uri) as a PNG encoded QR code.
+    pub fn qr_code_png(&self, chain_id: u64) -> Result<Vec<u8>, QrCodeError> {
+        let image = QrCode::new(self.payment_uri(chain_id))?
+            .render::<Luma<u8>>()
+            .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+            .build();
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+
+    /// Renders the [`payment_uri`](Invoice::payment_uri) as an SVG QR code.
+    pub fn qr_code_svg(&self, chain_id: u64) -> Result<String, QrCodeError> {
+        Ok(QrCode::new(self.payment_uri(chain_id))?
+            .render::<svg::Color>()
+            .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, U256};
+
+    fn invoice() -> Invoice {
+        Invoice {
+            to: Address::repeat_byte(0xAB),
+            amount: U256::from(1_000u64),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn png_has_png_signature() {
+        let png = invoice().qr_code_png(1).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn svg_is_rendered() {
+        let svg = invoice().qr_code_svg(1).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+}
@@ -1,8 +1,24 @@
+pub mod chains;
 pub mod gateway;
 pub mod invoice;
 mod web3;
 
-#[cfg(test)]
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "testkit")]
+pub mod testkit;
+
+#[cfg(any(test, feature = "server"))]
+pub mod server;
+
+#[cfg(any(test, feature = "grpc"))]
+pub mod grpc;
+
+#[cfg(any(test, feature = "cli"))]
+pub mod cli;
+
+#[cfg(any(test, feature = "testing"))]
 mod test_utils;
 
 #[cfg(test)]
@@ -23,12 +39,14 @@ mod tests {
         let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
 
         Ok(PaymentGateway::new(PaymentGatewayConfiguration {
-            rpc_urls: vec!["https://123.com".to_string()],
-            treasury_address: "0xdac17f958d2ee523a2206206994597c13d831ec7".parse::<Address>()?,
             min_confirmations: 10,
-            sender,
             poller_delay_seconds: 1,
             receipt_timeout_seconds: 60,
+            ..PaymentGatewayConfiguration::new(
+                vec!["https://123.com".to_string()],
+                "0xdac17f958d2ee523a2206206994597c13d831ec7".parse::<Address>()?,
+                sender,
+            )
         })?)
     }
 
@@ -129,6 +147,44 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn assert_typed_metadata_round_trips() -> TestResult {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Order {
+            order_id: String,
+            quantity: u32,
+        }
+        let gateway = create_gateway()?;
+        let order = Order {
+            order_id: "A-1".to_string(),
+            quantity: 3,
+        };
+        let (id, _) = gateway
+            .new_invoice_with_metadata(U256::from(1), &order, 3600, Default::default())
+            .await?;
+        let stored = gateway.get_invoice(&id).await?;
+        assert_eq!(stored.metadata::<Order>()?, order);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn assert_csv_backup_restores_invoices() -> TestResult {
+        let gateway = create_gateway()?;
+        let (id, original) = insert_test_invoice(&gateway).await?;
+        insert_test_invoice(&gateway).await?;
+        let path = std::env::temp_dir().join(format!("acceptevm-{id}.csv"));
+        assert_eq!(gateway.export_csv(&path).await?, 2);
+
+        let restored = create_gateway()?;
+        assert_eq!(restored.import_csv(&path).await?, 2);
+        std::fs::remove_file(&path)?;
+        let invoice = restored.get_invoice(&id).await?;
+        assert_eq!(invoice.to, original.to);
+        assert_eq!(invoice.wallet.inner, original.wallet.inner);
+        assert_eq!(invoice.message, original.message);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn assert_invoice_amount_preserved() -> TestResult {
         let gateway = create_gateway()?;
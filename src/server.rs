@@ -0,0 +1,377 @@
+//! HTTP API exposing a gateway to storefronts not written in Rust.
+//!
+//! | Route                    | Action                                       |
+//! |--------------------------|----------------------------------------------|
+//! | `POST /invoices`         | Create an invoice from a [`CreateInvoice`]    |
+//! | `GET /invoices`          | List pending invoices, `?status=` filters     |
+//! | `GET /invoices/{id}`     | Get a pending invoice                         |
+//! | `DELETE /invoices/{id}`  | Cancel a pending invoice                      |
+//! | `GET /events`            | Server-sent [`ServerEvent`]s                  |
+//!
+//! Invoices are returned without their wallet key. Paid invoices are only
+//! streamed on `/events` when the gateway's reflector comes from
+//! [`PaymentEvents::reflector`]. With an `event_signing_key` configured, every
+//! event is sent as a [`SignedEvent`] wrapping the serialized [`ServerEvent`].
+//!
+//! [`SignedEvent`]: crate::gateway::event_signing::SignedEvent
+use std::{convert::Infallible, sync::Arc};
+
+use alloy::primitives::{Address, U256};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::event::{receive, GatewayEvent};
+use crate::gateway::event_signing::EventSigningKey;
+use crate::gateway::{InvoiceRequest, PaymentGateway, Reflector};
+use crate::invoice::{Invoice, InvoiceOptions, InvoiceStatus};
+
+/// Body of `POST /invoices`. Exactly one of `amount` and `units` is required.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CreateInvoice {
+    /// Decimal amount, e.g. `"1.5"`, in the native currency or `token`
+    pub amount: Option<String>,
+    /// Amount in the smallest unit
+    pub units: Option<U256>,
+    pub expires_in_seconds: u64,
+    /// ERC20 token the invoice is paid in
+    pub token: Option<Address>,
+    /// Stored as the invoice message
+    pub message: Option<String>,
+    pub external_id: Option<String>,
+    pub treasury: Option<Address>,
+    pub min_confirmations: Option<u64>,
+}
+
+/// Query of `GET /invoices`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ListInvoices {
+    pub status: Option<InvoiceStatus>,
+}
+
+/// Event streamed on `GET /events`, as JSON tagged by `type`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum ServerEvent {
+    /// An invoice was delivered on the reflector; `invoice` has no wallet key
+    InvoicePaid { invoice_id: String, invoice: Value },
+    /// An operational event of the gateway
+    Gateway { event: GatewayEvent },
+}
+
+/// Feeds paid invoices into the `/events` stream.
+///
+/// Configure [`PaymentEvents::reflector`] as the gateway's `reflector`, or
+/// call [`PaymentEvents::publish`] from your own reflector callback.
+#[derive(Clone)]
+pub struct PaymentEvents {
+    sender: broadcast::Sender<ServerEvent>,
+}
+
+impl PaymentEvents {
+    /// Buffers up to `capacity` events for slow subscribers.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity.max(1)).0,
+        }
+    }
+
+    /// Reflector publishing every paid invoice to the `/events` stream.
+    pub fn reflector(&self) -> Reflector {
+        let events = self.clone();
+        Reflector::Callback(Arc::new(move |invoice_id, invoice| {
+            events.publish(invoice_id, &invoice);
+            Box::pin(async {})
+        }))
+    }
+
+    /// Publishes a paid invoice to current `/events` subscribers.
+    pub fn publish(&self, invoice_id: String, invoice: &Invoice) {
+        match invoice.redacted() {
+            Ok(invoice) => {
+                let _ = self.sender.send(ServerEvent::InvoicePaid {
+                    invoice_id,
+                    invoice,
+                });
+            }
+            Err(e) => tracing::error!("Failed to serialize paid invoice: {e}"),
+        }
+    }
+}
+
+impl Default for PaymentEvents {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    gateway: PaymentGateway,
+    events: PaymentEvents,
+}
+
+/// Error response: `{"error": "..."}` with a status matching the error.
+struct ApiError(GatewayError);
+
+impl From<GatewayError> for ApiError {
+    fn from(error: GatewayError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            GatewayError::NotFound => StatusCode::NOT_FOUND,
+            GatewayError::InvalidInvoiceRequest(_)
+            | GatewayError::InvalidAmount(_)
+            | GatewayError::Metadata(_)
+            | GatewayError::ChainMismatch { .. }
+            | GatewayError::Unsupported(_) => StatusCode::BAD_REQUEST,
+            GatewayError::UniqueAmountsExhausted => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(json!({ "error": self.0.to_string() }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+fn invoice_json(invoice_id: &str, invoice: &Invoice) -> ApiResult<Value> {
+    let invoice = invoice
+        .redacted()
+        .map_err(|e| GatewayError::Metadata(e.to_string()))?;
+    Ok(json!({ "invoice_id": invoice_id, "invoice": invoice }))
+}
+
+/// Routes of the API, serving `gateway`.
+pub fn router(gateway: PaymentGateway, events: PaymentEvents) -> Router {
+    Router::new()
+        .route("/invoices", get(list_invoices).post(create_invoice))
+        .route("/invoices/{id}", get(get_invoice).delete(cancel_invoice))
+        .route("/events", get(stream_events))
+        .with_state(AppState { gateway, events })
+}
+
+/// Serves the API on `listener` until the server fails.
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    gateway: PaymentGateway,
+    events: PaymentEvents,
+) -> std::io::Result<()> {
+    axum::serve(listener, router(gateway, events)).await
+}
+
+async fn create_invoice(
+    State(state): State<AppState>,
+    Json(body): Json<CreateInvoice>,
+) -> ApiResult<(StatusCode, Json<Value>)> {
+    let mut request = match (body.amount, body.units) {
+        (Some(amount), None) => InvoiceRequest::new(amount, body.expires_in_seconds),
+        (None, Some(units)) => InvoiceRequest::new(units, body.expires_in_seconds),
+        _ => {
+            let message = "exactly one of amount and units is required".to_string();
+            return Err(GatewayError::InvalidInvoiceRequest(message).into());
+        }
+    };
+    request = request
+        .options(InvoiceOptions {
+            token: body.token,
+            external_id: body.external_id,
+            treasury: body.treasury,
+            min_confirmations: body.min_confirmations,
+            ..Default::default()
+        })
+        .message(body.message.unwrap_or_default().into_bytes());
+    let (invoice_id, invoice) = state.gateway.create_invoice(request).await?;
+    Ok((StatusCode::CREATED, Json(invoice_json(&invoice_id, &invoice)?)))
+}
+
+async fn list_invoices(
+    State(state): State<AppState>,
+    Query(query): Query<ListInvoices>,
+) -> ApiResult<Json<Vec<Value>>> {
+    let invoices = match query.status {
+        Some(status) => state.gateway.get_invoices_by_status(status).await?,
+        None => state.gateway.get_all_invoices().await?,
+    };
+    let invoices = invoices
+        .iter()
+        .map(|(invoice_id, invoice)| invoice_json(invoice_id, invoice))
+        .collect::<ApiResult<_>>()?;
+    Ok(Json(invoices))
+}
+
+async fn get_invoice(
+    State(state): State<AppState>,
+    Path(invoice_id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let invoice = state.gateway.get_invoice(&invoice_id).await?;
+    Ok(Json(invoice_json(&invoice_id, &invoice)?))
+}
+
+/// The wallet key returned by the gateway is not sent over HTTP; funds that
+/// already reached a cancelled invoice cannot be recovered through the API.
+async fn cancel_invoice(
+    State(state): State<AppState>,
+    Path(invoice_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.gateway.cancel_invoice(&invoice_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn stream_events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let paid = receive(state.events.sender.subscribe());
+    let gateway = state.gateway.events().map(|event| ServerEvent::Gateway { event });
+    let signing_key = state.gateway.config.event_signing_key.clone();
+    let events = stream::select(paid, gateway).filter_map(move |event| {
+        let event = sse_event(&event, signing_key.as_ref());
+        async move {
+            match event {
+                Ok(event) => Some(Ok(event)),
+                Err(e) => {
+                    tracing::error!("Failed to serialize server event: {e}");
+                    None
+                }
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// The message of `event`, signed when the gateway has an `event_signing_key`.
+fn sse_event(event: &ServerEvent, signing_key: Option<&EventSigningKey>) -> Result<Event, String> {
+    let event = match signing_key {
+        Some(key) => Event::default().json_data(key.sign(event).map_err(|e| e.to_string())?),
+        None => Event::default().json_data(event),
+    };
+    event.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::event_signing::SignedEvent;
+    use crate::gateway::PaymentGatewayConfiguration;
+
+    async fn start() -> (String, PaymentGateway, PaymentEvents) {
+        start_with_key(None).await
+    }
+
+    async fn start_with_key(
+        event_signing_key: Option<EventSigningKey>,
+    ) -> (String, PaymentGateway, PaymentEvents) {
+        let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+        let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+            event_signing_key,
+            ..PaymentGatewayConfiguration::new(
+                vec!["http://127.0.0.1:1".to_string()],
+                Address::repeat_byte(0x01),
+                sender,
+            )
+        })
+        .unwrap();
+        let events = PaymentEvents::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, gateway.clone(), events.clone()));
+        (url, gateway, events)
+    }
+
+    #[tokio::test]
+    async fn invoices_are_created_listed_and_cancelled() {
+        let (url, gateway, _) = start().await;
+        let client = reqwest::Client::new();
+        let body = CreateInvoice {
+            amount: Some("1.5".to_string()),
+            expires_in_seconds: 3600,
+            external_id: Some("order-9".to_string()),
+            ..Default::default()
+        };
+        let created = client.post(format!("{url}/invoices")).json(&body).send().await.unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let created: Value = created.json().await.unwrap();
+        let id = created["invoice_id"].as_str().unwrap().to_string();
+        assert!(created["invoice"].get("wallet").is_none());
+        assert_eq!(created["invoice"]["external_id"], "order-9");
+
+        let listed: Vec<Value> = client
+            .get(format!("{url}/invoices?status=Pending"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["invoice_id"], id.as_str());
+
+        let cancelled = client.delete(format!("{url}/invoices/{id}")).send().await.unwrap();
+        assert_eq!(cancelled.status(), StatusCode::NO_CONTENT);
+        assert!(gateway.get_invoice(&id).await.is_err());
+        let missing = client.get(format!("{url}/invoices/{id}")).send().await.unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn invalid_requests_are_rejected() {
+        let (url, _, _) = start().await;
+        let body = CreateInvoice {
+            expires_in_seconds: 3600,
+            ..Default::default()
+        };
+        let response = reqwest::Client::new()
+            .post(format!("{url}/invoices"))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error: Value = response.json().await.unwrap();
+        assert!(error["error"].as_str().unwrap().contains("amount"));
+    }
+
+    #[tokio::test]
+    async fn paid_invoices_are_streamed_as_events() {
+        let (url, _, events) = start().await;
+        let mut response = reqwest::get(format!("{url}/events")).await.unwrap();
+        let invoice = Invoice {
+            amount: U256::from(5u64),
+            ..Default::default()
+        };
+        events.publish("paid-1".to_string(), &invoice);
+        let chunk = response.chunk().await.unwrap().unwrap();
+        let data = String::from_utf8(chunk.to_vec()).unwrap();
+        let data = data.trim().strip_prefix("data: ").unwrap();
+        let event: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(event["type"], "InvoicePaid");
+        assert_eq!(event["invoice_id"], "paid-1");
+        assert!(event["invoice"].get("wallet").is_none());
+    }
+
+    #[tokio::test]
+    async fn events_are_signed_with_the_event_signing_key() {
+        let key = EventSigningKey::Hmac("secret".to_string());
+        let (url, _, events) = start_with_key(Some(key.clone())).await;
+        let mut response = reqwest::get(format!("{url}/events")).await.unwrap();
+        events.publish("paid-1".to_string(), &Invoice::default());
+        let chunk = response.chunk().await.unwrap().unwrap();
+        let data = String::from_utf8(chunk.to_vec()).unwrap();
+        let data = data.trim().strip_prefix("data: ").unwrap();
+        let signed: SignedEvent = serde_json::from_str(data).unwrap();
+        let event: Value = key.verifier().verify(&signed).unwrap();
+        assert_eq!(event["type"], "InvoicePaid");
+        assert_eq!(event["invoice_id"], "paid-1");
+    }
+}
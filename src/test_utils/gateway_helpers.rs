@@ -1,10 +1,11 @@
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
 use tokio::sync::mpsc::{self, UnboundedReceiver};
 
 use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
 use crate::invoice::Invoice;
 
-use super::mock_node::MockNode;
+use super::{mock_node::MockNode, test_chain::TestChain};
 
 /// Build a `PaymentGateway` wired to the given mock node URL(s).
 ///
@@ -24,12 +25,10 @@ pub fn make_gateway_with_confirmations(
 ) -> (PaymentGateway, UnboundedReceiver<(String, Invoice)>) {
     let (tx, rx) = mpsc::unbounded_channel();
     let config = PaymentGatewayConfiguration {
-        rpc_urls,
-        treasury_address,
         poller_delay_seconds: 0,
         min_confirmations,
         receipt_timeout_seconds: 5,
-        sender: tx,
+        ..PaymentGatewayConfiguration::new(rpc_urls, treasury_address, tx)
     };
     let gateway = PaymentGateway::new(config).expect("gateway creation must not fail");
     (gateway, rx)
@@ -42,3 +41,42 @@ pub fn make_single_node_gateway(
 ) -> (PaymentGateway, UnboundedReceiver<(String, Invoice)>) {
     make_gateway(vec![node.url.clone()], treasury_address)
 }
+
+/// Single-node gateway pointing at `node` with 0 confirmations, its
+/// configuration adjusted by `configure` before the gateway is built.
+pub fn make_configured_gateway(
+    node: &MockNode,
+    treasury_address: Address,
+    configure: impl FnOnce(&mut PaymentGatewayConfiguration),
+) -> (PaymentGateway, UnboundedReceiver<(String, Invoice)>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut config = PaymentGatewayConfiguration {
+        poller_delay_seconds: 0,
+        min_confirmations: 0,
+        receipt_timeout_seconds: 5,
+        ..PaymentGatewayConfiguration::new(vec![node.url.clone()], treasury_address, tx)
+    };
+    configure(&mut config);
+    let gateway = PaymentGateway::new(config).expect("gateway creation must not fail");
+    (gateway, rx)
+}
+
+/// A fresh gas sponsor wallet holding one ETH on `node`.
+pub fn funded_gas_sponsor(node: &MockNode) -> PrivateKeySigner {
+    let sponsor = PrivateKeySigner::random();
+    node.set_balance(sponsor.address(), U256::from(10u128.pow(18)));
+    sponsor
+}
+
+/// Single-node gateway using the confirmation depth of a chain fixture.
+pub fn make_chain_gateway(
+    node: &MockNode,
+    chain: &TestChain,
+    treasury_address: Address,
+) -> (PaymentGateway, UnboundedReceiver<(String, Invoice)>) {
+    make_gateway_with_confirmations(
+        vec![node.url.clone()],
+        treasury_address,
+        chain.min_confirmations,
+    )
+}
@@ -2,18 +2,29 @@
 ///
 /// Serves just the `eth_*` methods that `acceptevm` calls, making integration
 /// tests fully self-contained with no external Anvil/Hardhat process needed.
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
+use alloy::consensus::transaction::Recovered;
 use alloy::consensus::TxEnvelope;
 use alloy::eips::eip2718::Decodable2718;
-use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{keccak256, Address, LogData, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::rpc::types::{Block, BlockTransactions, Header, Log, Transaction};
+use alloy::sol_types::SolCall;
 use axum::extract::State;
 use axum::routing::post;
 use axum::{Json, Router};
 use serde_json::{json, Value};
 use tokio::sync::oneshot;
 
+use super::test_chain::TestChain;
+use crate::gateway::clock::{Clock, SystemClock};
+use crate::web3::multicall::{IMulticall3, MULTICALL3};
+
 // ─── Receipt ─────────────────────────────────────────────────────────────────
 
 #[derive(Clone, Debug)]
@@ -22,34 +33,117 @@ pub struct MockReceipt {
     pub from: Address,
     pub to: Address,
     pub status: bool,
+    /// EIP-2718 transaction type
+    pub tx_type: u8,
+    pub effective_gas_price: u128,
+}
+
+/// Outcome of a submitted transaction, queued with
+/// [`MockNode::script_receipts`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptedReceipt {
+    /// Executed and mined in the current block
+    Success,
+    /// Mined with status 0: gas is charged and the nonce used, nothing moves
+    Reverted,
+    /// Stays in the mempool without a receipt
+    Pending,
+}
+
+/// An executed transaction and the block it was included in.
+#[derive(Clone, Debug)]
+pub struct MinedTx {
+    pub block_number: u64,
+    pub tx: TxEnvelope,
+    pub from: Address,
+}
+
+/// A raw transaction as it was submitted to the mock node.
+#[derive(Clone, Debug)]
+pub struct SentTx {
+    pub hash: B256,
+    pub from: Address,
+    pub nonce: u64,
+    /// Legacy gas price or EIP-1559 max fee per gas
+    pub fee_per_gas: u128,
+    pub gas_limit: u64,
 }
 
 // ─── State ───────────────────────────────────────────────────────────────────
 
 pub struct MockEvmState {
     pub balances: HashMap<Address, U256>,
+    /// (token, holder) → ERC20 balance
+    pub token_balances: HashMap<(Address, Address), U256>,
+    /// (contract, token id) → ERC721 owner
+    pub nft_owners: HashMap<(Address, U256), Address>,
+    /// (contract, token id, holder) → ERC1155 balance
+    pub erc1155_balances: HashMap<(Address, U256, Address), U256>,
+    /// token → ERC20 decimals
+    pub token_decimals: HashMap<Address, u8>,
+    /// feed → (answer, decimals, updated at) of a Chainlink price feed
+    pub price_feeds: HashMap<Address, (U256, u8, u64)>,
     pub nonces: HashMap<Address, u64>,
     /// tx_hash → receipt
     pub receipts: HashMap<B256, MockReceipt>,
     pub block_number: u64,
+    /// Unix time each block was mined at. Blocks before the node started
+    /// are one second apart.
+    pub block_timestamps: HashMap<u64, u64>,
     pub chain_id: u64,
+    /// Fee model and canned fee values
+    pub chain: TestChain,
     /// If set, the receipt for this hash will be withheld on the *first* fetch
     /// only (simulates a receipt disappearing after a reorg).
     pub drop_receipt_once: Option<B256>,
     /// Counters so tests can verify round-robin behaviour.
     pub request_count: u64,
+    /// Every executed transaction, in order, served by `eth_getBlockByNumber`
+    pub mined_txs: Vec<MinedTx>,
+    /// Every raw transaction submitted, in order
+    pub sent_txs: Vec<SentTx>,
+    /// While set, submitted transactions stay in the mempool: they are
+    /// recorded in `sent_txs` but never executed or given a receipt.
+    pub hold_txs: bool,
+    /// While set, `eth_estimateGas` fails as for a reverting call
+    pub fail_gas_estimation: bool,
+    /// Outcomes of the next submitted transactions; once empty they succeed
+    pub receipt_script: VecDeque<ScriptedReceipt>,
+    /// tx_hash → (from, to, value) of native transfers made by contracts
+    /// within the transaction, served by `debug_traceBlockByNumber`
+    pub internal_transfers: HashMap<B256, Vec<(Address, Address, U256)>>,
+    /// While set, every request is answered with an error, as by a node
+    /// that is down behind its load balancer
+    pub offline: bool,
 }
 
 impl MockEvmState {
-    pub fn new(chain_id: u64) -> Self {
+    pub fn new(chain: TestChain) -> Self {
         Self {
             balances: HashMap::new(),
+            token_balances: HashMap::new(),
+            nft_owners: HashMap::new(),
+            erc1155_balances: HashMap::new(),
+            token_decimals: HashMap::new(),
+            price_feeds: HashMap::new(),
             nonces: HashMap::new(),
             receipts: HashMap::new(),
-            block_number: 1,
-            chain_id,
+            block_number: chain.start_block,
+            block_timestamps: HashMap::from([(
+                chain.start_block,
+                SystemClock.unix_time_seconds(),
+            )]),
+            chain_id: chain.chain_id,
+            chain,
             drop_receipt_once: None,
             request_count: 0,
+            mined_txs: Vec::new(),
+            sent_txs: Vec::new(),
+            hold_txs: false,
+            fail_gas_estimation: false,
+            receipt_script: VecDeque::new(),
+            internal_transfers: HashMap::new(),
+            offline: false,
         }
     }
 }
@@ -71,13 +165,18 @@ impl MockNode {
     }
 
     pub async fn start_with_chain_id(chain_id: u64) -> Self {
+        Self::start_with_chain(TestChain::legacy(chain_id)).await
+    }
+
+    /// Spin up a mock node serving the fee model of a chain fixture.
+    pub async fn start_with_chain(chain: TestChain) -> Self {
         let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
             .await
             .expect("failed to bind mock node");
         let port = listener.local_addr().unwrap().port();
         let url = format!("http://127.0.0.1:{port}");
 
-        let state = Arc::new(Mutex::new(MockEvmState::new(chain_id)));
+        let state = Arc::new(Mutex::new(MockEvmState::new(chain)));
         let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
         let app_state = state.clone();
@@ -117,12 +216,100 @@ impl MockNode {
             .unwrap_or(U256::ZERO)
     }
 
+    pub fn set_token_balance(&self, token: Address, holder: Address, balance: U256) {
+        self.state
+            .lock()
+            .unwrap()
+            .token_balances
+            .insert((token, holder), balance);
+    }
+
+    pub fn get_token_balance(&self, token: Address, holder: Address) -> U256 {
+        self.state
+            .lock()
+            .unwrap()
+            .token_balances
+            .get(&(token, holder))
+            .cloned()
+            .unwrap_or(U256::ZERO)
+    }
+
+    pub fn set_nft_owner(&self, contract: Address, token_id: U256, owner: Address) {
+        self.state
+            .lock()
+            .unwrap()
+            .nft_owners
+            .insert((contract, token_id), owner);
+    }
+
+    pub fn get_nft_owner(&self, contract: Address, token_id: U256) -> Option<Address> {
+        self.state
+            .lock()
+            .unwrap()
+            .nft_owners
+            .get(&(contract, token_id))
+            .cloned()
+    }
+
+    pub fn set_erc1155_balance(
+        &self,
+        contract: Address,
+        token_id: U256,
+        holder: Address,
+        balance: U256,
+    ) {
+        self.state
+            .lock()
+            .unwrap()
+            .erc1155_balances
+            .insert((contract, token_id, holder), balance);
+    }
+
+    pub fn get_erc1155_balance(&self, contract: Address, token_id: U256, holder: Address) -> U256 {
+        self.state
+            .lock()
+            .unwrap()
+            .erc1155_balances
+            .get(&(contract, token_id, holder))
+            .cloned()
+            .unwrap_or(U256::ZERO)
+    }
+
+    pub fn set_token_decimals(&self, token: Address, decimals: u8) {
+        self.state
+            .lock()
+            .unwrap()
+            .token_decimals
+            .insert(token, decimals);
+    }
+
+    /// Serves a Chainlink price feed at `feed` answering `answer`, last
+    /// updated at unix time `updated_at`.
+    pub fn set_price_feed(&self, feed: Address, answer: U256, decimals: u8, updated_at: u64) {
+        self.state
+            .lock()
+            .unwrap()
+            .price_feeds
+            .insert(feed, (answer, decimals, updated_at));
+    }
+
     pub fn get_treasury_balance(&self, addr: Address) -> U256 {
         self.get_balance(addr)
     }
 
+    /// Simulates the endpoint being swapped for a node on another network.
+    pub fn set_chain_id(&self, chain_id: u64) {
+        self.state.lock().unwrap().chain_id = chain_id;
+    }
+
     pub fn mine_blocks(&self, n: u64) {
-        self.state.lock().unwrap().block_number += n;
+        let mut s = self.state.lock().unwrap();
+        let now = SystemClock.unix_time_seconds();
+        for _ in 0..n {
+            s.block_number += 1;
+            let number = s.block_number;
+            s.block_timestamps.insert(number, now);
+        }
     }
 
     pub fn block_number(&self) -> u64 {
@@ -133,6 +320,25 @@ impl MockNode {
         self.state.lock().unwrap().request_count
     }
 
+    /// Keeps submitted transactions pending (stuck in the mempool) while set.
+    pub fn hold_txs(&self, hold: bool) {
+        self.state.lock().unwrap().hold_txs = hold;
+    }
+
+    /// Fails every request while set.
+    pub fn set_offline(&self, offline: bool) {
+        self.state.lock().unwrap().offline = offline;
+    }
+
+    pub fn sent_txs(&self) -> Vec<SentTx> {
+        self.state.lock().unwrap().sent_txs.clone()
+    }
+
+    /// Queues the outcomes of the next submitted transactions, in order.
+    pub fn script_receipts(&self, outcomes: impl IntoIterator<Item = ScriptedReceipt>) {
+        self.state.lock().unwrap().receipt_script.extend(outcomes);
+    }
+
     /// Cause the receipt for `hash` to be withheld on the very next fetch.
     pub fn drop_receipt_once(&self, hash: B256) {
         self.state.lock().unwrap().drop_receipt_once = Some(hash);
@@ -148,6 +354,30 @@ impl MockNode {
             .next()
             .cloned()
     }
+
+    /// Records a native transfer of `value` from the contract `from` to `to`
+    /// within the mined transaction `tx_hash`, as a Safe wallet or smart
+    /// account executing a payment would, and credits `to`.
+    pub fn add_internal_transfer(&self, tx_hash: B256, from: Address, to: Address, value: U256) {
+        let mut s = self.state.lock().unwrap();
+        let balance = s.balances.entry(to).or_default();
+        *balance = balance.saturating_add(value);
+        s.internal_transfers.entry(tx_hash).or_default().push((from, to, value));
+    }
+
+    /// Signs `tx` with `from` and submits it through the node's RPC, like a
+    /// payer's wallet would. Returns the transaction hash.
+    pub async fn send_from(&self, from: PrivateKeySigner, tx: TransactionRequest) -> B256 {
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(from))
+            .connect_http(self.url.parse().expect("mock node URL is valid"));
+        let tx = tx.with_gas_price(self.state.lock().unwrap().chain.gas_price);
+        *provider
+            .send_transaction(tx)
+            .await
+            .expect("mock node must accept the transaction")
+            .tx_hash()
+    }
 }
 
 // ─── JSON-RPC handler ─────────────────────────────────────────────────────────
@@ -168,12 +398,17 @@ async fn handle_rpc(
         .cloned()
         .unwrap_or(json!([]));
 
-    {
+    let offline = {
         let mut s = state.lock().unwrap();
         s.request_count += 1;
-    }
+        s.offline
+    };
 
-    let result = dispatch(&state, method, &params).await;
+    let result = if offline {
+        Err("node unavailable".to_string())
+    } else {
+        dispatch(&state, method, &params).await
+    };
 
     match result {
         Ok(val) => Json(json!({ "jsonrpc": "2.0", "id": id, "result": val })),
@@ -229,21 +464,66 @@ async fn dispatch(
             Ok(json!(format!("{:#x}", nonce)))
         }
 
+        // ── ERC20 / Multicall3 ────────────────────────────────────────────────
+
+        "eth_call" => {
+            let call = params.get(0).ok_or("missing call param")?;
+            let to = parse_address(call, "to")?;
+            let data = decode_hex(
+                call.get("input")
+                    .or_else(|| call.get("data"))
+                    .and_then(|v| v.as_str())
+                    .ok_or("missing call data")?,
+            )?;
+            let output = read_only_call(&state.lock().unwrap(), to, &data)?;
+            Ok(json!(format!("0x{}", hex::encode(output))))
+        }
+
         // ── Gas ───────────────────────────────────────────────────────────────
 
         "eth_gasPrice" => {
-            // 1 gwei
-            Ok(json!("0x3b9aca00"))
+            let gas_price = state.lock().unwrap().chain.gas_price;
+            Ok(json!(format!("{:#x}", gas_price)))
         }
 
         "eth_estimateGas" => {
+            if state.lock().unwrap().fail_gas_estimation {
+                return Err("execution reverted".to_string());
+            }
             // Standard native transfer
             Ok(json!("0x5208"))
         }
 
-        // Reject EIP-1559 estimation so alloy falls back to legacy gas price.
-        "eth_feeHistory" | "eth_maxPriorityFeePerGas" => {
-            Err("not supported by mock node".to_string())
+        // Legacy chains reject EIP-1559 estimation so alloy falls back to
+        // the legacy gas price.
+        "eth_feeHistory" => {
+            let s = state.lock().unwrap();
+            if !s.chain.eip1559 {
+                return Err("not supported by mock node".to_string());
+            }
+            let count = params
+                .get(0)
+                .and_then(|v| v.as_str())
+                .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+                .unwrap_or(1)
+                .clamp(1, 1024);
+            let base_fee = format!("{:#x}", s.chain.base_fee);
+            let reward = format!("{:#x}", s.chain.priority_fee);
+            Ok(json!({
+                "oldestBlock": format!("{:#x}", s.block_number.saturating_sub(count - 1)),
+                // One entry per block plus the next block's base fee
+                "baseFeePerGas": vec![base_fee; count as usize + 1],
+                "gasUsedRatio": vec![0.5; count as usize],
+                "reward": vec![vec![reward]; count as usize],
+            }))
+        }
+
+        "eth_maxPriorityFeePerGas" => {
+            let s = state.lock().unwrap();
+            if !s.chain.eip1559 {
+                return Err("not supported by mock node".to_string());
+            }
+            Ok(json!(format!("{:#x}", s.chain.priority_fee)))
         }
 
         // ── Send transaction ──────────────────────────────────────────────────
@@ -269,7 +549,6 @@ async fn dispatch(
                 TxEnvelope::Eip1559(s) => s.recover_signer(),
                 TxEnvelope::Eip4844(s) => s.recover_signer(),
                 TxEnvelope::Eip7702(s) => s.recover_signer(),
-                _ => return Err("unknown transaction type".to_string()),
             }
             .map_err(|e| format!("signer recovery error: {e}"))?;
 
@@ -277,6 +556,7 @@ async fn dispatch(
             let value = tx.value();
             let gas_limit = tx.gas_limit();
             let gas_price = tx.gas_price().unwrap_or_else(|| tx.max_fee_per_gas());
+            let tx_type = tx.tx_type() as u8;
             // In alloy 2.0 Transaction::to() returns Option<Address>
             let to_addr = tx
                 .to()
@@ -284,26 +564,87 @@ async fn dispatch(
 
             let gas_cost = U256::from(gas_limit) * U256::from(gas_price);
 
+            let outcome = {
+                let mut s = state.lock().unwrap();
+                s.sent_txs.push(SentTx {
+                    hash: tx_hash,
+                    from: sender,
+                    nonce: tx.nonce(),
+                    fee_per_gas: gas_price,
+                    gas_limit,
+                });
+                if s.hold_txs {
+                    return Ok(json!(format!("{:#x}", tx_hash)));
+                }
+                s.receipt_script.pop_front().unwrap_or(ScriptedReceipt::Success)
+            };
+            if outcome == ScriptedReceipt::Pending {
+                return Ok(json!(format!("{:#x}", tx_hash)));
+            }
+            let succeeded = outcome == ScriptedReceipt::Success;
+
             // Mutate state: deduct from sender, credit recipient
             {
                 let mut s = state.lock().unwrap();
                 let sender_bal = s.balances.entry(sender).or_insert(U256::ZERO);
-                *sender_bal = sender_bal.saturating_sub(value).saturating_sub(gas_cost);
+                *sender_bal = sender_bal.saturating_sub(gas_cost);
+                if succeeded {
+                    *sender_bal = sender_bal.saturating_sub(value);
+                    *s.balances.entry(to_addr).or_insert(U256::ZERO) += value;
+                }
 
-                *s.balances.entry(to_addr).or_insert(U256::ZERO) += value;
+                // transfer(address,uint256) moves ERC20 balances
+                let input = if succeeded { tx.input().clone() } else { Default::default() };
+                if input.len() == 68 && input[..4] == ERC20_TRANSFER {
+                    let recipient = Address::from_slice(&input[16..36]);
+                    let amount = U256::from_be_slice(&input[36..68]);
+                    let from = s.token_balances.entry((to_addr, sender)).or_insert(U256::ZERO);
+                    *from = from.saturating_sub(amount);
+                    *s.token_balances.entry((to_addr, recipient)).or_insert(U256::ZERO) += amount;
+                }
+                // safeTransferFrom moves ERC721 ownership and ERC1155 balances
+                if input.len() == 100 && input[..4] == ERC721_SAFE_TRANSFER_FROM {
+                    let from = Address::from_slice(&input[16..36]);
+                    let recipient = Address::from_slice(&input[48..68]);
+                    let id = U256::from_be_slice(&input[68..100]);
+                    if s.nft_owners.get(&(to_addr, id)) == Some(&from) {
+                        s.nft_owners.insert((to_addr, id), recipient);
+                    }
+                }
+                if input.len() >= 132 && input[..4] == ERC1155_SAFE_TRANSFER_FROM {
+                    let from = Address::from_slice(&input[16..36]);
+                    let recipient = Address::from_slice(&input[48..68]);
+                    let id = U256::from_be_slice(&input[68..100]);
+                    let amount = U256::from_be_slice(&input[100..132]);
+                    let held = s.erc1155_balances.entry((to_addr, id, from)).or_insert(U256::ZERO);
+                    *held = held.saturating_sub(amount);
+                    *s.erc1155_balances
+                        .entry((to_addr, id, recipient))
+                        .or_insert(U256::ZERO) += amount;
+                }
 
                 let nonce = s.nonces.entry(sender).or_insert(0);
                 *nonce += 1;
 
                 // Store receipt at the *current* block
                 let block_number = s.block_number;
+                // The current block is sealed again with the new transaction
+                let now = SystemClock.unix_time_seconds();
+                s.block_timestamps.insert(block_number, now);
+                s.mined_txs.push(MinedTx {
+                    block_number,
+                    tx: tx.clone(),
+                    from: sender,
+                });
                 s.receipts.insert(
                     tx_hash,
                     MockReceipt {
                         block_number,
                         from: sender,
                         to: to_addr,
-                        status: true,
+                        status: succeeded,
+                        tx_type,
+                        effective_gas_price: gas_price,
                     },
                 );
             }
@@ -313,6 +654,91 @@ async fn dispatch(
 
         // ── Receipt ───────────────────────────────────────────────────────────
 
+        "eth_getBlockByNumber" => {
+            let s = state.lock().unwrap();
+            let number = match params.get(0).and_then(|v| v.as_str()) {
+                Some("latest") => Some(s.block_number),
+                Some(v) => u64::from_str_radix(v.trim_start_matches("0x"), 16).ok(),
+                None => None,
+            }
+            .ok_or("only numbered or latest blocks are supported")?;
+            if number > s.block_number {
+                return Ok(Value::Null);
+            }
+            Ok(block_json(&s, number))
+        }
+
+        // `callTracer` traces of the transactions of a block
+        "debug_traceBlockByNumber" => {
+            use alloy::consensus::Transaction as _;
+            let number = params
+                .get(0)
+                .and_then(|v| v.as_str())
+                .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+                .ok_or("only numbered blocks are supported")?;
+            let s = state.lock().unwrap();
+            let traces: Vec<Value> = s
+                .mined_txs
+                .iter()
+                .filter(|mined| mined.block_number == number)
+                .map(|mined| {
+                    let hash = *mined.tx.tx_hash();
+                    let calls: Vec<Value> = s
+                        .internal_transfers
+                        .get(&hash)
+                        .into_iter()
+                        .flatten()
+                        .map(|(from, to, value)| {
+                            json!({ "type": "CALL", "from": from, "to": to, "value": value })
+                        })
+                        .collect();
+                    json!({
+                        "txHash": hash,
+                        "result": {
+                            "type": "CALL",
+                            "from": mined.from,
+                            "to": mined.tx.to(),
+                            "value": mined.tx.value(),
+                            "calls": calls,
+                        },
+                    })
+                })
+                .collect();
+            Ok(Value::Array(traces))
+        }
+
+        // ERC20 `Transfer` logs of executed `transfer` calls
+        "eth_getLogs" => {
+            let filter = params.get(0).ok_or("missing filter param")?;
+            let token = parse_address(filter, "address")?;
+            let block = |key: &str| {
+                filter
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+            };
+            let (from_block, to_block) = (block("fromBlock"), block("toBlock"));
+            // Only the recipient topic is filtered on
+            let recipient = match filter.get("topics").and_then(|topics| topics.get(2)) {
+                Some(Value::String(topic)) => {
+                    Some(topic.parse::<B256>().map_err(|e| e.to_string())?)
+                }
+                _ => None,
+            };
+            let s = state.lock().unwrap();
+            let logs: Vec<Log> = s
+                .mined_txs
+                .iter()
+                .filter(|mined| {
+                    from_block.is_none_or(|from| mined.block_number >= from)
+                        && to_block.is_none_or(|to| mined.block_number <= to)
+                })
+                .filter_map(|mined| erc20_transfer_log(mined, token))
+                .filter(|log| recipient.is_none_or(|topic| log.topics()[2] == topic))
+                .collect();
+            serde_json::to_value(logs).map_err(|e| e.to_string())
+        }
+
         "eth_getTransactionReceipt" => {
             let hash = parse_b256(params, 0)?;
 
@@ -340,10 +766,10 @@ async fn dispatch(
                         "status": if r.status { "0x1" } else { "0x0" },
                         "gasUsed": "0x5208",
                         "cumulativeGasUsed": "0x5208",
-                        "effectiveGasPrice": "0x3b9aca00",
+                        "effectiveGasPrice": format!("{:#x}", r.effective_gas_price),
                         "logs": [],
                         "logsBloom": bloom,
-                        "type": "0x2"
+                        "type": format!("{:#x}", r.tx_type)
                     }))
                 }
             }
@@ -362,7 +788,145 @@ async fn dispatch(
 
 // ─── Helpers ──────────────────────────────────────────────────────────────────
 
-fn parse_address(params: &Value, idx: usize) -> Result<Address, String> {
+const ERC20_BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+const ERC20_TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const ERC721_OWNER_OF: [u8; 4] = [0x63, 0x52, 0x21, 0x1e];
+const ERC721_SAFE_TRANSFER_FROM: [u8; 4] = [0x42, 0x84, 0x2e, 0x0e];
+const ERC1155_BALANCE_OF: [u8; 4] = [0x00, 0xfd, 0xd5, 0x8e];
+const ERC1155_SAFE_TRANSFER_FROM: [u8; 4] = [0xf2, 0x42, 0x43, 0x2a];
+/// `decimals()` of ERC20 tokens and Chainlink price feeds
+const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+const LATEST_ROUND_DATA: [u8; 4] = [0xfe, 0xaf, 0x96, 0x8c];
+
+/// Answers ERC20 `balanceOf` and `decimals` calls, ERC721 `ownerOf` and
+/// ERC1155 `balanceOf` calls, Chainlink price feed reads and Multicall3 `aggregate3` batches of `balanceOf` and
+/// `getEthBalance` calls.
+fn read_only_call(s: &MockEvmState, to: Address, data: &[u8]) -> Result<Vec<u8>, String> {
+    if to == MULTICALL3 {
+        if let Ok(call) = IMulticall3::aggregate3Call::abi_decode(data) {
+            let results: Vec<IMulticall3::Result> = call
+                .calls
+                .iter()
+                .map(|c| match read_only_call(s, c.target, &c.callData) {
+                    Ok(output) => IMulticall3::Result {
+                        success: true,
+                        returnData: output.into(),
+                    },
+                    Err(_) => IMulticall3::Result {
+                        success: false,
+                        returnData: Default::default(),
+                    },
+                })
+                .collect();
+            return Ok(IMulticall3::aggregate3Call::abi_encode_returns(&results));
+        }
+        if let Ok(call) = IMulticall3::getEthBalanceCall::abi_decode(data) {
+            let balance = s.balances.get(&call.addr).cloned().unwrap_or(U256::ZERO);
+            return Ok(balance.to_be_bytes::<32>().to_vec());
+        }
+    }
+    let word = |value: U256| value.to_be_bytes::<32>().to_vec();
+    if data == DECIMALS {
+        let decimals = match s.price_feeds.get(&to) {
+            Some(&(_, decimals, _)) => decimals,
+            None => *s.token_decimals.get(&to).ok_or("unknown token decimals")?,
+        };
+        return Ok(word(U256::from(decimals)));
+    }
+    if data == LATEST_ROUND_DATA {
+        let &(answer, _, updated_at) = s.price_feeds.get(&to).ok_or("unknown price feed")?;
+        let round = U256::from(1u64);
+        let updated_at = U256::from(updated_at);
+        return Ok([round, answer, updated_at, updated_at, round]
+            .into_iter()
+            .flat_map(word)
+            .collect());
+    }
+    if data.len() == 36 && data[..4] == ERC721_OWNER_OF {
+        let id = U256::from_be_slice(&data[4..36]);
+        let owner = s.nft_owners.get(&(to, id)).ok_or("nonexistent token")?;
+        return Ok(owner.into_word().to_vec());
+    }
+    if data.len() == 68 && data[..4] == ERC1155_BALANCE_OF {
+        let holder = Address::from_slice(&data[16..36]);
+        let id = U256::from_be_slice(&data[36..68]);
+        let balance = s.erc1155_balances.get(&(to, id, holder)).cloned();
+        return Ok(word(balance.unwrap_or(U256::ZERO)));
+    }
+    // balanceOf(address)
+    if data.len() != 36 || data[..4] != ERC20_BALANCE_OF {
+        return Err("only ERC20, NFT, Chainlink and Multicall3 calls are supported".to_string());
+    }
+    let holder = Address::from_slice(&data[16..36]);
+    let balance = s
+        .token_balances
+        .get(&(to, holder))
+        .cloned()
+        .unwrap_or(U256::ZERO);
+    Ok(balance.to_be_bytes::<32>().to_vec())
+}
+
+/// Block `number` with its transactions in full.
+fn block_json(s: &MockEvmState, number: u64) -> Value {
+    let timestamp = s.block_timestamps.get(&number).copied().unwrap_or_else(|| {
+        let (&first, &mined_at) = s.block_timestamps.iter().min().expect("start block is mined");
+        mined_at.saturating_sub(first.saturating_sub(number))
+    });
+    let header = Header::new(alloy::consensus::Header {
+        number,
+        timestamp,
+        base_fee_per_gas: s.chain.eip1559.then_some(s.chain.base_fee as u64),
+        ..Default::default()
+    });
+    let hash = header.hash;
+    let transactions = s
+        .mined_txs
+        .iter()
+        .filter(|mined| mined.block_number == number)
+        .enumerate()
+        .map(|(index, mined)| Transaction {
+            inner: Recovered::new_unchecked(mined.tx.clone(), mined.from),
+            block_hash: Some(hash),
+            block_number: Some(number),
+            transaction_index: Some(index as u64),
+            effective_gas_price: None,
+            block_timestamp: None,
+        })
+        .collect();
+    let block = Block {
+        header,
+        uncles: Vec::new(),
+        transactions: BlockTransactions::Full(transactions),
+        withdrawals: None,
+    };
+    serde_json::to_value(block).unwrap_or(Value::Null)
+}
+
+/// `Transfer` log of `mined` when it is an ERC20 `transfer` call on `token`.
+fn erc20_transfer_log(mined: &MinedTx, token: Address) -> Option<Log> {
+    use alloy::consensus::Transaction as _;
+    let input = mined.tx.input();
+    if mined.tx.to() != Some(token) || input.len() != 68 || input[..4] != ERC20_TRANSFER {
+        return None;
+    }
+    let recipient = Address::from_slice(&input[16..36]);
+    let data = LogData::new_unchecked(
+        vec![
+            keccak256("Transfer(address,address,uint256)"),
+            mined.from.into_word(),
+            recipient.into_word(),
+        ],
+        input[36..68].to_vec().into(),
+    );
+    Some(Log {
+        inner: alloy::primitives::Log { address: token, data },
+        block_number: Some(mined.block_number),
+        transaction_hash: Some(*mined.tx.tx_hash()),
+        ..Default::default()
+    })
+}
+
+fn parse_address(params: &Value, idx: impl serde_json::value::Index) -> Result<Address, String> {
     params
         .get(idx)
         .and_then(|v| v.as_str())
@@ -443,6 +1007,56 @@ mod tests {
         assert_eq!(returned, expected);
     }
 
+    #[tokio::test]
+    async fn mock_node_serves_chain_fixture_fees() {
+        use alloy::providers::{Provider, ProviderBuilder};
+
+        let chain = TestChain::ethereum();
+        let node = MockNode::start_with_chain(chain.clone()).await;
+        let provider = ProviderBuilder::new().connect_http(node.url.parse().unwrap());
+
+        assert_eq!(provider.get_chain_id().await.unwrap(), chain.chain_id);
+        assert_eq!(provider.get_block_number().await.unwrap(), chain.start_block);
+        let fees = provider.estimate_eip1559_fees().await.unwrap();
+        assert!(fees.max_fee_per_gas >= chain.base_fee);
+
+        let legacy = MockNode::start_with_chain(TestChain::bsc()).await;
+        let provider = ProviderBuilder::new().connect_http(legacy.url.parse().unwrap());
+        assert!(provider.estimate_eip1559_fees().await.is_err());
+        assert_eq!(provider.get_gas_price().await.unwrap(), TestChain::bsc().gas_price);
+    }
+
+    #[tokio::test]
+    async fn mock_node_mine_blocks_advances_height() {
+        let node = MockNode::start().await;
+        let start = node.block_number();
+        node.mine_blocks(3);
+        assert_eq!(node.block_number(), start + 3);
+    }
+
+    #[tokio::test]
+    async fn mock_node_follows_receipt_script() {
+        let node = MockNode::start().await;
+        let payer = PrivateKeySigner::random();
+        let to = Address::repeat_byte(0x42);
+        let value = U256::from(1_000u64);
+        node.set_balance(payer.address(), U256::from(10u64).pow(U256::from(18u64)));
+        node.script_receipts([ScriptedReceipt::Reverted, ScriptedReceipt::Pending]);
+
+        let provider = ProviderBuilder::new().connect_http(node.url.parse().unwrap());
+        let tx = TransactionRequest::default().to(to).value(value);
+        let reverted = node.send_from(payer.clone(), tx.clone()).await;
+        let receipt = provider.get_transaction_receipt(reverted).await.unwrap().unwrap();
+        assert!(!receipt.status());
+        assert_eq!(node.get_balance(to), U256::ZERO);
+
+        let pending = node.send_from(payer.clone(), tx.clone().nonce(1)).await;
+        assert!(provider.get_transaction_receipt(pending).await.unwrap().is_none());
+
+        node.send_from(payer, tx.nonce(1)).await;
+        assert_eq!(node.get_balance(to), value);
+    }
+
     /// Full gateway pipeline smoke test — verifies that a funded invoice
     /// actually triggers the confirmation callback when the mock node is used.
     #[tokio::test]
@@ -485,13 +1099,14 @@ mod tests {
                 // Log node request count to see if it was even contacted
                 eprintln!("[smoke] timed out; node request count={}", node.request_count());
                 // Check if a treasury tx was submitted
-                let state = node.state.lock().unwrap();
-                eprintln!("[smoke] node receipts count={}", state.receipts.len());
-                for (h, r) in &state.receipts {
-                    eprintln!("[smoke]   receipt hash={h:#x} block={}", r.block_number);
+                {
+                    let state = node.state.lock().unwrap();
+                    eprintln!("[smoke] node receipts count={}", state.receipts.len());
+                    for (h, r) in &state.receipts {
+                        eprintln!("[smoke]   receipt hash={h:#x} block={}", r.block_number);
+                    }
                 }
-                drop(state);
-                
+
                 // Try to manually confirm via alloy
                 if let Some(hash) = node.any_tx_hash() {
                     let hash_str = format!("{hash:#x}");
@@ -1,2 +1,3 @@
 pub mod mock_node;
 pub mod gateway_helpers;
+pub mod test_chain;
@@ -0,0 +1,70 @@
+const GWEI: u128 = 1_000_000_000;
+
+/// Deterministic chain fixture for the mock node.
+///
+/// Each preset pins the chain id, fee model, canned fee values, starting block
+/// height and the confirmation depth commonly used on that network, so
+/// behaviour differences between chains (legacy vs EIP-1559 pricing,
+/// confirmation counts) can be exercised without network access.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestChain {
+    pub name: &'static str,
+    pub chain_id: u64,
+    /// Whether `eth_feeHistory` is served; sweeps then use EIP-1559 fees
+    pub eip1559: bool,
+    /// Value returned by `eth_gasPrice`
+    pub gas_price: u128,
+    /// Base fee reported by `eth_feeHistory` and in block headers
+    pub base_fee: u128,
+    /// Priority fee reward reported by `eth_feeHistory`
+    pub priority_fee: u128,
+    /// Block height the mock node starts at
+    pub start_block: u64,
+    /// Confirmation depth typically required on this chain
+    pub min_confirmations: u64,
+}
+
+impl TestChain {
+    /// Ethereum mainnet: EIP-1559 pricing, 12 confirmations.
+    pub fn ethereum() -> Self {
+        Self {
+            name: "ethereum",
+            chain_id: 1,
+            eip1559: true,
+            gas_price: 20 * GWEI,
+            base_fee: 15 * GWEI,
+            priority_fee: 2 * GWEI,
+            start_block: 19_000_000,
+            min_confirmations: 12,
+        }
+    }
+
+    /// BNB Smart Chain: legacy gas pricing, 15 confirmations.
+    pub fn bsc() -> Self {
+        Self {
+            name: "bsc",
+            chain_id: 56,
+            eip1559: false,
+            gas_price: 3 * GWEI,
+            base_fee: 0,
+            priority_fee: 0,
+            start_block: 35_000_000,
+            min_confirmations: 15,
+        }
+    }
+
+    /// Generic legacy chain used by `MockNode::start`: 1 gwei gas price,
+    /// starting at block 1.
+    pub fn legacy(chain_id: u64) -> Self {
+        Self {
+            name: "legacy",
+            chain_id,
+            eip1559: false,
+            gas_price: GWEI,
+            base_fee: 0,
+            priority_fee: 0,
+            start_block: 1,
+            min_confirmations: 0,
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Test harness for exercising gateway integrations without a live RPC.
+//!
+//! [`MockNode`] is an in-process JSON-RPC node serving the methods the
+//! gateway calls. Point a gateway at its `url` and script it: set native,
+//! token and NFT balances, mine blocks, and queue the outcome of submitted
+//! transactions with [`MockNode::script_receipts`] to drive `poll_payments`
+//! and sweeps through success, reverts and stuck transactions.
+//! [`TestChain`] presets pin the fee model and chain id of the node.
+pub use crate::test_utils::gateway_helpers::*;
+pub use crate::test_utils::mock_node::{
+    MinedTx, MockEvmState, MockNode, MockReceipt, ScriptedReceipt, SentTx,
+};
+pub use crate::test_utils::test_chain::TestChain;
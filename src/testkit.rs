@@ -0,0 +1,213 @@
+//! End-to-end test utilities running the gateway against a local Anvil chain.
+//!
+//! [`Anvil::spawn`] starts an `anvil` process, which must be on `PATH`, on a
+//! free port and stops it when dropped. [`Anvil::gateway`] builds a gateway
+//! polling it without delays or confirmations. [`Anvil::pay`] sends a payment
+//! from a freshly funded payer, like a customer's wallet would. Then
+//! [`wait_for_invoice`] and [`Anvil::wait_for_balance`] assert that the sweep
+//! reached the treasury.
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use thiserror::Error;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::gateway::{PaymentGateway, PaymentGatewayConfiguration};
+use crate::invoice::Invoice;
+
+/// Native currency given to payers on top of their payment, for gas.
+const PAYER_GAS_ALLOWANCE: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+#[derive(Error, Debug)]
+pub enum TestkitError {
+    #[error("Failed to start anvil: {0}")]
+    Spawn(#[from] std::io::Error),
+    #[error("Anvil RPC request failed: {0}")]
+    Rpc(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+}
+
+pub type Result<T> = std::result::Result<T, TestkitError>;
+
+fn rpc(e: impl std::fmt::Display) -> TestkitError {
+    TestkitError::Rpc(e.to_string())
+}
+
+/// A running `anvil` process, killed on drop.
+pub struct Anvil {
+    child: Child,
+    /// HTTP RPC endpoint of the chain
+    pub url: String,
+}
+
+impl Anvil {
+    /// Starts anvil with its defaults: chain id 31337 and instant mining.
+    pub async fn spawn() -> Result<Self> {
+        Self::spawn_with_args(&[]).await
+    }
+
+    /// Starts anvil with extra command line arguments, e.g.
+    /// `["--chain-id", "56"]`, and waits until it serves requests.
+    pub async fn spawn_with_args(args: &[&str]) -> Result<Self> {
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let child = Command::new("anvil")
+            .args(["--port", &port.to_string()])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let anvil = Self {
+            child,
+            url: format!("http://127.0.0.1:{port}"),
+        };
+        let started = Instant::now();
+        while anvil.provider().get_chain_id().await.is_err() {
+            if started.elapsed() > Duration::from_secs(10) {
+                return Err(TestkitError::Timeout("anvil did not start".to_string()));
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Ok(anvil)
+    }
+
+    /// Provider connected to the chain.
+    pub fn provider(&self) -> impl Provider + Clone {
+        ProviderBuilder::new().connect_http(self.url.parse().expect("anvil URL is valid"))
+    }
+
+    /// Gateway polling this chain without delays and without waiting for
+    /// confirmations, delivering settled invoices on the returned receiver.
+    pub fn gateway(
+        &self,
+        treasury: Address,
+    ) -> Result<(PaymentGateway, UnboundedReceiver<(String, Invoice)>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let gateway = PaymentGateway::new(PaymentGatewayConfiguration {
+            poller_delay_seconds: 0,
+            min_confirmations: 0,
+            receipt_timeout_seconds: 5,
+            ..PaymentGatewayConfiguration::new(vec![self.url.clone()], treasury, tx)
+        })
+        .map_err(rpc)?;
+        Ok((gateway, rx))
+    }
+
+    /// Sets the native balance of `address`.
+    pub async fn fund(&self, address: Address, balance: U256) -> Result<()> {
+        self.provider()
+            .raw_request::<_, serde_json::Value>("anvil_setBalance".into(), (address, balance))
+            .await
+            .map_err(rpc)?;
+        Ok(())
+    }
+
+    /// Mines `blocks` empty blocks.
+    pub async fn mine(&self, blocks: u64) -> Result<()> {
+        self.provider()
+            .raw_request::<_, serde_json::Value>("anvil_mine".into(), (U256::from(blocks),))
+            .await
+            .map_err(rpc)?;
+        Ok(())
+    }
+
+    /// Native balance of `address`.
+    pub async fn balance(&self, address: Address) -> Result<U256> {
+        self.provider().get_balance(address).await.map_err(rpc)
+    }
+
+    /// Pays `amount` of native currency to `to` from a new, funded payer and
+    /// waits for the transfer to be mined. Returns the transaction hash.
+    pub async fn pay(&self, to: Address, amount: U256) -> Result<B256> {
+        let payer = PrivateKeySigner::random();
+        self.fund(payer.address(), amount.saturating_add(PAYER_GAS_ALLOWANCE)).await?;
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(payer))
+            .connect_http(self.url.parse().expect("anvil URL is valid"));
+        let tx = TransactionRequest::default().with_to(to).with_value(amount);
+        let receipt = provider
+            .send_transaction(tx)
+            .await
+            .map_err(rpc)?
+            .get_receipt()
+            .await
+            .map_err(rpc)?;
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Pays the full amount of a native currency invoice.
+    pub async fn pay_invoice(&self, invoice: &Invoice) -> Result<B256> {
+        self.pay(invoice.to, invoice.amount).await
+    }
+
+    /// Waits until `address` holds at least `balance`, e.g. until a sweep
+    /// reached the treasury, and returns its balance.
+    pub async fn wait_for_balance(
+        &self,
+        address: Address,
+        balance: U256,
+        timeout: Duration,
+    ) -> Result<U256> {
+        let started = Instant::now();
+        loop {
+            let current = self.balance(address).await?;
+            if current >= balance {
+                return Ok(current);
+            }
+            if started.elapsed() > timeout {
+                return Err(TestkitError::Timeout(format!(
+                    "{address} holds {current}, expected at least {balance}"
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for Anvil {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Waits for the next invoice delivered by the gateway.
+pub async fn wait_for_invoice(
+    rx: &mut UnboundedReceiver<(String, Invoice)>,
+    timeout: Duration,
+) -> Result<(String, Invoice)> {
+    tokio::time::timeout(timeout, rx.recv())
+        .await
+        .map_err(|_| TestkitError::Timeout("no invoice was delivered".to_string()))?
+        .ok_or_else(|| TestkitError::Timeout("the gateway was dropped".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invoice::InvoiceStatus;
+
+    #[tokio::test]
+    #[ignore = "requires anvil on PATH"]
+    async fn paid_invoice_is_swept_to_the_treasury() {
+        let anvil = Anvil::spawn().await.unwrap();
+        let treasury = Address::repeat_byte(0xE1);
+        let (gateway, mut rx) = anvil.gateway(treasury).unwrap();
+        let amount = U256::from(10u64).pow(U256::from(17u64));
+        let (key, invoice) = gateway.new_invoice(amount, vec![], 3600).await.unwrap();
+        gateway.poll_payments().await;
+
+        anvil.pay_invoice(&invoice).await.unwrap();
+        let (delivered, swept) = wait_for_invoice(&mut rx, Duration::from_secs(30)).await.unwrap();
+        assert_eq!(delivered, key);
+        assert_eq!(swept.status, InvoiceStatus::Swept);
+        let received = swept.settlement.unwrap().swept_amount;
+        anvil.wait_for_balance(treasury, received, Duration::from_secs(5)).await.unwrap();
+    }
+}
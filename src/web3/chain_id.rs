@@ -0,0 +1,48 @@
+use alloy::primitives::ChainId;
+use alloy::providers::Provider;
+
+use crate::gateway::{error::GatewayError, PaymentGateway};
+use crate::web3::error::TransferError;
+use crate::web3::result::Result;
+
+/// Fetches the chain id from `provider` and caches it on the gateway if no
+/// chain id has been cached yet.
+pub async fn cache_chain_id(gateway: &PaymentGateway, provider: &impl Provider) -> Result<ChainId> {
+    let chain_id = gateway
+        .chain_id
+        .get_or_try_init(|| provider.get_chain_id())
+        .await?;
+    Ok(*chain_id)
+}
+
+/// Verifies that `provider` still reports the cached chain id.
+///
+/// Protects against load balancer misrouting or endpoint swaps: a mismatch
+/// returns `TransferError::ChainIdMismatch` so no transaction is ever signed
+/// for the wrong network.
+pub async fn verify_chain_id(gateway: &PaymentGateway, provider: &impl Provider) -> Result<ChainId> {
+    let expected = cache_chain_id(gateway, provider).await?;
+    let actual = provider.get_chain_id().await?;
+    if actual != expected {
+        return Err(TransferError::ChainIdMismatch { expected, actual });
+    }
+    Ok(expected)
+}
+
+/// Checks the chain id reported by `provider` against the configured
+/// `expected_chain_id`, if any, and returns the reported chain id.
+pub async fn check_expected_chain_id(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+) -> std::result::Result<ChainId, GatewayError> {
+    let actual = provider
+        .get_chain_id()
+        .await
+        .map_err(|e| GatewayError::Rpc(e.to_string()))?;
+    match gateway.config.expected_chain_id {
+        Some(expected) if expected != actual => {
+            Err(GatewayError::ChainMismatch { expected, actual })
+        }
+        _ => Ok(actual),
+    }
+}
@@ -0,0 +1,200 @@
+use alloy::consensus::Transaction as _;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::TransactionResponse;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{Block, Filter};
+use alloy::sol_types::SolEvent;
+use serde::Deserialize;
+
+use crate::invoice::DepositRecord;
+use crate::web3::error::TransferError;
+use crate::web3::result::Result;
+use crate::web3::transfers::token_transfers::IERC20;
+
+/// Finds the transfers of `token`, or of the native currency when `None`,
+/// that paid `balance` into `to`, oldest first.
+///
+/// Native transfers are found by walking back from the latest block, at most
+/// `lookback_blocks` deep, until they add up to `balance` or a block older
+/// than `since` is reached. Token transfers are read from the `Transfer` logs
+/// of the last `lookback_blocks` blocks.
+///
+/// Only plain native transfers are seen, unless `trace_internal` is set: the
+/// call traces of every block walked are then searched for value sent by
+/// contracts, e.g. Safe wallets and smart accounts, recorded with the contract
+/// as sender. This needs an RPC serving `debug_traceBlockByNumber`.
+pub async fn find_deposits(
+    provider: &impl Provider,
+    token: Option<Address>,
+    to: Address,
+    balance: U256,
+    since: u64,
+    lookback_blocks: u64,
+    trace_internal: bool,
+) -> Result<Vec<DepositRecord>> {
+    let latest = provider.get_block_number().await?;
+    let first = latest.saturating_sub(lookback_blocks);
+    let walk = NativeWalk { to, balance, since, trace_internal };
+    match token {
+        Some(token) => token_deposits(provider, token, to, first, latest).await,
+        None => native_deposits(provider, walk, first, latest).await,
+    }
+}
+
+/// What the walk for native deposits looks for.
+struct NativeWalk {
+    to: Address,
+    balance: U256,
+    since: u64,
+    trace_internal: bool,
+}
+
+async fn native_deposits(
+    provider: &impl Provider,
+    walk: NativeWalk,
+    first: u64,
+    latest: u64,
+) -> Result<Vec<DepositRecord>> {
+    let NativeWalk { to, balance, since, trace_internal } = walk;
+    let mut deposits = Vec::new();
+    let mut total = U256::ZERO;
+    for number in (first..=latest).rev() {
+        let Some(block) = provider
+            .get_block_by_number(BlockNumberOrTag::Number(number))
+            .full()
+            .await?
+        else {
+            continue;
+        };
+        if block.header.timestamp < since {
+            break;
+        }
+        let mut found = Vec::new();
+        for tx in block.transactions.txns() {
+            if tx.to() == Some(to) && !tx.value().is_zero() {
+                found.push(DepositRecord {
+                    tx_hash: tx.tx_hash(),
+                    from: tx.from(),
+                    amount: tx.value(),
+                    block_number: number,
+                });
+            }
+        }
+        if trace_internal {
+            found.extend(internal_deposits(provider, &block, to).await?);
+        }
+        // Collected newest first, reversed once the walk is done
+        for deposit in found.into_iter().rev() {
+            total = total.saturating_add(deposit.amount);
+            deposits.push(deposit);
+        }
+        if total >= balance {
+            break;
+        }
+    }
+    deposits.reverse();
+    Ok(deposits)
+}
+
+/// Call frame of the `callTracer`, with the fields needed to find value
+/// transfers.
+#[derive(Debug, Deserialize)]
+struct CallFrame {
+    #[serde(rename = "type")]
+    kind: String,
+    from: Address,
+    to: Option<Address>,
+    value: Option<U256>,
+    error: Option<String>,
+    #[serde(default)]
+    calls: Vec<CallFrame>,
+}
+
+/// Trace of one transaction of a block.
+#[derive(Debug, Deserialize)]
+struct TransactionTrace {
+    #[serde(rename = "txHash")]
+    tx_hash: Option<B256>,
+    result: CallFrame,
+}
+
+/// Native transfers into `to` made by contracts within the transactions of
+/// `block`, in transaction order. Top-level transfers are left to the
+/// transaction scan.
+async fn internal_deposits(
+    provider: &impl Provider,
+    block: &Block,
+    to: Address,
+) -> Result<Vec<DepositRecord>> {
+    let number = block.header.number;
+    let traces: Vec<TransactionTrace> = provider
+        .raw_request(
+            "debug_traceBlockByNumber".into(),
+            (BlockNumberOrTag::Number(number), serde_json::json!({ "tracer": "callTracer" })),
+        )
+        .await?;
+    let mut deposits = Vec::new();
+    // Older nodes leave out the hash; traces follow the transaction order
+    for (trace, tx) in traces.iter().zip(block.transactions.txns()) {
+        let tx_hash = trace.tx_hash.unwrap_or_else(|| tx.tx_hash());
+        if trace.result.error.is_some() {
+            continue;
+        }
+        let mut frames: Vec<&CallFrame> = trace.result.calls.iter().collect();
+        while let Some(frame) = frames.pop() {
+            // Value moved by a reverted call, or below it, never arrived
+            if frame.error.is_some() {
+                continue;
+            }
+            let amount = frame.value.unwrap_or_default();
+            if frame.kind == "CALL" && frame.to == Some(to) && !amount.is_zero() {
+                deposits.push(DepositRecord {
+                    tx_hash,
+                    from: frame.from,
+                    amount,
+                    block_number: number,
+                });
+            }
+            frames.extend(frame.calls.iter().rev());
+        }
+    }
+    Ok(deposits)
+}
+
+async fn token_deposits(
+    provider: &impl Provider,
+    token: Address,
+    to: Address,
+    first: u64,
+    latest: u64,
+) -> Result<Vec<DepositRecord>> {
+    let filter = Filter::new()
+        .address(token)
+        .event_signature(IERC20::Transfer::SIGNATURE_HASH)
+        .topic2(to.into_word())
+        .from_block(first)
+        .to_block(latest);
+    provider
+        .get_logs(&filter)
+        .await?
+        .into_iter()
+        .map(|log| {
+            let (Some(tx_hash), Some(block_number)) = (log.transaction_hash, log.block_number)
+            else {
+                return Err(TransferError::InvalidTokenResponse(
+                    "Transfer log without transaction".to_string(),
+                ));
+            };
+            let transfer = log
+                .log_decode::<IERC20::Transfer>()
+                .map_err(|e| TransferError::InvalidTokenResponse(e.to_string()))?;
+            Ok(DepositRecord {
+                tx_hash,
+                from: transfer.inner.from,
+                amount: transfer.inner.value,
+                block_number,
+            })
+        })
+        .collect()
+}
@@ -1,5 +1,8 @@
+use alloy::primitives::U256;
 use thiserror::Error;
 
+use crate::invoice::InvoiceErrorKind;
+
 #[derive(Error, Debug)]
 pub enum TransferError {
     #[error("Invalid wallet key: {0}")]
@@ -16,4 +19,59 @@ pub enum TransferError {
     PendingTransaction(#[from] alloy::providers::PendingTransactionError),
     #[error("Invalid transaction hash")]
     InvalidTxHash,
+    #[error("Failed to sign transaction: {0}")]
+    Signing(String),
+    #[error("Invoice wallet is HD-derived but no HD wallet is configured")]
+    MissingHdWallet,
+    #[error("Invoice uses a forwarder but no forwarder mode is configured")]
+    MissingForwarder,
+    #[error("Invoice wallet cannot pay the gas and no gas sponsor is configured")]
+    InsufficientGas,
+    #[error("Gas top-up was not mined in time")]
+    GasTopUpTimeout,
+    #[error("Gas top-up {0} reverted")]
+    GasTopUpReverted(alloy::primitives::B256),
+    #[error("Failed to record the gas top-up in the sponsor store: {0}")]
+    SponsorStore(String),
+    #[error("Invalid token contract response: {0}")]
+    InvalidTokenResponse(String),
+    #[error("Fee estimation failed: {0}")]
+    FeeEstimation(String),
+    #[error("Fee per gas {price} exceeds the configured ceiling of {ceiling}")]
+    GasPriceAboveCeiling { price: u128, ceiling: u128 },
+    #[error("Sweep fee {fee} exceeds the configured share of the invoice amount {amount}")]
+    SweepFeeAboveLimit { fee: U256, amount: U256 },
+    #[error("Failed to derive invoice wallet: {0}")]
+    WalletDerivation(#[from] crate::gateway::error::GatewayError),
+    #[error("Chain id mismatch: expected {expected}, provider reported {actual}")]
+    ChainIdMismatch { expected: u64, actual: u64 },
+}
+
+impl TransferError {
+    /// Classifies the error for the invoice's `last_error` record.
+    pub(crate) fn kind(&self) -> InvoiceErrorKind {
+        match self {
+            TransferError::InvalidWalletKey(_)
+            | TransferError::InvalidSignerKey(_)
+            | TransferError::MissingHdWallet
+            | TransferError::MissingForwarder
+            | TransferError::WalletDerivation(_) => InvoiceErrorKind::InvalidWallet,
+            TransferError::InvalidRpcUrl(_)
+            | TransferError::Transport(_)
+            | TransferError::PendingTransaction(_)
+            | TransferError::GasTopUpTimeout
+            | TransferError::InvalidTokenResponse(_)
+            | TransferError::FeeEstimation(_) => InvoiceErrorKind::Rpc,
+            TransferError::InsufficientBalance | TransferError::InsufficientGas => {
+                InvoiceErrorKind::InsufficientBalance
+            }
+            TransferError::Signing(_) => InvoiceErrorKind::Signing,
+            TransferError::ChainIdMismatch { .. } => InvoiceErrorKind::ChainIdMismatch,
+            TransferError::GasPriceAboveCeiling { .. } => InvoiceErrorKind::GasPriceAboveCeiling,
+            TransferError::SweepFeeAboveLimit { .. } => InvoiceErrorKind::SweepFeeAboveLimit,
+            TransferError::InvalidTxHash
+            | TransferError::GasTopUpReverted(_)
+            | TransferError::SponsorStore(_) => InvoiceErrorKind::Other,
+        }
+    }
 }
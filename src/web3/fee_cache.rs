@@ -0,0 +1,74 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use alloy::providers::Provider;
+
+use crate::gateway::fees::NetworkFees;
+use crate::gateway::PaymentGateway;
+use crate::web3::fee_estimation::estimate_fees;
+use crate::web3::result::Result;
+
+/// The last fees read from the network, reused for `fee_cache_seconds`.
+#[derive(Default)]
+pub(crate) struct FeeCache {
+    fees: Mutex<Option<(Instant, NetworkFees)>>,
+}
+
+impl FeeCache {
+    /// Cached fees no older than `ttl`.
+    fn get(&self, ttl: Duration, now: Instant) -> Option<NetworkFees> {
+        let fees = *self.fees.lock().unwrap_or_else(|e| e.into_inner());
+        fees.filter(|(fetched_at, _)| now.saturating_duration_since(*fetched_at) < ttl)
+            .map(|(_, fees)| fees)
+    }
+
+    fn set(&self, fees: NetworkFees, now: Instant) {
+        *self.fees.lock().unwrap_or_else(|e| e.into_inner()) = Some((now, fees));
+    }
+}
+
+/// Reads the current network fees for the configured `transaction_type` with
+/// the configured `fee_estimator`. Within `fee_cache_seconds` of the
+/// last read the cached fees are returned without any request.
+pub(crate) async fn network_fees(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+) -> Result<NetworkFees> {
+    let ttl = Duration::from_secs(gateway.config.fee_cache_seconds);
+    if let Some(fees) = gateway.fee_cache.get(ttl, Instant::now()) {
+        return Ok(fees);
+    }
+    let fees = estimate_fees(
+        gateway.config.transaction_type,
+        gateway.config.fee_estimator.as_ref(),
+        provider,
+    )
+    .await?;
+    gateway.fee_cache.set(fees, Instant::now());
+    Ok(fees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEES: NetworkFees = NetworkFees::Legacy { gas_price: 7 };
+
+    #[test]
+    fn fees_expire_after_ttl() {
+        let cache = FeeCache::default();
+        let now = Instant::now();
+        assert_eq!(cache.get(Duration::from_secs(5), now), None);
+        cache.set(FEES, now);
+        assert_eq!(cache.get(Duration::from_secs(5), now + Duration::from_secs(4)), Some(FEES));
+        assert_eq!(cache.get(Duration::from_secs(5), now + Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn zero_ttl_disables_the_cache() {
+        let cache = FeeCache::default();
+        let now = Instant::now();
+        cache.set(FEES, now);
+        assert_eq!(cache.get(Duration::ZERO, now), None);
+    }
+}
@@ -0,0 +1,132 @@
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::Provider;
+
+use crate::gateway::fees::{FeeEstimator, NetworkFees, TransactionType};
+use crate::web3::error::TransferError;
+use crate::web3::result::Result;
+
+/// Estimates fees for `transaction_type` with `estimator`. In `Auto` mode,
+/// chains without a base fee and failed estimates use the legacy gas price.
+pub(crate) async fn estimate_fees(
+    transaction_type: TransactionType,
+    estimator: &dyn FeeEstimator,
+    provider: &impl Provider,
+) -> Result<NetworkFees> {
+    let eip1559 = match transaction_type {
+        TransactionType::Auto => has_base_fee(provider).await,
+        TransactionType::Eip1559 => true,
+        TransactionType::Legacy => false,
+    };
+    if !eip1559 {
+        return legacy_fees(provider).await;
+    }
+    match estimator.estimate(provider.root().clone().erased()).await {
+        Ok(fees) => Ok(fees),
+        Err(e) if transaction_type == TransactionType::Eip1559 => {
+            Err(TransferError::FeeEstimation(e.to_string()))
+        }
+        Err(e) => {
+            tracing::warn!("Fee estimation failed, falling back to legacy: {e}");
+            legacy_fees(provider).await
+        }
+    }
+}
+
+/// Whether the latest block has a base fee. When the block cannot be read the
+/// estimator is tried anyway.
+async fn has_base_fee(provider: &impl Provider) -> bool {
+    match provider.get_block_by_number(BlockNumberOrTag::Latest).await {
+        Ok(Some(block)) => block.header.base_fee_per_gas.is_some(),
+        Ok(None) => true,
+        Err(e) => {
+            tracing::warn!("Could not read the latest block to detect EIP-1559: {e}");
+            true
+        }
+    }
+}
+
+async fn legacy_fees(provider: &impl Provider) -> Result<NetworkFees> {
+    Ok(NetworkFees::Legacy {
+        gas_price: provider.get_gas_price().await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::error::GatewayError;
+    use crate::gateway::fees::{FeeFuture, FeeHistoryEstimator};
+    use alloy::providers::DynProvider;
+    use crate::test_utils::{mock_node::MockNode, test_chain::TestChain};
+    use alloy::providers::ProviderBuilder;
+
+    const FEE_HISTORY: FeeHistoryEstimator = FeeHistoryEstimator {
+        blocks: 10,
+        reward_percentile: 50.0,
+    };
+
+    async fn fees(chain: TestChain, transaction_type: TransactionType) -> Result<NetworkFees> {
+        let node = MockNode::start_with_chain(chain).await;
+        let provider = ProviderBuilder::new().connect_http(node.url.parse().unwrap());
+        estimate_fees(transaction_type, &FEE_HISTORY, &provider).await
+    }
+
+    #[tokio::test]
+    async fn auto_detects_eip1559_from_the_latest_block() {
+        let chain = TestChain::ethereum();
+        assert_eq!(
+            fees(chain.clone(), TransactionType::Auto).await.unwrap(),
+            NetworkFees::Eip1559 {
+                max_fee_per_gas: 2 * chain.base_fee + chain.priority_fee,
+                max_priority_fee_per_gas: chain.priority_fee,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn auto_uses_gas_price_on_legacy_chains() {
+        assert_eq!(
+            fees(TestChain::bsc(), TransactionType::Auto).await.unwrap(),
+            NetworkFees::Legacy {
+                gas_price: TestChain::bsc().gas_price,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn forced_types_skip_detection() {
+        let chain = TestChain::ethereum();
+        assert_eq!(
+            fees(chain.clone(), TransactionType::Legacy).await.unwrap(),
+            NetworkFees::Legacy {
+                gas_price: chain.gas_price,
+            }
+        );
+        assert!(matches!(
+            fees(TestChain::bsc(), TransactionType::Eip1559).await,
+            Err(TransferError::FeeEstimation(_))
+        ));
+    }
+
+    /// Like a chain with base fees whose `eth_maxPriorityFeePerGas` errors.
+    struct Failing;
+
+    impl FeeEstimator for Failing {
+        fn estimate(&self, _provider: DynProvider) -> FeeFuture {
+            Box::pin(async { Err(GatewayError::Rpc("method not found".to_string())) })
+        }
+    }
+
+    #[tokio::test]
+    async fn auto_falls_back_to_gas_price_when_estimation_fails() {
+        let chain = TestChain::ethereum();
+        let node = MockNode::start_with_chain(chain.clone()).await;
+        let provider = ProviderBuilder::new().connect_http(node.url.parse().unwrap());
+        assert_eq!(
+            estimate_fees(TransactionType::Auto, &Failing, &provider).await.unwrap(),
+            NetworkFees::Legacy {
+                gas_price: chain.gas_price,
+            }
+        );
+    }
+}
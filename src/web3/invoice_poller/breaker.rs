@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use alloy::providers::Provider;
+
+use crate::gateway::{circuit_breaker::CircuitBreaker, event::GatewayEvent};
+
+use super::InvoicePoller;
+
+impl InvoicePoller {
+    /// Whether the next poll cycle may run. Under the configured
+    /// `circuit_breaker` this probes every open endpoint whose backoff ran
+    /// out with one request, then waits for the next probe while every
+    /// endpoint is still open.
+    pub(super) async fn circuit_closed(&self) -> bool {
+        let Some(policy) = &self.gateway.config.circuit_breaker else {
+            return true;
+        };
+        for rpc_url in self.gateway.breakers.probes_due(self.gateway.now_seconds()) {
+            self.probe(policy, &rpc_url).await;
+        }
+        let urls = &self.gateway.config.rpc_urls;
+        match self.gateway.breakers.all_open(urls, self.gateway.now_seconds()) {
+            None => true,
+            Some(seconds) => {
+                self.sleep(Duration::from_secs(seconds)).await;
+                false
+            }
+        }
+    }
+
+    /// Probes the open endpoint `rpc_url`, closing its circuit when it answers.
+    async fn probe(&self, policy: &CircuitBreaker, rpc_url: &str) {
+        let healthy = match rpc_url.parse() {
+            Ok(url) => self.gateway.connect(url).get_block_number().await.is_ok(),
+            Err(_) => false,
+        };
+        let now = self.gateway.now_seconds();
+        match self.gateway.breakers.probed(rpc_url, policy, now, healthy) {
+            None => {
+                tracing::info!("RPC '{rpc_url}' answers again, resuming requests to it");
+                self.gateway.emit(GatewayEvent::ProviderRecovered {
+                    rpc_url: rpc_url.to_string(),
+                });
+            }
+            Some(backoff) => {
+                tracing::warn!("RPC '{rpc_url}' still failing, skipping it for {backoff}s");
+            }
+        }
+    }
+
+    /// Counts a failed request to `rpc_url` towards its circuit, opening it
+    /// once the failures reach the threshold.
+    pub(super) fn breaker_failed(&self, rpc_url: &str) {
+        let Some(policy) = &self.gateway.config.circuit_breaker else {
+            return;
+        };
+        let failures = policy.failure_threshold;
+        let now = self.gateway.now_seconds();
+        if let Some(backoff) = self.gateway.breakers.failed(rpc_url, policy, now) {
+            tracing::warn!(
+                "RPC '{rpc_url}' failed {failures} times in a row, skipping it for {backoff}s"
+            );
+            self.gateway.emit(GatewayEvent::ProviderDegraded {
+                rpc_url: rpc_url.to_string(),
+                failures,
+                retry_in_seconds: backoff,
+            });
+        }
+    }
+}
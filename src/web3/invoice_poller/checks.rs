@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+
+/// Per-invoice schedule of balance checks.
+///
+/// Invoices without a check interval are due every poll cycle; the others
+/// are due once their interval has passed since their last check.
+#[derive(Default)]
+pub(crate) struct CheckSchedule {
+    /// invoice id → unix time of the next balance check
+    next: Mutex<AHashMap<String, u64>>,
+}
+
+impl CheckSchedule {
+    /// Returns whether the balance of `key` is due for a check at `now`.
+    pub(crate) fn is_due(&self, key: &str, now: u64) -> bool {
+        self.next
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .is_none_or(|next| now >= *next)
+    }
+
+    /// Records a balance check of `key` at `now`, scheduling the next one
+    /// `interval_seconds` later.
+    pub(crate) fn checked(&self, key: &str, interval_seconds: Option<u64>, now: u64) {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        match interval_seconds {
+            Some(interval) => next.insert(key.to_string(), now.saturating_add(interval)),
+            None => next.remove(key),
+        };
+    }
+
+    /// Drops the schedule of an invoice that is no longer checked.
+    pub(crate) fn forget(&self, key: &str) {
+        self.next
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unscheduled_invoices_are_due() {
+        let checks = CheckSchedule::default();
+        assert!(checks.is_due("a", 100));
+        checks.checked("a", None, 100);
+        assert!(checks.is_due("a", 100));
+    }
+
+    #[test]
+    fn interval_delays_next_check() {
+        let checks = CheckSchedule::default();
+        checks.checked("a", Some(60), 100);
+        assert!(!checks.is_due("a", 159));
+        assert!(checks.is_due("a", 160));
+        assert!(checks.is_due("b", 100));
+    }
+
+    #[test]
+    fn forget_resets_schedule() {
+        let checks = CheckSchedule::default();
+        checks.checked("a", Some(60), 100);
+        checks.forget("a");
+        assert!(checks.is_due("a", 101));
+    }
+}
@@ -0,0 +1,181 @@
+use alloy::providers::Provider;
+use tracing::Instrument;
+
+use crate::gateway::{
+    audit::AuditEntry, check::InvoiceCheck, error::GatewayError, recovery::RecoveryEntry,
+    PaymentGateway,
+};
+use crate::invoice::InvoiceStatus;
+use crate::web3::transfers::native_transfers::{confirm_treasury_transfer, TransferDepth};
+use crate::web3::transfers::sweep;
+
+use super::invoice_span;
+use super::poll::{invoice_balance, record_sweep_error, record_transfer};
+
+/// Sweeps an invoice on demand, outside of the poll cycle.
+///
+/// Any retry backoff is cleared first, so this also revives invoices whose
+/// automatic sweeps were given up on. A failed attempt keeps the previous
+/// status and is recorded as the invoice's `last_error`. Returns the hash of the treasury
+/// transfer; the poller confirms it like any other sweep.
+pub(crate) async fn sweep_invoice(
+    gateway: &PaymentGateway,
+    key: &str,
+) -> Result<String, GatewayError> {
+    let (mut invoice, previous_status) = {
+        let mut invoices = gateway.invoices.write().await;
+        let invoice = invoices.get_mut(key).ok_or(GatewayError::NotFound)?;
+        if matches!(invoice.status, InvoiceStatus::Sweeping | InvoiceStatus::Confirming) {
+            return Err(GatewayError::SweepInFlight);
+        }
+        let previous_status = invoice.status;
+        invoice.transition(InvoiceStatus::Sweeping, gateway.now_millis());
+        invoice.sweep_attempts = 0;
+        invoice.next_sweep_at = 0;
+        (invoice.clone(), previous_status)
+    };
+
+    let span = invoice_span(gateway, key, &invoice);
+    let result = sweep(gateway, &invoice).instrument(span.clone()).await;
+    let (outcome, audited) = span.in_scope(|| match result {
+        Ok(transfer) => {
+            let broadcast = AuditEntry::SweepBroadcast {
+                tx_hash: transfer.hash.clone(),
+                nonce: transfer.nonce,
+            };
+            record_transfer(&mut invoice, transfer, gateway.now_millis());
+            (Ok(invoice.hash.clone().unwrap_or_default()), Some(broadcast))
+        }
+        Err(error) => {
+            tracing::error!("Manual sweep failed at {:?} stage: {}", error.stage, error.error);
+            record_sweep_error(&mut invoice, error.stage, &error.error, gateway.now_seconds());
+            invoice.transition(previous_status, gateway.now_millis());
+            let audited = invoice.last_error.clone().map(|error| AuditEntry::Error { error });
+            (Err(GatewayError::Sweep(error.error.to_string())), audited)
+        }
+    });
+
+    if let Some(entry) = audited {
+        gateway.audit(key, entry).await;
+    }
+    if invoice.status != previous_status {
+        let changed = AuditEntry::StatusChanged {
+            from: previous_status,
+            to: invoice.status,
+        };
+        gateway.audit(key, changed).await;
+    }
+    let stored = match gateway.invoices.write().await.get_mut(key) {
+        Some(stored) => {
+            *stored = invoice.clone();
+            true
+        }
+        None => false,
+    };
+    if stored {
+        if let Err(e) = gateway.save_invoice(key, &invoice).await {
+            tracing::error!("Failed to save invoice {key}: {e}");
+        }
+    }
+    let id = key.to_string();
+    match (&outcome, invoice.last_error.clone()) {
+        (Ok(tx_hash), _) => {
+            let tx_hash = tx_hash.clone();
+            gateway.run_hook(|hooks| hooks.on_sweep_submitted(id, tx_hash)).await;
+        }
+        (Err(_), Some(error)) => gateway.run_hook(|hooks| hooks.on_error(id, error)).await,
+        (Err(_), None) => {}
+    }
+    outcome
+}
+
+/// Checks an invoice on demand, outside of the poll cycle, without changing
+/// it. Confirmations are counted for the treasury transfer of a `Confirming`
+/// invoice and for payments awaiting the invoice's own confirmations.
+pub(crate) async fn check_invoice_now(
+    gateway: &PaymentGateway,
+    key: &str,
+) -> Result<InvoiceCheck, GatewayError> {
+    let mut invoice = gateway.get_invoice(key).await?;
+    if invoice.shared_deposit {
+        return Err(GatewayError::Unsupported(
+            "checking invoices paid to the shared deposit address",
+        ));
+    }
+    let url = gateway
+        .next_rpc_url()
+        .parse()
+        .map_err(|e: url::ParseError| GatewayError::InvalidRpcUrl(e.to_string()))?;
+    let provider = gateway.connect(url);
+    let received = invoice_balance(&provider, &mut invoice)
+        .await
+        .map_err(|e| GatewayError::Rpc(e.to_string()))?;
+
+    let required = invoice
+        .min_confirmations
+        .unwrap_or(gateway.config.min_confirmations);
+    let confirmations = match (invoice.status, invoice.hash.as_deref(), invoice.deposit_block) {
+        (InvoiceStatus::Confirming, Some(tx_hash), _) => {
+            match confirm_treasury_transfer(gateway, tx_hash, required)
+                .await
+                .map_err(|e| GatewayError::Rpc(e.to_string()))?
+            {
+                TransferDepth::Unknown => None,
+                TransferDepth::Confirming(depth) => Some(depth),
+                TransferDepth::Confirmed => Some(required),
+            }
+        }
+        (InvoiceStatus::Pending, _, Some(deposit_block)) => {
+            let latest = provider.get_block_number().await.map_err(|e| GatewayError::Rpc(e.to_string()))?;
+            Some(latest.saturating_sub(deposit_block).min(required))
+        }
+        _ => None,
+    };
+
+    Ok(InvoiceCheck {
+        status: invoice.status,
+        received,
+        amount: invoice.amount,
+        paid: invoice.status != InvoiceStatus::Pending || received >= invoice.amount,
+        confirmations,
+        required_confirmations: required,
+    })
+}
+
+/// Reads the balances of the wallets of all pending and expired invoices,
+/// oldest invoice first.
+pub(crate) async fn recovery_report(
+    gateway: &PaymentGateway,
+) -> Result<Vec<RecoveryEntry>, GatewayError> {
+    let mut invoices = gateway.get_all_invoices().await?;
+    invoices.extend(gateway.get_expired_invoices().await?);
+    invoices.retain(|(_, invoice)| !invoice.shared_deposit);
+    invoices.sort_by_key(|(_, invoice)| invoice.created_at);
+
+    let url = gateway
+        .next_rpc_url()
+        .parse()
+        .map_err(|e: url::ParseError| GatewayError::InvalidRpcUrl(e.to_string()))?;
+    let provider = gateway.connect(url);
+    let mut report = Vec::with_capacity(invoices.len());
+    for (invoice_id, mut invoice) in invoices {
+        let balance = invoice_balance(&provider, &mut invoice)
+            .await
+            .map_err(|e| GatewayError::Rpc(e.to_string()))?;
+        let native_balance = if invoice.token.is_some() || invoice.nft.is_some() {
+            let balance = provider.get_balance(invoice.to).await;
+            Some(balance.map_err(|e| GatewayError::Rpc(e.to_string()))?)
+        } else {
+            None
+        };
+        report.push(RecoveryEntry {
+            invoice_id,
+            address: invoice.to,
+            status: invoice.status,
+            token: invoice.token,
+            balance,
+            native_balance,
+        });
+    }
+    Ok(report)
+}
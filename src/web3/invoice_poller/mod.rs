@@ -1,17 +1,96 @@
+mod breaker;
+mod checks;
+mod manual;
 mod poll;
+mod progress;
+mod quorum;
+mod refunds;
+mod reminders;
+mod schedule;
+mod shared;
+mod throttle;
 
-use crate::gateway::PaymentGateway;
+use std::sync::Arc;
 
+use tokio::sync::watch;
+use tracing::{field, Span};
+
+use crate::gateway::{PaymentGateway, PollerState};
+use crate::invoice::Invoice;
+
+pub(crate) use manual::{check_invoice_now, recovery_report, sweep_invoice};
 pub use poll::poll_payments;
 
+use self::{
+    checks::CheckSchedule, progress::ConfirmationProgress, reminders::ExpiryReminders, schedule::SweepSchedule,
+    shared::SharedDepositScan, throttle::NotificationThrottle,
+};
+
+/// Span following one invoice across poll cycles and sweeps, carrying its id,
+/// the chain id and the hash of its treasury transfer once broadcast.
+pub(crate) fn invoice_span(gateway: &PaymentGateway, key: &str, invoice: &Invoice) -> Span {
+    let span = tracing::info_span!(
+        "invoice",
+        invoice_id = %key,
+        chain_id = field::Empty,
+        tx_hash = field::Empty,
+    );
+    if let Some(chain_id) = gateway.cached_chain_id() {
+        span.record("chain_id", chain_id);
+    }
+    if let Some(hash) = invoice.hash.as_deref() {
+        span.record("tx_hash", hash);
+    }
+    span
+}
+
 /// Periodically checks invoices for incoming payments.
 /// Each poll cycle uses the next RPC URL via round-robin.
 pub(crate) struct InvoicePoller {
     pub(crate) gateway: PaymentGateway,
+    /// Shared with the `PollerHandle` controlling this poller
+    pub(crate) state: Arc<watch::Sender<PollerState>>,
+    /// Throttles `PartialPayment` events per invoice
+    pub(crate) partial_payments: NotificationThrottle,
+    /// Throttles `DustReceived` events per invoice
+    pub(crate) dust: NotificationThrottle,
+    /// Throttles `QuorumDisagreement` events per invoice
+    pub(crate) quorum: NotificationThrottle,
+    /// Expiry reminders already sent per invoice
+    pub(crate) reminders: ExpiryReminders,
+    /// Confirmation progress already reported per invoice
+    pub(crate) confirmations: ConfirmationProgress,
+    /// When paid invoices are swept
+    pub(crate) sweeps: SweepSchedule,
+    /// When invoices with a check interval are next checked for payment
+    pub(crate) checks: CheckSchedule,
+    /// Block scan matching payments to the shared deposit address
+    pub(crate) shared: SharedDepositScan,
 }
 
 impl InvoicePoller {
-    pub(crate) fn new(gateway: PaymentGateway) -> Self {
-        Self { gateway }
+    pub(crate) fn new(gateway: PaymentGateway, state: Arc<watch::Sender<PollerState>>) -> Self {
+        let partial_payments =
+            NotificationThrottle::new(gateway.config.partial_payment_throttle_seconds);
+        let dust = NotificationThrottle::new(gateway.config.partial_payment_throttle_seconds);
+        let quorum = NotificationThrottle::new(gateway.config.partial_payment_throttle_seconds);
+        let reminders = ExpiryReminders::new(&gateway.config.expiry_reminders);
+        let sweeps = SweepSchedule::new(gateway.config.sweep_policy, gateway.now_seconds());
+        Self {
+            gateway,
+            state,
+            partial_payments,
+            dust,
+            quorum,
+            reminders,
+            confirmations: ConfirmationProgress::default(),
+            sweeps,
+            checks: CheckSchedule::default(),
+            shared: SharedDepositScan::default(),
+        }
+    }
+
+    fn state(&self) -> PollerState {
+        *self.state.borrow()
     }
 }
@@ -1,24 +1,137 @@
-use alloy::providers::{Provider, ProviderBuilder};
+use std::sync::Arc;
 
-use crate::gateway::{get_unix_time_seconds, PaymentGateway};
-use crate::invoice::Invoice;
+use ahash::AHashMap;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use futures::future::join_all;
+use tokio::sync::watch;
+use tracing::Instrument;
+
+use crate::gateway::{
+    audit::AuditEntry,
+    error::GatewayError,
+    event::{GatewayEvent, InvoiceUpdate},
+    receipt::SignedReceipt, PaymentGateway, PollerState,
+};
+use crate::invoice::{
+    Invoice, InvoiceError, InvoiceErrorSource, InvoiceStatus, Settlement, SweepStage,
+    SweepStageError, ZeroizedVec,
+};
+use crate::web3::chain_id::cache_chain_id;
+use crate::web3::deposits::find_deposits;
+use crate::web3::error::TransferError;
+use crate::web3::multicall::balances;
 use crate::web3::result::Result;
 use crate::web3::transfers::native_transfers::{
-    confirm_treasury_transfer, send_native_to_treasury,
+    confirm_treasury_transfer, sweep_receipts, StagedError, TransferDepth, TreasuryTransfer,
 };
+use crate::web3::transfers::nft_transfers::nft_balance;
+use crate::web3::transfers::sweep;
+use crate::web3::transfers::token_transfers::token_balance;
 
-use super::InvoicePoller;
+use super::{invoice_span, InvoicePoller};
 
 impl InvoicePoller {
-    async fn check_invoice(&self, provider: &impl Provider, invoice: &Invoice) -> Result<bool> {
-        Ok(provider.get_balance(invoice.to).await? >= invoice.amount)
+    /// Reads the balances of the invoices `keys` awaiting payment through the
+    /// configured Multicall contract. Returns an empty map when no contract is
+    /// configured or the batched call fails, so balances are read one by one.
+    async fn prefetch_balances(
+        &self,
+        provider: &impl Provider,
+        rpc_url: &str,
+        keys: &[String],
+    ) -> AHashMap<String, U256> {
+        let Some(multicall) = self.gateway.config.multicall else {
+            return AHashMap::new();
+        };
+        let now = self.gateway.now_seconds();
+        let invoices = self.gateway.invoices.read().await;
+        let queries: Vec<_> = keys
+            .iter()
+            .filter_map(|key| Some((key, invoices.get(key)?)))
+            .filter(|(key, invoice)| {
+                !invoice.amount.is_zero()
+                    && self.checks.is_due(key, now)
+                    // Multi-token and NFT invoices are checked one by one
+                    && (invoice.payment_options.is_empty() || invoice.paid_with.is_some())
+                    && invoice.nft.is_none()
+                    && !invoice.shared_deposit
+                    && invoice.status != InvoiceStatus::Confirming
+                    && !matches!(
+                        invoice.status,
+                        InvoiceStatus::SweepFailed | InvoiceStatus::Sweeping
+                    )
+                    && now >= invoice.next_sweep_at
+            })
+            .map(|(_, invoice)| (invoice.to, invoice.token))
+            .collect();
+        drop(invoices);
+        if queries.is_empty() {
+            return AHashMap::new();
+        }
+        let result = balances(provider, multicall, &queries).await;
+        self.report_rpc(rpc_url, &result);
+        match result {
+            Ok(balances) => queries
+                .into_iter()
+                .zip(balances)
+                .filter_map(|((to, token), balance)| {
+                    Some((self.gateway.invoice_addresses.by_address(token, to)?, balance?))
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Multicall balance query failed, checking invoices one by one: {e}");
+                AHashMap::new()
+            }
+        }
     }
 
-    pub(crate) async fn poll(&self) {
-        loop {
+    pub(crate) async fn poll(&self) -> std::result::Result<(), GatewayError> {
+        self.wait_while_paused().await;
+        self.check_chain_id().await?;
+        self.cache_chain_id().await;
+        let mut chain_checked_at = self.gateway.now_seconds();
+        let mut purged_at = 0;
+        while self.state() != PollerState::Stopped {
+            self.wait_while_paused().await;
+            let now = self.gateway.now_seconds();
+            let interval = self.gateway.config.chain_check_interval_seconds;
+            if now.saturating_sub(chain_checked_at) >= interval {
+                self.check_chain_id().await?;
+                chain_checked_at = now;
+            }
+            let retention = self.gateway.config.retention;
+            if retention.is_enabled()
+                && now.saturating_sub(purged_at) >= retention.cleanup_interval_seconds
+            {
+                self.purge_invoices().await;
+                purged_at = now;
+            }
+            if !self.circuit_closed().await {
+                continue;
+            }
             self.poll_cycle().await;
+            if self.state() == PollerState::Draining && !self.has_sweeps_in_flight().await {
+                tracing::info!("Poller drained, no sweeps in flight");
+                self.state.send_replace(PollerState::Stopped);
+                break;
+            }
             self.delay().await;
         }
+        tracing::info!("Stopped polling payments");
+        Ok(())
+    }
+
+    /// Whether any invoice is being swept or awaits treasury confirmations.
+    async fn has_sweeps_in_flight(&self) -> bool {
+        self.gateway
+            .invoices
+            .read()
+            .await
+            .values()
+            .any(|invoice| {
+                matches!(invoice.status, InvoiceStatus::Sweeping | InvoiceStatus::Confirming)
+            })
     }
 
     async fn poll_cycle(&self) {
@@ -30,116 +143,878 @@ impl InvoicePoller {
                 return;
             }
         };
-        let provider = ProviderBuilder::new().connect_http(url);
+        let provider = self.gateway.connect(url);
 
         tracing::info!(
             "Pending invoices: {}",
             self.gateway.invoices.read().await.len()
         );
 
-        let all = match self.gateway.get_all_invoices().await {
-            Ok(all) => all,
-            Err(e) => {
-                tracing::error!("Could not get all invoices: {e}");
-                return;
+        if self.state() == PollerState::Running {
+            self.scan_shared_deposits(&provider).await;
+            if self.gateway.config.redeliver_dead_letters {
+                if let Err(e) = self.gateway.redeliver_dead_letters().await {
+                    tracing::error!("Failed to redeliver dead letters: {e}");
+                }
             }
-        };
+        }
 
-        for (key, mut invoice) in all {
-            self.process_invoice(&provider, &key, &mut invoice).await;
-            self.delay().await;
+        // Only the ids of the invoices to process are copied, shared deposit
+        // invoices for their expiry
+        let now = self.gateway.now_seconds();
+        let mut pending: Vec<_> = self
+            .gateway
+            .invoices
+            .read()
+            .await
+            .iter()
+            .filter(|(key, invoice)| {
+                invoice.shared_deposit
+                    || invoice.status == InvoiceStatus::Confirming
+                    || self.checks.is_due(key, now)
+            })
+            .map(|(key, invoice)| (invoice.check_interval_seconds, key.clone()))
+            .collect();
+        // Invoices checked most often go first
+        pending.sort_by_key(|(interval, _)| *interval);
+        let keys: Vec<String> = pending.into_iter().map(|(_, key)| key).collect();
+        let mut prefetched = self.prefetch_balances(&provider, rpc_url, &keys).await;
+        let batch_size = self.gateway.config.sweep_batch_size.max(1);
+        let mut due = Vec::with_capacity(batch_size);
+        let mut total = U256::ZERO;
+        for key in keys {
+            // Pick up changes made since the ids were taken, e.g. manual
+            // sweeps, and skip invoices cancelled in the meantime
+            self.wait_while_paused().await;
+            let Some(mut invoice) = self.gateway.invoices.read().await.get(&key).cloned() else {
+                continue;
+            };
+            match self.state() {
+                PollerState::Stopped => return,
+                // Only confirm sweeps that were already broadcast
+                PollerState::Draining if invoice.status != InvoiceStatus::Confirming => continue,
+                _ => {}
+            }
+            if invoice.shared_deposit {
+                self.expire_shared_invoice(&key, &invoice).await;
+                continue;
+            }
+            let confirming = invoice.status == InvoiceStatus::Confirming;
+            if !confirming && !self.checks.is_due(&key, self.gateway.now_seconds()) {
+                continue;
+            }
+            let cached = prefetched.remove(&key);
+            let span = invoice_span(&self.gateway, &key, &invoice);
+            let paid = self
+                .process_invoice(&provider, rpc_url, &key, &mut invoice, cached)
+                .instrument(span)
+                .await;
+            if let Some(balance) = paid {
+                total = total.saturating_add(balance);
+                due.push((key, invoice));
+                if self.sweeps.is_immediate() && due.len() >= batch_size {
+                    self.sweep_batch(std::mem::take(&mut due)).await;
+                }
+            }
+            // Balances read in bulk need no spacing between requests, and
+            // with a rate limit the requests space themselves
+            if cached.is_none() && self.gateway.config.rpc_rate_limit.is_none() {
+                self.delay().await;
+            }
         }
+        if self.state() == PollerState::Stopped || due.is_empty() {
+            return;
+        }
+        if !self.sweeps.permit(total, self.gateway.now_seconds()) {
+            tracing::info!("Holding {} paid invoices until the sweep policy permits", due.len());
+            return;
+        }
+        while !due.is_empty() {
+            let batch = due.drain(..batch_size.min(due.len())).collect();
+            self.sweep_batch(batch).await;
+        }
+    }
+
+    /// Sweeps the collected paid invoices concurrently.
+    async fn sweep_batch(&self, mut due: Vec<(String, Invoice)>) {
+        {
+            // Skip invoices cancelled while the batch was collected
+            let invoices = self.gateway.invoices.read().await;
+            due.retain(|(key, _)| invoices.contains_key(key));
+        }
+        if due.len() > 1 {
+            tracing::info!("Sweeping a batch of {} paid invoices", due.len());
+        }
+        join_all(due.iter_mut().map(|(key, invoice)| {
+            let span = invoice_span(&self.gateway, key, invoice);
+            self.send_to_treasury(key, invoice).instrument(span)
+        }))
+        .await;
     }
 
-    async fn process_invoice(&self, provider: &impl Provider, key: &str, invoice: &mut Invoice) {
+    /// Processes one invoice, returning its balance when it is paid and due for a sweep.
+    /// `cached` is the balance already read through Multicall, if any.
+    async fn process_invoice(
+        &self,
+        provider: &impl Provider,
+        rpc_url: &str,
+        key: &str,
+        invoice: &mut Invoice,
+        cached: Option<U256>,
+    ) -> Option<U256> {
         if invoice.amount.is_zero() {
             tracing::info!("No charge for invoice, confirming");
-            invoice.paid_at_timestamp = get_unix_time_seconds();
+            invoice.paid_at_timestamp = self.gateway.now_seconds();
+            invoice.transition(InvoiceStatus::Paid, self.gateway.now_millis());
             self.send_confirmed_invoice(key, invoice.clone()).await;
-            return;
+            return None;
         }
 
-        if invoice.hash.is_some() {
+        if invoice.status == InvoiceStatus::Confirming {
             self.handle_pending_tx(key, invoice).await;
-            return;
+            return None;
+        }
+
+        // Failed sweeps wait out their backoff; exhausted ones and those
+        // being swept manually are left alone
+        if matches!(invoice.status, InvoiceStatus::SweepFailed | InvoiceStatus::Sweeping)
+            || self.gateway.now_seconds() < invoice.next_sweep_at
+        {
+            return None;
         }
 
-        let is_paid = match self.check_invoice(provider, invoice).await {
-            Ok(paid) => paid,
+        let checked = match cached {
+            Some(balance) => Ok(balance),
+            None => {
+                let checked = invoice_balance(provider, invoice).await;
+                self.report_rpc(rpc_url, &checked);
+                checked
+            }
+        };
+        let balance = match checked {
+            Ok(balance) => {
+                self.checks
+                    .checked(key, invoice.check_interval_seconds, self.gateway.now_seconds());
+                let id = key.to_string();
+                self.gateway.run_hook(|hooks| hooks.on_check(id, balance)).await;
+                balance
+            }
             Err(e) => {
                 tracing::error!("Failed to check balance: {e}");
-                return;
+                let now = self.gateway.now_seconds();
+                record_error(invoice, InvoiceErrorSource::BalanceCheck, &e, now);
+                self.audit_error(key, invoice).await;
+                self.store_invoice(key, invoice).await;
+                return None;
             }
         };
 
-        if !is_paid {
-            if get_unix_time_seconds() > invoice.expires {
-                self.gateway.invoices.write().await.remove(key);
+        if balance < invoice.amount {
+            let balance = self.without_dust(key, invoice, balance);
+            // A payment dropped by a reorg has to be confirmed again
+            if invoice.deposit_block.take().is_some() {
+                self.store_invoice(key, invoice).await;
             }
-            return;
+            let now = self.gateway.now_seconds();
+            if now > invoice.expires {
+                let refunded = balance.is_zero()
+                    || !self.gateway.config.refund_expired_payments
+                    || self.refund_expired(provider, key, invoice, balance).await;
+                if !refunded {
+                    return None;
+                }
+                self.forget_invoice(key);
+                self.gateway.expire_invoice(key, invoice.clone()).await;
+                return None;
+            }
+            if !balance.is_zero() {
+                self.notify_partial_payment(key, invoice, balance);
+            }
+            self.notify_expiry_reminder(key, invoice, now);
+            return None;
+        }
+
+        if !self.is_deposit_confirmed(provider, key, invoice).await {
+            return None;
+        }
+        if !self.quorum_agrees(key, invoice).await {
+            return None;
         }
 
-        tracing::info!("Invoice paid, sending to treasury");
-        self.send_to_treasury(key, invoice).await;
+        // Detection happens once, when the payment moves the invoice out of
+        // Pending. Invoices held by the sweep policy, deferred or retrying a
+        // failed sweep keep their status until the sweep goes through.
+        if invoice.status == InvoiceStatus::Pending {
+            if invoice.deposits.is_empty() {
+                self.record_deposits(provider, invoice, balance).await;
+            }
+            let (id, detected) = (key.to_string(), invoice.clone());
+            self.gateway.run_hook(|hooks| hooks.on_detected(id, detected)).await;
+            if self.gateway.config.detect_only {
+                tracing::info!("Invoice paid, leaving the funds on the invoice address");
+                invoice.paid_at_timestamp = self.gateway.now_seconds();
+                invoice.transition(InvoiceStatus::Paid, self.gateway.now_millis());
+                self.send_confirmed_invoice(key, invoice.clone()).await;
+                return None;
+            }
+            tracing::info!("Invoice paid, sending to treasury");
+            invoice.transition(InvoiceStatus::Paid, self.gateway.now_millis());
+            // The payment has to survive a crash before the sweep starts
+            if !self.store_invoice(key, invoice).await {
+                return None;
+            }
+        }
+        Some(balance)
     }
 
     async fn handle_pending_tx(&self, key: &str, invoice: &mut Invoice) {
-        let confirmed = match invoice.hash.as_deref() {
-            Some(tx_hash) => confirm_treasury_transfer(&self.gateway, tx_hash).await,
+        let min_confirmations = self.min_confirmations(invoice);
+        let depth = match invoice.hash.as_deref() {
+            Some(tx_hash) => {
+                confirm_treasury_transfer(&self.gateway, tx_hash, min_confirmations).await
+            }
             None => return,
         };
+        if let Ok(TransferDepth::Confirming(confirmations)) = depth {
+            self.report_confirmations(key, confirmations, min_confirmations);
+        }
 
-        match confirmed {
-            Ok(true) => {
+        match depth {
+            Ok(TransferDepth::Confirmed) => {
                 tracing::info!(
                     "Treasury transfer confirmed: {}",
                     invoice.hash.as_deref().unwrap_or("unknown")
                 );
-                invoice.paid_at_timestamp = get_unix_time_seconds();
+                invoice.paid_at_timestamp = self.gateway.now_seconds();
+                invoice.transition(InvoiceStatus::Swept, self.gateway.now_millis());
+                if let Some(settlement) = invoice.settlement.as_mut() {
+                    let timings = &mut settlement.timings;
+                    let now_ms = self.gateway.now_millis();
+                    let confirm_ms = now_ms.saturating_sub(timings.broadcast_at_ms);
+                    timings.confirm_ms = Some(confirm_ms);
+                    tracing::info!(confirm_ms, "Treasury transfer reached required confirmations");
+                }
+                self.record_sweep_costs(invoice).await;
+                let (id, swept) = (key.to_string(), invoice.clone());
+                self.gateway.run_hook(|hooks| hooks.on_sweep_confirmed(id, swept)).await;
                 self.send_confirmed_invoice(key, invoice.clone()).await;
             }
-            Ok(false) => {
+            Ok(_) if !self.is_stuck(invoice) => {}
+            Ok(_) => {
                 tracing::info!(
-                    "Tx {} not yet confirmed, retrying with bumped fees",
+                    "Tx {} not confirmed in time, replacing it with bumped fees",
                     invoice.hash.as_deref().unwrap_or("unknown")
                 );
                 self.send_to_treasury(key, invoice).await;
             }
-            Err(e) => tracing::error!("Error checking treasury transfer: {e}"),
+            Err(e) => {
+                tracing::error!("Error checking treasury transfer: {e}");
+                record_sweep_error(invoice, SweepStage::Confirm, &e, self.gateway.now_seconds());
+                self.audit_error(key, invoice).await;
+                self.store_invoice(key, invoice).await;
+            }
+        }
+    }
+
+    /// Feeds the outcome of a request to `rpc_url` into RPC failover and the
+    /// circuit breaker.
+    fn report_rpc<T>(&self, rpc_url: &str, result: &Result<T>) {
+        match result {
+            Ok(_) => {
+                self.gateway.report_rpc_success(rpc_url);
+                self.gateway.breakers.succeeded(rpc_url);
+            }
+            Err(TransferError::Transport(_)) => {
+                self.gateway.report_rpc_failure(rpc_url);
+                self.breaker_failed(rpc_url);
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Confirmations required for `invoice`, honoring its own override.
+    fn min_confirmations(&self, invoice: &Invoice) -> u64 {
+        invoice
+            .min_confirmations
+            .unwrap_or(self.gateway.config.min_confirmations)
+    }
+
+    /// Whether the payment of an invoice with its own confirmation requirement
+    /// is deep enough to be swept. Records the block at which the payment was
+    /// first seen and counts confirmations from there.
+    async fn is_deposit_confirmed(
+        &self,
+        provider: &impl Provider,
+        key: &str,
+        invoice: &mut Invoice,
+    ) -> bool {
+        let Some(required) = invoice.min_confirmations.filter(|required| *required > 0) else {
+            return true;
+        };
+        let latest = match provider.get_block_number().await {
+            Ok(latest) => latest,
+            Err(e) => {
+                tracing::error!("Failed to fetch block number: {e}");
+                return false;
+            }
+        };
+        let Some(deposit_block) = invoice.deposit_block else {
+            tracing::info!("Invoice paid, waiting for {required} confirmations");
+            invoice.deposit_block = Some(latest);
+            self.store_invoice(key, invoice).await;
+            self.report_confirmations(key, 0, required);
+            return false;
+        };
+        let confirmations = latest.saturating_sub(deposit_block);
+        self.report_confirmations(key, confirmations.min(required), required);
+        confirmations >= required
+    }
+
+    /// Raises a `Confirming` event and updates the invoice's subscribers when
+    /// its confirmations changed since the last report.
+    fn report_confirmations(&self, key: &str, confirmations: u64, required: u64) {
+        if !self.confirmations.advanced(key, confirmations, required) {
+            return;
+        }
+        self.gateway.emit(GatewayEvent::Confirming {
+            invoice_id: key.to_string(),
+            confirmations,
+            required,
+        });
+        let progress = InvoiceUpdate::Confirmations {
+            confirmations,
+            required,
+        };
+        self.gateway.publish_update(key, progress);
+    }
+
+    /// Records who paid `invoice` and every transfer that contributed to
+    /// the payment. Failing to find them is logged and never holds up the
+    /// sweep.
+    pub(super) async fn record_deposits(
+        &self,
+        provider: &impl Provider,
+        invoice: &mut Invoice,
+        balance: U256,
+    ) {
+        // NFT payments are not looked up
+        if invoice.nft.is_some() {
+            return;
+        }
+        let deposits = find_deposits(
+            provider,
+            invoice.token,
+            invoice.to,
+            balance,
+            invoice.created_at,
+            self.gateway.config.deposit_lookback_blocks,
+            self.gateway.config.trace_internal_transfers,
+        )
+        .await;
+        match deposits {
+            Ok(deposits) if !deposits.is_empty() => {
+                invoice.payer = deposits.first().map(|deposit| deposit.from);
+                invoice.deposit_block_number = deposits.last().map(|deposit| deposit.block_number);
+                invoice.deposits = deposits;
+            }
+            Ok(_) => tracing::warn!("No deposit transaction found for paid invoice {}", invoice.to),
+            Err(e) => tracing::warn!("Failed to look up deposit transactions: {e}"),
+        }
+    }
+
+    /// Records the receipts of the confirmed sweep of `invoice`, the gas it
+    /// paid and what reached the treasury. Failing to read the receipts is
+    /// logged and never holds up the delivery.
+    async fn record_sweep_costs(&self, invoice: &mut Invoice) {
+        let Some(settlement) = invoice.settlement.as_mut() else {
+            return;
+        };
+        // The treasury transfer comes last
+        settlement.net_amount_to_treasury = settlement.payouts.last().map(|payout| payout.amount);
+        let receipts = match sweep_receipts(&self.gateway, &settlement.payouts).await {
+            Ok(Some(receipts)) => receipts,
+            Ok(None) => {
+                tracing::warn!("Receipts of the confirmed sweep are unavailable");
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read the receipts of the sweep: {e}");
+                return;
+            }
+        };
+        let gas_used = receipts.iter().map(|receipt| receipt.gas_used).sum();
+        settlement.sweep_gas_used = Some(gas_used);
+        settlement.sweep_effective_gas_price =
+            receipts.last().map(|receipt| receipt.effective_gas_price);
+        for (payout, receipt) in settlement.payouts.iter_mut().zip(receipts) {
+            payout.receipt = Some(receipt);
         }
     }
 
+    /// Whether the pending sweep of `invoice` has been waiting longer than the
+    /// replacement timeout since it was last broadcast.
+    fn is_stuck(&self, invoice: &Invoice) -> bool {
+        let broadcast_at_ms = invoice
+            .settlement
+            .as_ref()
+            .map_or(0, |settlement| settlement.timings.broadcast_at_ms);
+        let timeout_ms = self.gateway.config.replacement_timeout_seconds * 1000;
+        self.gateway.now_millis().saturating_sub(broadcast_at_ms) >= timeout_ms
+    }
+
     async fn send_to_treasury(&self, key: &str, invoice: &mut Invoice) {
-        match send_native_to_treasury(&self.gateway, invoice).await {
-            Ok((hash, nonce)) => {
-                invoice.hash = Some(hash);
-                invoice.nonce = Some(nonce);
-                self.gateway
-                    .invoices
-                    .write()
-                    .await
-                    .insert(key.to_string(), invoice.clone());
+        let is_replacement = invoice.status == InvoiceStatus::Confirming;
+        let was_deferred = invoice.status == InvoiceStatus::PaidAwaitingSweep;
+        if !is_replacement && invoice.status != InvoiceStatus::PaidAwaitingSweep {
+            invoice.transition(InvoiceStatus::Sweeping, self.gateway.now_millis());
+            self.store_invoice(key, invoice).await;
+        }
+
+        let result = sweep(&self.gateway, invoice).await;
+        match result {
+            Err(StagedError {
+                error: TransferError::GasPriceAboveCeiling { price, ceiling },
+                ..
+            }) => {
+                tracing::info!("Fee per gas {price} above ceiling {ceiling}, deferring sweep");
+                if !is_replacement {
+                    invoice.transition(InvoiceStatus::PaidAwaitingSweep, self.gateway.now_millis());
+                }
+                self.store_invoice(key, invoice).await;
+            }
+            Err(StagedError {
+                error: TransferError::SweepFeeAboveLimit { fee, amount },
+                ..
+            }) => {
+                tracing::info!("Sweep fee {fee} too high for amount {amount}, deferring sweep");
+                if !is_replacement && !was_deferred {
+                    self.gateway.emit(GatewayEvent::SweepDeferred {
+                        invoice_id: key.to_string(),
+                        fee,
+                        amount,
+                    });
+                }
+                if !is_replacement {
+                    invoice.transition(InvoiceStatus::PaidAwaitingSweep, self.gateway.now_millis());
+                }
+                self.store_invoice(key, invoice).await;
+            }
+            Ok(transfer) => {
+                let broadcast = AuditEntry::SweepBroadcast {
+                    tx_hash: transfer.hash.clone(),
+                    nonce: transfer.nonce,
+                };
+                let (id, tx_hash) = (key.to_string(), transfer.hash.clone());
+                record_transfer(invoice, transfer, self.gateway.now_millis());
+                self.gateway.audit(key, broadcast).await;
+                self.store_invoice(key, invoice).await;
+                self.gateway.run_hook(|hooks| hooks.on_sweep_submitted(id, tx_hash)).await;
+            }
+            Err(StagedError { stage, error }) => {
+                if let TransferError::ChainIdMismatch { expected, actual } = error {
+                    tracing::error!(
+                        "CRITICAL: RPC reported chain id {actual} instead of {expected}, sweep aborted"
+                    );
+                    self.gateway.emit(GatewayEvent::ChainIdMismatch {
+                        invoice_id: key.to_string(),
+                        expected,
+                        actual,
+                    });
+                } else {
+                    tracing::error!("Failed to send treasury transfer at {stage:?} stage: {error}");
+                }
+                record_sweep_error(invoice, stage, &error, self.gateway.now_seconds());
+                self.audit_error(key, invoice).await;
+                // A failed replacement leaves the original transfer pending
+                if !is_replacement {
+                    invoice.transition(InvoiceStatus::Failed, self.gateway.now_millis());
+                    self.schedule_sweep_retry(key, invoice);
+                }
+                self.store_invoice(key, invoice).await;
             }
-            Err(e) => tracing::error!("Failed to send treasury transfer: {e}"),
         }
     }
 
-    async fn send_confirmed_invoice(&self, key: &str, invoice: Invoice) {
-        self.gateway.invoices.write().await.remove(key);
-        if let Err(e) = self.gateway.config.sender.send((key.to_string(), invoice)) {
-            tracing::error!("Failed sending data: {e}");
+    /// Counts a failed sweep and either schedules the next attempt according to
+    /// the retry policy or gives up once it is exhausted.
+    fn schedule_sweep_retry(&self, key: &str, invoice: &mut Invoice) {
+        let policy = &self.gateway.config.sweep_retry;
+        invoice.sweep_attempts += 1;
+
+        if policy.is_exhausted(invoice.sweep_attempts) {
+            tracing::error!(
+                "Giving up on sweeping invoice after {} attempts",
+                invoice.sweep_attempts
+            );
+            invoice.transition(InvoiceStatus::SweepFailed, self.gateway.now_millis());
+            if let Some(error) = invoice.last_error.clone() {
+                self.gateway.emit(GatewayEvent::SweepFailed {
+                    invoice_id: key.to_string(),
+                    attempts: invoice.sweep_attempts,
+                    error,
+                });
+            }
+            return;
+        }
+
+        let backoff = policy.backoff_seconds(invoice.sweep_attempts);
+        tracing::warn!("Retrying sweep in {backoff}s (attempt {})", invoice.sweep_attempts);
+        invoice.next_sweep_at = self.gateway.now_seconds() + backoff;
+    }
+
+    /// Stops the poller when the RPC serves another chain than the configured
+    /// `expected_chain_id`. Failing requests are only logged.
+    async fn check_chain_id(&self) -> std::result::Result<(), GatewayError> {
+        if self.gateway.config.expected_chain_id.is_none() {
+            return Ok(());
+        }
+        match self.gateway.verify_chain_id().await {
+            Ok(_) => Ok(()),
+            Err(e @ GatewayError::ChainMismatch { .. }) => {
+                tracing::error!("Stopping poller: {e}");
+                self.state.send_replace(PollerState::Stopped);
+                Err(e)
+            }
+            Err(e) => {
+                tracing::warn!("Could not verify chain id: {e}");
+                Ok(())
+            }
+        }
+    }
+
+    /// Caches the chain id at startup so later sweeps can detect endpoint swaps.
+    async fn cache_chain_id(&self) {
+        let rpc_url = self.gateway.next_rpc_url();
+        let url = match rpc_url.parse() {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::error!("Invalid RPC URL '{rpc_url}': {e}");
+                return;
+            }
+        };
+        let provider = self.gateway.connect(url);
+        match cache_chain_id(&self.gateway, &provider).await {
+            Ok(chain_id) => tracing::info!("Using chain id {chain_id}"),
+            // Retried lazily before the first sweep
+            Err(e) => tracing::warn!("Could not fetch chain id at startup: {e}"),
+        }
+    }
+
+    /// The balance of an underpaid invoice as seen by payment detection: zero
+    /// when it is below the configured dust threshold of the invoice's asset,
+    /// which is then reported with a throttled `DustReceived` event.
+    fn without_dust(&self, key: &str, invoice: &Invoice, balance: U256) -> U256 {
+        let threshold = self
+            .gateway
+            .config
+            .dust_thresholds
+            .iter()
+            .find(|(token, _)| *token == invoice.token)
+            .map(|(_, threshold)| *threshold);
+        let is_dust = matches!(threshold, Some(threshold) if balance < threshold);
+        if balance.is_zero() || invoice.nft.is_some() || !is_dust {
+            return balance;
+        }
+        if self.dust.permit(key, balance, self.gateway.now_seconds()) {
+            self.gateway.emit(GatewayEvent::DustReceived {
+                invoice_id: key.to_string(),
+                amount: balance,
+            });
+        }
+        U256::ZERO
+    }
+
+    /// Emits a throttled `PartialPayment` event for an underpaid invoice.
+    fn notify_partial_payment(&self, key: &str, invoice: &Invoice, received: U256) {
+        if self
+            .partial_payments
+            .permit(key, received, self.gateway.now_seconds())
+        {
+            self.gateway.emit(GatewayEvent::PartialPayment {
+                invoice_id: key.to_string(),
+                received,
+                amount: invoice.amount,
+            });
+        }
+    }
+
+    /// Emits an `ExpiryReminder` event when an unpaid invoice crosses one of
+    /// the configured thresholds of its expiry window.
+    fn notify_expiry_reminder(&self, key: &str, invoice: &Invoice, now: u64) {
+        if let Some(percent) = self
+            .reminders
+            .due(key, invoice.created_at, invoice.expires, now)
+        {
+            self.gateway.emit(GatewayEvent::ExpiryReminder {
+                invoice_id: key.to_string(),
+                percent,
+                expires: invoice.expires,
+            });
+        }
+    }
+
+    /// Drops the notification and check schedule state of an invoice that
+    /// left the pending set.
+    pub(super) fn forget_invoice(&self, key: &str) {
+        self.partial_payments.forget(key);
+        self.dust.forget(key);
+        self.quorum.forget(key);
+        self.reminders.forget(key);
+        self.confirmations.forget(key);
+        self.checks.forget(key);
+    }
+
+    /// Purges what the `retention` policy no longer keeps and drops the
+    /// poller's state of purged invoices.
+    async fn purge_invoices(&self) {
+        match self.gateway.purge_invoices().await {
+            Ok(purged) => {
+                if !purged.is_empty() {
+                    tracing::info!("Purged {} invoices past their retention", purged.len());
+                }
+                purged.iter().for_each(|key| self.forget_invoice(key));
+            }
+            Err(e) => tracing::error!("Failed to purge invoices: {e}"),
+        }
+    }
+
+    /// Writes the poller's copy of an invoice back to the gateway and its
+    /// `invoice_store`. Never resurrects an invoice that was cancelled
+    /// meanwhile. Returns false when the invoice is gone or was not saved.
+    pub(super) async fn store_invoice(&self, key: &str, invoice: &Invoice) -> bool {
+        let previous = match self.gateway.invoices.write().await.get_mut(key) {
+            Some(stored) => std::mem::replace(stored, invoice.clone()),
+            None => return false,
+        };
+        // Paying a multi-token invoice settles its asset and amount
+        if (previous.token, previous.amount) != (invoice.token, invoice.amount) {
+            self.gateway.invoice_addresses.remove(key, &previous);
+            self.gateway.invoice_addresses.insert(key, invoice);
+        }
+        self.gateway.payment_watches.notify(key, invoice);
+        self.audit_status(key, previous.status, invoice.status).await;
+        match self.gateway.save_invoice(key, invoice).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to save invoice {key}: {e}");
+                false
+            }
         }
     }
 
+    /// Records the invoice's last error in the audit log and reports it to
+    /// the `on_error` hook.
+    pub(super) async fn audit_error(&self, key: &str, invoice: &Invoice) {
+        if let Some(error) = invoice.last_error.clone() {
+            self.gateway.audit(key, AuditEntry::Error { error: error.clone() }).await;
+            let id = key.to_string();
+            self.gateway.run_hook(|hooks| hooks.on_error(id, error)).await;
+        }
+    }
+
+    /// Records a status transition in the audit log.
+    pub(super) async fn audit_status(&self, key: &str, from: InvoiceStatus, to: InvoiceStatus) {
+        if from != to {
+            self.gateway.audit(key, AuditEntry::StatusChanged { from, to }).await;
+        }
+    }
+
+    /// Removes a settled invoice, records it in the paid history with an
+    /// `InvoicePaid` event, delivers it on the reflector and then removes it
+    /// from the `invoice_store`. Invoices the reflector fails to deliver go to
+    /// the dead-letter queue instead. Swept invoices are delivered without
+    /// their wallet key unless `retain_swept_wallets` is set.
+    pub(super) async fn send_confirmed_invoice(&self, key: &str, mut invoice: Invoice) {
+        self.forget_invoice(key);
+        let previous = self.gateway.invoices.write().await.remove(key);
+        if let Some(previous) = previous {
+            self.gateway.unindex_invoice(key, &previous);
+            self.audit_status(key, previous.status, invoice.status).await;
+        }
+        let delivered = AuditEntry::Delivered {
+            status: invoice.status,
+        };
+        self.gateway.audit(key, delivered).await;
+        if invoice.status == InvoiceStatus::Swept && !self.gateway.config.retain_swept_wallets {
+            // Replacing the wallet zeroizes the old key on drop
+            invoice.wallet = ZeroizedVec::default();
+            invoice.wallet_encrypted = false;
+        }
+        if invoice.status == InvoiceStatus::Swept {
+            self.sign_receipt(key, &mut invoice);
+        }
+        let limit = self.gateway.config.paid_history_limit;
+        self.gateway.paid.push(key, invoice.clone(), limit).await;
+        self.gateway.emit(GatewayEvent::InvoicePaid {
+            invoice_id: key.to_string(),
+            status: invoice.status,
+        });
+        self.gateway.payment_watches.notify(key, &invoice);
+        if let Err(e) = self.gateway.reflect(key, invoice.clone()).await {
+            tracing::error!("Failed sending data, dead-lettering the invoice: {e}");
+            self.gateway.dead_letter(key, invoice).await;
+        }
+        // Only now, so a crash before the delivery recovers the invoice
+        self.gateway.discard_saved_invoice(key).await;
+    }
+
+    /// Signs the proof of payment of a swept invoice with the gateway's
+    /// `receipt_signing_key`, if one is configured.
+    fn sign_receipt(&self, key: &str, invoice: &mut Invoice) {
+        let Some(signer) = &self.gateway.config.receipt_signing_key else {
+            return;
+        };
+        let Some(sweep_tx_hash) = invoice.hash.as_deref().and_then(|hash| hash.parse().ok()) else {
+            return;
+        };
+        let token = invoice.token.or(invoice.nft.map(|nft| nft.contract));
+        let receipt = SignedReceipt::sign(
+            signer,
+            key,
+            invoice.payer.unwrap_or(Address::ZERO),
+            invoice.amount,
+            token,
+            sweep_tx_hash,
+            self.gateway.cached_chain_id(),
+        );
+        match receipt {
+            Ok(receipt) => invoice.payment_receipt = Some(receipt),
+            Err(e) => tracing::error!("Failed to sign the payment receipt: {e}"),
+        }
+    }
+
+    /// Blocks while polling is paused, returning early when the poller is stopped.
+    async fn wait_while_paused(&self) {
+        let mut paused = self.gateway.polling_paused.subscribe();
+        let mut state = self.state.subscribe();
+        if *paused.borrow_and_update() {
+            tracing::info!("Polling paused");
+        }
+        while *paused.borrow_and_update() && self.state() != PollerState::Stopped {
+            tokio::select! {
+                _ = paused.changed() => {}
+                _ = state.changed() => {}
+            }
+        }
+    }
+
+    /// Waits for the poller delay, returning early when the poller state changes.
     async fn delay(&self) {
-        tokio::time::sleep(std::time::Duration::from_secs(
-            self.gateway.config.poller_delay_seconds,
-        ))
-        .await;
+        let delay = std::time::Duration::from_secs(self.gateway.config.poller_delay_seconds);
+        self.sleep(delay).await;
+    }
+
+    /// Waits for `duration`, returning early when the poller state changes.
+    pub(super) async fn sleep(&self, duration: std::time::Duration) {
+        let mut state = self.state.subscribe();
+        tokio::select! {
+            _ = self.gateway.config.runtime.sleep(duration) => {}
+            _ = state.changed() => {}
+        }
+    }
+}
+
+/// Tracks a broadcast treasury transfer, broadcast at `at_ms`, until it is confirmed.
+pub(super) fn record_transfer(invoice: &mut Invoice, transfer: TreasuryTransfer, at_ms: u64) {
+    tracing::Span::current().record("tx_hash", transfer.hash.as_str());
+    invoice.hash = Some(transfer.hash);
+    invoice.nonce = Some(transfer.nonce);
+    invoice.settlement = Some(transfer.settlement);
+    invoice.transition(InvoiceStatus::Confirming, at_ms);
+    invoice.sweep_attempts = 0;
+    invoice.next_sweep_at = 0;
+}
+
+/// Keeps the most recent error, raised at `now` in Unix seconds, on the
+/// invoice so it shows up in queries.
+pub(super) fn record_error(
+    invoice: &mut Invoice,
+    source: InvoiceErrorSource,
+    error: &TransferError,
+    now: u64,
+) {
+    invoice.last_error = Some(InvoiceError {
+        source,
+        kind: error.kind(),
+        message: error.to_string(),
+        timestamp: now,
+    });
+}
+
+/// Surfaces a failed sweep stage on the invoice's settlement record.
+pub(super) fn record_sweep_error(
+    invoice: &mut Invoice,
+    stage: SweepStage,
+    error: &TransferError,
+    now: u64,
+) {
+    record_error(invoice, InvoiceErrorSource::Sweep(stage), error, now);
+    let amount = invoice.amount;
+    invoice
+        .settlement
+        .get_or_insert_with(|| Settlement {
+            invoice_amount: amount,
+            ..Default::default()
+        })
+        .error = Some(SweepStageError {
+        stage,
+        message: error.to_string(),
+    });
+}
+
+/// Reads the balance of an invoice address in the invoice's asset, or the
+/// quantity of its NFT. Multi-token invoices are checked for every asset
+/// they accept.
+pub(super) async fn invoice_balance(
+    provider: &impl Provider,
+    invoice: &mut Invoice,
+) -> Result<U256> {
+    if let Some(nft) = &invoice.nft {
+        return nft_balance(provider, nft, invoice.to).await;
+    }
+    if invoice.paid_with.is_none() && !invoice.payment_options.is_empty() {
+        return check_payment_options(provider, invoice).await;
+    }
+    balance_of(provider, invoice.token, invoice.to).await
+}
+
+/// Checks every asset a multi-token invoice accepts. The first one paid
+/// in full becomes the invoice's `token` and `amount`; otherwise the
+/// balance of the invoice's own asset is returned.
+async fn check_payment_options(provider: &impl Provider, invoice: &mut Invoice) -> Result<U256> {
+    let mut own_balance = U256::ZERO;
+    for (i, option) in invoice.payment_options.clone().into_iter().enumerate() {
+        let balance = balance_of(provider, option.token, invoice.to).await?;
+        if balance >= option.amount {
+            tracing::info!("Invoice paid with option {i}: {:?}", option.token);
+            invoice.token = option.token;
+            invoice.amount = option.amount;
+            invoice.paid_with = Some(option);
+            return Ok(balance);
+        }
+        if i == 0 {
+            own_balance = balance;
+        }
+    }
+    Ok(own_balance)
+}
+
+/// Reads the balance of `holder` in `token`, or in the native currency.
+async fn balance_of(
+    provider: &impl Provider,
+    token: Option<Address>,
+    holder: Address,
+) -> Result<U256> {
+    match token {
+        Some(token) => token_balance(provider, token, holder).await,
+        None => Ok(provider.get_balance(holder).await?),
     }
 }
 
-pub async fn poll_payments(gateway: PaymentGateway) {
+pub async fn poll_payments(
+    gateway: PaymentGateway,
+    state: Arc<watch::Sender<PollerState>>,
+) -> std::result::Result<(), GatewayError> {
     tracing::info!("Starting polling payments");
-    InvoicePoller::new(gateway).poll().await;
+    InvoicePoller::new(gateway, state).poll().await
 }
@@ -0,0 +1,57 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+
+/// Tracks the confirmation progress last reported for each invoice, so a
+/// `Confirming` event is only raised when a new block changed it.
+#[derive(Default)]
+pub(crate) struct ConfirmationProgress {
+    /// invoice id → (confirmations, required) of the last report
+    reported: Mutex<AHashMap<String, (u64, u64)>>,
+}
+
+impl ConfirmationProgress {
+    /// Returns whether `confirmations` out of `required` differs from the last
+    /// report for `key`, and records it if so.
+    pub(crate) fn advanced(&self, key: &str, confirmations: u64, required: u64) -> bool {
+        let mut reported = self.reported.lock().unwrap_or_else(|e| e.into_inner());
+        let progress = (confirmations, required);
+        if reported.get(key) == Some(&progress) {
+            return false;
+        }
+        reported.insert(key.to_string(), progress);
+        true
+    }
+
+    /// Drops the state of an invoice that left the gateway.
+    pub(crate) fn forget(&self, key: &str) {
+        self.reported
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_progress_is_reported_once() {
+        let progress = ConfirmationProgress::default();
+        assert!(progress.advanced("a", 0, 3));
+        assert!(!progress.advanced("a", 0, 3));
+        assert!(progress.advanced("a", 1, 3));
+        assert!(progress.advanced("b", 1, 3));
+        // The treasury transfer counts its own confirmations from zero
+        assert!(progress.advanced("a", 0, 3));
+    }
+
+    #[test]
+    fn forget_resets_state() {
+        let progress = ConfirmationProgress::default();
+        assert!(progress.advanced("a", 2, 3));
+        progress.forget("a");
+        assert!(progress.advanced("a", 2, 3));
+    }
+}
@@ -0,0 +1,65 @@
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+
+use crate::gateway::event::GatewayEvent;
+use crate::invoice::Invoice;
+
+use super::poll::invoice_balance;
+use super::InvoicePoller;
+
+impl InvoicePoller {
+    /// Whether every endpoint of the configured `sweep_quorum` confirms the
+    /// payment of a high-value invoice. Invoices the quorum does not apply to
+    /// always pass. The first endpoint disagreeing or failing to answer is
+    /// reported with a throttled `QuorumDisagreement` event.
+    pub(super) async fn quorum_agrees(&self, key: &str, invoice: &Invoice) -> bool {
+        let Some(quorum) = &self.gateway.config.sweep_quorum else {
+            return true;
+        };
+        if !quorum.applies(invoice) {
+            return true;
+        }
+        let required = invoice.min_confirmations.filter(|required| *required > 0);
+        for (index, rpc_url) in quorum.rpc_urls.iter().enumerate() {
+            let url = match rpc_url.parse() {
+                Ok(url) => url,
+                Err(e) => {
+                    tracing::error!("Invalid quorum RPC URL '{rpc_url}': {e}");
+                    return false;
+                }
+            };
+            let provider = self.gateway.connect(url);
+            // Multi-token invoices are read in the asset they were paid with
+            let balance = invoice_balance(&provider, &mut invoice.clone()).await;
+            let confirmed = match (required, invoice.deposit_block) {
+                (Some(required), Some(deposit_block)) => provider
+                    .get_block_number()
+                    .await
+                    .map(|latest| latest.saturating_sub(deposit_block) >= required),
+                _ => Ok(true),
+            };
+            let agrees = match (balance, confirmed) {
+                (Ok(balance), Ok(confirmed)) => balance >= invoice.amount && confirmed,
+                (Err(e), _) => {
+                    tracing::warn!("Quorum endpoint {rpc_url} failed to read the balance: {e}");
+                    false
+                }
+                (_, Err(e)) => {
+                    tracing::warn!("Quorum endpoint {rpc_url} failed to read the block: {e}");
+                    false
+                }
+            };
+            if !agrees {
+                tracing::warn!("Quorum endpoint {rpc_url} does not confirm the payment");
+                if self.quorum.permit(key, U256::from(index), self.gateway.now_seconds()) {
+                    self.gateway.emit(GatewayEvent::QuorumDisagreement {
+                        invoice_id: key.to_string(),
+                        rpc_url: rpc_url.clone(),
+                    });
+                }
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,71 @@
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+
+use crate::gateway::{audit::AuditEntry, event::GatewayEvent};
+use crate::invoice::{Invoice, InvoiceErrorSource};
+use crate::web3::error::TransferError;
+use crate::web3::transfers::native_transfers::StagedError;
+use crate::web3::transfers::refund;
+
+use super::poll::record_error;
+use super::InvoicePoller;
+
+impl InvoicePoller {
+    /// Sends the `balance` found on an expired invoice back to whoever paid
+    /// it. Returns `false` when the refund failed and is retried on the next
+    /// cycle; invoices that cannot be refunded are left to expire.
+    pub(super) async fn refund_expired(
+        &self,
+        provider: &impl Provider,
+        key: &str,
+        invoice: &mut Invoice,
+        balance: U256,
+    ) -> bool {
+        if invoice.nft.is_some() || invoice.forwarder_salt.is_some() {
+            tracing::warn!("Expired invoice holds funds that cannot be refunded");
+            return true;
+        }
+        if invoice.payer.is_none() {
+            self.record_deposits(provider, invoice, balance).await;
+        }
+        let Some(payer) = invoice.payer else {
+            tracing::warn!("No payer found to refund, funds remain on {}", invoice.to);
+            return true;
+        };
+
+        match refund(&self.gateway, invoice, payer).await {
+            Ok(transfer) => {
+                tracing::info!("Refunded expired invoice to {payer}: {}", transfer.hash);
+                let amount = transfer.settlement.swept_amount;
+                let entry = AuditEntry::Refunded {
+                    payer,
+                    amount,
+                    tx_hash: transfer.hash.clone(),
+                };
+                self.gateway.audit(key, entry).await;
+                self.gateway.emit(GatewayEvent::Refunded {
+                    invoice_id: key.to_string(),
+                    payer,
+                    amount,
+                    tx_hash: transfer.hash,
+                });
+                true
+            }
+            Err(StagedError {
+                error: TransferError::InsufficientBalance,
+                ..
+            }) => {
+                tracing::warn!("Funds on expired invoice do not cover the gas of a refund");
+                true
+            }
+            Err(StagedError { stage, error }) => {
+                tracing::error!("Failed to refund expired invoice at {stage:?} stage: {error}");
+                let now = self.gateway.now_seconds();
+                record_error(invoice, InvoiceErrorSource::Sweep(stage), &error, now);
+                self.audit_error(key, invoice).await;
+                self.store_invoice(key, invoice).await;
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+
+/// Tracks which expiry reminders have been sent for each unpaid invoice.
+///
+/// Reminders are percentages of the invoice expiry window. When several
+/// thresholds were crossed since the last poll, only the latest one is due.
+pub(crate) struct ExpiryReminders {
+    /// Sorted, deduplicated reminder thresholds in percent
+    percents: Vec<u8>,
+    /// invoice id → number of thresholds already handled
+    sent: Mutex<AHashMap<String, usize>>,
+}
+
+impl ExpiryReminders {
+    pub(crate) fn new(percents: &[u8]) -> Self {
+        let mut percents: Vec<u8> = percents.iter().copied().filter(|p| *p < 100).collect();
+        percents.sort_unstable();
+        percents.dedup();
+        Self {
+            percents,
+            sent: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Returns the reminder threshold that became due for `key` at `now`,
+    /// if any, and marks it and all earlier thresholds as sent.
+    pub(crate) fn due(&self, key: &str, created_at: u64, expires: u64, now: u64) -> Option<u8> {
+        let window = expires.saturating_sub(created_at);
+        if self.percents.is_empty() || created_at == 0 || window == 0 {
+            return None;
+        }
+        let elapsed = now.saturating_sub(created_at);
+        let crossed = self
+            .percents
+            .iter()
+            .take_while(|p| elapsed.saturating_mul(100) >= window * u64::from(**p))
+            .count();
+
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let already = sent.get(key).copied().unwrap_or(0);
+        if crossed <= already {
+            return None;
+        }
+        sent.insert(key.to_string(), crossed);
+        Some(self.percents[crossed - 1])
+    }
+
+    /// Drops the state of an invoice that was paid or expired.
+    pub(crate) fn forget(&self, key: &str) {
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminders_fire_once_per_threshold() {
+        let reminders = ExpiryReminders::new(&[90, 50]);
+        assert_eq!(reminders.due("a", 1000, 1100, 1049), None);
+        assert_eq!(reminders.due("a", 1000, 1100, 1050), Some(50));
+        assert_eq!(reminders.due("a", 1000, 1100, 1060), None);
+        assert_eq!(reminders.due("a", 1000, 1100, 1095), Some(90));
+        assert_eq!(reminders.due("a", 1000, 1100, 1099), None);
+    }
+
+    #[test]
+    fn only_latest_crossed_threshold_is_due() {
+        let reminders = ExpiryReminders::new(&[50, 90]);
+        assert_eq!(reminders.due("a", 1000, 1100, 1095), Some(90));
+        assert_eq!(reminders.due("a", 1000, 1100, 1096), None);
+    }
+
+    #[test]
+    fn invoices_without_creation_time_are_skipped() {
+        let reminders = ExpiryReminders::new(&[50]);
+        assert_eq!(reminders.due("a", 0, 1100, 1095), None);
+    }
+
+    #[test]
+    fn forget_resets_state() {
+        let reminders = ExpiryReminders::new(&[50]);
+        assert_eq!(reminders.due("a", 1000, 1100, 1050), Some(50));
+        reminders.forget("a");
+        assert_eq!(reminders.due("a", 1000, 1100, 1050), Some(50));
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use alloy::primitives::U256;
+
+use crate::gateway::SweepPolicy;
+
+/// Applies the configured [`SweepPolicy`] to the paid invoices of a poll cycle.
+pub(crate) struct SweepSchedule {
+    policy: SweepPolicy,
+    /// Schedule window of the latest scheduled sweep
+    last_window: AtomicU64,
+}
+
+impl SweepSchedule {
+    /// Creates the schedule at `now`; a scheduled sweep first runs in the next window.
+    pub(crate) fn new(policy: SweepPolicy, now: u64) -> Self {
+        let schedule = Self {
+            policy,
+            last_window: AtomicU64::new(0),
+        };
+        schedule.last_window.store(schedule.window(now), Ordering::Relaxed);
+        schedule
+    }
+
+    /// Whether paid invoices are swept as soon as they are detected.
+    pub(crate) fn is_immediate(&self) -> bool {
+        self.policy == SweepPolicy::Immediate
+    }
+
+    /// Returns whether paid invoices holding `total` may be swept at `now`,
+    /// and records the scheduled sweep if so.
+    pub(crate) fn permit(&self, total: U256, now: u64) -> bool {
+        match self.policy {
+            SweepPolicy::Immediate => true,
+            SweepPolicy::Scheduled { .. } => {
+                let window = self.window(now);
+                self.last_window.fetch_max(window, Ordering::Relaxed) < window
+            }
+            SweepPolicy::Threshold { min_total } => total >= min_total,
+        }
+    }
+
+    fn window(&self, now: u64) -> u64 {
+        match self.policy {
+            SweepPolicy::Scheduled { every_seconds } => now / every_seconds.max(1),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn immediate_always_permits() {
+        let schedule = SweepSchedule::new(SweepPolicy::Immediate, 100);
+        assert!(schedule.is_immediate());
+        assert!(schedule.permit(U256::ZERO, 100));
+    }
+
+    #[test]
+    fn scheduled_permits_once_per_window() {
+        let schedule = SweepSchedule::new(SweepPolicy::Scheduled { every_seconds: 60 }, 130);
+        assert!(!schedule.permit(U256::ZERO, 179));
+        assert!(schedule.permit(U256::ZERO, 180));
+        assert!(!schedule.permit(U256::ZERO, 239));
+        assert!(schedule.permit(U256::ZERO, 1_000));
+    }
+
+    #[test]
+    fn threshold_requires_min_total() {
+        let policy = SweepPolicy::Threshold {
+            min_total: U256::from(10u64),
+        };
+        let schedule = SweepSchedule::new(policy, 0);
+        assert!(!schedule.permit(U256::from(9u64), 0));
+        assert!(schedule.permit(U256::from(10u64), 0));
+    }
+}
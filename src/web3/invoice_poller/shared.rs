@@ -0,0 +1,134 @@
+use std::sync::Mutex;
+
+use alloy::consensus::Transaction as _;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::TransactionResponse;
+use alloy::providers::Provider;
+use alloy::rpc::types::Block;
+use tracing::Instrument;
+
+use crate::gateway::UniqueAmounts;
+use crate::invoice::{DepositRecord, Invoice, InvoiceStatus};
+
+use super::{invoice_span, InvoicePoller};
+
+/// Most blocks scanned for shared deposit payments in one poll cycle.
+const MAX_BLOCKS_PER_CYCLE: u64 = 100;
+
+/// Progress of the block scan matching payments to the shared deposit
+/// address with invoices.
+#[derive(Default)]
+pub(crate) struct SharedDepositScan {
+    /// Next block to scan; unset until the first scan
+    next_block: Mutex<Option<u64>>,
+}
+
+impl SharedDepositScan {
+    fn next_block(&self) -> Option<u64> {
+        *self.next_block.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn scanned(&self, block: u64) {
+        *self.next_block.lock().unwrap_or_else(|e| e.into_inner()) = Some(block + 1);
+    }
+}
+
+impl InvoicePoller {
+    /// Scans the confirmed blocks since the last scan for transfers to the
+    /// shared deposit address and confirms the invoices whose amount they
+    /// match exactly. Transfers matching no open invoice are ignored.
+    pub(super) async fn scan_shared_deposits(&self, provider: &impl Provider) {
+        let Some(unique) = self.gateway.config.unique_amounts else {
+            return;
+        };
+        let latest = match provider.get_block_number().await {
+            Ok(latest) => latest,
+            Err(e) => {
+                tracing::error!("Failed to fetch block number: {e}");
+                return;
+            }
+        };
+        let Some(confirmed) = latest.checked_sub(self.gateway.config.min_confirmations) else {
+            return;
+        };
+        let first = self
+            .shared
+            .next_block()
+            .unwrap_or_else(|| confirmed.saturating_sub(unique.lookback_blocks));
+        let last = confirmed.min(first.saturating_add(MAX_BLOCKS_PER_CYCLE - 1));
+        for number in first..=last {
+            let block = provider
+                .get_block_by_number(BlockNumberOrTag::Number(number))
+                .full()
+                .await;
+            match block {
+                Ok(Some(block)) => self.match_shared_payments(&unique, &block).await,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!("Failed to fetch block {number}: {e}");
+                    break;
+                }
+            }
+            self.shared.scanned(number);
+        }
+    }
+
+    async fn match_shared_payments(&self, unique: &UniqueAmounts, block: &Block) {
+        for tx in block.transactions.txns() {
+            if tx.to() != Some(unique.address) {
+                continue;
+            }
+            // Invoices confirmed earlier in the scan are no longer stored
+            let keys = self.gateway.invoice_addresses.shared_by_amount(tx.value());
+            let invoices = self.gateway.invoices.read().await;
+            let matched = keys.into_iter().find_map(|key| {
+                let invoice = invoices.get(&key)?;
+                // An earlier payment of a since reused amount is no match, nor is
+                // an invoice that no longer awaits its payment
+                let awaiting = invoice.shared_deposit && invoice.status == InvoiceStatus::Pending;
+                (awaiting && invoice.created_at <= block.header.timestamp)
+                    .then(|| (key, invoice.clone()))
+            });
+            drop(invoices);
+            let Some((key, mut invoice)) = matched else {
+                tracing::info!("Transfer {} matches no open invoice", tx.tx_hash());
+                continue;
+            };
+            let span = invoice_span(&self.gateway, &key, &invoice);
+            span.in_scope(|| tracing::info!("Invoice paid by transfer {}", tx.tx_hash()));
+            invoice.payer = Some(tx.from());
+            invoice.deposits = vec![DepositRecord {
+                tx_hash: tx.tx_hash(),
+                from: tx.from(),
+                amount: tx.value(),
+                block_number: block.header.number,
+            }];
+            invoice.deposit_block_number = Some(block.header.number);
+            invoice.paid_at_timestamp = self.gateway.now_seconds();
+            invoice.transition(InvoiceStatus::Paid, self.gateway.now_millis());
+            self.send_confirmed_invoice(&key, invoice).instrument(span).await;
+        }
+    }
+
+    /// Archives an unpaid shared deposit invoice once it expires. Its payment
+    /// is detected by the block scan, not by checking a balance.
+    pub(super) async fn expire_shared_invoice(&self, key: &str, invoice: &Invoice) {
+        if self.gateway.now_seconds() > invoice.expires {
+            self.forget_invoice(key);
+            self.gateway.expire_invoice(key, invoice.clone()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_resumes_after_last_scanned_block() {
+        let scan = SharedDepositScan::default();
+        assert_eq!(scan.next_block(), None);
+        scan.scanned(41);
+        assert_eq!(scan.next_block(), Some(42));
+    }
+}
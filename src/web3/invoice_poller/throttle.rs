@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+use alloy::primitives::U256;
+
+/// Per-invoice throttle for intermediate notifications.
+///
+/// A notification is permitted when the reported value changed since the last
+/// permitted one and at least `interval_seconds` have passed. Terminal
+/// notifications must bypass the throttle so they are always delivered.
+pub(crate) struct NotificationThrottle {
+    interval_seconds: u64,
+    /// invoice id → (unix time, value) of the last permitted notification
+    last: Mutex<AHashMap<String, (u64, U256)>>,
+}
+
+impl NotificationThrottle {
+    pub(crate) fn new(interval_seconds: u64) -> Self {
+        Self {
+            interval_seconds,
+            last: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Returns whether a notification reporting `value` for `key` may be sent
+    /// at `now`, and records it if so.
+    pub(crate) fn permit(&self, key: &str, value: U256, now: u64) -> bool {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let permitted = match last.get(key) {
+            Some((at, previous)) => {
+                *previous != value && now.saturating_sub(*at) >= self.interval_seconds
+            }
+            None => true,
+        };
+        if permitted {
+            last.insert(key.to_string(), (now, value));
+        }
+        permitted
+    }
+
+    /// Drops the state of an invoice that reached a terminal state.
+    pub(crate) fn forget(&self, key: &str) {
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_notification_is_permitted() {
+        let throttle = NotificationThrottle::new(60);
+        assert!(throttle.permit("a", U256::from(1u64), 100));
+    }
+
+    #[test]
+    fn changes_within_interval_are_suppressed() {
+        let throttle = NotificationThrottle::new(60);
+        assert!(throttle.permit("a", U256::from(1u64), 100));
+        assert!(!throttle.permit("a", U256::from(2u64), 159));
+        assert!(throttle.permit("a", U256::from(2u64), 160));
+    }
+
+    #[test]
+    fn unchanged_value_is_never_repeated() {
+        let throttle = NotificationThrottle::new(0);
+        assert!(throttle.permit("a", U256::from(1u64), 100));
+        assert!(!throttle.permit("a", U256::from(1u64), 1_000));
+    }
+
+    #[test]
+    fn invoices_are_throttled_independently() {
+        let throttle = NotificationThrottle::new(60);
+        assert!(throttle.permit("a", U256::from(1u64), 100));
+        assert!(throttle.permit("b", U256::from(1u64), 100));
+    }
+
+    #[test]
+    fn forget_resets_state() {
+        let throttle = NotificationThrottle::new(60);
+        assert!(throttle.permit("a", U256::from(1u64), 100));
+        throttle.forget("a");
+        assert!(throttle.permit("a", U256::from(2u64), 101));
+    }
+}
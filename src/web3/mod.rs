@@ -1,4 +1,10 @@
+pub(crate) mod chain_id;
+pub(crate) mod deposits;
 pub mod error;
+pub(crate) mod fee_cache;
+pub(crate) mod fee_estimation;
 pub mod invoice_poller;
+pub mod multicall;
+pub(crate) mod rate_limit;
 mod result;
-mod transfers;
+pub mod transfers;
@@ -0,0 +1,116 @@
+use alloy::primitives::{address, Address, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+
+use crate::web3::error::TransferError;
+use crate::web3::result::Result;
+use crate::web3::transfers::token_transfers::IERC20;
+
+sol! {
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls)
+            external
+            payable
+            returns (Result[] memory returnData);
+        function getEthBalance(address addr) external view returns (uint256 balance);
+    }
+}
+
+/// Multicall3, deployed at the same address on most EVM networks.
+pub const MULTICALL3: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+/// Maximum number of balance queries bundled into one `eth_call`.
+const MAX_CALLS_PER_BATCH: usize = 100;
+
+/// Reads the balances of many `(holder, token)` pairs through the Multicall
+/// contract at `multicall`, one `eth_call` per batch of queries. A `None`
+/// token queries the native balance.
+///
+/// Returns one entry per query, `None` where the individual call failed.
+pub async fn balances(
+    provider: &impl Provider,
+    multicall: Address,
+    queries: &[(Address, Option<Address>)],
+) -> Result<Vec<Option<U256>>> {
+    let mut balances = Vec::with_capacity(queries.len());
+    for batch in queries.chunks(MAX_CALLS_PER_BATCH) {
+        let calls = batch
+            .iter()
+            .map(|&(holder, token)| match token {
+                Some(token) => IMulticall3::Call3 {
+                    target: token,
+                    allowFailure: true,
+                    callData: IERC20::balanceOfCall { account: holder }.abi_encode().into(),
+                },
+                None => IMulticall3::Call3 {
+                    target: multicall,
+                    allowFailure: true,
+                    callData: IMulticall3::getEthBalanceCall { addr: holder }.abi_encode().into(),
+                },
+            })
+            .collect();
+        let call = TransactionRequest::default()
+            .to(multicall)
+            .input(IMulticall3::aggregate3Call { calls }.abi_encode().into());
+        let output = provider.call(call).await?;
+        let results = IMulticall3::aggregate3Call::abi_decode_returns(&output)
+            .map_err(|e| TransferError::InvalidTokenResponse(e.to_string()))?;
+        if results.len() != batch.len() {
+            return Err(TransferError::InvalidTokenResponse(format!(
+                "expected {} multicall results, got {}",
+                batch.len(),
+                results.len()
+            )));
+        }
+        // balanceOf and getEthBalance both return a single uint256
+        balances.extend(results.into_iter().map(|result| {
+            result
+                .success
+                .then(|| IMulticall3::getEthBalanceCall::abi_decode_returns(&result.returnData))
+                .and_then(|balance| balance.ok())
+        }));
+    }
+    Ok(balances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_node::MockNode;
+    use alloy::providers::ProviderBuilder;
+
+    #[tokio::test]
+    async fn balances_are_split_into_batches() {
+        let node = MockNode::start().await;
+        let provider = ProviderBuilder::new().connect_http(node.url.parse().unwrap());
+        let token = Address::repeat_byte(0x70);
+        let queries: Vec<_> = (0..150u8)
+            .map(|i| {
+                let holder = Address::with_last_byte(i);
+                node.set_balance(holder, U256::from(i));
+                node.set_token_balance(token, holder, U256::from(i) * U256::from(2));
+                (holder, (i % 2 == 0).then_some(token))
+            })
+            .collect();
+
+        let requests = node.request_count();
+        let balances = balances(&provider, MULTICALL3, &queries).await.unwrap();
+        assert_eq!(node.request_count() - requests, 2);
+        assert_eq!(balances.len(), queries.len());
+        assert_eq!(balances[1], Some(U256::from(1)));
+        assert_eq!(balances[120], Some(U256::from(240)));
+    }
+}
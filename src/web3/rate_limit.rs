@@ -0,0 +1,160 @@
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::{TransportError, TransportFut};
+use tower::{Layer, Service};
+
+use crate::gateway::runtime::Runtime;
+
+/// ## RpcRateLimit
+///
+/// Token bucket shared by every RPC request of the gateway: up to `burst`
+/// requests go out at once, after which requests wait so that no more than
+/// `requests_per_second` are sent on average.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcRateLimit {
+    pub requests_per_second: u32,
+    pub burst: u32,
+}
+
+impl RpcRateLimit {
+    /// Allows `requests_per_second`, bursting up to one second's worth.
+    pub fn per_second(requests_per_second: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst: requests_per_second,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket enforcing an [`RpcRateLimit`].
+pub(crate) struct TokenBucket {
+    limit: RpcRateLimit,
+    bucket: Mutex<Bucket>,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl TokenBucket {
+    /// Starts with a full bucket. The limit must allow at least one request
+    /// per second and a burst of one.
+    pub(crate) fn new(limit: RpcRateLimit, runtime: Arc<dyn Runtime>) -> Self {
+        Self {
+            limit,
+            runtime,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(limit.burst),
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token, or returns how long to wait until one is available.
+    fn try_acquire(&self, now: Instant) -> Option<Duration> {
+        let rate = f64::from(self.limit.requests_per_second);
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(f64::from(self.limit.burst));
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+    }
+
+    /// Waits until the request may be sent.
+    pub(crate) async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire(Instant::now()) {
+            self.runtime.sleep(wait).await;
+        }
+    }
+}
+
+/// Transport layer passing every request through the gateway's
+/// [`TokenBucket`], if one is configured.
+#[derive(Clone)]
+pub(crate) struct RateLimitLayer(pub(crate) Option<Arc<TokenBucket>>);
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            bucket: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RateLimitService<S> {
+    inner: S,
+    bucket: Option<Arc<TokenBucket>>,
+}
+
+impl<S> Service<RequestPacket> for RateLimitService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let bucket = self.bucket.clone();
+        let mut inner = self.inner.clone();
+        Box::pin(async move {
+            if let Some(bucket) = bucket {
+                bucket.acquire().await;
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::runtime::TokioRuntime;
+
+    #[test]
+    fn burst_is_spent_before_requests_wait() {
+        let limit = RpcRateLimit {
+            requests_per_second: 10,
+            burst: 2,
+        };
+        let bucket = TokenBucket::new(limit, Arc::new(TokioRuntime));
+        let now = Instant::now();
+        assert_eq!(bucket.try_acquire(now), None);
+        assert_eq!(bucket.try_acquire(now), None);
+        let wait = bucket.try_acquire(now).unwrap();
+        assert!((wait.as_secs_f64() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tokens_refill_at_the_configured_rate() {
+        let bucket = TokenBucket::new(RpcRateLimit::per_second(4), Arc::new(TokioRuntime));
+        let start = Instant::now();
+        for _ in 0..4 {
+            assert_eq!(bucket.try_acquire(start), None);
+        }
+        assert!(bucket.try_acquire(start).is_some());
+        assert_eq!(bucket.try_acquire(start + Duration::from_millis(250)), None);
+        assert!(bucket.try_acquire(start + Duration::from_millis(250)).is_some());
+    }
+}
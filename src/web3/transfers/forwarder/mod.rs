@@ -0,0 +1,274 @@
+use std::time::Instant;
+
+use alloy::network::{Ethereum, EthereumWallet, NetworkTransactionBuilder, TransactionBuilder};
+use alloy::primitives::{address, Address, Bytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+
+use crate::gateway::PaymentGateway;
+use crate::invoice::{Invoice, Payout, Settlement, SweepStage, SweepTimings};
+use crate::web3::chain_id::verify_chain_id;
+use crate::web3::error::TransferError;
+use crate::web3::transfers::native_transfers::{
+    elapsed_ms, fees_of, next_nonce, replaced_settlement, with_fees, StagedError, TreasuryTransfer,
+};
+
+/// The deterministic deployment proxy, deployed at the same address on most
+/// EVM networks. Its calldata is `salt ++ init_code` and it deploys the init
+/// code with CREATE2.
+pub const DETERMINISTIC_DEPLOYER: Address = address!("4e59b44847b379578588920cA78FbF26c0B4956C");
+
+/// ## ForwarderMode
+///
+/// Replaces per-invoice wallets with counterfactual CREATE2 forwarders.
+///
+/// Each invoice address is the address a forwarder contract would be deployed
+/// to by `factory`. No private key exists for it. Once the invoice is paid,
+/// `deployer` deploys the forwarder, whose constructor moves the received
+/// funds to the treasury; any later deposit to the address is forwarded by
+/// its runtime code. The deployer pays the deployment gas.
+#[derive(Clone, Debug)]
+pub struct ForwarderMode {
+    /// CREATE2 factory taking `salt ++ init_code` as calldata
+    pub factory: Address,
+    /// Account paying for forwarder deployments
+    pub deployer: PrivateKeySigner,
+}
+
+impl ForwarderMode {
+    /// Uses the [`DETERMINISTIC_DEPLOYER`] as factory.
+    pub fn new(deployer: PrivateKeySigner) -> Self {
+        Self {
+            factory: DETERMINISTIC_DEPLOYER,
+            deployer,
+        }
+    }
+}
+
+/// Forwards the contract balance to `treasury`:
+/// `CALL(gas, treasury, selfbalance, 0, 0, 0, 0)`, avoiding `PUSH0` so it
+/// runs on chains without Shanghai.
+fn forward_code(treasury: Address) -> Vec<u8> {
+    let mut code = vec![
+        0x60, 0x00, // PUSH1 0 (retSize)
+        0x60, 0x00, // PUSH1 0 (retOffset)
+        0x60, 0x00, // PUSH1 0 (argsSize)
+        0x60, 0x00, // PUSH1 0 (argsOffset)
+        0x47, // SELFBALANCE (value)
+        0x73, // PUSH20 treasury
+    ];
+    code.extend_from_slice(treasury.as_slice());
+    code.extend_from_slice(&[
+        0x5a, // GAS
+        0xf1, // CALL
+    ]);
+    code
+}
+
+/// Creation code of a forwarder to `treasury`.
+///
+/// The constructor forwards the balance received before deployment and
+/// returns runtime code that forwards every later deposit.
+pub fn forwarder_init_code(treasury: Address) -> Bytes {
+    let forward = forward_code(treasury);
+
+    let mut runtime = forward.clone();
+    runtime.push(0x00); // STOP
+
+    let mut constructor = forward;
+    constructor.push(0x50); // POP call result
+    // Length of the remaining constructor code below
+    let runtime_offset = (constructor.len() + 12) as u8;
+    let runtime_len = runtime.len() as u8;
+    constructor.extend_from_slice(&[
+        0x60, runtime_len, // PUSH1 size
+        0x60, runtime_offset, // PUSH1 offset
+        0x60, 0x00, // PUSH1 destOffset
+        0x39, // CODECOPY
+        0x60, runtime_len, // PUSH1 size
+        0x60, 0x00, // PUSH1 offset
+        0xf3, // RETURN
+    ]);
+
+    constructor.extend_from_slice(&runtime);
+    constructor.into()
+}
+
+/// Address of the forwarder to `treasury` deployed by `factory` with `salt`.
+pub fn forwarder_address(factory: Address, treasury: Address, salt: B256) -> Address {
+    factory.create2_from_code(salt, forwarder_init_code(treasury))
+}
+
+/// Deploys the forwarder of a paid forwarder-mode invoice, which moves its
+/// funds to the treasury.
+///
+/// Mirrors the sweep stages of a native transfer. The deployment is sent by
+/// the configured deployer; when `invoice.nonce` is set this is a replacement
+/// tx that reuses the same deployer nonce with bumped fees.
+pub async fn deploy_forwarder(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+    salt: B256,
+) -> std::result::Result<TreasuryTransfer, StagedError> {
+    let forwarder = gateway
+        .config
+        .forwarder
+        .as_ref()
+        .ok_or(TransferError::MissingForwarder)
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let provider = gateway.connect(
+        gateway
+            .next_rpc_url()
+            .parse()
+            .map_err(StagedError::at(SweepStage::Estimate))?,
+    );
+
+    let started = Instant::now();
+    let (balance, tx) = estimate_deployment(gateway, &provider, forwarder, invoice, salt)
+        .await
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let estimate_ms = elapsed_ms(started);
+    let nonce = tx.nonce.unwrap_or_default();
+    let (max_fee_per_gas, max_priority_fee_per_gas) = fees_of(&tx);
+
+    let started = Instant::now();
+    let wallet = EthereumWallet::from(forwarder.deployer.clone());
+    let envelope = <TransactionRequest as NetworkTransactionBuilder<Ethereum>>::build(tx, &wallet)
+        .await
+        .map_err(|e| TransferError::Signing(e.to_string()))
+        .map_err(StagedError::at(SweepStage::Sign))?;
+    let sign_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let pending = provider
+        .send_tx_envelope(envelope)
+        .await
+        .map_err(StagedError::at(SweepStage::Broadcast))?;
+    let broadcast_ms = elapsed_ms(started);
+
+    tracing::info!(estimate_ms, sign_ms, broadcast_ms, "Forwarder deployment broadcast");
+
+    let hash = format!("{:?}", pending.tx_hash());
+    Ok(TreasuryTransfer {
+        hash: hash.clone(),
+        nonce,
+        settlement: Settlement {
+            invoice_amount: invoice.amount,
+            received_amount: balance,
+            // Gas is paid by the deployer, the full balance is forwarded
+            swept_amount: balance,
+            sponsored_gas: U256::ZERO,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            sweep_gas_used: None,
+            sweep_effective_gas_price: None,
+            net_amount_to_treasury: None,
+            payouts: vec![Payout {
+                to: gateway.config.treasury_address,
+                amount: balance,
+                hash,
+                receipt: None,
+            }],
+            timings: SweepTimings {
+                estimate_ms,
+                sign_ms,
+                broadcast_ms,
+                broadcast_at_ms: gateway.now_millis(),
+                confirm_ms: None,
+            },
+            error: None,
+        },
+    })
+}
+
+/// Estimation stage: reads the forwarder balance and builds the unsigned
+/// deployment. Returns `(balance, tx)`.
+async fn estimate_deployment(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    forwarder: &ForwarderMode,
+    invoice: &Invoice,
+    salt: B256,
+) -> Result<(U256, TransactionRequest), TransferError> {
+    let chain_id = verify_chain_id(gateway, provider).await?;
+
+    let balance = provider.get_balance(invoice.to).await?;
+    if balance.is_zero() {
+        return Err(TransferError::InsufficientBalance);
+    }
+
+    let deployer = forwarder.deployer.address();
+    let nonce = match invoice.nonce {
+        Some(n) => n,
+        None => next_nonce(gateway, provider, deployer).await?,
+    };
+
+    let mut input = salt.to_vec();
+    input.extend_from_slice(&forwarder_init_code(gateway.config.treasury_address));
+    let base = TransactionRequest::default()
+        .from(deployer)
+        .to(forwarder.factory)
+        .input(Bytes::from(input).into());
+    let gas_limit = provider.estimate_gas(base.clone()).await?;
+
+    let (_, tx) = with_fees(
+        gateway,
+        provider,
+        base.gas_limit(gas_limit).nonce(nonce),
+        gas_limit,
+        replaced_settlement(invoice),
+    )
+    .await?;
+    Ok((balance, tx.with_chain_id(chain_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::keccak256;
+
+    const TREASURY: Address = Address::repeat_byte(0x11);
+
+    #[test]
+    fn init_code_copies_runtime_from_its_end() {
+        let code = forwarder_init_code(TREASURY);
+        let runtime_len = code[34] as usize;
+        let runtime_offset = code[36] as usize;
+        assert_eq!(runtime_offset + runtime_len, code.len());
+
+        let runtime = &code[runtime_offset..];
+        assert_eq!(runtime[9], 0x73);
+        assert_eq!(&runtime[10..30], TREASURY.as_slice());
+        assert_eq!(&runtime[30..], &[0x5a, 0xf1, 0x00]);
+    }
+
+    #[test]
+    fn address_follows_create2() {
+        let salt = B256::repeat_byte(0x22);
+        let mut preimage = vec![0xff];
+        preimage.extend_from_slice(DETERMINISTIC_DEPLOYER.as_slice());
+        preimage.extend_from_slice(salt.as_slice());
+        preimage.extend_from_slice(keccak256(forwarder_init_code(TREASURY)).as_slice());
+        let expected = Address::from_slice(&keccak256(&preimage)[12..]);
+
+        assert_eq!(
+            forwarder_address(DETERMINISTIC_DEPLOYER, TREASURY, salt),
+            expected
+        );
+    }
+
+    #[test]
+    fn address_depends_on_treasury_and_salt() {
+        let salt = B256::repeat_byte(0x22);
+        let address = forwarder_address(DETERMINISTIC_DEPLOYER, TREASURY, salt);
+        assert_ne!(
+            address,
+            forwarder_address(DETERMINISTIC_DEPLOYER, Address::repeat_byte(0x12), salt)
+        );
+        assert_ne!(
+            address,
+            forwarder_address(DETERMINISTIC_DEPLOYER, TREASURY, B256::repeat_byte(0x23))
+        );
+    }
+}
@@ -1 +1,49 @@
+pub mod forwarder;
 pub mod native_transfers;
+pub mod nft_transfers;
+pub mod splits;
+pub mod token_transfers;
+
+use alloy::primitives::Address;
+
+use crate::gateway::PaymentGateway;
+use crate::invoice::Invoice;
+
+use self::forwarder::deploy_forwarder;
+use self::native_transfers::{send_native_to_treasury, StagedError, TreasuryTransfer};
+use self::nft_transfers::send_nft_to_treasury;
+use self::token_transfers::send_token_to_treasury;
+
+/// Sweeps a paid invoice with the transfer matching its kind: a forwarder
+/// deployment, an NFT transfer, a token transfer or a native transfer.
+pub(crate) async fn sweep(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+) -> Result<TreasuryTransfer, StagedError> {
+    if let (None, Some(nft)) = (invoice.forwarder_salt, invoice.nft) {
+        return send_nft_to_treasury(gateway, invoice, nft).await;
+    }
+    match (invoice.forwarder_salt, invoice.token) {
+        (Some(salt), _) => deploy_forwarder(gateway, invoice, salt).await,
+        (None, Some(token)) => send_token_to_treasury(gateway, invoice, token).await,
+        (None, None) => send_native_to_treasury(gateway, invoice).await,
+    }
+}
+
+/// Sends the whole balance of an expired invoice back to `payer` with the
+/// transfer matching its asset. Treasury splits are not taken off refunds, and
+/// `max_sweep_fee_bps` does not hold them back.
+pub(crate) async fn refund(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+    payer: Address,
+) -> Result<TreasuryTransfer, StagedError> {
+    let mut gateway = gateway.clone();
+    gateway.config.treasury_splits.clear();
+    gateway.config.max_sweep_fee_bps = None;
+    let invoice = Invoice {
+        treasury: Some(payer),
+        ..invoice.clone()
+    };
+    sweep(&gateway, &invoice).await
+}
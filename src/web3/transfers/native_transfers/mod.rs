@@ -1,20 +1,32 @@
-use alloy::network::EthereumWallet;
-use alloy::primitives::{B256, U256};
-use alloy::providers::{Provider, ProviderBuilder};
+use std::time::Instant;
+
+use alloy::consensus::TxEnvelope;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::Provider;
 use alloy::rpc::types::TransactionRequest;
 use alloy::signers::local::PrivateKeySigner;
 
+use crate::gateway::error::GatewayError;
+use crate::gateway::fees::NetworkFees;
+use crate::gateway::runtime;
+use crate::gateway::signer::{LocalSweepSigner, SweepSigner};
 use crate::gateway::PaymentGateway;
-use crate::invoice::Invoice;
+use crate::invoice::{
+    Invoice, Payout, Settlement, SweepReceipt, SweepStage, SweepTimings, ZeroizedVec,
+};
+use crate::web3::chain_id::verify_chain_id;
 use crate::web3::error::TransferError;
+use crate::web3::fee_cache::network_fees;
 use crate::web3::result::Result;
+use crate::web3::transfers::splits::{recipients, split_payouts};
 
 /// Replacement transactions must pay at least 10% higher fees to be accepted
 /// by the mempool (EIP-1559 / legacy). Expressed as a fraction: 11/10 = 110%.
 const FEE_BUMP_NUMERATOR: u128 = 11;
 const FEE_BUMP_DENOMINATOR: u128 = 10;
 
-fn bump_fee(fee: u128) -> u128 {
+pub(crate) fn bump_fee(fee: u128) -> u128 {
     let bumped = fee
         .saturating_mul(FEE_BUMP_NUMERATOR)
         .saturating_add(FEE_BUMP_DENOMINATOR - 1)
@@ -26,23 +38,177 @@ fn bump_fee(fee: u128) -> u128 {
     }
 }
 
+/// A broadcast treasury transfer.
+pub struct TreasuryTransfer {
+    pub hash: String,
+    pub nonce: u64,
+    pub settlement: Settlement,
+}
+
+/// A sweep failure tagged with the pipeline stage it happened in.
+#[derive(Debug)]
+pub struct StagedError {
+    pub stage: SweepStage,
+    pub error: TransferError,
+}
+
+impl StagedError {
+    /// Returns a closure tagging any error convertible into `TransferError` with `stage`.
+    pub(crate) fn at<E: Into<TransferError>>(stage: SweepStage) -> impl FnOnce(E) -> StagedError {
+        move |error| StagedError {
+            stage,
+            error: error.into(),
+        }
+    }
+}
+
+/// Settlement of the pending transfer that a new sweep of `invoice` replaces.
+pub(crate) fn replaced_settlement(invoice: &Invoice) -> Option<&Settlement> {
+    invoice.nonce.and(invoice.settlement.as_ref())
+}
+
+/// Returns the `(max_fee_per_gas, max_priority_fee_per_gas)` of a tx priced by
+/// [`with_fees`]; the max fee is the gas price for legacy txs.
+pub(crate) fn fees_of(tx: &TransactionRequest) -> (u128, u128) {
+    (
+        tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default(),
+        tx.max_priority_fee_per_gas.unwrap_or_default(),
+    )
+}
+
+/// Milliseconds elapsed since `start`.
+pub(crate) fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
 /// Sends the full native-token balance from a paid invoice's wallet to the
 /// treasury, minus gas costs.
 ///
-/// Returns `(tx_hash, nonce)` immediately after broadcasting — does NOT wait
-/// for on-chain confirmation. When `invoice.nonce` is set this is a
-/// replacement tx that reuses the same nonce with bumped fees.
+/// The sweep runs in explicit stages (estimate, sign, broadcast) that are
+/// timed individually; failures are tagged with the stage they occurred in.
+///
+/// The balance is re-read right before building the tx, so anything that
+/// arrived after detection (e.g. a customer double-send) is swept too.
+///
+/// With `treasury_splits` configured, one transfer per split is sent ahead of
+/// the treasury transfer, using consecutive nonces. The returned hash is the
+/// one of the final treasury transfer, which confirms after all others.
+///
+/// The provider's chain id is checked against the cached one before signing,
+/// and the tx is pinned to that chain id.
+///
+/// Returns immediately after broadcasting — does NOT wait for on-chain
+/// confirmation. When `invoice.nonce` is set this is a replacement tx that
+/// reuses the same nonce with bumped fees.
 pub async fn send_native_to_treasury(
     gateway: &PaymentGateway,
     invoice: &Invoice,
-) -> Result<(String, u64)> {
-    let key_bytes: [u8; 32] = invoice.wallet.inner.as_slice().try_into()?;
-    let signer = PrivateKeySigner::from_bytes(&key_bytes.into())?;
-    let wallet = EthereumWallet::from(signer);
+) -> std::result::Result<TreasuryTransfer, StagedError> {
+    let provider = gateway.connect(
+        gateway
+            .next_rpc_url()
+            .parse()
+            .map_err(StagedError::at(SweepStage::Estimate))?,
+    );
+
+    let started = Instant::now();
+    let plan = estimate_transfer(gateway, &provider, invoice)
+        .await
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let estimate_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let envelopes = sign_all(gateway, invoice, plan.txs.clone())
+        .await
+        .map_err(StagedError::at(SweepStage::Sign))?;
+    let sign_ms = elapsed_ms(started);
 
-    let provider = ProviderBuilder::new()
-        .wallet(wallet)
-        .connect_http(gateway.next_rpc_url().parse()?);
+    let started = Instant::now();
+    let hashes = broadcast_all(&provider, envelopes)
+        .await
+        .map_err(StagedError::at(SweepStage::Broadcast))?;
+    let broadcast_ms = elapsed_ms(started);
+
+    tracing::info!(estimate_ms, sign_ms, broadcast_ms, "Treasury transfer broadcast");
+
+    let timings = SweepTimings {
+        estimate_ms,
+        sign_ms,
+        broadcast_ms,
+        broadcast_at_ms: gateway.now_millis(),
+        confirm_ms: None,
+    };
+    Ok(plan.into_transfer(invoice, U256::ZERO, hashes, timings))
+}
+
+/// Unsigned transfers of a sweep, one per payout.
+pub(crate) struct SweepPlan {
+    /// Balance of the invoice address when the sweep was built
+    pub(crate) balance: U256,
+    /// Maximum gas cost of all transfers together
+    pub(crate) max_gas_cost: U256,
+    /// `(recipient, amount)` of every transfer, in nonce order
+    pub(crate) payouts: Vec<(Address, U256)>,
+    pub(crate) txs: Vec<TransactionRequest>,
+}
+
+impl SweepPlan {
+    /// Amount leaving the invoice wallet towards the recipients.
+    fn swept_amount(&self) -> U256 {
+        self.payouts.iter().map(|(_, amount)| *amount).sum()
+    }
+
+    /// Records the broadcast sweep; `hashes` are in the order of `txs`.
+    pub(crate) fn into_transfer(
+        self,
+        invoice: &Invoice,
+        sponsored_gas: U256,
+        hashes: Vec<String>,
+        timings: SweepTimings,
+    ) -> TreasuryTransfer {
+        let swept_amount = self.swept_amount();
+        let nonce = self.txs[0].nonce.unwrap_or_default();
+        let (max_fee_per_gas, max_priority_fee_per_gas) = fees_of(&self.txs[0]);
+        let payouts = self
+            .payouts
+            .into_iter()
+            .zip(hashes)
+            .map(|((to, amount), hash)| Payout {
+                to,
+                amount,
+                hash,
+                receipt: None,
+            })
+            .collect::<Vec<_>>();
+        TreasuryTransfer {
+            hash: payouts.last().map(|payout| payout.hash.clone()).unwrap_or_default(),
+            nonce,
+            settlement: Settlement {
+                invoice_amount: invoice.amount,
+                received_amount: self.balance,
+                swept_amount,
+                sponsored_gas,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                sweep_gas_used: None,
+                sweep_effective_gas_price: None,
+                net_amount_to_treasury: None,
+                payouts,
+                timings,
+                error: None,
+            },
+        }
+    }
+}
+
+/// Estimation stage: reads the balance, nonce, gas limits and fees and builds
+/// the unsigned transfers.
+async fn estimate_transfer(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    invoice: &Invoice,
+) -> Result<SweepPlan> {
+    let chain_id = verify_chain_id(gateway, provider).await?;
 
     let balance = provider.get_balance(invoice.to).await?;
     if balance.is_zero() {
@@ -51,182 +217,363 @@ pub async fn send_native_to_treasury(
 
     let nonce = match invoice.nonce {
         Some(n) => n,
-        None => provider.get_transaction_count(invoice.to).await?,
+        None => next_nonce(gateway, provider, invoice.to).await?,
     };
 
-    // Estimate gas with a zero-value tx — the actual value is set after we
+    // Estimate gas with zero-value txs — the actual values are set after we
     // know the total gas cost so we can send `balance - gas_cost`.
-    let gas_limit = provider
-        .estimate_gas(
-            TransactionRequest::default()
-                .from(invoice.to)
-                .to(gateway.config.treasury_address)
-                .value(U256::ZERO),
-        )
-        .await?;
-
-    let is_replacement = invoice.nonce.is_some();
-    let treasury = gateway.config.treasury_address;
-
-    let (max_gas_cost, tx) = build_tx(
-        &provider,
-        invoice,
-        treasury,
-        balance,
-        gas_limit,
-        nonce,
-        is_replacement,
-    )
-    .await?;
+    let mut gas_limits = Vec::new();
+    for recipient in recipients(gateway, invoice) {
+        let zero_value = TransactionRequest::default()
+            .from(invoice.to)
+            .to(recipient)
+            .value(U256::ZERO);
+        gas_limits.push(sweep_gas_limit(gateway, provider, invoice, zero_value).await?);
+    }
+
+    let base = TransactionRequest::default().from(invoice.to);
+    let total_gas = gas_limits.iter().sum();
+    let (max_gas_cost, base) =
+        with_fees(gateway, provider, base, total_gas, replaced_settlement(invoice)).await?;
+
+    check_sweep_fee(gateway, invoice, max_gas_cost)?;
 
     // After subtracting gas there must be something left to actually send.
-    if balance.saturating_sub(max_gas_cost).is_zero() {
+    let swept_amount = balance.saturating_sub(max_gas_cost);
+    if swept_amount.is_zero() {
         return Err(TransferError::InsufficientBalance);
     }
 
-    let pending = provider.send_transaction(tx).await?;
-    Ok((format!("{:?}", pending.tx_hash()), nonce))
+    let payouts = split_payouts(gateway, invoice, swept_amount);
+    let txs = payouts
+        .iter()
+        .zip(gas_limits)
+        .zip(nonce..)
+        .map(|(((recipient, amount), gas_limit), nonce)| {
+            base.clone()
+                .to(*recipient)
+                .value(*amount)
+                .gas_limit(gas_limit)
+                .nonce(nonce)
+                .with_chain_id(chain_id)
+        })
+        .collect();
+    Ok(SweepPlan {
+        balance,
+        max_gas_cost,
+        payouts,
+        txs,
+    })
 }
 
-/// Builds the treasury transfer tx, trying EIP-1559 fee estimation first and
-/// falling back to legacy gas pricing if the network doesn't support it.
-///
-/// The transfer value is set to `balance - gas_cost` so the entire wallet is
-/// drained. Replacement txs get a 10% fee bump to satisfy mempool rules.
-async fn build_tx(
+/// Signing stage: signs every transfer of a sweep with the invoice key,
+/// through the configured sweep signer or locally for HD-derived and
+/// default invoices.
+pub(crate) async fn sign_all(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+    txs: Vec<TransactionRequest>,
+) -> Result<Vec<TxEnvelope>> {
+    let (signer, key): (&dyn SweepSigner, ZeroizedVec) = match invoice.derivation_index {
+        Some(_) => {
+            let derived = invoice_signer(gateway, invoice)?;
+            let key = ZeroizedVec {
+                inner: derived.credential().to_bytes().to_vec(),
+            };
+            (&LocalSweepSigner, key)
+        }
+        None => (
+            gateway.config.sweep_signer.as_deref().unwrap_or(&LocalSweepSigner),
+            gateway.invoice_wallet(invoice)?,
+        ),
+    };
+    let mut envelopes = Vec::with_capacity(txs.len());
+    for tx in txs {
+        let envelope = signer.sign(&key, tx).await.map_err(|e| match e {
+            GatewayError::Signing(reason) => TransferError::Signing(reason),
+            e => TransferError::WalletDerivation(e),
+        })?;
+        envelopes.push(envelope);
+    }
+    Ok(envelopes)
+}
+
+/// Broadcast stage: submits signed transfers in order and returns their hashes.
+pub(crate) async fn broadcast_all(
+    provider: &impl Provider,
+    envelopes: Vec<TxEnvelope>,
+) -> Result<Vec<String>> {
+    let mut hashes = Vec::with_capacity(envelopes.len());
+    for envelope in envelopes {
+        let pending = provider.send_tx_envelope(envelope).await?;
+        hashes.push(format!("{:?}", pending.tx_hash()));
+    }
+    Ok(hashes)
+}
+
+/// Restores the invoice wallet, deriving it from the configured HD wallet
+/// when the invoice only stores a derivation index. Encrypted wallets are
+/// decrypted only for the lifetime of this call.
+pub(crate) fn invoice_signer(gateway: &PaymentGateway, invoice: &Invoice) -> Result<PrivateKeySigner> {
+    match invoice.derivation_index {
+        Some(index) => {
+            let hd_wallet = gateway
+                .config
+                .hd_wallet
+                .as_ref()
+                .ok_or(TransferError::MissingHdWallet)?;
+            Ok(hd_wallet.derive(index)?)
+        }
+        None => {
+            let wallet = gateway.invoice_wallet(invoice)?;
+            let key_bytes: [u8; 32] = wallet.inner.as_slice().try_into()?;
+            Ok(PrivateKeySigner::from_bytes(&key_bytes.into())?)
+        }
+    }
+}
+
+/// Picks the nonce of the next transaction from `address` using the pending
+/// transaction count and the configured nonce manager.
+pub(crate) async fn next_nonce(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    address: Address,
+) -> Result<u64> {
+    let pending_count = provider.get_transaction_count(address).pending().await?;
+    Ok(match &gateway.config.nonce_manager {
+        Some(manager) => manager.next_nonce(address, pending_count),
+        None => pending_count,
+    })
+}
+
+/// Gas limit of a sweep transfer: the estimate for `tx`, raised to the
+/// invoice's `gas_limit` or else the gateway's `sweep_gas_limit`. When
+/// estimation fails the override is used as is, if there is one.
+pub(crate) async fn sweep_gas_limit(
+    gateway: &PaymentGateway,
     provider: &impl Provider,
     invoice: &Invoice,
-    treasury: alloy::primitives::Address,
-    balance: U256,
+    tx: TransactionRequest,
+) -> Result<u64> {
+    let gas_limit = invoice.gas_limit.or(gateway.config.sweep_gas_limit);
+    match (provider.estimate_gas(tx).await, gas_limit) {
+        (Ok(estimate), gas_limit) => Ok(estimate.max(gas_limit.unwrap_or(0))),
+        (Err(e), Some(gas_limit)) => {
+            tracing::warn!("Gas estimation failed, using gas limit {gas_limit}: {e}");
+            Ok(gas_limit)
+        }
+        (Err(e), None) => Err(e.into()),
+    }
+}
+
+/// Sets the fees of `tx`, trying EIP-1559 fee estimation first and falling
+/// back to legacy gas pricing if the network doesn't support it. Fee data is
+/// reused for `fee_cache_seconds`.
+///
+/// Returns the maximum gas cost alongside the tx. When `replacing` a pending
+/// transfer, fees are bumped by 10% over the higher of the current estimate
+/// and the fees of the replaced tx, so repeated replacements keep escalating
+/// and satisfy mempool rules.
+///
+/// Fails with [`TransferError::GasPriceAboveCeiling`] when the fee per gas
+/// exceeds the configured `max_gas_price`.
+pub(crate) async fn with_fees(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    tx: TransactionRequest,
     gas_limit: u64,
-    nonce: u64,
-    is_replacement: bool,
+    replacing: Option<&Settlement>,
 ) -> Result<(U256, TransactionRequest)> {
-    let base = TransactionRequest::default()
-        .from(invoice.to)
-        .to(treasury)
-        .gas_limit(gas_limit)
-        .nonce(nonce);
-
-    match provider.estimate_eip1559_fees().await {
-        Ok(eip1559) => {
-            let max_fee = if is_replacement {
-                bump_fee(eip1559.max_fee_per_gas)
-            } else {
-                eip1559.max_fee_per_gas
-            };
-            let priority = if is_replacement {
-                bump_fee(eip1559.max_priority_fee_per_gas)
-            } else {
-                eip1559.max_priority_fee_per_gas
+    let replace = |estimate: u128, previous: u128| bump_fee(estimate.max(previous));
+
+    match network_fees(gateway, provider).await? {
+        NetworkFees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        } => {
+            let (max_fee, priority) = match replacing {
+                Some(previous) => (
+                    replace(max_fee_per_gas, previous.max_fee_per_gas),
+                    replace(max_priority_fee_per_gas, previous.max_priority_fee_per_gas),
+                ),
+                None => (max_fee_per_gas, max_priority_fee_per_gas),
             };
+            check_gas_ceiling(gateway, max_fee)?;
             let cost = U256::from(gas_limit) * U256::from(max_fee);
 
             Ok((
                 cost,
-                base.value(balance.saturating_sub(cost))
-                    .max_fee_per_gas(max_fee)
+                tx.max_fee_per_gas(max_fee)
                     .max_priority_fee_per_gas(priority),
             ))
         }
-        Err(e) => {
-            tracing::warn!("EIP-1559 estimation failed, falling back to legacy: {e}");
-
-            let gas_price = if is_replacement {
-                bump_fee(provider.get_gas_price().await?)
-            } else {
-                provider.get_gas_price().await?
+        NetworkFees::Legacy { gas_price } => {
+            let gas_price = match replacing {
+                Some(previous) => replace(gas_price, previous.max_fee_per_gas),
+                None => gas_price,
             };
+            check_gas_ceiling(gateway, gas_price)?;
             let cost = U256::from(gas_limit) * U256::from(gas_price);
 
-            Ok((
-                cost,
-                base.value(balance.saturating_sub(cost))
-                    .gas_price(gas_price),
-            ))
+            Ok((cost, tx.gas_price(gas_price)))
         }
     }
 }
 
-/// Checks whether a previously broadcast treasury transfer has been confirmed
-/// with sufficient block depth (`min_confirmations` from config).
+fn check_gas_ceiling(gateway: &PaymentGateway, fee_per_gas: u128) -> Result<()> {
+    match gateway.config.max_gas_price {
+        Some(ceiling) if fee_per_gas > ceiling => Err(TransferError::GasPriceAboveCeiling {
+            price: fee_per_gas,
+            ceiling,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Fails with [`TransferError::SweepFeeAboveLimit`] when `fee` exceeds the
+/// configured `max_sweep_fee_bps` of the invoice amount.
+fn check_sweep_fee(gateway: &PaymentGateway, invoice: &Invoice, fee: U256) -> Result<()> {
+    let Some(bps) = gateway.config.max_sweep_fee_bps else {
+        return Ok(());
+    };
+    let limit = invoice.amount * U256::from(bps) / U256::from(10_000u64);
+    if fee > limit {
+        return Err(TransferError::SweepFeeAboveLimit {
+            fee,
+            amount: invoice.amount,
+        });
+    }
+    Ok(())
+}
+
+/// How deep a broadcast treasury transfer is, see [`confirm_treasury_transfer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferDepth {
+    /// Not mined yet, or its depth could not be determined this time
+    Unknown,
+    /// Mined and this many blocks deep, short of the required confirmations
+    Confirming(u64),
+    /// Deep enough and still present after re-checking for reorgs
+    Confirmed,
+}
+
+/// Checks how deep a previously broadcast treasury transfer is, and whether
+/// it has been confirmed with at least `min_confirmations` blocks of depth.
 ///
 /// All RPC calls are wrapped in a timeout to prevent hanging on unresponsive
-/// nodes. Returns `Ok(false)` on any timeout or transient error so the poller
-/// retries on the next cycle.
+/// nodes. Returns `Ok(TransferDepth::Unknown)` on any timeout or transient
+/// error so the poller retries on the next cycle.
 ///
 /// After reaching the required depth the receipt is re-fetched to guard
 /// against block reorgs that could silently drop the transaction.
 pub async fn confirm_treasury_transfer(
     gateway: &PaymentGateway,
     tx_hash_str: &str,
-) -> Result<bool> {
+    min_confirmations: u64,
+) -> Result<TransferDepth> {
     let hash: B256 = tx_hash_str.parse().map_err(|e| {
         tracing::error!("Invalid transaction hash '{tx_hash_str}': {e}");
         TransferError::InvalidTxHash
     })?;
 
-    let provider = ProviderBuilder::new().connect_http(gateway.next_rpc_url().parse()?);
+    let rpc_url = gateway.next_rpc_url();
+    let provider = gateway.connect(rpc_url.parse()?);
     let timeout = std::time::Duration::from_secs(gateway.config.receipt_timeout_seconds);
 
     // Step 1: fetch the receipt
-    let receipt = match timed(&timeout, provider.get_transaction_receipt(hash)).await {
-        Some(Ok(Some(r))) => r,
-        Some(Ok(None)) => return Ok(false),
+    let receipt = match timed(gateway, &timeout, provider.get_transaction_receipt(hash)).await {
+        Some(Ok(receipt)) => {
+            gateway.report_rpc_success(rpc_url);
+            match receipt {
+                Some(r) => r,
+                None => return Ok(TransferDepth::Unknown),
+            }
+        }
         Some(Err(e)) => {
             tracing::error!("Error fetching receipt for {tx_hash_str}: {e}");
-            return Ok(false);
+            gateway.report_rpc_failure(rpc_url);
+            return Ok(TransferDepth::Unknown);
         }
         None => {
             tracing::warn!("Receipt check timed out for {tx_hash_str}");
-            return Ok(false);
+            gateway.report_rpc_failure(rpc_url);
+            return Ok(TransferDepth::Unknown);
         }
     };
 
     // Step 2: check confirmation depth
     let tx_block = match receipt.block_number {
         Some(block) => block,
-        None => return Ok(false),
+        None => return Ok(TransferDepth::Unknown),
     };
 
-    let latest_block = match timed(&timeout, provider.get_block_number()).await {
+    let latest_block = match timed(gateway, &timeout, provider.get_block_number()).await {
         Some(Ok(block)) => block,
         Some(Err(e)) => {
             tracing::error!("Error fetching latest block number: {e}");
-            return Ok(false);
+            return Ok(TransferDepth::Unknown);
         }
         None => {
             tracing::warn!("Block number fetch timed out");
-            return Ok(false);
+            return Ok(TransferDepth::Unknown);
         }
     };
 
-    if latest_block.saturating_sub(tx_block) < gateway.config.min_confirmations {
-        return Ok(false);
+    let depth = latest_block.saturating_sub(tx_block);
+    if depth < min_confirmations {
+        return Ok(TransferDepth::Confirming(depth));
     }
 
     // Step 3: re-fetch receipt to ensure it survived potential reorgs
-    match timed(&timeout, provider.get_transaction_receipt(hash)).await {
-        Some(Ok(Some(_))) => Ok(true),
+    match timed(gateway, &timeout, provider.get_transaction_receipt(hash)).await {
+        Some(Ok(Some(_))) => Ok(TransferDepth::Confirmed),
         Some(Ok(None)) => {
             tracing::warn!("Receipt for {tx_hash_str} disappeared after reorg");
-            Ok(false)
+            Ok(TransferDepth::Unknown)
         }
         Some(Err(e)) => {
             tracing::error!("Error re-fetching receipt for {tx_hash_str}: {e}");
-            Ok(false)
+            Ok(TransferDepth::Unknown)
         }
         None => {
             tracing::warn!("Receipt re-fetch timed out for {tx_hash_str}");
-            Ok(false)
+            Ok(TransferDepth::Unknown)
         }
     }
 }
 
+/// Reads the receipt of every transfer of a confirmed sweep, in the order of
+/// `payouts`. Returns `None` while any receipt is unavailable.
+pub async fn sweep_receipts(
+    gateway: &PaymentGateway,
+    payouts: &[Payout],
+) -> Result<Option<Vec<SweepReceipt>>> {
+    let provider = gateway.connect(gateway.next_rpc_url().parse()?);
+    let mut receipts = Vec::with_capacity(payouts.len());
+    for payout in payouts {
+        let hash: B256 = payout.hash.parse().map_err(|_| TransferError::InvalidTxHash)?;
+        let Some(receipt) = provider.get_transaction_receipt(hash).await? else {
+            return Ok(None);
+        };
+        receipts.push(SweepReceipt {
+            hash: receipt.transaction_hash,
+            block_number: receipt.block_number.unwrap_or_default(),
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+            status: receipt.status(),
+        });
+    }
+    Ok(Some(receipts))
+}
+
 /// Wraps a future in a timeout, returning `None` on expiry instead of a
 /// nested `Result<Result<T>, Elapsed>`.
-async fn timed<F: std::future::Future>(timeout: &std::time::Duration, fut: F) -> Option<F::Output> {
-    tokio::time::timeout(*timeout, fut).await.ok()
+async fn timed<F: std::future::Future>(
+    gateway: &PaymentGateway,
+    timeout: &std::time::Duration,
+    fut: F,
+) -> Option<F::Output> {
+    runtime::timeout(gateway.config.runtime.as_ref(), *timeout, fut).await
 }
 
 #[cfg(test)]
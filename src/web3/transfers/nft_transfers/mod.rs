@@ -0,0 +1,208 @@
+use std::time::Instant;
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+
+use crate::gateway::PaymentGateway;
+use crate::invoice::{Invoice, NftPayment, NftStandard, SweepStage, SweepTimings};
+use crate::web3::chain_id::verify_chain_id;
+use crate::web3::result::Result;
+use crate::web3::transfers::native_transfers::{
+    broadcast_all, elapsed_ms, next_nonce, replaced_settlement, sign_all,
+    sweep_gas_limit, with_fees, StagedError, SweepPlan, TreasuryTransfer,
+};
+use crate::web3::transfers::splits::treasury_of;
+use crate::web3::transfers::token_transfers::sponsor_gas;
+use crate::web3::error::TransferError;
+
+sol! {
+    interface IERC721 {
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function safeTransferFrom(address from, address to, uint256 tokenId) external;
+    }
+
+    interface IERC1155 {
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+        function safeTransferFrom(
+            address from,
+            address to,
+            uint256 id,
+            uint256 amount,
+            bytes data
+        ) external;
+    }
+}
+
+/// Reads how many of the tokens described by `nft` `holder` owns; zero or one
+/// for ERC-721.
+pub async fn nft_balance(
+    provider: &impl Provider,
+    nft: &NftPayment,
+    holder: Address,
+) -> Result<U256> {
+    let input = match nft.standard {
+        NftStandard::Erc721 => IERC721::ownerOfCall {
+            tokenId: nft.token_id,
+        }
+        .abi_encode(),
+        NftStandard::Erc1155 { .. } => IERC1155::balanceOfCall {
+            account: holder,
+            id: nft.token_id,
+        }
+        .abi_encode(),
+    };
+    let call = TransactionRequest::default()
+        .to(nft.contract)
+        .input(input.into());
+    let output = provider.call(call).await?;
+    let invalid = |e: alloy::sol_types::Error| TransferError::InvalidTokenResponse(e.to_string());
+    match nft.standard {
+        NftStandard::Erc721 => {
+            let owner = IERC721::ownerOfCall::abi_decode_returns(&output).map_err(invalid)?;
+            Ok(U256::from(u8::from(owner == holder)))
+        }
+        NftStandard::Erc1155 { .. } => {
+            IERC1155::balanceOfCall::abi_decode_returns(&output).map_err(invalid)
+        }
+    }
+}
+
+/// Sends the NFT of a paid NFT invoice to the treasury with `safeTransferFrom`.
+///
+/// Runs the same stages as a token sweep, with the gas sponsor topping up
+/// the invoice wallet when needed. Treasury splits do not apply: the whole
+/// quantity goes to the treasury address.
+pub async fn send_nft_to_treasury(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+    nft: NftPayment,
+) -> std::result::Result<TreasuryTransfer, StagedError> {
+    let provider = gateway.connect(
+        gateway
+            .next_rpc_url()
+            .parse()
+            .map_err(StagedError::at(SweepStage::Estimate))?,
+    );
+
+    let started = Instant::now();
+    let plan = estimate_nft_transfer(gateway, &provider, invoice, &nft)
+        .await
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let top_up = sponsor_gas(gateway, &provider, invoice.to, plan.max_gas_cost)
+        .await
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let estimate_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let envelopes = sign_all(gateway, invoice, plan.txs.clone())
+        .await
+        .map_err(StagedError::at(SweepStage::Sign))?;
+    let sign_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let hashes = broadcast_all(&provider, envelopes)
+        .await
+        .map_err(StagedError::at(SweepStage::Broadcast))?;
+    let broadcast_ms = elapsed_ms(started);
+
+    tracing::info!(estimate_ms, sign_ms, broadcast_ms, "NFT treasury transfer broadcast");
+
+    let sponsored_before = invoice
+        .settlement
+        .as_ref()
+        .map_or(U256::ZERO, |settlement| settlement.sponsored_gas);
+    let timings = SweepTimings {
+        estimate_ms,
+        sign_ms,
+        broadcast_ms,
+        broadcast_at_ms: gateway.now_millis(),
+        confirm_ms: None,
+    };
+    Ok(plan.into_transfer(invoice, sponsored_before + top_up, hashes, timings))
+}
+
+/// Estimation stage: reads the NFT balance, nonce, gas limit and fees and
+/// builds the unsigned `safeTransferFrom`.
+async fn estimate_nft_transfer(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    invoice: &Invoice,
+    nft: &NftPayment,
+) -> Result<SweepPlan> {
+    let chain_id = verify_chain_id(gateway, provider).await?;
+
+    let balance = nft_balance(provider, nft, invoice.to).await?;
+    if balance.is_zero() {
+        return Err(TransferError::InsufficientBalance);
+    }
+
+    let nonce = match invoice.nonce {
+        Some(n) => n,
+        None => next_nonce(gateway, provider, invoice.to).await?,
+    };
+
+    let treasury = treasury_of(gateway, invoice);
+    let input = match nft.standard {
+        NftStandard::Erc721 => IERC721::safeTransferFromCall {
+            from: invoice.to,
+            to: treasury,
+            tokenId: nft.token_id,
+        }
+        .abi_encode(),
+        NftStandard::Erc1155 { .. } => IERC1155::safeTransferFromCall {
+            from: invoice.to,
+            to: treasury,
+            id: nft.token_id,
+            amount: balance,
+            data: Bytes::new(),
+        }
+        .abi_encode(),
+    };
+    let call = TransactionRequest::default()
+        .from(invoice.to)
+        .to(nft.contract)
+        .input(input.into());
+    let gas_limit = sweep_gas_limit(gateway, provider, invoice, call.clone()).await?;
+    let (max_gas_cost, tx) = with_fees(
+        gateway,
+        provider,
+        call.gas_limit(gas_limit),
+        gas_limit,
+        replaced_settlement(invoice),
+    )
+    .await?;
+
+    Ok(SweepPlan {
+        balance,
+        max_gas_cost,
+        payouts: vec![(treasury, balance)],
+        txs: vec![tx.nonce(nonce).with_chain_id(chain_id)],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_calls_use_safe_transfer_selectors() {
+        let erc721 = IERC721::safeTransferFromCall {
+            from: Address::ZERO,
+            to: Address::ZERO,
+            tokenId: U256::ZERO,
+        };
+        assert_eq!(&erc721.abi_encode()[..4], &[0x42, 0x84, 0x2e, 0x0e]);
+        let erc1155 = IERC1155::safeTransferFromCall {
+            from: Address::ZERO,
+            to: Address::ZERO,
+            id: U256::ZERO,
+            amount: U256::ZERO,
+            data: Bytes::new(),
+        };
+        assert_eq!(&erc1155.abi_encode()[..4], &[0xf2, 0x42, 0x43, 0x2a]);
+    }
+}
@@ -0,0 +1,92 @@
+use alloy::primitives::{Address, U256};
+
+use crate::gateway::PaymentGateway;
+use crate::invoice::Invoice;
+
+/// Basis points making up the whole swept amount.
+pub const BASIS_POINTS: u16 = 10_000;
+
+/// Splits a swept `amount` into payouts: one per configured treasury split,
+/// followed by the treasury of `invoice` receiving the remainder.
+pub(crate) fn split_payouts(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+    amount: U256,
+) -> Vec<(Address, U256)> {
+    let mut remainder = amount;
+    let mut payouts: Vec<(Address, U256)> = gateway
+        .config
+        .treasury_splits
+        .iter()
+        .map(|&(address, basis_points)| {
+            let share = amount * U256::from(basis_points) / U256::from(BASIS_POINTS);
+            remainder -= share;
+            (address, share)
+        })
+        .collect();
+    payouts.push((treasury_of(gateway, invoice), remainder));
+    payouts
+}
+
+/// Recipients of a sweep, in payout order.
+pub(crate) fn recipients(gateway: &PaymentGateway, invoice: &Invoice) -> Vec<Address> {
+    let splits = gateway.config.treasury_splits.iter().map(|&(address, _)| address);
+    splits.chain([treasury_of(gateway, invoice)]).collect()
+}
+
+/// Treasury `invoice` is swept to: its own, or the gateway's.
+pub(crate) fn treasury_of(gateway: &PaymentGateway, invoice: &Invoice) -> Address {
+    invoice.treasury.unwrap_or(gateway.config.treasury_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::PaymentGatewayConfiguration;
+
+    const TREASURY: Address = Address::repeat_byte(0x01);
+    const PLATFORM: Address = Address::repeat_byte(0x02);
+
+    fn gateway(treasury_splits: Vec<(Address, u16)>) -> PaymentGateway {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        PaymentGateway::new(PaymentGatewayConfiguration {
+            treasury_splits,
+            ..PaymentGatewayConfiguration::new(vec!["http://localhost".into()], TREASURY, tx)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn without_splits_treasury_gets_everything() {
+        let payouts = split_payouts(&gateway(vec![]), &Invoice::default(), U256::from(1_000u64));
+        assert_eq!(payouts, vec![(TREASURY, U256::from(1_000u64))]);
+    }
+
+    #[test]
+    fn treasury_gets_remainder_after_splits() {
+        let payouts = split_payouts(
+            &gateway(vec![(PLATFORM, 500)]),
+            &Invoice::default(),
+            U256::from(1_001u64),
+        );
+        assert_eq!(
+            payouts,
+            vec![(PLATFORM, U256::from(50u64)), (TREASURY, U256::from(951u64))]
+        );
+    }
+
+    #[test]
+    fn invoice_treasury_replaces_gateway_treasury() {
+        let invoice = Invoice {
+            treasury: Some(PLATFORM),
+            ..Default::default()
+        };
+        assert_eq!(recipients(&gateway(vec![]), &invoice), vec![PLATFORM]);
+    }
+
+    #[test]
+    fn recipients_follow_payout_order() {
+        let recipients = recipients(&gateway(vec![(PLATFORM, 500)]), &Invoice::default());
+        assert_eq!(recipients, vec![PLATFORM, TREASURY]);
+    }
+}
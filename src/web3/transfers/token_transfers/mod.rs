@@ -0,0 +1,389 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use alloy::eips::Encodable2718;
+use alloy::network::{Ethereum, NetworkTransactionBuilder, NetworkWallet, TransactionBuilder};
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::sponsor::{FundingOutcome, SponsorFunding, SponsorState};
+use crate::gateway::PaymentGateway;
+use crate::invoice::{Invoice, SweepStage, SweepTimings};
+use crate::web3::chain_id::verify_chain_id;
+use crate::web3::error::TransferError;
+use crate::web3::result::Result;
+use crate::web3::transfers::native_transfers::{
+    broadcast_all, elapsed_ms, next_nonce, replaced_settlement, sign_all,
+    sweep_gas_limit, with_fees, StagedError, SweepPlan, TreasuryTransfer,
+};
+use crate::web3::transfers::splits::split_payouts;
+
+sol! {
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function decimals() external view returns (uint8);
+        function transfer(address to, uint256 amount) external returns (bool);
+
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+}
+
+/// How often the top-up receipt is polled while waiting for it.
+const TOP_UP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reads the ERC20 `token` balance of `holder`.
+pub async fn token_balance(
+    provider: &impl Provider,
+    token: Address,
+    holder: Address,
+) -> Result<U256> {
+    let call = TransactionRequest::default()
+        .to(token)
+        .input(IERC20::balanceOfCall { account: holder }.abi_encode().into());
+    let output = provider.call(call).await?;
+    IERC20::balanceOfCall::abi_decode_returns(&output)
+        .map_err(|e| TransferError::InvalidTokenResponse(e.to_string()))
+}
+
+/// Reads the number of decimals of the ERC20 `token`.
+pub async fn token_decimals(provider: &impl Provider, token: Address) -> Result<u8> {
+    let call = TransactionRequest::default()
+        .to(token)
+        .input(IERC20::decimalsCall {}.abi_encode().into());
+    let output = provider.call(call).await?;
+    IERC20::decimalsCall::abi_decode_returns(&output)
+        .map_err(|e| TransferError::InvalidTokenResponse(e.to_string()))
+}
+
+/// Sends the full `token` balance of a paid token invoice to the treasury.
+///
+/// Runs the same stages as a native sweep, including one transfer per
+/// configured treasury split. The invoice wallet pays the gas in native
+/// currency; when it holds less than the estimated cost, the configured gas
+/// sponsor first sends exactly the missing amount, which is recorded in the
+/// settlement's `sponsored_gas`.
+///
+/// When `invoice.nonce` is set this is a replacement tx that reuses the same
+/// nonce with bumped fees.
+pub async fn send_token_to_treasury(
+    gateway: &PaymentGateway,
+    invoice: &Invoice,
+    token: Address,
+) -> std::result::Result<TreasuryTransfer, StagedError> {
+    let provider = gateway.connect(
+        gateway
+            .next_rpc_url()
+            .parse()
+            .map_err(StagedError::at(SweepStage::Estimate))?,
+    );
+
+    let started = Instant::now();
+    let plan = estimate_token_transfer(gateway, &provider, invoice, token)
+        .await
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let top_up = sponsor_gas(gateway, &provider, invoice.to, plan.max_gas_cost)
+        .await
+        .map_err(StagedError::at(SweepStage::Estimate))?;
+    let estimate_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let envelopes = sign_all(gateway, invoice, plan.txs.clone())
+        .await
+        .map_err(StagedError::at(SweepStage::Sign))?;
+    let sign_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let hashes = broadcast_all(&provider, envelopes)
+        .await
+        .map_err(StagedError::at(SweepStage::Broadcast))?;
+    let broadcast_ms = elapsed_ms(started);
+
+    tracing::info!(estimate_ms, sign_ms, broadcast_ms, "Token treasury transfer broadcast");
+
+    // Earlier top-ups of replaced transfers are still accounted for
+    let sponsored_before = invoice
+        .settlement
+        .as_ref()
+        .map_or(U256::ZERO, |settlement| settlement.sponsored_gas);
+    let timings = SweepTimings {
+        estimate_ms,
+        sign_ms,
+        broadcast_ms,
+        broadcast_at_ms: gateway.now_millis(),
+        confirm_ms: None,
+    };
+    Ok(plan.into_transfer(invoice, sponsored_before + top_up, hashes, timings))
+}
+
+/// Estimation stage: reads the token balance, nonce, gas limits and fees and
+/// builds the unsigned token transfers.
+async fn estimate_token_transfer(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    invoice: &Invoice,
+    token: Address,
+) -> Result<SweepPlan> {
+    let chain_id = verify_chain_id(gateway, provider).await?;
+
+    let balance = token_balance(provider, token, invoice.to).await?;
+    if balance.is_zero() {
+        return Err(TransferError::InsufficientBalance);
+    }
+
+    let nonce = match invoice.nonce {
+        Some(n) => n,
+        None => next_nonce(gateway, provider, invoice.to).await?,
+    };
+
+    // Gas is paid in native currency, so the whole token balance is split
+    let payouts = split_payouts(gateway, invoice, balance);
+    let mut calls = Vec::with_capacity(payouts.len());
+    for &(recipient, amount) in &payouts {
+        let transfer = IERC20::transferCall {
+            to: recipient,
+            amount,
+        };
+        let call = TransactionRequest::default()
+            .from(invoice.to)
+            .to(token)
+            .input(transfer.abi_encode().into());
+        let gas_limit = sweep_gas_limit(gateway, provider, invoice, call.clone()).await?;
+        calls.push(call.gas_limit(gas_limit));
+    }
+
+    let base = TransactionRequest::default().from(invoice.to);
+    let total_gas = calls.iter().filter_map(|call| call.gas).sum();
+    let (max_gas_cost, base) =
+        with_fees(gateway, provider, base, total_gas, replaced_settlement(invoice)).await?;
+
+    let txs = calls
+        .into_iter()
+        .zip(nonce..)
+        .map(|(call, nonce)| {
+            TransactionRequest {
+                to: call.to,
+                input: call.input,
+                gas: call.gas,
+                ..base.clone()
+            }
+            .nonce(nonce)
+            .with_chain_id(chain_id)
+        })
+        .collect();
+    Ok(SweepPlan {
+        balance,
+        max_gas_cost,
+        payouts,
+        txs,
+    })
+}
+
+/// Tops up `wallet` from the gas sponsor so it holds at least `gas_cost` in
+/// native currency, and waits for the top-up to be mined. Top-ups are
+/// broadcast one at a time, whichever nonce manager is configured, and only
+/// after the provider's chain id was verified; a reverted top-up fails the
+/// sweep.
+///
+/// Every top-up is recorded in the `sponsor_store` with its nonce before it
+/// is broadcast and settled once its receipt arrives. A wallet with a pending
+/// top-up, e.g. sent before a restart or by a replica, is not topped up again
+/// and that top-up is awaited instead.
+///
+/// Returns the amount sent, which is zero when the wallet already holds enough.
+pub(crate) async fn sponsor_gas(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    wallet: Address,
+    gas_cost: U256,
+) -> Result<U256> {
+    let state = gateway.sponsor_store.load().await.map_err(sponsor_store_error)?;
+    if let Some(funding) = state.pending_for(wallet) {
+        return await_top_up(gateway, provider, funding.clone()).await;
+    }
+    let native = provider.get_balance(wallet).await?;
+    if native >= gas_cost {
+        return Ok(U256::ZERO);
+    }
+    let sponsor = gateway
+        .config
+        .gas_sponsor
+        .as_ref()
+        .ok_or(TransferError::InsufficientGas)?;
+    let missing = gas_cost - native;
+    let chain_id = verify_chain_id(gateway, provider).await?;
+    let sponsor_address = NetworkWallet::<Ethereum>::default_signer_address(sponsor);
+
+    // Concurrent sweeps of a batch would otherwise read the same pending nonce
+    let sending = gateway.sponsor_lock.lock().await;
+    let chain_nonce = next_nonce(gateway, provider, sponsor_address).await?;
+    let now = gateway.now_seconds();
+    let reserved = Arc::new(Mutex::new(None));
+    let state = {
+        let reserved = reserved.clone();
+        let reserve = move |state: &mut SponsorState| {
+            *reserved.lock().unwrap_or_else(|e| e.into_inner()) =
+                state.reserve(wallet, missing, chain_nonce, now);
+        };
+        gateway.sponsor_store.update(Box::new(reserve)).await.map_err(sponsor_store_error)?
+    };
+    let reserved = reserved.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let Some(nonce) = reserved else {
+        // Another gateway sharing the store reserved a top-up of the wallet first
+        drop(sending);
+        let funding = state.pending_for(wallet).cloned();
+        return match funding {
+            Some(funding) => await_top_up(gateway, provider, funding).await,
+            None => Ok(U256::ZERO),
+        };
+    };
+
+    let sent = async {
+        let base = TransactionRequest::default()
+            .from(sponsor_address)
+            .to(wallet)
+            .value(missing)
+            .nonce(nonce);
+        let gas_limit = provider.estimate_gas(base.clone()).await?;
+        let (_, tx) =
+            with_fees(gateway, provider, base.gas_limit(gas_limit), gas_limit, None).await?;
+        let tx = tx.with_chain_id(chain_id);
+        let envelope =
+            <TransactionRequest as NetworkTransactionBuilder<Ethereum>>::build(tx, sponsor)
+                .await
+                .map_err(|e| TransferError::Signing(e.to_string()))?;
+        let hash = *envelope.tx_hash();
+        let raw = Bytes::from(envelope.encoded_2718());
+        let record = {
+            let raw = raw.clone();
+            move |state: &mut SponsorState| state.signed(wallet, nonce, hash, raw)
+        };
+        gateway.sponsor_store.update(Box::new(record)).await.map_err(sponsor_store_error)?;
+        Ok::<_, TransferError>(*provider.send_raw_transaction(&raw).await?.tx_hash())
+    }
+    .await;
+    let hash = match sent {
+        Ok(hash) => hash,
+        Err(e) => {
+            // The nonce is free again; a broadcast that did reach the node is
+            // found by the next reservation through the pending count
+            settle(gateway, wallet, nonce, FundingOutcome::Dropped).await?;
+            return Err(e);
+        }
+    };
+    drop(sending);
+    tracing::info!("Sponsored {missing} wei of gas to {wallet} in {hash}");
+
+    let funding = SponsorFunding {
+        wallet,
+        amount: missing,
+        nonce,
+        tx_hash: Some(hash),
+        raw_tx: None,
+        created_at: now,
+    };
+    await_top_up(gateway, provider, funding).await
+}
+
+/// Waits for the receipt of a pending top-up and settles it in the
+/// `sponsor_store`. A top-up that is not mined in time is broadcast again,
+/// or forgotten when its nonce was taken by another transaction, so the next
+/// sweep attempt funds the wallet anew.
+async fn await_top_up(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    funding: SponsorFunding,
+) -> Result<U256> {
+    let SponsorFunding { wallet, nonce, .. } = funding;
+    let deadline = Instant::now() + Duration::from_secs(gateway.config.receipt_timeout_seconds);
+    loop {
+        // Signed by another gateway, or settled by it
+        let state = gateway.sponsor_store.load().await.map_err(sponsor_store_error)?;
+        let mut pending = state.pending.into_iter();
+        let Some(funding) = pending.find(|f| f.wallet == wallet && f.nonce == nonce) else {
+            return Ok(U256::ZERO);
+        };
+        if let Some(hash) = funding.tx_hash {
+            match provider.get_transaction_receipt(hash).await? {
+                Some(receipt) if receipt.status() => {
+                    settle(gateway, wallet, nonce, FundingOutcome::Mined).await?;
+                    return Ok(funding.amount);
+                }
+                Some(_) => {
+                    settle(gateway, wallet, nonce, FundingOutcome::Reverted).await?;
+                    return Err(TransferError::GasTopUpReverted(hash));
+                }
+                None => {}
+            }
+        }
+        if Instant::now() >= deadline {
+            give_up_top_up(gateway, provider, funding).await?;
+            return Err(TransferError::GasTopUpTimeout);
+        }
+        gateway.config.runtime.sleep(TOP_UP_POLL_INTERVAL).await;
+    }
+}
+
+/// Deals with a top-up that was not mined within the receipt timeout.
+async fn give_up_top_up(
+    gateway: &PaymentGateway,
+    provider: &impl Provider,
+    funding: SponsorFunding,
+) -> Result<()> {
+    let (Some(raw_tx), Some(sponsor)) = (&funding.raw_tx, &gateway.config.gas_sponsor) else {
+        // Reserved but never signed, e.g. by a gateway that crashed meanwhile
+        let age = gateway.now_seconds().saturating_sub(funding.created_at);
+        if age >= gateway.config.receipt_timeout_seconds {
+            settle(gateway, funding.wallet, funding.nonce, FundingOutcome::Dropped).await?;
+        }
+        return Ok(());
+    };
+    let sponsor_address = NetworkWallet::<Ethereum>::default_signer_address(sponsor);
+    if provider.get_transaction_count(sponsor_address).await? > funding.nonce {
+        // The nonce was mined, and not by this top-up which has no receipt
+        settle(gateway, funding.wallet, funding.nonce, FundingOutcome::Dropped).await?;
+    } else if let Err(e) = provider.send_raw_transaction(raw_tx).await {
+        tracing::debug!("Rebroadcasting gas top-up of {}: {e}", funding.wallet);
+    }
+    Ok(())
+}
+
+async fn settle(
+    gateway: &PaymentGateway,
+    wallet: Address,
+    nonce: u64,
+    outcome: FundingOutcome,
+) -> Result<()> {
+    let settle = move |state: &mut SponsorState| state.settle(wallet, nonce, outcome);
+    gateway.sponsor_store.update(Box::new(settle)).await.map_err(sponsor_store_error)?;
+    Ok(())
+}
+
+fn sponsor_store_error(e: GatewayError) -> TransferError {
+    TransferError::SponsorStore(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_call_uses_erc20_selector() {
+        let call = IERC20::transferCall {
+            to: Address::repeat_byte(0x01),
+            amount: U256::from(5u64),
+        };
+        assert_eq!(&call.abi_encode()[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+    }
+
+    #[test]
+    fn balance_of_call_uses_erc20_selector() {
+        let call = IERC20::balanceOfCall {
+            account: Address::repeat_byte(0x01),
+        };
+        assert_eq!(&call.abi_encode()[..4], &[0x70, 0xa0, 0x82, 0x31]);
+    }
+}